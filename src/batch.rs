@@ -0,0 +1,151 @@
+/* This module renders a whole list of axioms
+ * in one run, e.g. to generate a data set.
+ * Every axiom is written to its own file.
+ */
+
+use anyhow::{anyhow, Result};
+
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+const INDEX_PLACEHOLDER: &str = "{index}";
+
+/**
+ * How many axioms of a batch were rendered and how many failed.
+ */
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Rendered {} of {} axioms, {} failed.",
+            self.succeeded,
+            self.succeeded + self.failed,
+            self.failed
+        )
+    }
+}
+
+/**
+ * The output path of the axiom with the given index, e.g. `out_3.wav` for the pattern `out_{index}.wav`.
+ */
+pub fn output_path(output_pattern: &str, index: usize) -> PathBuf {
+    PathBuf::from(output_pattern.replace(INDEX_PLACEHOLDER, &index.to_string()))
+}
+
+/**
+ * Read one axiom per line and render each of them to the output path the pattern gives
+ * for its line number, starting with 1. Empty lines are skipped. An axiom which can't be
+ * rendered is reported to `errors` and the batch continues with the next one.
+ */
+pub fn run(
+    input: impl BufRead,
+    output_pattern: &str,
+    mut render: impl FnMut(&str, PathBuf) -> Result<()>,
+    errors: &mut impl Write,
+) -> Result<Summary> {
+    if !output_pattern.contains(INDEX_PLACEHOLDER) {
+        return Err(anyhow!(
+            "The output pattern '{}' doesn't contain '{}', all axioms would be written to the same file.",
+            output_pattern,
+            INDEX_PLACEHOLDER
+        ));
+    }
+
+    let mut summary = Summary::default();
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line = line?;
+        let axiom = line.trim();
+
+        if axiom.is_empty() {
+            continue;
+        }
+
+        let index = line_index + 1;
+
+        match render(axiom, output_path(output_pattern, index)) {
+            Ok(()) => summary.succeeded += 1,
+            Err(e) => {
+                writeln!(errors, "error: line {} '{}': {}", index, axiom, e)?;
+                summary.failed += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{output_path, run, Summary};
+
+    use anyhow::anyhow;
+    use std::path::PathBuf;
+
+    #[test]
+    fn output_path_test() {
+        assert_eq!(
+            output_path("out_{index}.wav", 3),
+            PathBuf::from("out_3.wav")
+        );
+        assert_eq!(
+            output_path("{index}/{index}.wav", 12),
+            PathBuf::from("12/12.wav")
+        );
+    }
+
+    #[test]
+    fn run_test() {
+        let input = "ABC\n\n  BCA  \nfail\nCAB\n";
+        let mut rendered = vec![];
+        let mut errors = vec![];
+
+        let summary = run(
+            input.as_bytes(),
+            "out_{index}.wav",
+            |axiom, output| match axiom {
+                "fail" => Err(anyhow!("Unexpected symbol")),
+                _ => {
+                    rendered.push((axiom.to_string(), output));
+                    Ok(())
+                }
+            },
+            &mut errors,
+        )
+        .unwrap();
+
+        assert_eq!(
+            summary,
+            Summary {
+                succeeded: 3,
+                failed: 1
+            }
+        );
+        assert_eq!(summary.to_string(), "Rendered 3 of 4 axioms, 1 failed.");
+        assert_eq!(
+            rendered,
+            vec![
+                (String::from("ABC"), PathBuf::from("out_1.wav")),
+                (String::from("BCA"), PathBuf::from("out_3.wav")),
+                (String::from("CAB"), PathBuf::from("out_5.wav")),
+            ]
+        );
+        assert_eq!(
+            String::from_utf8(errors).unwrap(),
+            "error: line 4 'fail': Unexpected symbol\n"
+        );
+    }
+
+    #[test]
+    fn pattern_without_index_test() {
+        let result = run("A\n".as_bytes(), "out.wav", |_, _| Ok(()), &mut vec![]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,1683 @@
+/* This module contains the command line interface.
+ * Every command is run by a function taking the parsed
+ * arguments, so that the commands can be tested without
+ * spawning the binary.
+ */
+
+use anyhow::{anyhow, Result};
+use clap::{ArgEnum, ArgGroup, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueSource};
+use serde::{Deserialize, Serialize};
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use fundsp::hacker::*;
+
+use crate::batch;
+
+use music_generator::musical_notation;
+use music_generator::musical_notation::{MusicalElement, Temperament, TimeSignature};
+
+use music_generator::voice::action::{Action, ActionMap, AtomType, ElementOrigin, MusicalState, OvertoneAction, SimpleAction};
+use music_generator::voice::pitch_class::format_interval_vector;
+use music_generator::voice::{with_context, NoteContext, Voice, BEATS_PER_WHOLE};
+
+use music_generator::l_system::{Atom, Axiom, RuleSet, DEFAULT_MAX_ATOMS};
+use music_generator::progress::{NoProgress, Progress, Stage};
+use music_generator::project::{Instrument, Project};
+use music_generator::synthesis::{guard_clipping, render_with_progress, write_loop_points, write_pcm_f32, PitchModulation, SpatializeMode, WaveTable, WaveTableOscillator};
+
+#[derive(Clone, Debug, PartialEq, ArgEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum PitchStandard {
+    Baroque,
+    Chorton,
+    Classical,
+    Stuttgart,
+}
+
+#[derive(Clone, ArgEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum VolumeCurve {
+    Linear,
+    Exponential,
+    SCurve,
+}
+
+#[derive(Clone, Debug, PartialEq, ArgEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum TemperamentKind {
+    EqualTemperament,
+    JustIntonation
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ArgEnum)]
+enum NoteActionKind {
+    /// the letters are the degrees of the scale
+    #[default]
+    Simple,
+    /// the letters are the partials of the harmonic series of --fundamental
+    Overtone,
+}
+
+/**
+ * Parse a scale kind of `ScaleKind::ALL` or a custom scale like "2,1,3,1,1,3,1".
+ */
+fn parse_scale_kind(s: &str) -> Result<musical_notation::ScaleKind, String> {
+    s.parse().map_err(|e: musical_notation::ScaleKindParseError| e.to_string())
+}
+
+fn parse_tonic(s: &str) -> Result<(&'static musical_notation::Note, &'static musical_notation::Accidental), String> {
+    match s {
+        "C" => Ok((
+            &musical_notation::Note::C,
+            &musical_notation::Accidental::Natural,
+        )),
+        "C#" => Ok((
+            &musical_notation::Note::C,
+            &musical_notation::Accidental::Sharp,
+        )),
+        "Db" => Ok((
+            &musical_notation::Note::D,
+            &musical_notation::Accidental::Flat,
+        )),
+        "D" => Ok((
+            &musical_notation::Note::D,
+            &musical_notation::Accidental::Natural,
+        )),
+        "D#" => Ok((
+            &musical_notation::Note::D,
+            &musical_notation::Accidental::Sharp,
+        )),
+        "Eb" => Ok((
+            &musical_notation::Note::E,
+            &musical_notation::Accidental::Flat,
+        )),
+        "E" => Ok((
+            &musical_notation::Note::E,
+            &musical_notation::Accidental::Natural,
+        )),
+        "F" => Ok((
+            &musical_notation::Note::F,
+            &musical_notation::Accidental::Natural,
+        )),
+        "F#" => Ok((
+            &musical_notation::Note::F,
+            &musical_notation::Accidental::Sharp,
+        )),
+        "Gb" => Ok((
+            &musical_notation::Note::G,
+            &musical_notation::Accidental::Flat,
+        )),
+        "G" => Ok((
+            &musical_notation::Note::G,
+            &musical_notation::Accidental::Natural,
+        )),
+        "G#" => Ok((
+            &musical_notation::Note::G,
+            &musical_notation::Accidental::Sharp,
+        )),
+        "Ab" => Ok((
+            &musical_notation::Note::A,
+            &musical_notation::Accidental::Flat,
+        )),
+        "A" => Ok((
+            &musical_notation::Note::A,
+            &musical_notation::Accidental::Natural,
+        )),
+        "A#" => Ok((
+            &musical_notation::Note::A,
+            &musical_notation::Accidental::Sharp,
+        )),
+        "Bb" => Ok((
+            &musical_notation::Note::B,
+            &musical_notation::Accidental::Flat,
+        )),
+        "B" => Ok((
+            &musical_notation::Note::B,
+            &musical_notation::Accidental::Natural,
+        )),
+        _ => Err(
+            "Please provide a valid tonic. Examples of correct values are 'C', 'F#', 'Gb'."
+                .to_string(),
+        ),
+    }
+}
+
+/**
+ * A delayed, transposed and attenuated copy of the voice played along with it, see `Voice::canon`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Echo {
+    delay_units: u16,
+    transpose_semitones: i16,
+    volume_scale: f64,
+}
+
+/**
+ * Parse an echo given as 'delay,semitones,volume scale', e.g. '8,-12,0.5'.
+ */
+fn parse_echo(s: &str) -> Result<Echo, String> {
+    let error = || format!("Please provide the echo as 'delay,semitones,volume scale', e.g. '8,-12,0.5', not '{}'.", s);
+    match s.split(',').map(str::trim).collect::<Vec<&str>>()[..] {
+        [delay_units, transpose_semitones, volume_scale] => Ok(Echo {
+            delay_units: delay_units.parse().map_err(|_| error())?,
+            transpose_semitones: transpose_semitones.parse().map_err(|_| error())?,
+            volume_scale: volume_scale.parse().map_err(|_| error())?,
+        }),
+        _ => Err(error()),
+    }
+}
+
+/**
+ * Parse the width of the stereo field given with --pan-by-pitch, which lies between 0 and 2.
+ */
+fn parse_pan_width(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(width) if (0.0..=2.0).contains(&width) => Ok(width),
+        _ => Err(format!("Please provide the width of the stereo field as a number from 0 to 2, not '{}'.", s)),
+    }
+}
+
+/**
+ * The range given with --fit-range as the lowest and the highest tone with their octaves.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FitRange {
+    low: (musical_notation::Tone, i16),
+    high: (musical_notation::Tone, i16),
+}
+
+/**
+ * Parse a tone followed by its octave in scientific pitch notation, e.g. 'A1' or 'C#7'.
+ */
+fn parse_tone_in_octave(s: &str) -> Option<(musical_notation::Tone, i16)> {
+    let octave_start = s.find(|c: char| c.is_ascii_digit() || c == '-')?;
+    Some((s[..octave_start].parse().ok()?, s[octave_start..].parse().ok()?))
+}
+
+fn parse_fundamental(s: &str) -> Result<(musical_notation::Tone, i16), String> {
+    parse_tone_in_octave(s.trim()).ok_or_else(|| format!("Please provide the fundamental as a tone with its octave, e.g. 'C2', not '{}'.", s))
+}
+
+/**
+ * Parse a range given as 'lowest..highest' tone with their octaves, e.g. 'A1..C7'.
+ */
+fn parse_fit_range(s: &str) -> Result<FitRange, String> {
+    let error = || format!("Please provide the range as 'lowest..highest' tone with their octaves, e.g. 'A1..C7', not '{}'.", s);
+    let (low, high) = s.split_once("..").ok_or_else(error)?;
+    let low = parse_tone_in_octave(low.trim()).ok_or_else(error)?;
+    let high = parse_tone_in_octave(high.trim()).ok_or_else(error)?;
+
+    if (low.1, low.0.get_position()) >= (high.1, high.0.get_position()) {
+        return Err(format!("The lowest tone of the range '{}' has to be below the highest one.", s));
+    }
+
+    Ok(FitRange { low, high })
+}
+
+/**
+ * The parameters a WAV file was rendered with, written as JSON next to it,
+ * so that the render can be recreated. The axiom is missing for a loaded voice,
+ * the paths of the files the axiom or the rules were read from are given if there are any.
+ * The seed is only present if the render made use of random numbers.
+ */
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct RenderMetadata {
+    axiom: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    axiom_file: Option<PathBuf>,
+    rules: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rules_file: Option<PathBuf>,
+    iterations: usize,
+    scale_tonic: String,
+    scale_kind: String,
+    temperament_kind: TemperamentKind,
+    pitch_standard: PitchStandard,
+    bpm: u16,
+    seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transpose_semitones: Option<i16>,
+}
+
+impl RenderMetadata {
+    fn new(shared: &SharedArgs, args: &VoiceArgs) -> RenderMetadata {
+        RenderMetadata {
+            axiom: args.axiom.clone(),
+            axiom_file: args.axiom_file.clone(),
+            rules: args.rules.clone(),
+            rules_file: args.rules_file.clone(),
+            iterations: args.iterations,
+            scale_tonic: scale_tonic(shared).to_string(),
+            scale_kind: shared.scale_kind.to_string(),
+            temperament_kind: shared.temperament_kind.clone(),
+            pitch_standard: shared.pitch_standard.clone(),
+            bpm: shared.bpm,
+            seed: None,
+            transpose_semitones: None,
+        }
+    }
+
+    /**
+     * The path of the metadata belonging to the WAV file at the given path, e.g. `out.wav.json`.
+     */
+    fn path_for(output: &std::path::Path) -> PathBuf {
+        let mut path = output.as_os_str().to_owned();
+        path.push(".json");
+        PathBuf::from(path)
+    }
+
+    fn save(&self, output: &std::path::Path) -> Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(RenderMetadata::path_for(output))?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/**
+ * The parameters of a TOML file given with --config. Every key is optional and
+ * named like the corresponding command line argument with underscores instead of
+ * dashes, e.g. `scale_tonic` for --scale-tonic. Keys of arguments the chosen command
+ * doesn't have are ignored. A config file may look like this:
+ *
+ * axiom = "ABA"
+ * output = "voice.wav"
+ * pitch_standard = "baroque"
+ * scale_tonic = "F#"
+ * scale_kind = "minor"
+ * temperament_kind = "equal-temperament"
+ * pitch_ceiling = 2000.0
+ * volume_curve = "s-curve"
+ * bpm = 90
+ * seed = 42
+ * rules = ["A->ABA", "B->BAB"]
+ * iterations = 3
+ * mapping = "mapping.toml"
+ *
+ * Unknown keys are reported as a warning and otherwise ignored.
+ */
+#[derive(Deserialize)]
+struct Config {
+    axiom: Option<String>,
+    output: Option<PathBuf>,
+    pitch_standard: Option<PitchStandard>,
+    scale_tonic: Option<String>,
+    scale_kind: Option<String>,
+    temperament_kind: Option<TemperamentKind>,
+    pitch_ceiling: Option<f64>,
+    volume_curve: Option<VolumeCurve>,
+    bpm: Option<u16>,
+    seed: Option<u64>,
+    rules: Option<Vec<String>>,
+    iterations: Option<usize>,
+    mapping: Option<PathBuf>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+impl Config {
+    fn from_file(path: &std::path::Path) -> Result<Config> {
+        let config: Config = toml::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| anyhow!("Invalid config file '{}': {}", path.display(), e))?;
+
+        for key in config.unknown.keys() {
+            eprintln!(
+                "warning: unknown key '{}' in config file '{}' is ignored",
+                key,
+                path.display()
+            );
+        }
+
+        Ok(config)
+    }
+
+    /**
+     * Overwrite every argument, that wasn't given on the command line,
+     * with the value of this config.
+     */
+    fn apply(self, shared: &mut SharedArgs, command: &mut Command, matches: &ArgMatches) -> Result<()> {
+        let from_command_line = |id: &str| given_on_command_line(matches, id);
+
+        if let Some(args) = command.voice_args_mut() {
+            if args.axiom_file.is_none() {
+                args.axiom = args.axiom.take().or(self.axiom);
+            }
+            if let (false, None, Some(rules)) = (from_command_line("rules"), &args.rules_file, self.rules) {
+                args.rules = rules;
+            }
+            if let (false, Some(iterations)) = (from_command_line("iterations"), self.iterations) {
+                args.iterations = iterations;
+            }
+            args.mapping = args.mapping.take().or(self.mapping);
+        }
+
+        match command {
+            Command::Render(args) => {
+                args.output = args.output.take().or(self.output);
+
+                if let (false, Some(volume_curve)) = (from_command_line("volume-curve"), self.volume_curve) {
+                    args.volume_curve = volume_curve;
+                }
+            }
+            Command::Export(args) => args.output = args.output.take().or(self.output),
+            Command::Expand(_) | Command::Inspect(_) | Command::List(_) => {}
+        }
+
+        shared.pitch_ceiling = shared.pitch_ceiling.or(self.pitch_ceiling);
+        shared.seed = shared.seed.or(self.seed);
+
+        if let (false, Some(pitch_standard)) = (from_command_line("pitch-standard"), self.pitch_standard) {
+            shared.pitch_standard = pitch_standard;
+        }
+        if let (false, Some(scale_tonic)) = (from_command_line("scale-tonic"), self.scale_tonic) {
+            shared.scale_tonic = parse_tonic(&scale_tonic).map_err(|e| anyhow!(e))?;
+        }
+        if let (false, Some(scale_kind)) = (from_command_line("scale-kind"), self.scale_kind) {
+            shared.scale_kind = parse_scale_kind(&scale_kind).map_err(|e| anyhow!(e))?;
+        }
+        if let (false, Some(temperament_kind)) = (from_command_line("temperament-kind"), self.temperament_kind) {
+            shared.temperament_kind = temperament_kind;
+        }
+        if let (false, Some(bpm)) = (from_command_line("bpm"), self.bpm) {
+            shared.bpm = bpm;
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * Check whether the argument with the given id was given on the command line,
+ * either before the subcommand or after any of the nested subcommands.
+ */
+fn given_on_command_line(matches: &ArgMatches, id: &str) -> bool {
+    let mut current = Some(matches);
+
+    while let Some(matches) = current {
+        if matches.try_contains_id(id).is_ok() && matches.value_source(id) == Some(ValueSource::CommandLine) {
+            return true;
+        }
+
+        current = matches.subcommand().map(|(_, sub_matches)| sub_matches);
+    }
+
+    false
+}
+
+/// play a voice
+#[derive(Parser)]
+#[clap(author, version, about)]
+pub struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+    /// the arguments of render, which is run if no command is given
+    #[clap(flatten)]
+    render: RenderArgs,
+    #[clap(flatten)]
+    shared: SharedArgs,
+}
+
+// the command is parsed once, so the size of the render arguments doesn't matter
+#[derive(Subcommand)]
+pub enum Command {
+    /// render the voice to a WAV file, this is the default command
+    Render(Box<RenderArgs>),
+    /// print the axiom after applying the rules
+    Expand(VoiceArgs),
+    /// write the voice to a file in another format
+    Export(ExportArgs),
+    /// print information about the voice without rendering it
+    #[clap(subcommand)]
+    Inspect(InspectCommand),
+    /// print the names of the scales, temperaments, instruments or pitch standards, sorted by name
+    #[clap(subcommand)]
+    List(ListCommand),
+}
+
+impl Command {
+    fn voice_args_mut(&mut self) -> Option<&mut VoiceArgs> {
+        match self {
+            Command::Render(args) => Some(&mut args.voice),
+            Command::Expand(args) => Some(args),
+            Command::Export(args) => Some(&mut args.voice),
+            Command::Inspect(InspectCommand::Stats(args)) => Some(args),
+            Command::Inspect(InspectCommand::DryRun(args)) => Some(args),
+            Command::Inspect(InspectCommand::PitchTable(_)) => None,
+            Command::List(_) => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum InspectCommand {
+    /// print the length of the axiom, the number of notes and rests, the duration, the pitch classes with their interval vector and the range of the voice
+    Stats(VoiceArgs),
+    /// print the start time, duration, generating atom, pitch and volume of every note
+    DryRun(VoiceArgs),
+    /// print the tone, octave, frequency, MIDI note and deviation from equal temperament of every tone of the scale
+    PitchTable(PitchTableArgs),
+}
+
+#[derive(Clone, Copy, Subcommand)]
+pub enum ListCommand {
+    /// the scale kinds and their number of tones
+    Scales,
+    /// the temperaments
+    Temperaments,
+    /// the instruments of a project file
+    Instruments,
+    /// the pitch standards and the frequency of A4
+    PitchStandards,
+}
+
+#[derive(Args)]
+pub struct PitchTableArgs {
+    /// the octave the table starts in
+    #[clap(long, default_value_t = 3, allow_hyphen_values = true)]
+    lowest_octave: i16,
+    /// the octave the table ends in
+    #[clap(long, default_value_t = 5, allow_hyphen_values = true)]
+    highest_octave: i16,
+}
+
+/// the options shared by all commands
+#[derive(Args)]
+#[clap(group(ArgGroup::new("scale").multiple(true).args(&["scale-tonic", "scale-kind"])))]
+pub struct SharedArgs {
+    /// load the parameters from a TOML file, parameters given on the command line take precedence
+    #[clap(parse(from_os_str), long, global = true)]
+    config: Option<PathBuf>,
+    /// render the piece described by a TOML project file, ignoring all other parameters
+    #[clap(parse(from_os_str), long, global = true)]
+    project: Option<PathBuf>,
+    #[clap(arg_enum, short, long, global = true, default_value_t = PitchStandard::Stuttgart)]
+    pitch_standard: PitchStandard,
+    #[clap(long, global = true, default_value = "C", value_parser = parse_tonic)]
+    scale_tonic: (&'static musical_notation::Note, &'static musical_notation::Accidental),
+    #[clap(long, global = true, default_value = "major", value_parser = parse_scale_kind)]
+    scale_kind: musical_notation::ScaleKind,
+    #[clap(arg_enum, long, global = true, default_value_t = TemperamentKind::EqualTemperament)]
+    temperament_kind: TemperamentKind,
+    /// fold pitches above this frequency in Hz down by octaves
+    #[clap(long, global = true)]
+    pitch_ceiling: Option<f64>,
+    /// the tempo in eighth notes per minute
+    #[clap(long, global = true, default_value_t = 120)]
+    bpm: u16,
+    /// the seed of the random numbers, e.g. of --detune-cents, defaults to 0
+    #[clap(long, global = true)]
+    seed: Option<u64>,
+}
+
+impl Default for SharedArgs {
+    fn default() -> Self {
+        SharedArgs {
+            config: None,
+            project: None,
+            pitch_standard: PitchStandard::Stuttgart,
+            scale_tonic: (&musical_notation::Note::C, &musical_notation::Accidental::Natural),
+            scale_kind: musical_notation::ScaleKind::Major,
+            temperament_kind: TemperamentKind::EqualTemperament,
+            pitch_ceiling: None,
+            bpm: 120,
+            seed: None,
+        }
+    }
+}
+
+/// the arguments describing the voice
+#[derive(Args, Default)]
+pub struct VoiceArgs {
+    /// the axiom of the voice
+    axiom: Option<String>,
+    /// read the axiom from the first line of this file that is neither empty nor a comment starting with '#'
+    #[clap(parse(from_os_str), long, conflicts_with = "axiom")]
+    axiom_file: Option<PathBuf>,
+    /// a rule like 'A->ABA' applied to the axiom, can be given multiple times
+    #[clap(long)]
+    rules: Vec<String>,
+    /// read the rules from this file, one per line, skipping empty lines and comments starting with '#'
+    #[clap(parse(from_os_str), long, conflicts_with = "rules")]
+    rules_file: Option<PathBuf>,
+    /// how often the rules are applied to the axiom
+    #[clap(long, default_value_t = 0)]
+    iterations: usize,
+    /// stop with an error instead of expanding the axiom to more than this number of atoms
+    #[clap(long, default_value_t = DEFAULT_MAX_ATOMS)]
+    max_atoms: usize,
+    /// a TOML file assigning actions like "rest", "push" or "octave_up" to symbols, all other symbols are notes
+    #[clap(parse(from_os_str), long)]
+    mapping: Option<PathBuf>,
+    /// fold the notes outside of this range given as 'lowest..highest' tone, e.g. 'A1..C7', by octaves into it
+    #[clap(long, value_parser = parse_fit_range)]
+    fit_range: Option<FitRange>,
+    /// what the letters of the axiom are mapped to
+    #[clap(arg_enum, long, default_value_t = NoteActionKind::Simple)]
+    action: NoteActionKind,
+    /// the fundamental of --action overtone as a tone with its octave, defaults to 'C2'
+    #[clap(long, value_parser = parse_fundamental)]
+    fundamental: Option<(musical_notation::Tone, i16)>,
+    /// let the volume of the partials of --action overtone roll off with 1/n
+    #[clap(long)]
+    rolloff: bool,
+    /// use the voice stored as JSON at this path instead of generating one from the axiom
+    #[clap(parse(from_os_str), long)]
+    load_voice: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+    #[clap(flatten)]
+    voice: VoiceArgs,
+    /// the output path, '-' streams the samples to stdout as interleaved little-endian f32 PCM instead of a WAV file
+    #[clap(parse(from_os_str), short = 'o', long = "output")]
+    output: Option<PathBuf>,
+    /// render every axiom of this file, one per line, '-' reads the axioms from stdin
+    #[clap(parse(from_os_str), long, conflicts_with_all = &["output", "axiom", "axiom-file"])]
+    batch_file: Option<PathBuf>,
+    /// the output paths of a batch, '{index}' is replaced by the line number of the axiom
+    #[clap(long, default_value = "out_{index}.wav")]
+    output_pattern: String,
+    /// render the voice once in the key of each of these comma separated tonics, e.g. 'C,D,Eb', to files named like 'out-Eb.wav'
+    #[clap(long, value_parser = parse_tonic, value_delimiter = ',', conflicts_with_all = &["batch-file", "all-keys"])]
+    keys: Vec<(&'static musical_notation::Note, &'static musical_notation::Accidental)>,
+    /// render the voice once in each of the twelve keys
+    #[clap(long, conflicts_with = "batch-file")]
+    all_keys: bool,
+    /// render the voice once transposed by each of these comma separated numbers of semitones, e.g. '-2,0,2', to files named like 'out+2.wav'
+    #[clap(long, value_delimiter = ',', allow_hyphen_values = true, conflicts_with_all = &["batch-file", "keys", "all-keys"])]
+    transpose_semitones: Vec<i16>,
+    /// how the volume of a note is converted to the gain of the instrument
+    #[clap(arg_enum, long, default_value_t = VolumeCurve::Linear)]
+    volume_curve: VolumeCurve,
+    /// play the notes with the first cycle of this WAV file instead of a sine wave
+    #[clap(parse(from_os_str), long)]
+    wavetable: Option<PathBuf>,
+    /// how often a second the vibrato swings the pitch of the built-in instruments
+    #[clap(long, default_value_t = 5.0)]
+    vibrato_hz: f64,
+    /// how far the vibrato swings the pitch above and below the note in cents
+    #[clap(long, default_value_t = 0.0)]
+    vibrato_cents: f64,
+    /// detune every note by a random number of cents up to this one, seeded by --seed
+    #[clap(long, default_value_t = 0.0)]
+    detune_cents: f64,
+    /// the portion of a slide note during which it glides in from the previous note
+    #[clap(long, default_value_t = 0.3)]
+    slide_portion: f64,
+    /// play the voice along with a copy of it, given as 'delay in eighths,semitones,volume scale', e.g. '8,-12,0.5'
+    #[clap(long, value_parser = parse_echo, allow_hyphen_values = true)]
+    echo: Option<Echo>,
+    /// step the volume of the notes on each quarter note beat of a bar by these comma separated numbers, e.g. '2,0,1,0' for 4/4 with strong downbeats
+    #[clap(long, value_delimiter = ',', allow_hyphen_values = true)]
+    accent: Vec<i8>,
+    /// save the audio as rendered, even if it exceeds full scale, instead of attenuating it
+    #[clap(long)]
+    no_clip_guard: bool,
+    /// pan every note by its pitch from the left for the lowest to the right for the highest note, spread over this width of the stereo field from 0 to 2
+    #[clap(long, value_parser = parse_pan_width)]
+    pan_by_pitch: Option<f64>,
+    /// mark a loop in the WAV file starting after this number of eighths, requires --loop-end
+    #[clap(long, requires = "loop-end")]
+    loop_start: Option<u16>,
+    /// the number of eighths after which the loop marked in the WAV file ends
+    #[clap(long, requires = "loop-start")]
+    loop_end: Option<u16>,
+    /// store the generated voice as JSON at this path
+    #[clap(parse(from_os_str), long)]
+    save_voice: Option<PathBuf>,
+}
+
+#[derive(Clone, ArgEnum)]
+enum ExportFormat {
+    /// the notes and rests as text, one per line
+    Text,
+    /// the voice as JSON, which can be loaded with --load-voice
+    Json,
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[clap(flatten)]
+    voice: VoiceArgs,
+    #[clap(arg_enum, long, default_value_t = ExportFormat::Text)]
+    format: ExportFormat,
+    /// the output path, the voice is printed if it's missing
+    #[clap(parse(from_os_str), short = 'o', long = "output")]
+    output: Option<PathBuf>,
+}
+
+/**
+ * Parse the command line, apply the config file and run the chosen command.
+ */
+pub fn run() -> Result<()> {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches)?;
+
+    let mut shared = cli.shared;
+
+    if let Some(path) = &shared.project {
+        return render_project(path, cli.render.voice.max_atoms);
+    }
+
+    let mut command = cli.command.unwrap_or_else(|| Command::Render(Box::new(cli.render)));
+
+    if let Some(path) = shared.config.clone() {
+        Config::from_file(&path)?.apply(&mut shared, &mut command, &matches)?;
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+
+    match command {
+        Command::Render(args) => render(&shared, *args),
+        Command::Expand(args) => expand(&args, &mut out),
+        Command::Export(args) => export(&shared, &args, &mut out),
+        Command::Inspect(InspectCommand::Stats(args)) => inspect_stats(&shared, &args, &mut out),
+        Command::Inspect(InspectCommand::DryRun(args)) => inspect_dry_run(&shared, &args, &mut out),
+        Command::Inspect(InspectCommand::PitchTable(args)) => inspect_pitch_table(&shared, &args, &mut out),
+        Command::List(command) => list(command, &mut out),
+    }
+}
+
+pub fn render(shared: &SharedArgs, args: RenderArgs) -> Result<()> {
+    if let Some(batch_file) = &args.batch_file {
+        return render_batch(shared, &args, batch_file);
+    }
+
+    let output = match &args.output {
+        Some(output) => output.clone(),
+        None => return Err(anyhow!("Please provide an output path.")),
+    };
+
+    if let (Some(loop_start), Some(loop_end)) = (args.loop_start, args.loop_end) {
+        if loop_start >= loop_end {
+            return Err(anyhow!("The loop has to start before it ends, {} is not before {}.", loop_start, loop_end));
+        }
+        if is_stdout(&output) {
+            return Err(anyhow!("A loop can only be marked in a WAV file, not in the samples streamed to stdout."));
+        }
+    }
+    if is_stdout(&output) && (args.all_keys || !args.keys.is_empty() || !args.transpose_semitones.is_empty()) {
+        return Err(anyhow!("Only a single voice can be streamed to stdout, please provide an output path for --keys, --all-keys or --transpose-semitones."));
+    }
+    if args.all_keys || !args.keys.is_empty() {
+        return render_keys(shared, &args, &output);
+    }
+    if !args.transpose_semitones.is_empty() {
+        return render_transpositions(shared, &args, &output);
+    }
+
+    let progress = TerminalProgress::new();
+    let voice = generate_voice(shared, &args.voice, &progress)?;
+
+    if let Some(path) = &args.save_voice {
+        voice.save_json(path)?;
+    }
+
+    sequence_helper(voice, output.clone(), shared, &args, render_sound(shared, &args)?, &progress)?;
+
+    if !is_stdout(&output) {
+        let mut metadata = RenderMetadata::new(shared, &args.voice);
+        metadata.seed = used_seed(shared, &args);
+        metadata.save(&output)?;
+    }
+
+    Ok(())
+}
+
+fn load_wavetable(args: &RenderArgs) -> Result<Option<Arc<WaveTable>>> {
+    match &args.wavetable {
+        Some(path) => Ok(Some(Arc::new(WaveTable::from_wav(path)?))),
+        None => Ok(None),
+    }
+}
+
+/**
+ * The sound of a rendered voice: a sine wave or the wavetable, modulated as given by the arguments.
+ */
+fn render_sound(shared: &SharedArgs, args: &RenderArgs) -> Result<Sound> {
+    Ok(Sound {
+        instrument: Instrument::Sine,
+        pan: 0.0,
+        gain: 1.0,
+        wavetable: load_wavetable(args)?,
+        modulation: PitchModulation {
+            vibrato_hz: args.vibrato_hz,
+            vibrato_cents: args.vibrato_cents,
+            detune_cents: args.detune_cents,
+            slide_portion: args.slide_portion,
+        },
+        seed: shared.seed.unwrap_or_default(),
+        spatialize: match args.pan_by_pitch {
+            Some(width) => SpatializeMode::ByPitch { width },
+            None => SpatializeMode::Fixed,
+        },
+    })
+}
+
+/**
+ * The seed of the random numbers, if the render uses any.
+ */
+fn used_seed(shared: &SharedArgs, args: &RenderArgs) -> Option<u64> {
+    match args.detune_cents {
+        detune_cents if detune_cents != 0.0 => Some(shared.seed.unwrap_or_default()),
+        _ => None,
+    }
+}
+
+fn volume_curve(volume_curve: &VolumeCurve) -> musical_notation::VolumeCurve {
+    match volume_curve {
+        VolumeCurve::Linear => musical_notation::VolumeCurve::Linear,
+        VolumeCurve::Exponential => musical_notation::VolumeCurve::Exponential,
+        VolumeCurve::SCurve => musical_notation::VolumeCurve::SCurve,
+    }
+}
+
+/**
+ * The output path with the suffix appended to the file name before the extension,
+ * e.g. `out-Eb.wav` for `out.wav` and the suffix `-Eb`.
+ */
+fn suffixed_path(output: &Path, suffix: &str) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match output.extension() {
+        Some(extension) => format!("{}{}.{}", stem, suffix, extension.to_string_lossy()),
+        None => format!("{}{}", stem, suffix),
+    };
+    output.with_file_name(file_name)
+}
+
+/**
+ * Render the voice in the key of every tonic given with --keys or in all twelve keys.
+ * The axiom is expanded only once and the voice of each key is generated from it.
+ */
+fn render_keys(shared: &SharedArgs, args: &RenderArgs, output: &Path) -> Result<()> {
+    if args.voice.load_voice.is_some() {
+        return Err(anyhow!("A loaded voice can't be rendered in other keys, use --transpose-semitones instead."));
+    }
+
+    let tonics: Vec<musical_notation::Tone> = match args.all_keys {
+        true => (1..=12).map(|position| musical_notation::Tone::from_position(position, true)).collect(),
+        false => args.keys.iter().map(|(note, accidental)| musical_notation::Tone::new(**note, **accidental)).collect(),
+    };
+    let progress = TerminalProgress::new();
+    let axiom = expand_axiom(&args.voice, &progress)?;
+    let sound = render_sound(shared, args)?;
+
+    for tonic in tonics {
+        let voice = voice_in_key(shared, &args.voice, &axiom, tonic, &progress)?;
+        let path = suffixed_path(output, &format!("-{}", tonic));
+
+        sequence_helper(voice, path.clone(), shared, args, sound.clone(), &progress)?;
+
+        let mut metadata = RenderMetadata::new(shared, &args.voice);
+        metadata.scale_tonic = tonic.to_string();
+        metadata.seed = used_seed(shared, args);
+        metadata.save(&path)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Generate the voice once and render it transposed by every number of semitones given with --transpose-semitones.
+ */
+fn render_transpositions(shared: &SharedArgs, args: &RenderArgs, output: &Path) -> Result<()> {
+    let progress = TerminalProgress::new();
+    let voice = generate_voice(shared, &args.voice, &progress)?;
+    let sound = render_sound(shared, args)?;
+
+    for semitones in &args.transpose_semitones {
+        let path = suffixed_path(output, &format!("{:+}", semitones));
+
+        sequence_helper(voice.transpose(*semitones), path.clone(), shared, args, sound.clone(), &progress)?;
+
+        let mut metadata = RenderMetadata::new(shared, &args.voice);
+        metadata.transpose_semitones = Some(*semitones);
+        metadata.seed = used_seed(shared, args);
+        metadata.save(&path)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Render every axiom of the batch file with the rules and settings of the given arguments.
+ */
+fn render_batch(shared: &SharedArgs, args: &RenderArgs, batch_file: &Path) -> Result<()> {
+    let input: Box<dyn std::io::BufRead> = match batch_file.to_str() {
+        Some("-") => Box::new(std::io::stdin().lock()),
+        _ => Box::new(std::io::BufReader::new(std::fs::File::open(batch_file)?)),
+    };
+
+    let render_axiom = |axiom: &str, output: PathBuf| {
+        render(shared, RenderArgs {
+            voice: VoiceArgs {
+                axiom: Some(axiom.to_string()),
+                axiom_file: None,
+                rules: args.voice.rules.clone(),
+                rules_file: args.voice.rules_file.clone(),
+                iterations: args.voice.iterations,
+                max_atoms: args.voice.max_atoms,
+                mapping: args.voice.mapping.clone(),
+                fit_range: args.voice.fit_range,
+                action: args.voice.action,
+                fundamental: args.voice.fundamental,
+                rolloff: args.voice.rolloff,
+                load_voice: None,
+            },
+            output: Some(output),
+            batch_file: None,
+            output_pattern: args.output_pattern.clone(),
+            keys: vec![],
+            all_keys: false,
+            transpose_semitones: vec![],
+            wavetable: args.wavetable.clone(),
+            vibrato_hz: args.vibrato_hz,
+            vibrato_cents: args.vibrato_cents,
+            detune_cents: args.detune_cents,
+            slide_portion: args.slide_portion,
+            echo: args.echo,
+            accent: args.accent.clone(),
+            no_clip_guard: args.no_clip_guard,
+            pan_by_pitch: args.pan_by_pitch,
+            loop_start: args.loop_start,
+            loop_end: args.loop_end,
+            volume_curve: args.volume_curve.clone(),
+            save_voice: None,
+        })
+    };
+
+    let summary = batch::run(input, &args.output_pattern, render_axiom, &mut std::io::stderr())?;
+    println!("{}", summary);
+
+    match summary.failed {
+        0 => Ok(()),
+        failed => Err(anyhow!("{} of the axioms couldn't be rendered.", failed)),
+    }
+}
+
+/**
+ * Render all voices of the project file at the given path into the output file it names,
+ * failing if its axiom would grow beyond the maximum number of atoms.
+ */
+pub fn render_project(path: &Path, max_atoms: usize) -> Result<()> {
+    let project = Project::from_toml(path, max_atoms)?;
+
+    let output = match &project.render.output {
+        Some(output) => output.clone(),
+        None => return Err(anyhow!("Please provide an output path in the [render] table of the project.")),
+    };
+
+    let voices = project
+        .voices()?
+        .into_iter()
+        .map(|(_, voice_table, voice)| {
+            (voice, Sound { instrument: voice_table.instrument, pan: voice_table.pan, gain: voice_table.gain, wavetable: None, modulation: PitchModulation::default(), seed: 0, spatialize: SpatializeMode::Fixed })
+        })
+        .collect::<Vec<(Voice, Sound)>>();
+
+    sequence_voices(
+        &voices,
+        &output,
+        musical_notation::VolumeCurve::default(),
+        project.render.bpm,
+        project.render.sample_rate,
+        &TerminalProgress::new(),
+        true,
+    )
+}
+
+pub fn expand(args: &VoiceArgs, out: &mut impl Write) -> Result<()> {
+    writeln!(out, "{:?}", expand_axiom(args, &NoProgress)?)?;
+    Ok(())
+}
+
+pub fn export(shared: &SharedArgs, args: &ExportArgs, out: &mut impl Write) -> Result<()> {
+    let voice = generate_voice(shared, &args.voice, &NoProgress)?;
+
+    let mut file;
+    let out: &mut dyn Write = match &args.output {
+        Some(path) => {
+            file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            &mut file
+        }
+        None => out,
+    };
+
+    match args.format {
+        ExportFormat::Text => {
+            for musical_element in voice.musical_elements() {
+                writeln!(out, "{}", format_musical_element(musical_element))?;
+            }
+        }
+        ExportFormat::Json => {
+            serde_json::to_writer(&mut *out, &voice)?;
+            writeln!(out)?;
+        }
+    }
+
+    Ok(out.flush()?)
+}
+
+pub fn inspect_stats(shared: &SharedArgs, args: &VoiceArgs, out: &mut impl Write) -> Result<()> {
+    let voice = generate_voice(shared, args, &NoProgress)?;
+
+    if args.load_voice.is_none() {
+        writeln!(out, "atoms: {}", expand_axiom(args, &NoProgress)?.atoms().len())?;
+    }
+    writeln!(out, "notes: {}", voice.get_note_count())?;
+    writeln!(out, "rests: {}", voice.get_rest_count())?;
+    writeln!(out, "duration: {} ({:.2} s)", voice.total_duration(), voice.get_duration(shared.bpm))?;
+
+    let pitch_class_set = voice.pitch_class_set(pitch_standard(shared));
+    writeln!(out, "pitch classes: {}", pitch_class_set)?;
+    writeln!(out, "interval vector: {}", format_interval_vector(&pitch_class_set.interval_vector()))?;
+
+    match voice.pitch_range() {
+        Some((lowest, highest)) => writeln!(out, "range: {:.3?} - {:.3?}", lowest, highest)?,
+        None => writeln!(out, "range: -")?,
+    }
+
+    Ok(())
+}
+
+pub fn inspect_dry_run(shared: &SharedArgs, args: &VoiceArgs, out: &mut impl Write) -> Result<()> {
+    let (voice, origins) = generate_traced_voice(shared, args)?;
+    let mut elapsed = musical_notation::Duration::zero();
+    let mut elapsed_timed_seconds = 0.0;
+
+    writeln!(out, "{:>9}  {:<8}  {:<6}  element", "start (s)", "duration", "atom")?;
+
+    for (index, musical_element) in voice.musical_elements().iter().enumerate() {
+        let duration = match musical_element {
+            MusicalElement::TimedNote { seconds, .. } => format!("{:.3} s", seconds),
+            musical_element => musical_element.get_duration().to_string(),
+        };
+        let atom = match origins.get(index) {
+            Some(origin) => format!("{} {}", origin.atom_index, origin.symbol),
+            None => String::from("-"),
+        };
+
+        writeln!(
+            out,
+            "{:>9.3}  {:<8}  {:<6}  {}",
+            elapsed.to_seconds(shared.bpm, BEATS_PER_WHOLE) + elapsed_timed_seconds,
+            duration,
+            atom,
+            format_musical_element(musical_element)
+        )?;
+
+        elapsed = elapsed + musical_element.get_duration();
+        if let MusicalElement::TimedNote { seconds, .. } = musical_element {
+            elapsed_timed_seconds += seconds;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn inspect_pitch_table(shared: &SharedArgs, args: &PitchTableArgs, out: &mut impl Write) -> Result<()> {
+    let key = create_key(shared, scale_tonic(shared))?;
+
+    writeln!(out, "{:<4}  {:>6}  {:>10}  {:>4}  {:>7}", "tone", "octave", "hz", "midi", "cents")?;
+
+    for row in musical_notation::pitch_table(&key, args.lowest_octave..=args.highest_octave) {
+        writeln!(
+            out,
+            "{:<4}  {:>6}  {:>10.3}  {:>4}  {:>+7.2}",
+            row.tone.to_string(),
+            row.octave,
+            row.pitch.get_hz(),
+            row.midi_note,
+            row.cents
+        )?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Print one line per entry of the registry the command lists, sorted by name.
+ */
+pub fn list(command: ListCommand, out: &mut impl Write) -> Result<()> {
+    let mut entries: Vec<(&str, String)> = match command {
+        ListCommand::Scales => musical_notation::ScaleKind::ALL
+            .iter()
+            .map(|scale_kind| (scale_kind.name(), format!("{} tones", scale_kind.get_degrees_in_scale())))
+            .collect(),
+        ListCommand::Temperaments => musical_notation::TEMPERAMENTS
+            .iter()
+            .map(|(name, description)| (*name, description.to_string()))
+            .collect(),
+        ListCommand::Instruments => Instrument::ALL
+            .iter()
+            .map(|instrument| (instrument.name(), instrument.description().to_string()))
+            .collect(),
+        ListCommand::PitchStandards => musical_notation::PITCH_STANDARDS
+            .iter()
+            .map(|(name, hz)| (*name, format!("{:.1} Hz", hz)))
+            .collect(),
+    };
+    entries.sort();
+
+    for (name, description) in entries {
+        writeln!(out, "{:<18}  {}", name, description)?;
+    }
+
+    Ok(())
+}
+
+fn format_musical_element(musical_element: &MusicalElement) -> String {
+    match musical_element {
+        MusicalElement::Rest { duration } => format!("rest {}", duration),
+        MusicalElement::Note { pitch, duration, volume, .. } => {
+            format!("note {} {:.3} Hz {}", duration, pitch.get_hz(), volume)
+        }
+        MusicalElement::TimedNote { pitch, seconds, volume } => {
+            format!("timed-note {:.3} s {:.3} Hz {}", seconds, pitch.get_hz(), volume)
+        }
+    }
+}
+
+/**
+ * How a voice sounds in the rendered mix.
+ */
+#[derive(Clone)]
+struct Sound {
+    instrument: Instrument,
+    pan: f64,
+    gain: f64,
+    /// plays the wavetable instead of the instrument
+    wavetable: Option<Arc<WaveTable>>,
+    /// the vibrato is only applied to the instrument, the detune to the wavetable as well
+    modulation: PitchModulation,
+    /// the seed of the random detune
+    seed: u64,
+    /// where the notes are placed around the pan
+    spatialize: SpatializeMode,
+}
+
+/**
+ * Whether the output path is '-', which streams the rendered samples to stdout.
+ */
+fn is_stdout(output: &Path) -> bool {
+    output.to_str() == Some("-")
+}
+
+/**
+ * Save the wave as a WAV file or, for the output path '-', write its samples to stdout
+ * as interleaved little-endian f32 PCM.
+ */
+fn save_wave(wave: &Wave64, dest_path: &Path) -> Result<()> {
+    if is_stdout(dest_path) {
+        let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+        write_pcm_f32(wave, &mut out)?;
+        out.flush()?;
+    } else {
+        wave.save_wav16(dest_path)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Render the voice alone or, with an echo, as a two voice score of the voice and its echo.
+ * The accents are applied before the echo copies the voice, the loop is marked after saving.
+ */
+fn sequence_helper(
+    voice: Voice,
+    dest_path: PathBuf,
+    shared: &SharedArgs,
+    args: &RenderArgs,
+    sound: Sound,
+    progress: &dyn Progress,
+) -> Result<()> {
+    let voice = match args.accent.len() {
+        0 => voice,
+        beats => voice.apply_accents(TimeSignature::new(u8::try_from(beats).unwrap_or(u8::MAX), 4), &args.accent),
+    };
+
+    let voices = match args.echo {
+        Some(echo) => {
+            let canon = voice.canon(echo.delay_units, echo.transpose_semitones, echo.volume_scale);
+            vec![(voice, sound.clone()), (canon, sound)]
+        }
+        None => vec![(voice, sound)],
+    };
+    let sample_rate = 44100.0;
+    sequence_voices(&voices, &dest_path, volume_curve(&args.volume_curve), shared.bpm, sample_rate, progress, !args.no_clip_guard)?;
+
+    if let (Some(loop_start), Some(loop_end)) = (args.loop_start, args.loop_end) {
+        let samples = |units: u16| (musical_notation::Duration::time_units(units).to_seconds(shared.bpm, BEATS_PER_WHOLE) * sample_rate).round() as usize;
+        write_loop_points(&dest_path, sample_rate, samples(loop_start), samples(loop_end))?;
+    }
+
+    Ok(())
+}
+
+fn sequence_voices(
+    voices: &[(Voice, Sound)],
+    dest_path: &Path,
+    volume_curve: musical_notation::VolumeCurve,
+    bpm: u16,
+    sample_rate: f64,
+    progress: &dyn Progress,
+    clip_guard: bool,
+) -> Result<()> {
+    let mut sequencer = Sequencer::new(sample_rate, 2);
+
+    let env = || envelope(cos);
+
+    for (voice, sound) in voices {
+        for (index, pitch) in voice.audibility_report() {
+            eprintln!(
+                "warning: the note at position {} has the inaudible pitch {:.3} Hz",
+                index + 1,
+                pitch.get_hz()
+            );
+        }
+
+        let range = voice.pitch_range();
+        let magic = |context: &NoteContext| -> Box<dyn AudioUnit64> {
+            let pan_position = sound.spatialize.pan_of(sound.pan, context.pitch, range);
+            let gain = 200.0 * sound.gain * volume_curve.gain(context.volume);
+            let detune_cents = sound.modulation.detune_of(sound.seed, context.index);
+            if let Some(wavetable) = &sound.wavetable {
+                let pitch = musical_notation::Pitch(context.pitch.get_hz() * 2f64.powf(detune_cents / 1200.0));
+                return Box::new((gain * An(WaveTableOscillator::new(pitch, wavetable)) * env()) >> pan(pan_position));
+            }
+            let frequency = || sound.modulation.sliding_frequency(context.slide_from, context.pitch, context.duration_secs, detune_cents);
+            match sound.instrument {
+                Instrument::Sine => Box::new((gain * (frequency() >> sine()) * env()) >> pan(pan_position)),
+                Instrument::Square => Box::new((gain * (frequency() >> square()) * env()) >> pan(pan_position)),
+                Instrument::Saw => Box::new((gain * (frequency() >> saw()) * env()) >> pan(pan_position)),
+                Instrument::Triangle => Box::new((gain * (frequency() >> triangle()) * env()) >> pan(pan_position)),
+            }
+        };
+
+        voice.sequence_with(&mut sequencer, bpm, with_context(magic));
+    }
+
+    let duration = voices
+        .iter()
+        .map(|(voice, _)| voice.get_duration(bpm))
+        .fold(0.0, f64::max);
+
+    let wave = render_with_progress(sample_rate, duration, &mut sequencer, progress);
+    // let wave = wave.filter(duration, &mut (reverb_stereo(0.1, 2.0) * 3.0));
+    let mut wave = wave.filter_latency(duration, &mut (limiter_stereo((0.01, 0.1))));
+    if clip_guard {
+        if let Some(factor) = guard_clipping(&mut wave) {
+            eprintln!("warning: the rendered audio exceeds full scale and was attenuated by a factor of {:.3}", factor);
+        }
+    }
+    save_wave(&wave, dest_path)?;
+
+    Ok(())
+    /*
+    let sample_rate = 44100.0;
+    let env = || envelope(|t| cos(t));
+    // let test = |pitch: f64| 200.0_f64 * sine_hz(pitch) * env();
+    // let test = |pitch: f64| test(pitch) >> pan(0.0);
+    let test = |pitch: f64| brown() * pitch >> sine() >> pan(0.0);
+    let mut sequencer = Sequencer::new(sample_rate, 2);
+    let duration = length as f64 / bpm_hz(bpm) + 2.0;
+    let wave = Wave64::render(sample_rate, duration, &mut sequencer);
+    let wave = wave.filter(duration, &mut (reverb_stereo(0.1, 2.0) * 3.0));
+    let wave = wave.filter_latency(duration, &mut (limiter_stereo((0.01, 0.1))));
+    wave.save_wav16(std::path::Path::new("sequence.wav")).unwrap()
+    */
+}
+
+/**
+ * Parse the axiom and apply the rules the given number of times,
+ * failing if the axiom would grow beyond the maximum number of atoms.
+ */
+fn expand_axiom(args: &VoiceArgs, progress: &dyn Progress) -> Result<Axiom> {
+    let axiom = match (&args.axiom, &args.axiom_file) {
+        (_, Some(path)) => Axiom::from_file(path)?,
+        (Some(axiom), None) => Axiom::from(axiom)?,
+        (None, None) => return Err(anyhow!("Please provide an axiom.")),
+    };
+
+    let ruleset = match &args.rules_file {
+        Some(path) => RuleSet::from_file(path)?,
+        None if args.rules.is_empty() => return Ok(axiom),
+        None => args.rules.join("\n").parse::<RuleSet>()?,
+    };
+
+    Ok(ruleset.expand_bounded_with_progress(&axiom, args.iterations, args.max_atoms, progress)?)
+}
+
+/**
+ * Create the key of the scale tonic using the chosen temperament and pitch standard.
+ */
+fn create_key(
+    shared: &SharedArgs,
+    tonic: musical_notation::Tone,
+) -> Result<musical_notation::Key<musical_notation::EqualTemperament>> {
+    match shared.temperament_kind {
+        TemperamentKind::EqualTemperament => Ok(musical_notation::Key::from_tonic(
+            tonic,
+            Arc::new(musical_notation::EqualTemperament::new(pitch_standard(shared))),
+        )),
+        TemperamentKind::JustIntonation => {
+            Err(anyhow!("The temperament 'just-intonation' is not supported yet."))
+        }
+    }
+}
+
+fn pitch_standard(shared: &SharedArgs) -> f64 {
+    match shared.pitch_standard {
+        PitchStandard::Baroque => musical_notation::BAROQUE_PITCH,
+        PitchStandard::Chorton => musical_notation::CHORTON_PITCH,
+        PitchStandard::Classical => musical_notation::CLASSICAL_PITCH,
+        PitchStandard::Stuttgart => musical_notation::STUTTGART_PITCH,
+    }
+}
+
+/**
+ * Fold the notes of the voice into the range given with --fit-range, whose tones are
+ * equal tempered at the pitch standard.
+ */
+fn fit_range(shared: &SharedArgs, args: &VoiceArgs, voice: Voice) -> Voice {
+    match args.fit_range {
+        Some(FitRange { low, high }) => {
+            voice.fold_into_range(equal_tempered_pitch(shared, low), equal_tempered_pitch(shared, high))
+        }
+        None => voice,
+    }
+}
+
+/**
+ * The pitch of the tone in the octave, equal tempered at the pitch standard.
+ */
+fn equal_tempered_pitch(shared: &SharedArgs, (tone, octave): (musical_notation::Tone, i16)) -> musical_notation::Pitch {
+    musical_notation::EqualTemperament::new(pitch_standard(shared)).get_pitch(octave, tone.get_position_in_octave()).unwrap()
+}
+
+fn scale_tonic(shared: &SharedArgs) -> musical_notation::Tone {
+    musical_notation::Tone::new(*shared.scale_tonic.0, *shared.scale_tonic.1)
+}
+
+/**
+ * Load the voice given with --load-voice or generate it from the expanded axiom.
+ */
+fn generate_voice(shared: &SharedArgs, args: &VoiceArgs, progress: &dyn Progress) -> Result<Voice> {
+    if let Some(path) = &args.load_voice {
+        return Ok(fit_range(shared, args, Voice::load_json(path)?));
+    }
+
+    voice_in_key(shared, args, &expand_axiom(args, progress)?, scale_tonic(shared), progress)
+}
+
+/**
+ * Generate the voice of the expanded axiom in the key built on the given tonic.
+ */
+fn voice_in_key(
+    shared: &SharedArgs,
+    args: &VoiceArgs,
+    axiom: &Axiom,
+    tonic: musical_notation::Tone,
+    progress: &dyn Progress,
+) -> Result<Voice> {
+    let atom_types = atom_types_in_key(shared, args, axiom, tonic)?;
+
+    Ok(fit_range(shared, args, Voice::from_with_progress(axiom, atom_types, progress)?))
+}
+
+/**
+ * Generate the voice like `generate_voice` along with the origin of every element.
+ * A loaded voice has no origins.
+ */
+fn generate_traced_voice(shared: &SharedArgs, args: &VoiceArgs) -> Result<(Voice, Vec<ElementOrigin>)> {
+    if let Some(path) = &args.load_voice {
+        return Ok((fit_range(shared, args, Voice::load_json(path)?), vec![]));
+    }
+
+    let axiom = expand_axiom(args, &NoProgress)?;
+    let atom_types = atom_types_in_key(shared, args, &axiom, scale_tonic(shared))?;
+    let (voice, origins) = Voice::from_traced(&axiom, atom_types)?;
+
+    Ok((fit_range(shared, args, voice), origins))
+}
+
+/**
+ * Map the atoms of the axiom with the mapping of the args, the notes using a SimpleAction
+ * in the key built on the given tonic or, with --action overtone, an OvertoneAction.
+ */
+fn atom_types_in_key<'a>(
+    shared: &SharedArgs,
+    args: &VoiceArgs,
+    axiom: &'a Axiom,
+    tonic: musical_notation::Tone,
+) -> Result<HashMap<&'a Atom, AtomType<MusicalState>>> {
+    let action: Arc<dyn Action<MusicalState> + Send + Sync> = match args.action {
+        NoteActionKind::Simple => {
+            let mut simple_action = SimpleAction::new(create_key(shared, tonic)?, &shared.scale_kind);
+            simple_action.set_pitch_ceiling(shared.pitch_ceiling.map(musical_notation::Pitch));
+            Arc::new(simple_action)
+        }
+        NoteActionKind::Overtone => {
+            let fundamental = args.fundamental.unwrap_or((musical_notation::Tone::new(musical_notation::Note::C, musical_notation::Accidental::Natural), 2));
+            let mut overtone_action = OvertoneAction::new(equal_tempered_pitch(shared, fundamental));
+            overtone_action.set_rolloff(args.rolloff);
+            Arc::new(overtone_action)
+        }
+    };
+
+    let action_map = match &args.mapping {
+        Some(path) => ActionMap::from_toml(path)?,
+        None => ActionMap::new(),
+    };
+
+    Ok(action_map.atom_types(axiom, action))
+}
+
+/**
+ * Prints the progress of the stages to stderr. On a terminal a single line is
+ * updated in place, otherwise a line is printed when a stage is finished and
+ * every few seconds in between, so that logs aren't flooded.
+ */
+struct TerminalProgress {
+    is_terminal: bool,
+    last_report: Cell<Instant>,
+}
+
+impl TerminalProgress {
+    fn new() -> TerminalProgress {
+        TerminalProgress { is_terminal: std::io::stderr().is_terminal(), last_report: Cell::new(Instant::now()) }
+    }
+}
+
+impl Progress for TerminalProgress {
+    fn on_stage(&self, stage: Stage, done: u64, total: u64) {
+        let interval = match self.is_terminal {
+            true => std::time::Duration::from_millis(100),
+            false => std::time::Duration::from_secs(5),
+        };
+        let finished = done == total;
+
+        let due = self.last_report.get().elapsed() >= interval;
+        // on a terminal the line shows the running stage as soon as it starts
+        let starting = self.is_terminal && done == 0;
+
+        if !(finished || starting || due) {
+            return;
+        }
+        self.last_report.set(Instant::now());
+
+        let percent = match total {
+            0 => 100.0,
+            _ => 100.0 * done as f64 / total as f64,
+        };
+        match (self.is_terminal, finished) {
+            (true, false) => eprint!("\r\x1b[2K{}: {}/{} ({:.0}%)", stage, done, total, percent),
+            (true, true) => eprintln!("\r\x1b[2K{}: {}/{} (100%)", stage, done, total),
+            (false, _) => eprintln!("{}: {}/{} ({:.0}%)", stage, done, total, percent),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, export, DEFAULT_MAX_ATOMS, inspect_dry_run, inspect_pitch_table, inspect_stats, list, parse_echo, parse_fit_range, parse_pan_width, parse_tonic, musical_notation, Echo, ExportArgs, FitRange, ExportFormat, ListCommand, NoteActionKind, PitchStandard, PitchTableArgs, SharedArgs, TemperamentKind, VoiceArgs};
+    use music_generator::musical_notation::{PITCH_STANDARDS, TEMPERAMENTS};
+    use super::RenderMetadata;
+
+    fn voice_args(axiom: &str, rules: &[&str], iterations: usize) -> VoiceArgs {
+        VoiceArgs {
+            axiom: Some(axiom.to_string()),
+            axiom_file: None,
+            rules: rules.iter().map(|rule| rule.to_string()).collect(),
+            rules_file: None,
+            iterations,
+            max_atoms: DEFAULT_MAX_ATOMS,
+            mapping: None,
+            fit_range: None,
+            action: NoteActionKind::Simple,
+            fundamental: None,
+            rolloff: false,
+            load_voice: None,
+        }
+    }
+
+    fn output_of(command: impl FnOnce(&mut Vec<u8>) -> anyhow::Result<()>) -> String {
+        let mut out = vec![];
+        command(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn expand_test() {
+        let args = voice_args("A", &["A->AB", "B->A"], 3);
+        assert_eq!(output_of(|out| expand(&args, out)), "ABAAB\n");
+
+        let args = voice_args("AB", &[], 3);
+        assert_eq!(output_of(|out| expand(&args, out)), "AB\n");
+
+        assert!(expand(&VoiceArgs::default(), &mut vec![]).is_err());
+
+        let mut args = voice_args("A", &["A->AA"], 3);
+        args.max_atoms = 8;
+        assert_eq!(output_of(|out| expand(&args, out)), "AAAAAAAA\n");
+        args.max_atoms = 7;
+        assert_eq!(
+            expand(&args, &mut vec![]).unwrap_err().to_string(),
+            "Expanding the Axiom would produce 8 atoms, more than the maximum of 7."
+        );
+    }
+
+    #[test]
+    fn parse_pan_width_test() {
+        assert_eq!(parse_pan_width("0"), Ok(0.0));
+        assert_eq!(parse_pan_width(" 1.5 "), Ok(1.5));
+        assert_eq!(parse_pan_width("2"), Ok(2.0));
+        for invalid in ["-0.1", "2.1", "NaN", "inf", "x", ""] {
+            assert!(parse_pan_width(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parse_echo_test() {
+        assert_eq!(parse_echo("8,-12,0.5"), Ok(Echo { delay_units: 8, transpose_semitones: -12, volume_scale: 0.5 }));
+        assert_eq!(parse_echo(" 16, 7, 1 "), Ok(Echo { delay_units: 16, transpose_semitones: 7, volume_scale: 1.0 }));
+        for invalid in ["8,-12", "8,-12,0.5,1", "-8,0,1", "8,x,1", ""] {
+            assert!(parse_echo(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parse_fit_range_test() {
+        let c = musical_notation::Tone::new(musical_notation::Note::C, musical_notation::Accidental::Natural);
+        let a = musical_notation::Tone::new(musical_notation::Note::A, musical_notation::Accidental::Natural);
+        let f_sharp = musical_notation::Tone::new(musical_notation::Note::F, musical_notation::Accidental::Sharp);
+        assert_eq!(parse_fit_range("A1..C7"), Ok(FitRange { low: (a, 1), high: (c, 7) }));
+        assert_eq!(parse_fit_range(" C-1 .. F#4 "), Ok(FitRange { low: (c, -1), high: (f_sharp, 4) }));
+        for invalid in ["A1", "A1..", "A..C7", "H1..C7", "C7..A1", "C4..C4", ""] {
+            assert!(parse_fit_range(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn fit_range_test() {
+        let mut args = voice_args("Aw", &[], 0);
+        assert_eq!(output_of(|out| inspect_stats(&SharedArgs::default(), &args, out)).lines().last(), Some("range: Pitch(261.626) - Pitch(31608.531)"));
+
+        args.fit_range = Some(parse_fit_range("A1..C7").unwrap());
+        assert_eq!(output_of(|out| inspect_stats(&SharedArgs::default(), &args, out)).lines().last(), Some("range: Pitch(261.626) - Pitch(1975.533)"));
+    }
+
+    #[test]
+    fn inspect_stats_test() {
+        let shared = SharedArgs {
+            bpm: 60,
+            ..Default::default()
+        };
+        let args = voice_args("A", &["A->ABx"], 1);
+
+        assert_eq!(
+            output_of(|out| inspect_stats(&shared, &args, out)),
+            "atoms: 3\n\
+             notes: 2\n\
+             rests: 1\n\
+             duration: 3/8 (3.00 s)\n\
+             pitch classes: {0,2}\n\
+             interval vector: <010000>\n\
+             range: Pitch(261.626) - Pitch(293.665)\n"
+        );
+    }
+
+    #[test]
+    fn inspect_dry_run_test() {
+        let shared = SharedArgs {
+            bpm: 60,
+            ..Default::default()
+        };
+        let args = voice_args("AxB", &[], 0);
+
+        assert_eq!(
+            output_of(|out| inspect_dry_run(&shared, &args, out)),
+            "start (s)  duration  atom    element\n    \
+                 0.000  1/8       0 A     note 1/8 261.626 Hz m\n    \
+                 1.000  1/8       1 x     rest 1/8\n    \
+                 2.000  1/8       2 B     note 1/8 293.665 Hz m\n"
+        );
+    }
+
+    #[test]
+    fn inspect_dry_run_overtone_test() {
+        let shared = SharedArgs {
+            bpm: 60,
+            ..Default::default()
+        };
+        let mut args = voice_args("ABC", &[], 0);
+        args.action = NoteActionKind::Overtone;
+        args.fundamental = Some(("A".parse().unwrap(), 2));
+
+        assert_eq!(
+            output_of(|out| inspect_dry_run(&shared, &args, out)),
+            "start (s)  duration  atom    element\n    \
+                 0.000  1/8       0 A     note 1/8 110.000 Hz m\n    \
+                 1.000  1/8       1 B     note 1/8 220.000 Hz m\n    \
+                 2.000  1/8       2 C     note 1/8 330.000 Hz m\n"
+        );
+    }
+
+    #[test]
+    fn inspect_pitch_table_test() {
+        let shared = SharedArgs {
+            scale_tonic: parse_tonic("D").unwrap(),
+            ..Default::default()
+        };
+        let args = PitchTableArgs {
+            lowest_octave: 4,
+            highest_octave: 4,
+        };
+
+        assert_eq!(
+            output_of(|out| inspect_pitch_table(&shared, &args, out)),
+            "tone  octave          hz  midi    cents\n\
+             D          4     293.665    62    +0.00\n\
+             E          4     329.628    64    +0.00\n\
+             F#         4     369.994    66    +0.00\n\
+             G          4     391.995    67    +0.00\n\
+             A          4     440.000    69    +0.00\n\
+             B          4     493.883    71    +0.00\n\
+             C#         5     554.365    73    +0.00\n"
+        );
+    }
+
+    #[test]
+    fn list_test() {
+        assert_eq!(
+            output_of(|out| list(ListCommand::PitchStandards, out)),
+            "baroque             415.0 Hz\n\
+             chorton             466.0 Hz\n\
+             classical           429.5 Hz\n\
+             stuttgart           440.0 Hz\n"
+        );
+        assert_eq!(
+            output_of(|out| list(ListCommand::Scales, out)),
+            "blues               6 tones\n\
+             chromatic           12 tones\n\
+             full-blues          9 tones\n\
+             major               7 tones\n\
+             minor               7 tones\n\
+             relative-minor      7 tones\n"
+        );
+        assert_eq!(output_of(|out| list(ListCommand::Instruments, out)).lines().count(), 4);
+        assert_eq!(output_of(|out| list(ListCommand::Temperaments, out)).lines().count(), 2);
+    }
+
+    #[test]
+    fn registries_cover_command_line_values() {
+        // a new pitch standard or temperament has to be added here and to its registry
+        for pitch_standard in [PitchStandard::Baroque, PitchStandard::Chorton, PitchStandard::Classical, PitchStandard::Stuttgart] {
+            let name = match pitch_standard {
+                PitchStandard::Baroque => "baroque",
+                PitchStandard::Chorton => "chorton",
+                PitchStandard::Classical => "classical",
+                PitchStandard::Stuttgart => "stuttgart",
+            };
+            assert!(PITCH_STANDARDS.iter().any(|(other, _)| *other == name));
+        }
+        assert_eq!(PITCH_STANDARDS.len(), 4);
+
+        for temperament_kind in [TemperamentKind::EqualTemperament, TemperamentKind::JustIntonation] {
+            let name = match temperament_kind {
+                TemperamentKind::EqualTemperament => "equal-temperament",
+                TemperamentKind::JustIntonation => "just-intonation",
+            };
+            assert!(TEMPERAMENTS.iter().any(|(other, _)| *other == name));
+        }
+        assert_eq!(TEMPERAMENTS.len(), 2);
+    }
+
+    #[test]
+    fn export_text_test() {
+        let args = ExportArgs {
+            voice: voice_args("Ax", &[], 0),
+            format: ExportFormat::Text,
+            output: None,
+        };
+
+        assert_eq!(
+            output_of(|out| export(&SharedArgs::default(), &args, out)),
+            "note 1/8 261.626 Hz m\nrest 1/8\n"
+        );
+    }
+
+    #[test]
+    fn render_metadata_test() {
+        let shared = SharedArgs {
+            scale_tonic: (&musical_notation::Note::F, &musical_notation::Accidental::Sharp),
+            scale_kind: musical_notation::ScaleKind::Minor,
+            pitch_standard: PitchStandard::Baroque,
+            bpm: 90,
+            ..Default::default()
+        };
+        let metadata = RenderMetadata::new(&shared, &voice_args("A", &["A->AB"], 2));
+
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert_eq!(
+            json,
+            "{\"axiom\":\"A\",\"rules\":[\"A->AB\"],\"iterations\":2,\"scale_tonic\":\"F#\",\
+             \"scale_kind\":\"minor\",\"temperament_kind\":\"equal-temperament\",\
+             \"pitch_standard\":\"baroque\",\"bpm\":90,\"seed\":null}"
+        );
+        assert_eq!(serde_json::from_str::<RenderMetadata>(&json).unwrap(), metadata);
+
+        assert_eq!(
+            RenderMetadata::path_for(std::path::Path::new("out/voice.wav")),
+            std::path::PathBuf::from("out/voice.wav.json")
+        );
+    }
+}
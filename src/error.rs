@@ -0,0 +1,128 @@
+/* This module contains the error type
+ * shared by the whole crate. It wraps
+ * the specific error types of the modules,
+ * which stay available for matching.
+ */
+
+use std::fmt;
+
+pub use crate::l_system::error::{LSystemOverflowError, RepresentationError};
+pub use crate::musical_notation::{
+    IntervalPatternError, KeyCreationError, ScaleKindParseError, ToneParseError, VolumeParseError,
+};
+#[cfg(feature = "serde")]
+pub use crate::project::error::ProjectError;
+pub use crate::synthesis::error::WaveTableError;
+pub use crate::voice::action::action_map::error::ActionMapError;
+pub use crate::voice::action::error::ActionError;
+pub use crate::voice::action::simple_action::error::{MappingError, PitchError, RangeError};
+pub use crate::voice::action::zip_rhythm_action::error::RhythmError;
+
+/**
+ * The error returned by the public constructors of this crate.
+ * Display and `source` are forwarded to the wrapped error.
+ */
+#[derive(Debug)]
+pub enum Error {
+    Representation(RepresentationError),
+    LSystemOverflow(LSystemOverflowError),
+    Action(ActionError),
+    ActionMap(ActionMapError),
+    KeyCreation(KeyCreationError),
+    Mapping(MappingError),
+    Pitch(PitchError),
+    Range(RangeError),
+    VolumeParse(VolumeParseError),
+    ToneParse(ToneParseError),
+    ScaleKindParse(ScaleKindParseError),
+    IntervalPattern(IntervalPatternError),
+    #[cfg(feature = "serde")]
+    Project(ProjectError),
+    WaveTable(WaveTableError),
+    Rhythm(RhythmError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    fn inner(&self) -> &(dyn std::error::Error + 'static) {
+        match self {
+            Error::Representation(error) => error,
+            Error::LSystemOverflow(error) => error,
+            Error::Action(error) => error,
+            Error::ActionMap(error) => error,
+            Error::KeyCreation(error) => error,
+            Error::Mapping(error) => error,
+            Error::Pitch(error) => error,
+            Error::Range(error) => error,
+            Error::VolumeParse(error) => error,
+            Error::ToneParse(error) => error,
+            Error::ScaleKindParse(error) => error,
+            Error::IntervalPattern(error) => error,
+            #[cfg(feature = "serde")]
+            Error::Project(error) => error,
+            Error::WaveTable(error) => error,
+            Error::Rhythm(error) => error,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.inner())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner().source()
+    }
+}
+
+macro_rules! impl_from {
+    ($($variant:ident($error:ty)),*) => {
+        $(
+            impl From<$error> for Error {
+                fn from(error: $error) -> Self {
+                    Error::$variant(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(
+    Representation(RepresentationError),
+    LSystemOverflow(LSystemOverflowError),
+    Action(ActionError),
+    ActionMap(ActionMapError),
+    KeyCreation(KeyCreationError),
+    Mapping(MappingError),
+    Pitch(PitchError),
+    Range(RangeError),
+    VolumeParse(VolumeParseError),
+    ToneParse(ToneParseError),
+    ScaleKindParse(ScaleKindParseError),
+    IntervalPattern(IntervalPatternError),
+    WaveTable(WaveTableError),
+    Rhythm(RhythmError)
+);
+
+#[cfg(feature = "serde")]
+impl_from!(Project(ProjectError));
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, RepresentationError};
+
+    #[test]
+    fn display_test() {
+        let error: Error = RepresentationError::new("Axiom is empty").into();
+
+        assert!(matches!(error, Error::Representation(_)));
+        assert_eq!(
+            format!("{}", error),
+            "There was an Error with the Representation of an L-System Element: Axiom is empty."
+        );
+    }
+}
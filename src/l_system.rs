@@ -13,6 +13,15 @@ pub mod error {
                 message: message.to_string(),
             }
         }
+
+        /**
+         * Add the clause of a program, in which this error occurred, to the message.
+         */
+        pub(super) fn in_clause(self, clause: &str) -> RepresentationError {
+            RepresentationError {
+                message: format!("{} in the clause '{}'", self.message, clause),
+            }
+        }
     }
 
     impl fmt::Display for RepresentationError {
@@ -27,30 +36,190 @@ pub mod error {
 
     impl Error for RepresentationError {}
 
-    impl From<RepresentationError> for String {
-        fn from(error: RepresentationError) -> Self {
-            format!("{}", error)
+    /**
+     * The error returned when expanding an Axiom would exceed the maximum number of atoms.
+     */
+    #[derive(Debug)]
+    pub struct LSystemOverflowError {
+        atoms: usize,
+        max_atoms: usize,
+    }
+
+    impl LSystemOverflowError {
+        pub fn new(atoms: usize, max_atoms: usize) -> LSystemOverflowError {
+            LSystemOverflowError { atoms, max_atoms }
+        }
+
+        /**
+         * The number of atoms the expansion would have produced.
+         */
+        pub fn atoms(&self) -> usize {
+            self.atoms
+        }
+
+        pub fn max_atoms(&self) -> usize {
+            self.max_atoms
         }
     }
+
+    impl fmt::Display for LSystemOverflowError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "Expanding the Axiom would produce {} atoms, more than the maximum of {}.",
+                self.atoms, self.max_atoms
+            )
+        }
+    }
+
+    impl Error for LSystemOverflowError {}
 }
 
-use error::RepresentationError;
+use crate::progress::{NoProgress, Progress, Stage};
+use error::{LSystemOverflowError, RepresentationError};
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::BitOr;
+use std::path::Path;
+use std::str::FromStr;
 
 // #--- Atom ---#
 
+/**
+ * The longest name of a multi-character atom in bytes.
+ */
+pub const MAX_TOKEN_LENGTH: usize = 15;
+
+/**
+ * The number of atoms an expanded Axiom may have unless another maximum is given.
+ */
+pub const DEFAULT_MAX_ATOMS: usize = 100_000;
+
+/**
+ * The name of a multi-character atom, written as `{name}` in an axiom or a rule.
+ * It's stored inline, so that atoms stay `Copy`.
+ */
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Token {
+    bytes: [u8; MAX_TOKEN_LENGTH],
+    length: u8,
+}
+
+impl Token {
+    pub fn new(name: &str) -> Result<Token, RepresentationError> {
+        if name.is_empty() {
+            return Err(RepresentationError::new("Token is empty"));
+        }
+        if name.len() > MAX_TOKEN_LENGTH {
+            return Err(RepresentationError::new(&format!(
+                "Token '{}' is longer than {} bytes",
+                name, MAX_TOKEN_LENGTH
+            )));
+        }
+        if name.contains(['{', '}']) {
+            return Err(RepresentationError::new(&format!(
+                "Token '{}' contains a brace",
+                name
+            )));
+        }
+
+        let mut bytes = [0; MAX_TOKEN_LENGTH];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+
+        Ok(Token {
+            bytes,
+            length: name.len() as u8,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        // the bytes are copied from a str in `new`
+        std::str::from_utf8(&self.bytes[..self.length as usize]).unwrap()
+    }
+}
+
+/**
+ * What an Atom stands for: either a single character or a multi-character token.
+ * Both are displayed as they are written, the token in braces.
+ */
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub enum Symbol {
+    Char(char),
+    Token(Token),
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Symbol::Char(character) => write!(f, "{}", character),
+            Symbol::Token(token) => write!(f, "{{{}}}", token.as_str()),
+        }
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self)
+    }
+}
+
+impl From<char> for Symbol {
+    fn from(character: char) -> Symbol {
+        Symbol::Char(character)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> String {
+        symbol.to_string()
+    }
+}
+
+impl TryFrom<String> for Symbol {
+    type Error = RepresentationError;
+
+    fn try_from(string_representation: String) -> Result<Symbol, RepresentationError> {
+        string_representation.parse()
+    }
+}
+
+/**
+ * Parse a single character or a token in braces, e.g. `F` or `{turn}`.
+ */
+impl FromStr for Symbol {
+    type Err = RepresentationError;
+
+    fn from_str(string_representation: &str) -> Result<Symbol, RepresentationError> {
+        Ok(Atom::from_string(string_representation)?.symbol)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Atom {
-    pub symbol: char,
+    pub symbol: Symbol,
 }
 
 impl Atom {
     fn from_string(string_representation: &str) -> Result<Atom, RepresentationError> {
+        if let Some(name) = string_representation
+            .strip_prefix('{')
+            .and_then(|rest| rest.strip_suffix('}'))
+        {
+            return Ok(Atom {
+                symbol: Symbol::Token(Token::new(name)?),
+            });
+        }
+
         let mut i = string_representation.chars();
 
         if let Some(first) = i.next() {
-            if None != i.next() {
+            if i.next().is_some() {
                 Err(RepresentationError::new(
                     "Atom contains more that one character",
                 ))
@@ -64,7 +233,7 @@ impl Atom {
 
     fn from_char(char_representation: char) -> Atom {
         Atom {
-            symbol: char_representation,
+            symbol: Symbol::Char(char_representation),
         }
     }
 }
@@ -77,24 +246,76 @@ impl fmt::Debug for Atom {
 
 // #--- Axiom ---#
 
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Axiom {
     pub atom_list: Vec<Atom>,
 }
 
 impl Axiom {
-    pub fn from(string_representation: &str) -> Result<Axiom, RepresentationError> {
+    pub fn from(string_representation: &str) -> crate::Result<Axiom> {
+        Ok(Axiom::parse(string_representation)?)
+    }
+
+    /**
+     * Read an Axiom from the first line of the file at the given path that is neither
+     * empty nor a comment starting with a '#'. Surrounding whitespace is removed.
+     */
+    pub fn from_file(path: &Path) -> crate::Result<Axiom> {
+        let string_representation = std::fs::read_to_string(path).map_err(|e| {
+            RepresentationError::new(&format!(
+                "Axiom file '{}' couldn't be read: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        match string_representation
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            Some(line) => Axiom::from(line),
+            None => Err(RepresentationError::new(&format!(
+                "Axiom file '{}' doesn't contain an axiom",
+                path.display()
+            ))
+            .into()),
+        }
+    }
+
+    fn parse(string_representation: &str) -> Result<Axiom, RepresentationError> {
         if string_representation.is_empty() {
             return Err(RepresentationError::new("Axiom is empty"));
         }
 
-        let iter = string_representation.chars();
+        let mut iter = string_representation.chars();
         let mut axiom = Axiom { atom_list: vec![] };
 
-        for character in iter {
-            axiom.atom_list.push(Atom::from_char(character));
+        // a '{' starts a token lasting until the next '}', every other character is an atom
+        while let Some(character) = iter.next() {
+            match character {
+                '{' => {
+                    let rest = iter.as_str();
+                    let end = rest.find('}').ok_or_else(|| {
+                        RepresentationError::new("Axiom contains a '{' without a '}'")
+                    })?;
+
+                    axiom.atom_list.push(Atom {
+                        symbol: Symbol::Token(Token::new(&rest[..end])?),
+                    });
+                    iter = rest[end + 1..].chars();
+                }
+                '}' => {
+                    return Err(RepresentationError::new(
+                        "Axiom contains a '}' without a '{'",
+                    ))
+                }
+                _ => axiom.atom_list.push(Atom::from_char(character)),
+            }
         }
 
-        return Ok(axiom);
+        Ok(axiom)
     }
 
     pub fn apply(&mut self, rule: &Rule) {
@@ -114,10 +335,18 @@ impl Axiom {
     }
 
     pub fn apply_ruleset(&mut self, ruleset: &RuleSet) {
+        self.atom_list = self.apply_ruleset_new(ruleset).atom_list;
+    }
+
+    /**
+     * Apply the ruleset like `apply_ruleset`, but return the result
+     * as a new Axiom and leave this one unchanged.
+     */
+    pub fn apply_ruleset_new(&self, ruleset: &RuleSet) -> Axiom {
         let mut new_atom_list: Vec<Atom> = vec![];
 
         for atom in &self.atom_list {
-            match ruleset.rules.get(&atom) {
+            match ruleset.rules.get(atom) {
                 Some(axiom) => {
                     for atom in &axiom.atom_list {
                         new_atom_list.push(*atom);
@@ -127,10 +356,51 @@ impl Axiom {
             };
         }
 
-        self.atom_list = new_atom_list;
+        Axiom {
+            atom_list: new_atom_list,
+        }
+    }
+
+    /**
+     * Apply the ruleset like `apply_ruleset`, unless the result would have more than
+     * max_atoms atoms. In that case this axiom is left unchanged and the error tells
+     * how many atoms the result would have had.
+     */
+    pub fn apply_ruleset_bounded(
+        &mut self,
+        ruleset: &RuleSet,
+        max_atoms: usize,
+    ) -> Result<(), LSystemOverflowError> {
+        let atoms = self.atom_list.iter().fold(0usize, |atoms, atom| {
+            atoms.saturating_add(ruleset.rules.get(atom).map_or(1, |rhs| rhs.atom_list.len()))
+        });
+
+        if atoms > max_atoms {
+            return Err(LSystemOverflowError::new(atoms, max_atoms));
+        }
+
+        self.apply_ruleset(ruleset);
+        Ok(())
     }
 
-    pub fn atoms(&self) -> std::slice::Iter<Atom> {
+    /**
+     * Apply the ruleset n times like `apply_ruleset_bounded`. The iterations before
+     * the first one that would exceed max_atoms atoms are kept.
+     */
+    pub fn apply_n_bounded(
+        &mut self,
+        ruleset: &RuleSet,
+        n: usize,
+        max_atoms: usize,
+    ) -> Result<(), LSystemOverflowError> {
+        for _ in 0..n {
+            self.apply_ruleset_bounded(ruleset, max_atoms)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn atoms(&self) -> std::slice::Iter<'_, Atom> {
         self.atom_list.iter()
     }
 }
@@ -138,30 +408,51 @@ impl Axiom {
 impl fmt::Debug for Axiom {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         for atom in &self.atom_list {
-            match write!(f, "{:?}", atom) {
-                Err(e) => return Err(e),
-                Ok(_) => {}
-            };
+            write!(f, "{:?}", atom)?;
         }
 
         Ok(())
     }
 }
 
+/**
+ * `&axiom | &ruleset` is a shorthand for `axiom.apply_ruleset_new(&ruleset)`.
+ */
+impl BitOr<&RuleSet> for &Axiom {
+    type Output = Axiom;
+
+    fn bitor(self, ruleset: &RuleSet) -> Axiom {
+        self.apply_ruleset_new(ruleset)
+    }
+}
+
+impl BitOr<&RuleSet> for Axiom {
+    type Output = Axiom;
+
+    fn bitor(self, ruleset: &RuleSet) -> Axiom {
+        self.apply_ruleset_new(ruleset)
+    }
+}
+
 // #--- Rule ---#
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rule {
     lhs: Atom,
     rhs: Axiom,
 }
 
 impl Rule {
-    pub fn from(string_representation: &str) -> Result<Rule, RepresentationError> {
+    pub fn from(string_representation: &str) -> crate::Result<Rule> {
+        Ok(Rule::parse(string_representation)?)
+    }
+
+    fn parse(string_representation: &str) -> Result<Rule, RepresentationError> {
         match string_representation.split_once("->") {
             None => Err(RepresentationError::new("Rule didn't contain a '->'")),
             Some((lhs_str, rhs_str)) => Ok(Rule {
                 lhs: Atom::from_string(lhs_str.trim())?,
-                rhs: Axiom::from(rhs_str.trim())?,
+                rhs: Axiom::parse(rhs_str.trim())?,
             }),
         }
     }
@@ -173,34 +464,220 @@ impl fmt::Debug for Rule {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RuleSet {
+    #[cfg_attr(feature = "serde", serde(with = "sorted_rules"))]
     rules: HashMap<Atom, Axiom>,
 }
 
 impl RuleSet {
-    pub fn from(rule_list: Vec<Rule>) -> Result<RuleSet, RepresentationError> {
+    pub fn from(rule_list: Vec<Rule>) -> crate::Result<RuleSet> {
+        Ok(RuleSet::from_rules(rule_list)?)
+    }
+
+    fn from_rules(rule_list: Vec<Rule>) -> Result<RuleSet, RepresentationError> {
         let mut rules: HashMap<Atom, Axiom> = HashMap::new();
 
         for rule in rule_list {
-            match rules.insert(rule.lhs, rule.rhs) {
-                Some(_) => {
-                    return Err(RepresentationError::new(&format!(
-                        "RuleSet contains two Rules with the lhs-Atom '{:?}'",
-                        &rule.lhs
-                    )));
+            if rules.insert(rule.lhs, rule.rhs).is_some() {
+                return Err(RepresentationError::new(&format!(
+                    "RuleSet contains two Rules with the lhs-Atom '{:?}'",
+                    &rule.lhs
+                )));
+            }
+        }
+
+        Ok(RuleSet { rules })
+    }
+
+    /**
+     * Apply this ruleset the given number of times to a copy of the axiom.
+     * The axiom itself is left unchanged.
+     */
+    pub fn expand(&self, axiom: &Axiom, iterations: usize) -> Axiom {
+        self.expand_with_progress(axiom, iterations, &NoProgress)
+    }
+
+    /**
+     * Expand the axiom like `expand`, reporting each finished iteration
+     * as the Expansion stage to the progress.
+     */
+    pub fn expand_with_progress(
+        &self,
+        axiom: &Axiom,
+        iterations: usize,
+        progress: &dyn Progress,
+    ) -> Axiom {
+        let mut expanded_axiom = axiom.clone();
+        progress.on_stage(Stage::Expansion, 0, iterations as u64);
+
+        for iteration in 0..iterations {
+            expanded_axiom = expanded_axiom.apply_ruleset_new(self);
+            progress.on_stage(Stage::Expansion, iteration as u64 + 1, iterations as u64);
+        }
+
+        expanded_axiom
+    }
+
+    /**
+     * Expand the axiom like `expand`, unless it would grow beyond max_atoms atoms.
+     */
+    pub fn expand_bounded(
+        &self,
+        axiom: &Axiom,
+        iterations: usize,
+        max_atoms: usize,
+    ) -> Result<Axiom, LSystemOverflowError> {
+        self.expand_bounded_with_progress(axiom, iterations, max_atoms, &NoProgress)
+    }
+
+    /**
+     * Expand the axiom like `expand_bounded`, reporting each finished iteration
+     * as the Expansion stage to the progress.
+     */
+    pub fn expand_bounded_with_progress(
+        &self,
+        axiom: &Axiom,
+        iterations: usize,
+        max_atoms: usize,
+        progress: &dyn Progress,
+    ) -> Result<Axiom, LSystemOverflowError> {
+        let mut expanded_axiom = axiom.clone();
+        progress.on_stage(Stage::Expansion, 0, iterations as u64);
+
+        for iteration in 0..iterations {
+            expanded_axiom.apply_ruleset_bounded(self, max_atoms)?;
+            progress.on_stage(Stage::Expansion, iteration as u64 + 1, iterations as u64);
+        }
+
+        Ok(expanded_axiom)
+    }
+
+    /**
+     * Describe the expansion of the axiom by this ruleset as a graph in the DOT language
+     * of Graphviz. Every atom of every generation up to the given depth is a node labeled
+     * with its symbol, and each atom has an edge to every atom it is replaced with.
+     * Atoms without a rule have their copy in the next generation as their only child.
+     */
+    pub fn to_dot(&self, start_axiom: &Axiom, depth: usize) -> String {
+        use std::fmt::Write;
+
+        let mut dot = String::from("digraph LSystem {\n");
+        // the ids and atoms of the current generation
+        let mut generation: Vec<(usize, Atom)> = vec![];
+        let mut next_id = 0;
+
+        for atom in start_axiom.atoms() {
+            writeln!(
+                dot,
+                "    n{} [label={:?}];",
+                next_id,
+                atom.symbol.to_string()
+            )
+            .unwrap();
+            generation.push((next_id, *atom));
+            next_id += 1;
+        }
+
+        for _ in 0..depth {
+            let mut next_generation: Vec<(usize, Atom)> = vec![];
+
+            for (parent_id, parent) in &generation {
+                let children = match self.rules.get(parent) {
+                    Some(axiom) => axiom.atom_list.clone(),
+                    None => vec![*parent],
+                };
+
+                for child in children {
+                    writeln!(
+                        dot,
+                        "    n{} [label={:?}];",
+                        next_id,
+                        child.symbol.to_string()
+                    )
+                    .unwrap();
+                    writeln!(dot, "    n{} -> n{};", parent_id, next_id).unwrap();
+                    next_generation.push((next_id, child));
+                    next_id += 1;
+                }
+            }
+
+            generation = next_generation;
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /**
+     * Read a RuleSet from the file at the given path.
+     * See `RuleSet::from_str` for the expected format.
+     */
+    pub fn from_file(path: &Path) -> Result<RuleSet, RepresentationError> {
+        match std::fs::read_to_string(path) {
+            Ok(string_representation) => string_representation.parse(),
+            Err(e) => Err(RepresentationError::new(&format!(
+                "RuleSet file '{}' couldn't be read: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+}
+
+impl FromStr for RuleSet {
+    type Err = RepresentationError;
+
+    /**
+     * Parse a RuleSet with one Rule like "A->ABA" per line, or several separated by commas
+     * like in "A->ABA, B->BAB", the form a RuleSet is displayed in. A comma only separates
+     * Rules if the text after it contains a '->', otherwise it is an Atom of the rhs.
+     * Empty lines and lines starting with a '#' are ignored.
+     */
+    fn from_str(string_representation: &str) -> Result<RuleSet, RepresentationError> {
+        let mut rule_list: Vec<Rule> = vec![];
+
+        for line in string_representation.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut rules_in_line: Vec<String> = vec![];
+            for part in line.split(',') {
+                match rules_in_line.last_mut() {
+                    Some(rule) if !part.contains("->") => {
+                        rule.push(',');
+                        rule.push_str(part);
+                    }
+                    _ => rules_in_line.push(part.to_string()),
                 }
-                None => {}
+            }
+
+            for rule in rules_in_line {
+                rule_list.push(Rule::parse(&rule)?);
             }
         }
 
-        return Ok(RuleSet { rules });
+        RuleSet::from_rules(rule_list)
+    }
+}
+
+/**
+ * Display the Rules sorted by their lhs and separated by commas,
+ * which `RuleSet::from_str` parses back into the same RuleSet.
+ */
+impl fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
     }
 }
 
 impl fmt::Debug for RuleSet {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         let mut set_of_rules: Vec<(&Atom, &Axiom)> = self.rules.iter().collect();
-        set_of_rules.sort_by(|(lhs_1, _), (lhs_2, _)| lhs_1.cmp(lhs_2));
+        set_of_rules.sort_by_key(|(lhs_1, _)| *lhs_1);
 
         write!(
             f,
@@ -214,12 +691,103 @@ impl fmt::Debug for RuleSet {
     }
 }
 
+// #--- Program ---#
+
+const AXIOM_PREFIX: &str = "axiom:";
+
+/**
+ * Parse an Axiom and a RuleSet from a single string of clauses separated by ';',
+ * e.g. "axiom: ABA; A->ABA; B->BAB". Exactly one clause has to start with
+ * "axiom:", all others are Rules. Empty clauses are ignored.
+ */
+pub fn parse_program(program: &str) -> Result<(Axiom, RuleSet), RepresentationError> {
+    let mut axiom: Option<Axiom> = None;
+    let mut rule_list: Vec<Rule> = vec![];
+
+    for clause in program.split(';').map(str::trim) {
+        if clause.is_empty() {
+            continue;
+        }
+
+        match clause.strip_prefix(AXIOM_PREFIX) {
+            Some(axiom_str) => {
+                if axiom.is_some() {
+                    return Err(RepresentationError::new(&format!(
+                        "Program contains a second axiom in the clause '{}'",
+                        clause
+                    )));
+                }
+
+                axiom = Some(Axiom::parse(axiom_str.trim()).map_err(|e| e.in_clause(clause))?);
+            }
+            None => rule_list.push(Rule::parse(clause).map_err(|e| e.in_clause(clause))?),
+        }
+    }
+
+    match axiom {
+        Some(axiom) => Ok((axiom, RuleSet::from_rules(rule_list)?)),
+        None => Err(RepresentationError::new(
+            "Program doesn't contain a clause starting with 'axiom:'",
+        )),
+    }
+}
+
+/**
+ * Load an Axiom and a RuleSet from two files, see `Axiom::from_file` and `RuleSet::from_file`.
+ */
+pub fn load_l_system(axiom_path: &Path, rules_path: &Path) -> crate::Result<(Axiom, RuleSet)> {
+    Ok((
+        Axiom::from_file(axiom_path)?,
+        RuleSet::from_file(rules_path)?,
+    ))
+}
+
+/**
+ * Serializes the rules of a RuleSet as a list of (lhs, rhs) pairs
+ * sorted by their lhs, so that the output is deterministic.
+ */
+#[cfg(feature = "serde")]
+mod sorted_rules {
+    use super::{Atom, Axiom};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        rules: &HashMap<Atom, Axiom>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut set_of_rules: Vec<(&Atom, &Axiom)> = rules.iter().collect();
+        set_of_rules.sort_by_key(|(lhs, _)| *lhs);
+        set_of_rules.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<Atom, Axiom>, D::Error> {
+        let mut rules: HashMap<Atom, Axiom> = HashMap::new();
+
+        for (lhs, rhs) in Vec::<(Atom, Axiom)>::deserialize(deserializer)? {
+            if rules.insert(lhs, rhs).is_some() {
+                return Err(D::Error::custom(format!(
+                    "RuleSet contains two Rules with the lhs-Atom '{:?}'",
+                    lhs
+                )));
+            }
+        }
+
+        Ok(rules)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Atom, Axiom, Rule, RuleSet};
+    use super::{load_l_system, parse_program, Atom, Axiom, Rule, RuleSet, Symbol};
+    use crate::progress::tests::CollectingProgress;
+    use crate::progress::Stage;
 
     #[test]
-    fn create_and_display_atom_test() -> Result<(), String> {
+    fn create_and_display_atom_test() -> crate::Result<()> {
         assert_eq!(format!("{:?}", Atom::from_string("A")?), "A");
         assert_eq!(format!("{:?}", Atom::from_char('A')), "A");
         Ok(())
@@ -255,7 +823,53 @@ mod tests {
     }
 
     #[test]
-    fn create_and_display_axiom_test() -> Result<(), String> {
+    fn token_atom_test() -> crate::Result<()> {
+        let axiom = Axiom::from("F{turn}F{turn}")?;
+        assert_eq!(axiom.atoms().len(), 4);
+        assert_eq!(axiom.atom_list[0].symbol, Symbol::Char('F'));
+        assert_eq!(axiom.atom_list[1].symbol, "{turn}".parse::<Symbol>()?);
+        assert_eq!(axiom.atom_list[1], axiom.atom_list[3]);
+        assert_eq!(format!("{:?}", axiom), "F{turn}F{turn}");
+
+        let ruleset = RuleSet::from(vec![
+            Rule::from("{turn} -> {turn}F{left}")?,
+            Rule::from("F->FF")?,
+        ])?;
+        assert_eq!(
+            format!("{:?}", ruleset.expand(&axiom, 1)),
+            "FF{turn}F{left}FF{turn}F{left}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn single_char_atoms_unchanged_test() -> crate::Result<()> {
+        let axiom = Axiom::from("AB+[-C]")?;
+        assert!(axiom
+            .atoms()
+            .all(|atom| matches!(atom.symbol, Symbol::Char(_))));
+        assert_eq!(format!("{:?}", axiom), "AB+[-C]");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_token_test() {
+        let message = |representation: &str| match Axiom::from(representation) {
+            Err(e) => e.to_string(),
+            Ok(axiom) => panic!("Parsed '{:?}'.", axiom),
+        };
+
+        assert!(message("F{turn").ends_with("Axiom contains a '{' without a '}'."));
+        assert!(message("Fturn}").ends_with("Axiom contains a '}' without a '{'."));
+        assert!(message("F{}").ends_with("Token is empty."));
+        assert!(message("{a_very_long_token_name}").ends_with("is longer than 15 bytes."));
+        assert!(Atom::from_string("{turn}").is_ok());
+        assert!(Atom::from_string("{turn").is_err());
+    }
+
+    #[test]
+    fn create_and_display_axiom_test() -> crate::Result<()> {
         assert_eq!(format!("{:?}", Axiom::from("ABA")?), "ABA");
         Ok(())
     }
@@ -269,7 +883,7 @@ mod tests {
     }
 
     #[test]
-    fn create_and_display_rule_test() -> Result<(), String> {
+    fn create_and_display_rule_test() -> crate::Result<()> {
         assert_eq!(format!("{:?}", Rule::from("A->ABA")?), "A->ABA");
         Ok(())
     }
@@ -337,7 +951,7 @@ mod tests {
     }
 
     #[test]
-    fn create_and_display_ruleset_test() -> Result<(), String> {
+    fn create_and_display_ruleset_test() -> crate::Result<()> {
         assert_eq!(
             format!("{:?}", RuleSet::from(vec![Rule::from("A->ABA")?])?),
             "A->ABA"
@@ -364,7 +978,112 @@ mod tests {
     }
 
     #[test]
-    fn apply_rule_to_axiom_test() -> Result<(), String> {
+    fn parse_ruleset_test() -> crate::Result<()> {
+        let ruleset: RuleSet = "
+            A->ABA
+            B -> BAB
+        "
+        .parse()?;
+        assert_eq!(format!("{:?}", ruleset), "A->ABA, B->BAB");
+
+        let ruleset: RuleSet = "# the dragon curve\nL->L+KF\n  \n# K->K\nK->FL-K\n".parse()?;
+        assert_eq!(format!("{:?}", ruleset), "K->FL-K, L->L+KF");
+
+        let ruleset: RuleSet = "".parse()?;
+        assert_eq!(format!("{:?}", ruleset), "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn ruleset_round_trip_test() -> crate::Result<()> {
+        let ruleset: RuleSet = "A->AB{up}, B -> BA,C\n  {up}->{down}A ,C->,".parse()?;
+        assert_eq!(
+            ruleset.to_string(),
+            "A->AB{up}, B->BA,C, C->,, {up}->{down}A"
+        );
+
+        let parsed: RuleSet = ruleset.to_string().parse()?;
+        assert_eq!(parsed.to_string(), ruleset.to_string());
+        assert_eq!(parsed.rules.len(), 4);
+
+        let empty: RuleSet = RuleSet::from(vec![])?.to_string().parse()?;
+        assert!(empty.rules.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_ruleset_with_same_axioms_test() {
+        match "A->ABA\nA->BAB".parse::<RuleSet>() {
+            Err(e) => assert_eq!(
+                format!("{}", e),
+                "There was an Error with the Representation of an L-System Element: RuleSet contains two Rules with the lhs-Atom 'A'."
+            ),
+            Ok(_) => panic!("Parsed ruleset with same axioms side."),
+        }
+
+        match "A->ABA\nB=>BAB".parse::<RuleSet>() {
+            Err(e) => assert_eq!(
+                format!("{}", e),
+                "There was an Error with the Representation of an L-System Element: Rule didn't contain a '->'."
+            ),
+            Ok(_) => panic!("Parsed ruleset with invalid rule."),
+        }
+    }
+
+    #[test]
+    fn parse_program_test() -> crate::Result<()> {
+        let (axiom, ruleset) = parse_program("axiom: ABA; A->ABA; B->BAB")?;
+        assert_eq!(format!("{:?}", axiom), "ABA");
+        assert_eq!(format!("{:?}", ruleset), "A->ABA, B->BAB");
+
+        let (axiom, ruleset) = parse_program(" L->L+KF;axiom:FL ; K->FL-K;")?;
+        assert_eq!(format!("{:?}", axiom), "FL");
+        assert_eq!(format!("{:?}", ruleset), "K->FL-K, L->L+KF");
+
+        let (axiom, ruleset) = parse_program("axiom: A")?;
+        assert_eq!(format!("{:?}", axiom), "A");
+        assert_eq!(format!("{:?}", ruleset), "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid_program_test() {
+        const PREFIX: &str = "There was an Error with the Representation of an L-System Element: ";
+
+        for (program, message) in [
+            (
+                "A->ABA; B->BAB",
+                "Program doesn't contain a clause starting with 'axiom:'.",
+            ),
+            (
+                "axiom: A; axiom: B",
+                "Program contains a second axiom in the clause 'axiom: B'.",
+            ),
+            (
+                "axiom: ABA; A=>ABA",
+                "Rule didn't contain a '->' in the clause 'A=>ABA'.",
+            ),
+            ("axiom: ; A->ABA", "Axiom is empty in the clause 'axiom:'."),
+            (
+                "axiom: A; AB->A",
+                "Atom contains more that one character in the clause 'AB->A'.",
+            ),
+            (
+                "axiom: A; A->B; A->C",
+                "RuleSet contains two Rules with the lhs-Atom 'A'.",
+            ),
+        ] {
+            match parse_program(program) {
+                Err(e) => assert_eq!(format!("{}", e), format!("{}{}", PREFIX, message)),
+                Ok(_) => panic!("Parsed the invalid program '{}'.", program),
+            }
+        }
+    }
+
+    #[test]
+    fn apply_rule_to_axiom_test() -> crate::Result<()> {
         let mut axiom: Axiom = Axiom::from("ABA")?;
         let rule: Rule = Rule::from("A->ABA")?;
         axiom.apply(&rule);
@@ -375,7 +1094,7 @@ mod tests {
     }
 
     #[test]
-    fn apply_ruleset_to_axiom_test() -> Result<(), String> {
+    fn apply_ruleset_to_axiom_test() -> crate::Result<()> {
         let mut axiom: Axiom = Axiom::from("ABA")?;
         let ruleset: RuleSet = RuleSet::from(vec![Rule::from("A->ABA")?, Rule::from("B->BAB")?])?;
         axiom.apply_ruleset(&ruleset);
@@ -386,7 +1105,159 @@ mod tests {
     }
 
     #[test]
-    fn dragon_curve_test() -> Result<(), String> {
+    fn apply_ruleset_immutable_test() -> crate::Result<()> {
+        let axiom: Axiom = Axiom::from("FL")?;
+        let ruleset: RuleSet = RuleSet::from(vec![Rule::from("L->L+KF")?, Rule::from("K->FL-K")?])?;
+
+        let new_axiom = axiom.apply_ruleset_new(&ruleset);
+        assert_eq!(format!("{:?}", axiom), "FL");
+        assert_eq!(format!("{:?}", new_axiom), "FL+KF");
+
+        let mut mutated_axiom: Axiom = Axiom::from("FL")?;
+        mutated_axiom.apply_ruleset(&ruleset);
+        mutated_axiom.apply_ruleset(&ruleset);
+        assert_eq!(
+            format!("{:?}", &new_axiom | &ruleset),
+            format!("{:?}", mutated_axiom)
+        );
+        assert_eq!(format!("{:?}", new_axiom), "FL+KF");
+
+        assert_eq!(
+            format!("{:?}", axiom | &ruleset | &ruleset),
+            format!("{:?}", mutated_axiom)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn apply_ruleset_bounded_test() -> crate::Result<()> {
+        let ruleset = RuleSet::from(vec![Rule::from("A->AB")?, Rule::from("B->A")?])?;
+
+        // AB has 2 atoms, ABA 3, ABAAB 5
+        let mut axiom = Axiom::from("AB")?;
+        axiom.apply_ruleset_bounded(&ruleset, 3)?;
+        assert_eq!(format!("{:?}", axiom), "ABA");
+
+        let error = axiom.apply_ruleset_bounded(&ruleset, 4).unwrap_err();
+        assert_eq!(error.atoms(), 5);
+        assert_eq!(
+            error.to_string(),
+            "Expanding the Axiom would produce 5 atoms, more than the maximum of 4."
+        );
+        assert_eq!(format!("{:?}", axiom), "ABA");
+
+        axiom.apply_ruleset_bounded(&ruleset, 5)?;
+        assert_eq!(format!("{:?}", axiom), "ABAAB");
+
+        let mut axiom = Axiom::from("AB")?;
+        axiom.apply_n_bounded(&ruleset, 3, 8)?;
+        assert_eq!(format!("{:?}", axiom), "ABAABABA");
+
+        // the iterations before the overflow are kept
+        let mut axiom = Axiom::from("AB")?;
+        assert_eq!(
+            axiom.apply_n_bounded(&ruleset, 4, 12).unwrap_err().atoms(),
+            13
+        );
+        assert_eq!(format!("{:?}", axiom), "ABAABABA");
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_test() -> crate::Result<()> {
+        let axiom: Axiom = Axiom::from("FL")?;
+        let ruleset: RuleSet = RuleSet::from(vec![Rule::from("L->L+KF")?, Rule::from("K->FL-K")?])?;
+
+        assert_eq!(
+            format!("{:?}", ruleset.expand(&axiom, 3)),
+            "FL+KF+FL-KF+FL+KF-FL-KF"
+        );
+        assert_eq!(format!("{:?}", ruleset.expand(&axiom, 0)), "FL");
+        assert_eq!(format!("{:?}", axiom), "FL");
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_with_progress_test() -> crate::Result<()> {
+        let axiom = Axiom::from("A")?;
+        let ruleset = RuleSet::from(vec![Rule::from("A->AB")?])?;
+        let progress = CollectingProgress::default();
+
+        let expanded_axiom = ruleset.expand_with_progress(&axiom, 3, &progress);
+
+        assert_eq!(format!("{:?}", expanded_axiom), "ABBB");
+        assert_eq!(
+            *progress.reports.borrow(),
+            (0..=3)
+                .map(|done| (Stage::Expansion, done, 3))
+                .collect::<Vec<_>>()
+        );
+        progress.assert_in_order();
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_bounded_test() -> crate::Result<()> {
+        let axiom = Axiom::from("A")?;
+        let ruleset = RuleSet::from(vec![Rule::from("A->AB")?])?;
+        let progress = CollectingProgress::default();
+
+        let expanded_axiom = ruleset.expand_bounded_with_progress(&axiom, 3, 4, &progress)?;
+        assert_eq!(format!("{:?}", expanded_axiom), "ABBB");
+        assert_eq!(progress.reports.borrow().len(), 4);
+
+        let error = ruleset.expand_bounded(&axiom, 4, 4).unwrap_err();
+        assert_eq!((error.atoms(), error.max_atoms()), (5, 4));
+        assert_eq!(format!("{:?}", axiom), "A");
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_dot_test() -> crate::Result<()> {
+        let axiom = Axiom::from("A")?;
+        let ruleset = RuleSet::from(vec![Rule::from("A->ABA")?, Rule::from("B->BAB")?])?;
+        let dot = ruleset.to_dot(&axiom, 2);
+
+        let lines: Vec<&str> = dot.lines().collect();
+        assert_eq!(lines.first(), Some(&"digraph LSystem {"));
+        assert_eq!(lines.last(), Some(&"}"));
+
+        let nodes: Vec<&&str> = lines
+            .iter()
+            .filter(|line| line.contains("[label="))
+            .collect();
+        let edges: Vec<&&str> = lines.iter().filter(|line| line.contains("->")).collect();
+        assert_eq!(nodes.len(), 1 + 3 + 9);
+        assert_eq!(edges.len(), 3 + 9);
+        assert_eq!(nodes.len() + edges.len() + 2, lines.len());
+
+        for node in nodes {
+            let node = node.trim();
+            assert!(node.starts_with('n') && node.ends_with("\"];"), "{}", node);
+        }
+
+        assert!(dot.contains("    n0 [label=\"A\"];\n"));
+        assert!(dot.contains("    n0 -> n1;\n    n2 [label=\"B\"];\n    n0 -> n2;\n"));
+
+        // atoms without a rule are copied
+        let ruleset = RuleSet::from(vec![Rule::from("A->ABA")?])?;
+        let dot = ruleset.to_dot(&axiom, 2);
+        assert_eq!(dot.matches("[label=").count(), 1 + 3 + 7);
+        assert_eq!(
+            ruleset.to_dot(&axiom, 0),
+            "digraph LSystem {\n    n0 [label=\"A\"];\n}\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn dragon_curve_test() -> crate::Result<()> {
         let mut axiom: Axiom = Axiom::from("FL")?;
         let ruleset: RuleSet = RuleSet::from(vec![Rule::from("L->L+KF")?, Rule::from("K->FL-K")?])?;
 
@@ -401,4 +1272,80 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_test() -> crate::Result<()> {
+        let atom: Atom = Atom::from_char('A');
+        let json = serde_json::to_string(&atom).unwrap();
+        assert_eq!(json, r#"{"symbol":"A"}"#);
+        assert_eq!(serde_json::from_str::<Atom>(&json).unwrap(), atom);
+
+        let atom: Atom = Atom::from_string("{turn}")?;
+        let json = serde_json::to_string(&atom).unwrap();
+        assert_eq!(json, r#"{"symbol":"{turn}"}"#);
+        assert_eq!(serde_json::from_str::<Atom>(&json).unwrap(), atom);
+
+        let axiom: Axiom = Axiom::from("FL+")?;
+        let json = serde_json::to_string(&axiom).unwrap();
+        assert_eq!(
+            format!("{:?}", serde_json::from_str::<Axiom>(&json).unwrap()),
+            "FL+"
+        );
+
+        let rule: Rule = Rule::from("A->ABA")?;
+        let json = serde_json::to_string(&rule).unwrap();
+        assert_eq!(
+            format!("{:?}", serde_json::from_str::<Rule>(&json).unwrap()),
+            "A->ABA"
+        );
+
+        let ruleset: RuleSet = "L->L+KF\nK->FL-K\nA->B".parse()?;
+        let json = serde_json::to_string(&ruleset).unwrap();
+        assert_eq!(
+            json,
+            r#"{"rules":[[{"symbol":"A"},{"atom_list":[{"symbol":"B"}]}],[{"symbol":"K"},{"atom_list":[{"symbol":"F"},{"symbol":"L"},{"symbol":"-"},{"symbol":"K"}]}],[{"symbol":"L"},{"atom_list":[{"symbol":"L"},{"symbol":"+"},{"symbol":"K"},{"symbol":"F"}]}]]}"#
+        );
+        assert_eq!(
+            format!("{:?}", serde_json::from_str::<RuleSet>(&json).unwrap()),
+            "A->B, K->FL-K, L->L+KF"
+        );
+
+        let duplicate = r#"{"rules":[[{"symbol":"A"},{"atom_list":[{"symbol":"B"}]}],[{"symbol":"A"},{"atom_list":[{"symbol":"C"}]}]]}"#;
+        assert!(serde_json::from_str::<RuleSet>(duplicate).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_l_system_test() {
+        let axiom_path = std::env::temp_dir().join("music_generator_axiom.txt");
+        let rules_path = std::env::temp_dir().join("music_generator_rules.txt");
+        std::fs::write(
+            &axiom_path,
+            "# the start of the melody\n\n  A{up}B  \nBBB\n",
+        )
+        .unwrap();
+        std::fs::write(&rules_path, "# grow\nA->AB\n\nB->A{up}\n").unwrap();
+
+        let (axiom, ruleset) = load_l_system(&axiom_path, &rules_path).unwrap();
+        let expected_ruleset: RuleSet = "A->AB\nB->A{up}".parse().unwrap();
+        assert_eq!(format!("{:?}", axiom), "A{up}B");
+        assert_eq!(
+            format!("{:?}", ruleset.expand(&axiom, 3)),
+            format!(
+                "{:?}",
+                expected_ruleset.expand(&Axiom::from("A{up}B").unwrap(), 3)
+            )
+        );
+
+        std::fs::write(&axiom_path, "# only a comment\n\n").unwrap();
+        assert!(Axiom::from_file(&axiom_path)
+            .unwrap_err()
+            .to_string()
+            .ends_with("doesn't contain an axiom."));
+        assert!(
+            Axiom::from_file(&std::env::temp_dir().join("music_generator_missing.txt")).is_err()
+        );
+    }
 }
@@ -1,5 +1,17 @@
 #![allow(dead_code)]
 
+pub mod error;
 pub mod l_system;
 pub mod musical_notation;
+pub mod prelude;
+pub mod progress;
+#[cfg(feature = "serde")]
+pub mod project;
+pub mod realtime;
+pub mod rng;
+pub mod synthesis;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_helpers;
 pub mod voice;
+
+pub use error::{Error, Result};
@@ -1,16 +1,47 @@
 mod pitch;
-pub use pitch::temperament::{EqualTemperament, Temperament};
+pub use pitch::error::{
+    IntervalPatternError, KeyCreationError, ScaleKindParseError, ToneParseError,
+};
+pub use pitch::table::{pitch_table, PitchRow};
+pub use pitch::temperament::proportionen::Proportion;
+pub use pitch::temperament::{
+    compare_seven_tone_to_equal_temperament, compare_to_equal_temperament, EqualTemperament,
+    JustIntonation, SevenToneTemperament, Temperament,
+};
 pub use pitch::temperament::{BAROQUE_PITCH, CHORTON_PITCH, CLASSICAL_PITCH, STUTTGART_PITCH};
-pub use pitch::{Accidental, Key, Note, Pitch, ScaleKind};
+pub use pitch::temperament::{PITCH_STANDARDS, TEMPERAMENTS};
+pub use pitch::{
+    harmonic_series, Accidental, IntervalName, IntervalPattern, Key, Note, Pitch, ScaleKind, Tone,
+};
 
 mod duration;
 pub use duration::Duration;
 
+mod time_signature;
+pub use time_signature::TimeSignature;
+
 mod volume;
-pub use volume::Volume;
+pub use volume::{Volume, VolumeCurve, VolumeParseError};
 pub use volume::{F, FF, FFF, M, MF, MP, P, PP, PPP, SILENT};
 
-#[derive(Debug)]
+/**
+ * Defines how a note is played in relation to the neighbouring elements.
+ * Staccato notes are cut short, legato notes slightly overlap the next element.
+ * A slide note glides in from the pitch of the note right before it and is
+ * played like a normal note if a rest or nothing precedes it.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Articulation {
+    Legato,
+    #[default]
+    Normal,
+    Staccato,
+    Slide,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MusicalElement {
     Rest {
         duration: Duration,
@@ -19,14 +50,78 @@ pub enum MusicalElement {
         pitch: Pitch,
         duration: Duration,
         volume: Volume,
+        articulation: Articulation,
+    },
+    TimedNote {
+        pitch: Pitch,
+        seconds: f64,
+        volume: Volume,
     },
 }
 
 impl MusicalElement {
+    /**
+     * Create a Note played with the normal articulation, which is
+     * less verbose than spelling out the variant when writing note lists.
+     * The name Note is taken by the note names of a Tone.
+     */
+    pub fn note(pitch: Pitch, duration: Duration, volume: Volume) -> MusicalElement {
+        MusicalElement::Note {
+            pitch,
+            duration,
+            volume,
+            articulation: Articulation::Normal,
+        }
+    }
+
+    /**
+     * Create a Rest lasting the given number of time units, where one time unit is an eighth note.
+     */
+    pub fn rest(units: u16) -> MusicalElement {
+        MusicalElement::Rest {
+            duration: Duration::time_units(units),
+        }
+    }
+
+    /**
+     * Get the musical duration of the element. A TimedNote lasts a fixed number
+     * of seconds independent of the tempo, so its musical duration is zero.
+     */
     pub fn get_duration(&self) -> Duration {
         match self {
             MusicalElement::Rest { duration } => *duration,
             MusicalElement::Note { duration, .. } => *duration,
+            MusicalElement::TimedNote { .. } => Duration::zero(),
+        }
+    }
+
+    /**
+     * Get the number of seconds the element lasts at the given tempo.
+     */
+    pub fn to_seconds(&self, bpm: u16, beats_per_whole: u32) -> f64 {
+        match self {
+            MusicalElement::TimedNote { seconds, .. } => *seconds,
+            musical_element => musical_element
+                .get_duration()
+                .to_seconds(bpm, beats_per_whole),
         }
     }
 }
+
+/**
+ * The pitch, duration and volume of a note, which becomes a Note of the
+ * normal articulation. A list of them reads like a score when writing a
+ * voice by hand.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NoteSpec {
+    pub pitch: Pitch,
+    pub duration: Duration,
+    pub volume: Volume,
+}
+
+impl From<NoteSpec> for MusicalElement {
+    fn from(note_spec: NoteSpec) -> MusicalElement {
+        MusicalElement::note(note_spec.pitch, note_spec.duration, note_spec.volume)
+    }
+}
@@ -1,14 +1,441 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+
+/**
+ * The number of time units that make up a whole note. One time unit
+ * corresponds to an eighth note.
+ */
+const TIME_UNITS_PER_WHOLE: u32 = 8;
+
+/**
+ * Defines the duration of a MusicalElement as a rational number of whole notes.
+ * Durations are always kept in lowest terms, a quarter note for example is stored as 1/4.
+ *
+ * For backwards compatibility a Duration can still be created from a number of
+ * boxes of the [time unit box system](https://en.wikipedia.org/wiki/Time_unit_box_system),
+ * where one box refers to an eighth note.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawDuration")
+)]
+pub struct Duration {
+    num: u32,
+    den: u32,
+}
+
 /**
- * Defines the duration of a MusicalElement using the
- * [time unit box system](https://en.wikipedia.org/wiki/Time_unit_box_system).
- * The number that Duration contains refers the the number of boxes of a fixed unit of time
- * that the MusicalElement is played for.
+ * The fields of a deserialized Duration, which are checked
+ * and reduced to lowest terms like in `Duration::new`.
  */
-#[derive(Debug, Copy, Clone)]
-pub struct Duration(pub u16);
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawDuration {
+    num: u32,
+    den: u32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawDuration> for Duration {
+    type Error = String;
+
+    fn try_from(raw: RawDuration) -> Result<Duration, String> {
+        match raw.den {
+            0 => Err(String::from(
+                "The denominator of a Duration must not be zero",
+            )),
+            den => Ok(Duration::reduced(raw.num as u64, den as u64)),
+        }
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    let mut a = a;
+    let mut b = b;
+
+    while b > 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+
+    a
+}
 
 impl Duration {
-    pub fn get_time_units(&self) -> u16 {
-        self.0
+    /**
+     * Create a Duration of num/den whole notes.
+     * Panics if den is zero.
+     */
+    pub fn new(num: u32, den: u32) -> Duration {
+        assert!(den > 0, "The denominator of a Duration must not be zero");
+        Duration::reduced(num as u64, den as u64)
+    }
+
+    /**
+     * Create the Duration num/den in lowest terms. If the terms still don't fit into a u32,
+     * the Duration is approximated by the nearest fraction whose terms do, which saturates
+     * at u32::MAX whole notes and rounds Durations shorter than 1/(2 * u32::MAX) to zero.
+     */
+    fn reduced(num: u64, den: u64) -> Duration {
+        let divisor = gcd(num, den);
+        let (num, den) = (num / divisor, den / divisor);
+        let max = u32::MAX as u64;
+
+        if num <= max && den <= max {
+            return Duration {
+                num: num as u32,
+                den: den as u32,
+            };
+        }
+
+        // scale both terms down until the larger one is u32::MAX
+        let larger = num.max(den) as u128;
+        let scale = |term: u64| ((term as u128 * max as u128 + larger / 2) / larger) as u64;
+        let (num, den) = (scale(num), scale(den).max(1));
+        let divisor = gcd(num, den);
+
+        Duration {
+            num: (num / divisor) as u32,
+            den: (den / divisor) as u32,
+        }
+    }
+
+    /**
+     * A Duration of length zero.
+     */
+    pub fn zero() -> Duration {
+        Duration { num: 0, den: 1 }
+    }
+
+    pub fn whole() -> Duration {
+        Duration::new(1, 1)
+    }
+
+    pub fn half() -> Duration {
+        Duration::new(1, 2)
+    }
+
+    pub fn quarter() -> Duration {
+        Duration::new(1, 4)
+    }
+
+    pub fn eighth() -> Duration {
+        Duration::new(1, 8)
+    }
+
+    pub fn sixteenth() -> Duration {
+        Duration::new(1, 16)
+    }
+
+    /**
+     * Create a Duration from a number of time units,
+     * where one time unit is an eighth note.
+     */
+    pub fn time_units(time_units: u16) -> Duration {
+        Duration::new(time_units as u32, TIME_UNITS_PER_WHOLE)
+    }
+
+    /**
+     * Extend the given Duration by half of its length.
+     */
+    pub fn dotted(base: Duration) -> Duration {
+        Duration::reduced(base.num as u64 * 3, base.den as u64 * 2)
+    }
+
+    /**
+     * The Duration of one note of a tuplet, in which n notes of the given base Duration
+     * are played in the time of m notes of the base Duration.
+     * A triplet of eighth notes for example is `Duration::tuplet(Duration::eighth(), 3, 2)`.
+     * Panics if n is zero.
+     */
+    pub fn tuplet(base: Duration, n: u32, m: u32) -> Duration {
+        assert!(n > 0, "A tuplet must consist of at least one note");
+        Duration::reduced(base.num as u64 * m as u64, base.den as u64 * n as u64)
+    }
+
+    pub fn numerator(&self) -> u32 {
+        self.num
+    }
+
+    pub fn denominator(&self) -> u32 {
+        self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    /**
+     * The number of whole notes this Duration spans.
+     */
+    pub fn as_whole_notes(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /**
+     * Calculate the length of this Duration in seconds, when bpm beats are played
+     * per minute and a whole note consists of beats_per_whole beats.
+     */
+    pub fn to_seconds(&self, bpm: u16, beats_per_whole: u32) -> f64 {
+        self.as_whole_notes() * beats_per_whole as f64 * 60.0 / bpm as f64
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, rhs: Duration) -> Duration {
+        Duration::reduced(
+            self.num as u64 * rhs.den as u64 + rhs.num as u64 * self.den as u64,
+            self.den as u64 * rhs.den as u64,
+        )
+    }
+}
+
+impl Sub for Duration {
+    type Output = Duration;
+
+    /**
+     * Panics if rhs is longer than self.
+     */
+    fn sub(self, rhs: Duration) -> Duration {
+        assert!(rhs <= self, "A Duration must not be negative");
+        Duration::reduced(
+            self.num as u64 * rhs.den as u64 - rhs.num as u64 * self.den as u64,
+            self.den as u64 * rhs.den as u64,
+        )
+    }
+}
+
+impl Mul<u32> for Duration {
+    type Output = Duration;
+
+    fn mul(self, rhs: u32) -> Duration {
+        Duration::reduced(self.num as u64 * rhs as u64, self.den as u64)
+    }
+}
+
+impl Div<u32> for Duration {
+    type Output = Duration;
+
+    /**
+     * Split the Duration into rhs equal parts. Durations are rational, so nothing remains.
+     * Panics if rhs is zero.
+     */
+    fn div(self, rhs: u32) -> Duration {
+        assert!(rhs > 0, "A Duration must not be divided by zero");
+        Duration::reduced(self.num as u64, self.den as u64 * rhs as u64)
+    }
+}
+
+impl std::iter::Sum for Duration {
+    fn sum<I: Iterator<Item = Duration>>(iter: I) -> Duration {
+        iter.fold(Duration::zero(), |acc, duration| acc + duration)
+    }
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+impl Ord for Duration {
+    fn cmp(&self, other: &Duration) -> Ordering {
+        (self.num as u64 * other.den as u64).cmp(&(other.num as u64 * self.den as u64))
+    }
+}
+
+impl PartialOrd for Duration {
+    fn partial_cmp(&self, other: &Duration) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Duration;
+
+    #[test]
+    fn reduce_test() {
+        assert_eq!(Duration::new(2, 8), Duration::quarter());
+        assert_eq!(Duration::new(4, 4), Duration::whole());
+        assert_eq!(Duration::new(0, 7), Duration::zero());
+        assert_eq!(Duration::new(6, 16).numerator(), 3);
+        assert_eq!(Duration::new(6, 16).denominator(), 8);
+    }
+
+    #[test]
+    fn overflow_test() {
+        // the terms of 3/2^32 don't fit into a u32, the nearest fraction which does is 3/(2^32 - 1)
+        assert_eq!(Duration::new(3, 1 << 31) / 2, Duration::new(3, u32::MAX));
+        assert_eq!(
+            Duration::new(1, u32::MAX) + Duration::new(1, u32::MAX - 1),
+            Duration::new(2, u32::MAX)
+        );
+        assert_eq!(Duration::new(1, u32::MAX) / 3, Duration::zero());
+        assert_eq!(Duration::new(u32::MAX, 1) * 2, Duration::new(u32::MAX, 1));
+        assert_eq!(Duration::new(u32::MAX, 2) * 3, Duration::new(u32::MAX, 1));
+    }
+
+    #[test]
+    fn display_test() {
+        assert_eq!(format!("{}", Duration::quarter()), "1/4");
+        assert_eq!(format!("{}", Duration::whole()), "1/1");
+        assert_eq!(format!("{}", Duration::dotted(Duration::eighth())), "3/16");
+    }
+
+    #[test]
+    fn time_units_test() {
+        assert_eq!(Duration::time_units(1), Duration::eighth());
+        assert_eq!(Duration::time_units(2), Duration::quarter());
+        assert_eq!(Duration::time_units(8), Duration::whole());
+        assert_eq!(Duration::time_units(0), Duration::zero());
+    }
+
+    #[test]
+    fn dotted_test() {
+        assert_eq!(Duration::dotted(Duration::quarter()), Duration::new(3, 8));
+        assert_eq!(Duration::dotted(Duration::half()), Duration::new(3, 4));
+        assert_eq!(
+            Duration::dotted(Duration::quarter()),
+            Duration::quarter() + Duration::eighth()
+        );
+    }
+
+    #[test]
+    fn tuplet_test() {
+        let triplet_eighth = Duration::tuplet(Duration::eighth(), 3, 2);
+        assert_eq!(triplet_eighth, Duration::new(1, 12));
+        assert_eq!(Duration::quarter() / 3, triplet_eighth);
+        assert_eq!(triplet_eighth * 3, Duration::quarter());
+
+        let quintuplet_sixteenth = Duration::tuplet(Duration::sixteenth(), 5, 4);
+        assert_eq!(quintuplet_sixteenth, Duration::new(1, 20));
+        assert_eq!(quintuplet_sixteenth * 5, Duration::quarter());
+
+        let duplet_eighth = Duration::tuplet(Duration::eighth(), 2, 3);
+        assert_eq!(duplet_eighth, Duration::new(3, 16));
+        assert_eq!(duplet_eighth * 2, Duration::dotted(Duration::quarter()));
+
+        let triplet_quarter = Duration::tuplet(Duration::quarter(), 3, 2);
+        assert_eq!(
+            triplet_quarter + triplet_quarter + triplet_quarter,
+            Duration::half()
+        );
+    }
+
+    #[test]
+    fn dotted_against_straight_test() {
+        // a dotted quarter and an eighth fill the same time as two quarters
+        assert_eq!(
+            Duration::dotted(Duration::quarter()) + Duration::eighth(),
+            Duration::quarter() * 2
+        );
+    }
+
+    #[test]
+    fn add_and_sum_test() {
+        assert_eq!(Duration::quarter() + Duration::quarter(), Duration::half());
+        assert_eq!(
+            Duration::quarter() + Duration::tuplet(Duration::eighth(), 3, 2),
+            Duration::new(1, 3)
+        );
+        assert_eq!(
+            vec![Duration::half(), Duration::quarter(), Duration::quarter()]
+                .into_iter()
+                .sum::<Duration>(),
+            Duration::whole()
+        );
+        assert_eq!(
+            Vec::<Duration>::new().into_iter().sum::<Duration>(),
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    fn sub_test() {
+        assert_eq!(Duration::whole() - Duration::quarter(), Duration::new(3, 4));
+        assert_eq!(Duration::quarter() - Duration::quarter(), Duration::zero());
+        assert_eq!(
+            Duration::quarter() - Duration::tuplet(Duration::eighth(), 3, 2),
+            Duration::new(1, 6)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_sub_test() {
+        let _ = Duration::eighth() - Duration::quarter();
+    }
+
+    #[test]
+    fn ordering_test() {
+        assert!(Duration::eighth() < Duration::quarter());
+        assert!(Duration::tuplet(Duration::eighth(), 3, 2) < Duration::eighth());
+        assert!(Duration::dotted(Duration::quarter()) > Duration::quarter());
+        assert!(Duration::new(2, 4) <= Duration::half());
+    }
+
+    #[test]
+    fn to_seconds_test() {
+        // a quarter note at 60 bpm with the quarter note getting the beat
+        assert_eq!(
+            format!("{:.3?}", Duration::quarter().to_seconds(60, 4)),
+            "1.000"
+        );
+        // a whole note at 120 bpm in 4/4
+        assert_eq!(
+            format!("{:.3?}", Duration::whole().to_seconds(120, 4)),
+            "2.000"
+        );
+        // a dotted quarter at 90 bpm
+        assert_eq!(
+            format!(
+                "{:.3?}",
+                Duration::dotted(Duration::quarter()).to_seconds(90, 4)
+            ),
+            "1.000"
+        );
+        // a triplet eighth at 120 bpm
+        assert_eq!(
+            format!(
+                "{:.3?}",
+                Duration::tuplet(Duration::eighth(), 3, 2).to_seconds(120, 4)
+            ),
+            "0.167"
+        );
+        // one time unit at 120 bpm with the eighth note getting the beat
+        assert_eq!(
+            format!("{:.3?}", Duration::time_units(1).to_seconds(120, 8)),
+            "0.500"
+        );
+        assert_eq!(
+            format!("{:.3?}", Duration::zero().to_seconds(120, 8)),
+            "0.000"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_test() {
+        let duration = Duration::tuplet(Duration::eighth(), 3, 2);
+        let json = serde_json::to_string(&duration).unwrap();
+        assert_eq!(json, r#"{"num":1,"den":12}"#);
+        assert_eq!(serde_json::from_str::<Duration>(&json).unwrap(), duration);
+
+        // deserialized durations are reduced and checked like in Duration::new
+        assert_eq!(
+            serde_json::from_str::<Duration>(r#"{"num":2,"den":16}"#).unwrap(),
+            Duration::eighth()
+        );
+        assert!(serde_json::from_str::<Duration>(r#"{"num":1,"den":0}"#)
+            .unwrap_err()
+            .to_string()
+            .contains("The denominator of a Duration must not be zero"));
     }
 }
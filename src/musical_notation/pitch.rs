@@ -1,34 +1,89 @@
 const OCTAVE_ADDITIVE: u8 = 12;
 const OCTAVE_MULTIPLICATIVE: u8 = 2;
 
-use std::rc::Rc;
+use std::sync::Arc;
 
+pub mod error;
+pub mod table;
 pub mod temperament;
 
 /**
  * Defines the pitch of a note in Herz.
  */
 #[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pitch(pub f64);
 
 impl Pitch {
     pub fn get_hz(&self) -> f64 {
         self.0
     }
+
+    /**
+     * Transpose this pitch down by as many octaves as needed
+     * for it not to exceed the given ceiling.
+     */
+    pub fn fold_below(&self, ceiling: Pitch) -> Pitch {
+        let mut hz = self.0;
+
+        if ceiling.0 > 0.0 {
+            while hz > ceiling.0 {
+                hz /= OCTAVE_MULTIPLICATIVE as f64;
+            }
+        }
+
+        Pitch(hz)
+    }
+
+    /**
+     * Transpose this pitch up (positive octaves) or down (negative octaves) by whole octaves.
+     */
+    pub fn shift_octaves(&self, octaves: i16) -> Pitch {
+        Pitch(self.0 * (OCTAVE_MULTIPLICATIVE as f64).powi(octaves as i32))
+    }
+
+    /**
+     * The interval from the other pitch up to this one in cents.
+     * The result is negative if this pitch is lower than the other one.
+     */
+    pub fn cents_from(&self, other: Pitch) -> f64 {
+        1200.0 * (self.0 / other.0).log2()
+    }
+}
+
+/**
+ * Get the first count partials of the harmonic series of the fundamental,
+ * i.e. the integer multiples of its frequency starting with the fundamental itself.
+ */
+pub fn harmonic_series(fundamental: Pitch, count: usize) -> Vec<Pitch> {
+    (1..=count)
+        .map(|partial| Pitch(fundamental.0 * partial as f64))
+        .collect()
 }
 
 const DEGREES_IN_SCALE: u8 = 7;
 //                                                              c  d  e  f  g  a  b  c
 const SEMITONES_IN_MAJOR_SCALE: [u8; DEGREES_IN_SCALE as usize] = [2, 2, 1, 2, 2, 2, 1];
+// 1 b3 4 b5 5 b7
+const SEMITONES_IN_BLUES_SCALE: [u8; 6] = [3, 2, 1, 1, 3, 2];
+// 1 2 b3 3 4 b5 5 6 b7
+const SEMITONES_IN_FULL_BLUES_SCALE: [u8; 9] = [2, 1, 1, 1, 1, 1, 2, 1, 2];
 
-#[derive(Debug, Clone)]
+// the octave the root of a chord parsed from its symbol lies in
+const CHORD_ROOT_OCTAVE: i16 = 4;
+// the largest difference in cents between the tonics of enharmonic keys
+const ENHARMONIC_TOLERANCE_CENTS: f64 = 0.01;
+// a pitch belongs to the nearest tone, i.e. the one less than half a semitone away
+const NEAREST_TONE_TOLERANCE_CENTS: f64 = 50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Accidental {
     Flat,
     Natural,
     Sharp,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Note {
     C,
     D,
@@ -51,14 +106,493 @@ impl Note {
             Note::B => 6,
         }
     }
+
+    fn from_index(index: u8) -> Note {
+        match index % DEGREES_IN_SCALE {
+            0 => Note::C,
+            1 => Note::D,
+            2 => Note::E,
+            3 => Note::F,
+            4 => Note::G,
+            5 => Note::A,
+            _ => Note::B,
+        }
+    }
+
+    /**
+     * The number of semitones between C and this note without any accidental.
+     */
+    fn get_semitones_above_c(&self) -> u8 {
+        SEMITONES_IN_MAJOR_SCALE[0..self.get_index() as usize]
+            .iter()
+            .sum::<u8>()
+    }
+}
+
+/**
+ * A Tone is a note name together with its accidental, e.g. C# or Bb.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tone {
+    pub note: Note,
+    pub accidental: Accidental,
+}
+
+impl Tone {
+    pub fn new(note: Note, accidental: Accidental) -> Self {
+        Tone { note, accidental }
+    }
+
+    /**
+     * Get the position of this tone in the twelve-tone system,
+     * where C has the position 1 and B the position 12.
+     * Cb therefore has the position 12 and B# the position 1.
+     */
+    pub fn get_position(&self) -> u8 {
+        let semitones = self.note.get_semitones_above_c() + OCTAVE_ADDITIVE;
+        let semitones = match self.accidental {
+            Accidental::Flat => semitones - 1,
+            Accidental::Natural => semitones,
+            Accidental::Sharp => semitones + 1,
+        };
+        semitones % OCTAVE_ADDITIVE + 1
+    }
+
+    /**
+     * Get the position like `get_position`, but counted from the C of the octave its note
+     * name lies in. Cb therefore has the position 0 and B# the position 13, so that Cb4
+     * sounds like B3 and B#4 like C5 when the position is used with the octave.
+     */
+    pub fn get_position_in_octave(&self) -> i16 {
+        let position = self.note.get_semitones_above_c() as i16 + 1;
+
+        match self.accidental {
+            Accidental::Flat => position - 1,
+            Accidental::Natural => position,
+            Accidental::Sharp => position + 1,
+        }
+    }
+
+    /**
+     * Get the tone at the given position of the twelve-tone system.
+     * Black keys are spelled with a flat if prefer_flats is set and with a sharp otherwise.
+     */
+    pub fn from_position(position: u8, prefer_flats: bool) -> Tone {
+        let position = (position + OCTAVE_ADDITIVE - 1) % OCTAVE_ADDITIVE + 1;
+
+        match position {
+            1 => Tone::new(Note::C, Accidental::Natural),
+            3 => Tone::new(Note::D, Accidental::Natural),
+            5 => Tone::new(Note::E, Accidental::Natural),
+            6 => Tone::new(Note::F, Accidental::Natural),
+            8 => Tone::new(Note::G, Accidental::Natural),
+            10 => Tone::new(Note::A, Accidental::Natural),
+            12 => Tone::new(Note::B, Accidental::Natural),
+            _ => match prefer_flats {
+                true => Tone::from_position(position + 1, false).with_accidental(Accidental::Flat),
+                false => {
+                    Tone::from_position(position - 1, false).with_accidental(Accidental::Sharp)
+                }
+            },
+        }
+    }
+
+    /**
+     * Get the 21 tones with at most one accidental in the order of the circle of fifths,
+     * from Fb to B#.
+     */
+    pub fn all() -> impl Iterator<Item = Tone> {
+        const NOTES_IN_FIFTHS: [Note; 7] = [
+            Note::F,
+            Note::C,
+            Note::G,
+            Note::D,
+            Note::A,
+            Note::E,
+            Note::B,
+        ];
+
+        [Accidental::Flat, Accidental::Natural, Accidental::Sharp]
+            .into_iter()
+            .flat_map(|accidental| {
+                NOTES_IN_FIFTHS
+                    .into_iter()
+                    .map(move |note| Tone::new(note, accidental))
+            })
+    }
+
+    /**
+     * Get the note name and accidental of this tone as static references,
+     * as they are used to create a Key.
+     */
+    fn as_static(&self) -> (&'static Note, &'static Accidental) {
+        let note = match self.note {
+            Note::C => &Note::C,
+            Note::D => &Note::D,
+            Note::E => &Note::E,
+            Note::F => &Note::F,
+            Note::G => &Note::G,
+            Note::A => &Note::A,
+            Note::B => &Note::B,
+        };
+        let accidental = match self.accidental {
+            Accidental::Flat => &Accidental::Flat,
+            Accidental::Natural => &Accidental::Natural,
+            Accidental::Sharp => &Accidental::Sharp,
+        };
+        (note, accidental)
+    }
+
+    /**
+     * Name the interval from this tone up to the other one within an octave. The name
+     * depends on the note names as well as on the number of semitones between them,
+     * so C to F# is an augmented fourth while C to Gb is a diminished fifth.
+     */
+    pub fn interval_to(&self, other: Tone) -> IntervalName {
+        let degrees =
+            (other.note.get_index() + DEGREES_IN_SCALE - self.note.get_index()) % DEGREES_IN_SCALE;
+        let semitones =
+            (other.get_position() + OCTAVE_ADDITIVE - self.get_position()) % OCTAVE_ADDITIVE;
+
+        IntervalName::from_degrees_and_semitones(degrees, semitones)
+    }
+
+    /**
+     * Get the tone a tritone away from this tone, spelled as its diminished fifth,
+     * e.g. Gb for C and C for F# or Gb, so that applying it twice returns to the tone
+     * or its enharmonic equivalent. Cb is spelled as B.
+     */
+    pub fn get_tritone(&self) -> Tone {
+        // a diminished fifth spans five note names and six semitones
+        let tritone = Tone::spell(
+            Note::from_index(self.note.get_index() + 4),
+            (self.get_position() + 5) % OCTAVE_ADDITIVE + 1,
+        );
+
+        match tritone == Tone::new(Note::C, Accidental::Flat) {
+            true => Tone::new(Note::B, Accidental::Natural),
+            false => tritone,
+        }
+    }
+
+    fn with_accidental(&self, accidental: Accidental) -> Tone {
+        Tone::new(self.note, accidental)
+    }
+
+    /**
+     * Spell the tone at the given position of the twelve-tone system using the given
+     * note name. If the note name would need more than one accidental, the tone is spelled
+     * with the enharmonic note name closest to the given one instead.
+     */
+    fn spell(note: Note, position: u8) -> Tone {
+        let natural = (note.get_semitones_above_c() + 1) as i16;
+        let difference = (position as i16 - natural).rem_euclid(OCTAVE_ADDITIVE as i16);
+
+        match difference {
+            0 => Tone::new(note, Accidental::Natural),
+            1 => Tone::new(note, Accidental::Sharp),
+            11 => Tone::new(note, Accidental::Flat),
+            2..=5 => Tone::spell(Note::from_index(note.get_index() + 1), position),
+            _ => Tone::spell(
+                Note::from_index(note.get_index() + DEGREES_IN_SCALE - 1),
+                position,
+            ),
+        }
+    }
+}
+
+/**
+ * The name of an ascending interval within an octave, e.g. a major third.
+ * Both the augmented fourth and the diminished fifth are tritones.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntervalName {
+    PerfectUnison,
+    AugmentedUnison,
+    DiminishedSecond,
+    MinorSecond,
+    MajorSecond,
+    AugmentedSecond,
+    DiminishedThird,
+    MinorThird,
+    MajorThird,
+    AugmentedThird,
+    DiminishedFourth,
+    PerfectFourth,
+    AugmentedFourth,
+    DiminishedFifth,
+    PerfectFifth,
+    AugmentedFifth,
+    DiminishedSixth,
+    MinorSixth,
+    MajorSixth,
+    AugmentedSixth,
+    DiminishedSeventh,
+    MinorSeventh,
+    MajorSeventh,
+    AugmentedSeventh,
+    DiminishedOctave,
+    /**
+     * An interval altered by more than one semitone, e.g. the doubly augmented
+     * fourth from Cb to F#, given by its number and its semitones.
+     */
+    Other {
+        number: u8,
+        semitones: u8,
+    },
+}
+
+// the degrees and semitones every named interval spans, a unison spans 0 degrees
+const NAMED_INTERVALS: [(u8, u8, IntervalName); 25] = [
+    (0, 0, IntervalName::PerfectUnison),
+    (0, 1, IntervalName::AugmentedUnison),
+    (0, 11, IntervalName::DiminishedOctave),
+    (1, 0, IntervalName::DiminishedSecond),
+    (1, 1, IntervalName::MinorSecond),
+    (1, 2, IntervalName::MajorSecond),
+    (1, 3, IntervalName::AugmentedSecond),
+    (2, 2, IntervalName::DiminishedThird),
+    (2, 3, IntervalName::MinorThird),
+    (2, 4, IntervalName::MajorThird),
+    (2, 5, IntervalName::AugmentedThird),
+    (3, 4, IntervalName::DiminishedFourth),
+    (3, 5, IntervalName::PerfectFourth),
+    (3, 6, IntervalName::AugmentedFourth),
+    (4, 6, IntervalName::DiminishedFifth),
+    (4, 7, IntervalName::PerfectFifth),
+    (4, 8, IntervalName::AugmentedFifth),
+    (5, 7, IntervalName::DiminishedSixth),
+    (5, 8, IntervalName::MinorSixth),
+    (5, 9, IntervalName::MajorSixth),
+    (5, 10, IntervalName::AugmentedSixth),
+    (6, 9, IntervalName::DiminishedSeventh),
+    (6, 10, IntervalName::MinorSeventh),
+    (6, 11, IntervalName::MajorSeventh),
+    (6, 0, IntervalName::AugmentedSeventh),
+];
+
+impl IntervalName {
+    /**
+     * Name the interval spanning the given number of degrees and semitones,
+     * where a unison spans 0 degrees.
+     */
+    fn from_degrees_and_semitones(degrees: u8, semitones: u8) -> IntervalName {
+        match NAMED_INTERVALS
+            .iter()
+            .find(|(named_degrees, named_semitones, _)| {
+                (*named_degrees, *named_semitones) == (degrees, semitones)
+            }) {
+            Some((_, _, interval_name)) => *interval_name,
+            None => IntervalName::Other {
+                number: degrees + 1,
+                semitones,
+            },
+        }
+    }
+
+    /**
+     * Get the number of the interval, 1 for a unison and 7 for a seventh,
+     * and the semitones it spans.
+     */
+    fn number_and_semitones(&self) -> (u8, u8) {
+        match self {
+            IntervalName::Other { number, semitones } => (*number, *semitones),
+            interval_name => NAMED_INTERVALS
+                .iter()
+                .find(|(_, _, named_interval)| named_interval == interval_name)
+                .map(|(degrees, semitones, _)| (degrees + 1, *semitones))
+                .expect("Every interval except Other is named"),
+        }
+    }
+}
+
+/**
+ * Write the name of the scale kind or the comma separated intervals of a Custom scale,
+ * which parse back to the same scale kind.
+ */
+impl std::fmt::Display for ScaleKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScaleKind::Custom(pattern) => {
+                let intervals: Vec<String> = pattern
+                    .intervals()
+                    .iter()
+                    .map(|interval| interval.to_string())
+                    .collect();
+                write!(f, "{}", intervals.join(","))
+            }
+            scale_kind => write!(f, "{}", scale_kind.name()),
+        }
+    }
+}
+
+impl std::str::FromStr for ScaleKind {
+    type Err = error::ScaleKindParseError;
+
+    /**
+     * Parse one of the scale kinds in `ScaleKind::ALL` from its name or a Custom scale
+     * from the comma separated semitones between its tones, e.g. "2,1,3,1,1,3,1".
+     */
+    fn from_str(s: &str) -> Result<ScaleKind, error::ScaleKindParseError> {
+        if let Some(scale_kind) = ScaleKind::ALL
+            .iter()
+            .find(|scale_kind| scale_kind.name() == s)
+        {
+            return Ok(scale_kind.clone());
+        }
+
+        let intervals: Option<Vec<u8>> = s
+            .split(',')
+            .map(|interval| interval.trim().parse().ok())
+            .collect();
+        match intervals.map(ScaleKind::custom) {
+            Some(Ok(scale_kind)) => Ok(scale_kind),
+            _ => Err(error::ScaleKindParseError::new(s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Tone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.accidental {
+            Accidental::Flat => write!(f, "{:?}b", self.note),
+            Accidental::Natural => write!(f, "{:?}", self.note),
+            Accidental::Sharp => write!(f, "{:?}#", self.note),
+        }
+    }
+}
+
+impl std::str::FromStr for Tone {
+    type Err = error::ToneParseError;
+
+    /**
+     * Parse a tone from its note name followed by an optional accidental, e.g. "C", "F#" or "Bb".
+     */
+    fn from_str(s: &str) -> Result<Tone, error::ToneParseError> {
+        let mut chars = s.trim().chars();
+
+        let note = match chars.next() {
+            Some('C') => Note::C,
+            Some('D') => Note::D,
+            Some('E') => Note::E,
+            Some('F') => Note::F,
+            Some('G') => Note::G,
+            Some('A') => Note::A,
+            Some('B') => Note::B,
+            _ => return Err(error::ToneParseError::new(s)),
+        };
+        let accidental = match chars.next() {
+            None => Accidental::Natural,
+            Some('#') => Accidental::Sharp,
+            Some('b') => Accidental::Flat,
+            Some(_) => return Err(error::ToneParseError::new(s)),
+        };
+
+        match chars.next() {
+            None => Ok(Tone::new(note, accidental)),
+            Some(_) => Err(error::ToneParseError::new(s)),
+        }
+    }
+}
+
+/**
+ * The number of semitones between the consecutive tones of a Custom scale,
+ * e.g. 2, 1, 3, 1, 1, 3, 1 for the hungarian minor scale. Clones share the intervals.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalPattern {
+    intervals: Arc<[u8]>,
+}
+
+impl IntervalPattern {
+    /**
+     * Create the pattern of the given intervals. It fails if there is no interval
+     * or one of them is zero, as every tone of a scale has to lie above the previous one.
+     */
+    pub fn new(intervals: impl Into<Vec<u8>>) -> Result<Self, error::IntervalPatternError> {
+        let intervals = intervals.into();
+
+        match intervals.is_empty() || intervals.contains(&0) {
+            true => Err(error::IntervalPatternError::new(&intervals)),
+            false => Ok(IntervalPattern {
+                intervals: intervals.into(),
+            }),
+        }
+    }
+
+    pub fn intervals(&self) -> &[u8] {
+        &self.intervals
+    }
 }
 
-#[derive(Debug)]
+/**
+ * The kinds of scales a Key can produce.
+ * A Custom scale is defined by the number of semitones between its consecutive tones,
+ * e.g. `ScaleKind::custom([2, 1, 3, 1, 1, 3, 1])` for the hungarian minor scale.
+ * The intervals don't have to add up to an octave, in which case the pattern
+ * is repeated from the last tone on.
+ * The Blues scale consists of the degrees 1, b3, 4, b5, 5 and b7, the FullBlues
+ * scale additionally contains the degrees 2, 3 and 6.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ScaleKind {
     Major,
     Minor,
     RelativeMinor,
     Chromatic,
+    Blues,
+    FullBlues,
+    Custom(IntervalPattern),
+}
+
+impl ScaleKind {
+    /**
+     * Create a Custom scale kind of the given intervals, see `IntervalPattern::new`.
+     */
+    pub fn custom(intervals: impl Into<Vec<u8>>) -> Result<Self, error::IntervalPatternError> {
+        Ok(ScaleKind::Custom(IntervalPattern::new(intervals)?))
+    }
+
+    /**
+     * Every scale kind except Custom, which isn't a single scale.
+     */
+    pub const ALL: &'static [ScaleKind] = &[
+        ScaleKind::Major,
+        ScaleKind::Minor,
+        ScaleKind::RelativeMinor,
+        ScaleKind::Chromatic,
+        ScaleKind::Blues,
+        ScaleKind::FullBlues,
+    ];
+
+    /**
+     * The name of the scale kind in kebab case, e.g. "relative-minor".
+     * All Custom scales are named "custom".
+     */
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScaleKind::Major => "major",
+            ScaleKind::Minor => "minor",
+            ScaleKind::RelativeMinor => "relative-minor",
+            ScaleKind::Chromatic => "chromatic",
+            ScaleKind::Blues => "blues",
+            ScaleKind::FullBlues => "full-blues",
+            ScaleKind::Custom(_) => "custom",
+        }
+    }
+
+    /**
+     * Get the number of tones in one repetition of the scale.
+     */
+    pub fn get_degrees_in_scale(&self) -> u8 {
+        match self {
+            ScaleKind::Major | ScaleKind::Minor | ScaleKind::RelativeMinor => DEGREES_IN_SCALE,
+            ScaleKind::Chromatic => OCTAVE_ADDITIVE,
+            ScaleKind::Blues => SEMITONES_IN_BLUES_SCALE.len() as u8,
+            ScaleKind::FullBlues => SEMITONES_IN_FULL_BLUES_SCALE.len() as u8,
+            ScaleKind::Custom(pattern) => pattern.intervals().len() as u8,
+        }
+    }
 }
 
 pub struct Key<T>
@@ -67,14 +601,14 @@ where
 {
     note: &'static Note,
     accidental: &'static Accidental,
-    temperament: Rc<T>,
+    temperament: Arc<T>,
 }
 
 impl<T> Key<T>
 where
     T: temperament::Temperament,
 {
-    pub fn new(note: &'static Note, accidental: &'static Accidental, temperament: Rc<T>) -> Self {
+    pub fn new(note: &'static Note, accidental: &'static Accidental, temperament: Arc<T>) -> Self {
         Key {
             note,
             accidental,
@@ -82,218 +616,1629 @@ where
         }
     }
 
-    /**
-     * Get the key of the respective position in the twelve-tone system.
-     * position - a position of 1 or 13 indicates the key of do
-     * major is a boolean value indicating whether the key is intended to
-     * to be used as a minor or major scale
-     */
-    fn key_by_position(&self, position: u8, major: bool) -> Option<Key<T>> {
-        let mut position: u8 = position - 1;
-        position %= OCTAVE_ADDITIVE;
-        position += 1;
+    /**
+     * Create the key built on the given tone.
+     */
+    pub fn from_tonic(tonic: Tone, temperament: Arc<T>) -> Self {
+        let (note, accidental) = tonic.as_static();
+        Key::new(note, accidental, temperament)
+    }
+
+    /**
+     * Get the key whose tonic lies the given interval above the tonic of this key,
+     * e.g. G major for C major and a perfect fifth. The tonic is spelled with the
+     * note name the interval leads to, unless that would need a double accidental.
+     * Cb is spelled as B like the tritone of `Tone::get_tritone`. The temperament doesn't
+     * depend on the tonic and is reused. Fails for an interval whose number isn't between 1 and 7.
+     */
+    pub fn modulate(&self, interval: IntervalName) -> Result<Key<T>, error::KeyCreationError> {
+        let (number, semitones) = interval.number_and_semitones();
+        if !(1..=DEGREES_IN_SCALE).contains(&number) {
+            return Err(error::KeyCreationError::new(number, DEGREES_IN_SCALE));
+        }
+
+        let tonic = self.tonic();
+        let new_tonic = Tone::spell(
+            Note::from_index(tonic.note.get_index() + number - 1),
+            (tonic.get_position() - 1 + semitones % OCTAVE_ADDITIVE) % OCTAVE_ADDITIVE + 1,
+        );
+
+        match new_tonic == Tone::new(Note::C, Accidental::Flat) {
+            true => Ok(self.with_tonic(Tone::new(Note::B, Accidental::Natural))),
+            false => Ok(self.with_tonic(new_tonic)),
+        }
+    }
+
+    /**
+     * Get the key built on the given tonic using the temperament of this key.
+     */
+    pub fn with_tonic(&self, tonic: Tone) -> Key<T> {
+        Key::from_tonic(tonic, Arc::clone(&self.temperament))
+    }
+
+    /**
+     * Get the 15 major keys with up to seven flats or sharps in their key signature in the
+     * order of the circle of fifths, from Cb to C# major. The keys share an equal temperament
+     * tuned to the Stuttgart pitch. The other tones of `Tone::all` are left out, as the keys
+     * built on them would need double flats or sharps.
+     */
+    pub fn all_major() -> impl Iterator<Item = Key<T>> {
+        let temperament = Arc::new(T::new(temperament::STUTTGART_PITCH));
+        Tone::all()
+            .skip(1)
+            .take(15)
+            .map(move |tonic| Key::from_tonic(tonic, Arc::clone(&temperament)))
+    }
+
+    /**
+     * Get the keys built on the tonics of the 15 minor keys with up to seven flats or sharps
+     * in their key signature like `all_major`, from Ab to A# minor. Their minor scales are
+     * calculated with `ScaleKind::Minor`.
+     */
+    pub fn all_minor() -> impl Iterator<Item = Key<T>> {
+        let temperament = Arc::new(T::new(temperament::STUTTGART_PITCH));
+        Tone::all()
+            .skip(4)
+            .take(15)
+            .map(move |tonic| Key::from_tonic(tonic, Arc::clone(&temperament)))
+    }
+
+    /**
+     * The tone this key is built on.
+     */
+    pub fn tonic(&self) -> Tone {
+        Tone::new(*self.note, *self.accidental)
+    }
+
+    /**
+     * Check whether both keys have the same tonic pitch, no matter how the tonics are
+     * spelled, e.g. C# major and Db major in equal temperament. The pitches of the tonics
+     * are calculated in the same octave by the temperament of each key and compared
+     * with a tolerance of a hundredth of a cent.
+     */
+    pub fn is_enharmonic_to<U: temperament::Temperament>(&self, other: &Key<U>) -> bool {
+        let pitch = self
+            .temperament
+            .get_pitch(CHORD_ROOT_OCTAVE, self.tonic().get_position() as i16);
+        let other_pitch = other
+            .temperament
+            .get_pitch(CHORD_ROOT_OCTAVE, other.tonic().get_position() as i16);
+
+        match (pitch, other_pitch) {
+            (Some(pitch), Some(other_pitch)) => {
+                pitch.cents_from(other_pitch).abs() <= ENHARMONIC_TOLERANCE_CENTS
+            }
+            _ => false,
+        }
+    }
+
+    /**
+     * Get the key built on the other spelling of the tonic with at most one accidental,
+     * e.g. Db major for C# major or B# major for C major. D, G and A have no such spelling.
+     */
+    pub fn enharmonic_equivalent(&self) -> Option<Key<T>> {
+        let tonic = self.tonic();
+
+        (0..DEGREES_IN_SCALE)
+            .map(Note::from_index)
+            .flat_map(|note| {
+                [Accidental::Flat, Accidental::Natural, Accidental::Sharp]
+                    .into_iter()
+                    .map(move |accidental| Tone::new(note, accidental))
+            })
+            .find(|tone| *tone != tonic && tone.get_position() == tonic.get_position())
+            .map(|tone| Key::from_tonic(tone, Arc::clone(&self.temperament)))
+    }
+
+    /**
+     * Get the degree of the major scale of this key whose tone is nearest to the pitch
+     * in any octave. Returns None if the nearest tone doesn't belong to the scale,
+     * i.e. the pitch isn't within half a semitone of one of the scale's tones.
+     */
+    pub fn get_degree_of_pitch(&self, pitch: Pitch) -> Option<u8> {
+        (1..=DEGREES_IN_SCALE).find(|degree| {
+            self.temperament
+                .get_pitch(CHORD_ROOT_OCTAVE, self.get_position(*degree) as i16)
+                .is_some_and(|tone_pitch| {
+                    let cents = pitch.cents_from(tone_pitch).rem_euclid(1200.0);
+                    cents.min(1200.0 - cents) < NEAREST_TONE_TOLERANCE_CENTS
+                })
+        })
+    }
+
+    /**
+     * Find the degree of the major scale of this key whose tone is nearest to the pitch like
+     * `Temperament::nearest_tone`, but only among the tones of the scale. Returns the degree,
+     * the octave of its tone and the distance of the pitch from it in cents, positive if the
+     * pitch is sharper. A pitch exactly between two tones of the scale belongs to the lower one.
+     */
+    pub fn nearest_degree(&self, pitch: Pitch) -> Option<(u8, i16, f64)> {
+        let positions: Vec<i16> = (1..=DEGREES_IN_SCALE)
+            .map(|degree| self.get_position(degree) as i16)
+            .collect();
+        let (position, octave, cents) =
+            temperament::nearest_position(pitch, &positions, |octave, position| {
+                self.temperament.get_pitch(octave, position)
+            })?;
+
+        let degree = positions.iter().position(|other| *other == position)? as u8 + 1;
+        // positions beyond the octave belong to the tones of the next one
+        let octave = octave + (position - 1).div_euclid(OCTAVE_ADDITIVE as i16);
+
+        Some((degree, octave, cents))
+    }
+
+    /**
+     * Move the pitch the given number of degrees up (positive) or down (negative)
+     * the major scale of this key, keeping its octave. The pitch is taken for the
+     * nearest tone like in `get_degree_of_pitch`, so the result is a tone of the scale.
+     * Returns None if the pitch doesn't belong to the scale.
+     */
+    pub fn shift_by_degrees(&self, pitch: Pitch, degrees: i8) -> Option<Pitch> {
+        let degree = self.get_degree_of_pitch(pitch)?;
+        let tone_pitch = self
+            .get_scale(&ScaleKind::Major, CHORD_ROOT_OCTAVE, degree, 1)?
+            .first()
+            .copied()?;
+        let octaves = (pitch.cents_from(tone_pitch) / 1200.0).round() as i16;
+
+        // count the degrees from the first degree of the octave of the tone
+        let index = degree as i16 - 1 + degrees as i16;
+        let degrees_in_scale = DEGREES_IN_SCALE as i16;
+        let shifted_pitch = self
+            .get_scale(
+                &ScaleKind::Major,
+                CHORD_ROOT_OCTAVE + index.div_euclid(degrees_in_scale),
+                (index.rem_euclid(degrees_in_scale) + 1) as u8,
+                1,
+            )?
+            .first()
+            .copied()?;
+
+        Some(shifted_pitch.shift_octaves(octaves))
+    }
+
+    /**
+     * Get the index of the tone in the array of `get_tones`, i.e. its degree of the major
+     * scale of this key counted from 0. Enharmonic spellings count as the same tone, e.g.
+     * Gb for F# in G major. Returns None if the tone doesn't belong to the scale.
+     */
+    pub fn position_of(&self, tone: Tone) -> Option<u8> {
+        self.get_tones()
+            .iter()
+            .position(|scale_tone| scale_tone.get_position() == tone.get_position())
+            .map(|position| position as u8)
+    }
+
+    /**
+     * Get the degree of the major scale of this key the tone belongs to, counted from 1
+     * like the degrees of `get_scale`. Returns None if the tone doesn't belong to the scale.
+     */
+    pub fn degree_of(&self, tone: Tone) -> Option<u8> {
+        self.position_of(tone).map(|position| position + 1)
+    }
+
+    /**
+     * Get the spelled tones of the major scale of this key, starting with the tonic.
+     */
+    pub fn get_tones(&self) -> [Tone; DEGREES_IN_SCALE as usize] {
+        let tonic_index = self.note.get_index();
+        let mut tones = [self.tonic(); DEGREES_IN_SCALE as usize];
+
+        for (degree, tone) in tones.iter_mut().enumerate() {
+            let position = (self.get_position(degree as u8 + 1) - 1) % OCTAVE_ADDITIVE + 1;
+            *tone = Tone::spell(Note::from_index(tonic_index + degree as u8), position);
+        }
+
+        tones
+    }
+
+    /**
+     * Get the tone one semitone above the given tone. If it belongs to the
+     * major scale of this key it is spelled as in the key, otherwise
+     * it is spelled with a sharp as is common for ascending voice leading.
+     */
+    pub fn chromatic_neighbour_above(&self, tone: Tone) -> Tone {
+        self.spell_in_key(tone.get_position() + 1, false)
+    }
+
+    /**
+     * Get the tone one semitone below the given tone. If it belongs to the
+     * major scale of this key it is spelled as in the key, otherwise
+     * it is spelled with a flat as is common for descending voice leading.
+     */
+    pub fn chromatic_neighbour_below(&self, tone: Tone) -> Tone {
+        self.spell_in_key(tone.get_position() + OCTAVE_ADDITIVE - 1, true)
+    }
+
+    /**
+     * Get the chromatic neighbours above and below the given target tone,
+     * from which the target can be approached.
+     */
+    pub fn chromatic_approach_tones(&self, target_tone: Tone) -> (Tone, Tone) {
+        (
+            self.chromatic_neighbour_above(target_tone),
+            self.chromatic_neighbour_below(target_tone),
+        )
+    }
+
+    /**
+     * Get the tones, with their pitches in the given octave, that chromatically connect
+     * the given degrees of the major scale of this key, excluding the degrees themselves.
+     * The tones are ordered from from_degree to to_degree, which descends if to_degree
+     * is lower. Ascending tones are spelled with sharps and descending ones with flats,
+     * unless they belong to the key. Degrees a semitone apart have no passing tones.
+     */
+    pub fn get_passing_tones(
+        &self,
+        from_degree: u8,
+        to_degree: u8,
+        octave: i16,
+    ) -> Vec<(Tone, Pitch)> {
+        if from_degree < 1 || to_degree < 1 {
+            return vec![];
+        }
+
+        let from_position = self.get_position(from_degree);
+        let to_position = self.get_position(to_degree);
+
+        let positions: Vec<u8> = if from_position < to_position {
+            (from_position + 1..to_position).collect()
+        } else {
+            (to_position + 1..from_position).rev().collect()
+        };
+
+        positions
+            .into_iter()
+            .filter_map(|position| {
+                let tone = self.spell_in_key(position, from_position > to_position);
+                self.temperament
+                    .get_pitch(octave, position as i16)
+                    .map(|pitch| (tone, pitch))
+            })
+            .collect()
+    }
+
+    /**
+     * Get the major key whose dominant is the chord on the given degree of this key,
+     * i.e. the key built a perfect fifth above the root of that degree. In C major
+     * the secondary dominant of the second degree (D minor) is A major.
+     * The temperament of this key is reused.
+     */
+    pub fn get_secondary_dominant_key(
+        &self,
+        target_degree: u8,
+    ) -> Result<Key<T>, error::KeyCreationError> {
+        if !(1..=DEGREES_IN_SCALE).contains(&target_degree) {
+            return Err(error::KeyCreationError::new(
+                target_degree,
+                DEGREES_IN_SCALE,
+            ));
+        }
+
+        let root = self.get_tones()[target_degree as usize - 1];
+        // a perfect fifth spans five note names and seven semitones
+        let fifth = Tone::spell(
+            Note::from_index(root.note.get_index() + 4),
+            (root.get_position() + 6) % OCTAVE_ADDITIVE + 1,
+        );
+        let (note, accidental) = fifth.as_static();
+
+        Ok(Key::new(note, accidental, Arc::clone(&self.temperament)))
+    }
+
+    /**
+     * Get the key whose tonic lies a tritone (six semitones) above the tonic of this
+     * key, as used for a tritone substitution, e.g. Gb major for C major.
+     * The tonic is spelled by `tritone_of_tone` and the temperament of this key is reused,
+     * the scale kind is chosen when getting the scale of the returned key.
+     * It modulates by a diminished fifth, which has a valid number, so it doesn't fail
+     * for any key.
+     */
+    pub fn get_tritone_substitution(&self) -> Result<Key<T>, error::KeyCreationError> {
+        self.modulate(IntervalName::DiminishedFifth)
+    }
+
+    /**
+     * Get the tone a tritone away from the given tone, see `Tone::get_tritone`.
+     */
+    pub fn tritone_of_tone(&self, tone: Tone) -> Tone {
+        tone.get_tritone()
+    }
+
+    /**
+     * Get the pitches of the chord with the given symbol, e.g. "Cmaj7" or "F#m".
+     * The symbol consists of the root (a note name optionally followed by '#' or 'b')
+     * and a quality: major (no suffix or "maj"), "m"/"min", "dim", "aug"/"+", and the
+     * seventh chords "7", "maj7", "m7"/"min7", "m7b5", "dim7" and "aug7".
+     * The root doesn't have to belong to this key and lies in octave 4, the other
+     * pitches are stacked above it using the temperament of this key.
+     * Returns None if the symbol is invalid.
+     */
+    pub fn parse_chord(&self, symbol: &str) -> Option<Vec<Pitch>> {
+        let mut chars = symbol.chars();
+        let note = match chars.next()? {
+            'C' => Note::C,
+            'D' => Note::D,
+            'E' => Note::E,
+            'F' => Note::F,
+            'G' => Note::G,
+            'A' => Note::A,
+            'B' => Note::B,
+            _ => return None,
+        };
+        let quality = chars.as_str();
+        let (accidental, quality) = match quality.chars().next() {
+            Some('#') => (Accidental::Sharp, &quality[1..]),
+            Some('b') => (Accidental::Flat, &quality[1..]),
+            _ => (Accidental::Natural, quality),
+        };
+
+        let intervals: &[i16] = match quality {
+            "" | "maj" => &[0, 4, 7],
+            "m" | "min" => &[0, 3, 7],
+            "dim" => &[0, 3, 6],
+            "aug" | "+" => &[0, 4, 8],
+            "7" => &[0, 4, 7, 10],
+            "maj7" => &[0, 4, 7, 11],
+            "m7" | "min7" => &[0, 3, 7, 10],
+            "m7b5" => &[0, 3, 6, 10],
+            "dim7" => &[0, 3, 6, 9],
+            "aug7" => &[0, 4, 8, 10],
+            _ => return None,
+        };
+
+        let root = Tone::new(note, accidental).get_position_in_octave();
+
+        intervals
+            .iter()
+            .map(|interval| {
+                self.temperament
+                    .get_pitch(CHORD_ROOT_OCTAVE, root + interval)
+            })
+            .collect()
+    }
+
+    /**
+     * Spell the tone nearest to the pitch, see `Temperament::nearest_tone`, and get its octave.
+     * A tone of the scale is spelled like in the scale, any other tone with a flat if
+     * prefer_flats is set and with a sharp otherwise, e.g. A# or Bb in C major.
+     * The octave is the one of the note name, so B#4 in C# major sounds like C5.
+     */
+    pub fn spell(&self, pitch: Pitch, prefer_flats: bool) -> Option<(Tone, i16)> {
+        let (nearest_tone, octave, _) = self.temperament.nearest_tone(pitch)?;
+        let tone = self.spell_in_key(nearest_tone.get_position(), prefer_flats);
+
+        let octave = match (nearest_tone.note, tone.note) {
+            (Note::C, Note::B) => octave - 1,
+            (Note::B, Note::C) => octave + 1,
+            _ => octave,
+        };
+
+        Some((tone, octave))
+    }
+
+    fn spell_in_key(&self, position: u8, prefer_flats: bool) -> Tone {
+        let tone = Tone::from_position(position, prefer_flats);
+
+        match self
+            .get_tones()
+            .iter()
+            .find(|scale_tone| scale_tone.get_position() == tone.get_position())
+        {
+            Some(scale_tone) => *scale_tone,
+            None => tone,
+        }
+    }
+
+    /**
+     * Invert the major scale of this key around its tonic. Every interval
+     * of the scale is mirrored, so that the scale descends from the tonic
+     * with the intervals the major scale ascends with. The inversion
+     * of C major is C Bb Ab G F Eb Db, which contains the tones of C phrygian.
+     * Tones that would need a double accidental are spelled enharmonically.
+     */
+    pub fn invert_scale(&self) -> [Tone; DEGREES_IN_SCALE as usize] {
+        let tonic_index = self.note.get_index();
+        let tonic_position = self.tonic().get_position();
+        let mut tones = [self.tonic(); DEGREES_IN_SCALE as usize];
+
+        for (degree, tone) in tones.iter_mut().enumerate() {
+            let interval = SEMITONES_IN_MAJOR_SCALE[0..degree].iter().sum::<u8>();
+            let position = (tonic_position + OCTAVE_ADDITIVE - 1 - interval) % OCTAVE_ADDITIVE + 1;
+            *tone = Tone::spell(
+                Note::from_index(tonic_index + DEGREES_IN_SCALE - degree as u8),
+                position,
+            );
+        }
+
+        tones
+    }
+
+    /**
+     * Get the spelled tones of one repetition of the custom scale with the given intervals,
+     * starting with the tonic. Scales with seven tones use every note name once. Other scales
+     * are spelled with flats if more of their tones lie a minor rather than a major interval
+     * above the tonic (e.g. the blues scale), and with sharps otherwise.
+     */
+    pub fn get_custom_tones(&self, intervals: &[u8]) -> Vec<Tone> {
+        let tonic = self.tonic();
+        let tonic_position = tonic.get_position() as u16;
+
+        let positions: Vec<u8> = (0..intervals.len())
+            .map(|degree| {
+                let interval = intervals[0..degree].iter().map(|i| *i as u16).sum::<u16>();
+                ((tonic_position - 1 + interval) % OCTAVE_ADDITIVE as u16 + 1) as u8
+            })
+            .collect();
+
+        if intervals.len() == DEGREES_IN_SCALE as usize {
+            return positions
+                .iter()
+                .enumerate()
+                .map(|(degree, position)| {
+                    Tone::spell(
+                        Note::from_index(tonic.note.get_index() + degree as u8),
+                        *position,
+                    )
+                })
+                .collect();
+        }
+
+        let mut minor_intervals = 0;
+        let mut major_intervals = 0;
+
+        for position in &positions {
+            match (*position as u16 + OCTAVE_ADDITIVE as u16 - tonic_position)
+                % OCTAVE_ADDITIVE as u16
+            {
+                1 | 3 | 8 | 10 => minor_intervals += 1,
+                2 | 4 | 9 | 11 => major_intervals += 1,
+                _ => {}
+            }
+        }
+
+        let prefer_flats = minor_intervals > major_intervals;
+
+        positions
+            .iter()
+            .enumerate()
+            .map(|(degree, position)| match degree {
+                0 => tonic,
+                _ => Tone::from_position(*position, prefer_flats),
+            })
+            .collect()
+    }
+
+    /**
+     * Get the key of the respective position in the twelve-tone system.
+     * position - a position of 1 or 13 indicates the key of do
+     * major is a boolean value indicating whether the key is intended to
+     * to be used as a minor or major scale
+     */
+    fn key_by_position(&self, position: u8, major: bool) -> Option<Key<T>> {
+        let mut position: u8 = position - 1;
+        position %= OCTAVE_ADDITIVE;
+        position += 1;
+
+        let temperament: Arc<T> = Arc::clone(&self.temperament);
+
+        match position {
+            1 => Some(Key::new(&Note::C, &Accidental::Natural, temperament)),
+            2 => Some(match major {
+                true => Key::new(&Note::C, &Accidental::Sharp, temperament),
+                false => Key::new(&Note::D, &Accidental::Flat, temperament),
+            }),
+            3 => Some(Key::new(&Note::D, &Accidental::Natural, temperament)),
+            4 => Some(match major {
+                true => Key::new(&Note::D, &Accidental::Sharp, temperament),
+                false => Key::new(&Note::E, &Accidental::Flat, temperament),
+            }),
+            5 => Some(Key::new(&Note::E, &Accidental::Natural, temperament)),
+            6 => Some(Key::new(&Note::F, &Accidental::Natural, temperament)),
+            7 => Some(match major {
+                true => Key::new(&Note::F, &Accidental::Sharp, temperament),
+                false => Key::new(&Note::G, &Accidental::Flat, temperament),
+            }),
+            8 => Some(Key::new(&Note::G, &Accidental::Natural, temperament)),
+            9 => Some(match major {
+                true => Key::new(&Note::G, &Accidental::Sharp, temperament),
+                false => Key::new(&Note::A, &Accidental::Flat, temperament),
+            }),
+            10 => Some(Key::new(&Note::A, &Accidental::Natural, temperament)),
+            11 => Some(match major {
+                true => Key::new(&Note::A, &Accidental::Sharp, temperament),
+                false => Key::new(&Note::B, &Accidental::Flat, temperament),
+            }),
+            12 => Some(Key::new(&Note::B, &Accidental::Natural, temperament)),
+            _ => None,
+        }
+    }
+
+    fn get_degree(&self, position: u8) -> Option<u8> {
+        let mut position = position - 1;
+        position %= OCTAVE_ADDITIVE;
+        position += 1;
+
+        for degree in 1..(DEGREES_IN_SCALE + 1) {
+            let mut position_of_degree = self.get_position(degree) - 1;
+            position_of_degree %= OCTAVE_ADDITIVE;
+            position_of_degree += 1;
+
+            if position == position_of_degree {
+                return Some(degree);
+            }
+        }
+
+        None
+    }
+
+    /**
+     * Get the position of the tone in the twelve-tone system based
+     * on the given scale-degree of the major scale.
+     * For the Key of Mi the positions for the
+     * degrees from 1 to 7 would be the following.
+     * degree:   1  2  3  4  5  6  7 |  8 / 1
+     * position: 4  6  8  9 11 13 15 | 16 (-12 = 4)
+     *             +2 +2 +1 +2 +2 +2 | +1
+     */
+    fn get_position(&self, degree: u8) -> u8 {
+        let mut end: u8 = degree - 1;
+
+        let mut position: u8 = 0;
+
+        if end > DEGREES_IN_SCALE {
+            end -= DEGREES_IN_SCALE;
+            let octaves: u8 = end / DEGREES_IN_SCALE;
+            end %= DEGREES_IN_SCALE;
+            position += (octaves + 1) * OCTAVE_ADDITIVE;
+            position += SEMITONES_IN_MAJOR_SCALE[0..end as usize].iter().sum::<u8>();
+        } else {
+            position = SEMITONES_IN_MAJOR_SCALE[0..end as usize].iter().sum::<u8>();
+        }
+
+        let offset = SEMITONES_IN_MAJOR_SCALE[0..self.note.get_index() as usize]
+            .iter()
+            .sum::<u8>();
+
+        // the tonic Cb lies below the C of the octave, so it wraps to the position of B
+        position += match self.accidental {
+            Accidental::Flat => (offset + OCTAVE_ADDITIVE - 1) % OCTAVE_ADDITIVE,
+            Accidental::Natural => offset,
+            Accidental::Sharp => offset + 1,
+        };
+
+        position + 1
+    }
+
+    /**
+     * Calculate an array of consecutive pitches of the given scale using the given Temperament.
+     * The Pitches will start in the given octave with the given scale-degree and comprise the given
+     * number of pitches.
+     */
+    pub fn get_scale(
+        &self,
+        scale_kind: &ScaleKind,
+        octave: i16,
+        degree: u8,
+        number_of_pitches: u8,
+    ) -> Option<Vec<Pitch>> {
+        match scale_kind {
+            ScaleKind::Major => {
+                let mut pitches: Vec<Pitch> = vec![];
+
+                for degree in degree..(degree + number_of_pitches) {
+                    match self
+                        .temperament
+                        .get_pitch(octave, self.get_position(degree) as i16)
+                    {
+                        Some(pitch) => pitches.push(pitch),
+                        None => return None,
+                    }
+                }
+
+                Some(pitches)
+            }
+            ScaleKind::RelativeMinor => {
+                let mut degree = degree - 1;
+                degree -= 5;
+                degree %= DEGREES_IN_SCALE;
+                degree += 1;
+
+                let submediant = self.get_position(1 + 5);
+
+                match self.key_by_position(submediant, false) {
+                    Some(relative_minor) => relative_minor.get_scale(
+                        &ScaleKind::Major,
+                        octave,
+                        degree,
+                        number_of_pitches,
+                    ),
+                    None => None,
+                }
+            }
+            ScaleKind::Minor => {
+                // The natural minor scale has the same tones as the major scale of its relative
+                // major key three semitones above the tonic, e.g. A minor and C major. The tonic
+                // is the sixth degree of the relative major, so the degrees of the minor scale
+                // are the ones of the relative major shifted by five. The octave is corrected
+                // by the octaves between the tonic and the sixth degree of the relative major.
+                let tonic = self.get_position(1);
+                match self.key_by_position(tonic + 3, false) {
+                    Some(minor) => {
+                        let mapped_tonic_degree = minor.get_degree(tonic).unwrap();
+                        let mapped_tonic = minor.get_position(mapped_tonic_degree);
+
+                        let octave = octave
+                            + ((tonic as i8 - mapped_tonic as i8) / OCTAVE_ADDITIVE as i8) as i16;
+
+                        minor.get_scale(
+                            &ScaleKind::Major,
+                            octave,
+                            mapped_tonic_degree + (degree - 1),
+                            number_of_pitches,
+                        )
+                    }
+                    None => None,
+                }
+            }
+            ScaleKind::Chromatic => {
+                let mut pitches: Vec<Pitch> = vec![];
+
+                for degree in degree..(degree + number_of_pitches) {
+                    match self.temperament.get_pitch(octave, degree as i16) {
+                        Some(pitch) => pitches.push(pitch),
+                        None => return None,
+                    }
+                }
+
+                Some(pitches)
+            }
+            ScaleKind::Blues => self.get_interval_scale(
+                &SEMITONES_IN_BLUES_SCALE,
+                octave,
+                degree,
+                number_of_pitches,
+            ),
+            ScaleKind::FullBlues => self.get_interval_scale(
+                &SEMITONES_IN_FULL_BLUES_SCALE,
+                octave,
+                degree,
+                number_of_pitches,
+            ),
+            ScaleKind::Custom(pattern) => {
+                self.get_interval_scale(pattern.intervals(), octave, degree, number_of_pitches)
+            }
+        }
+    }
+
+    /**
+     * Calculate the triad built on the given scale-degree in the given octave by stacking
+     * the third and the fifth degree above it in the scale. The degree counts from 1 and may
+     * reach into the following octaves like the pitches of `get_scale`.
+     */
+    pub fn triad(&self, scale_kind: &ScaleKind, octave: i16, degree: u8) -> Option<[Pitch; 3]> {
+        if degree == 0 {
+            return None;
+        }
+
+        let pitches = self.get_scale(scale_kind, octave, 1, degree.checked_add(4)?)?;
+        let root = degree as usize - 1;
+
+        Some([pitches[root], pitches[root + 2], pitches[root + 4]])
+    }
+
+    /**
+     * Calculate consecutive pitches of the given scale walking downward like `get_scale`
+     * walks upward: the pitches start in the given octave with the given scale-degree and
+     * each one is a degree below the previous one, moving to the octave below after the
+     * first degree. Custom scales, whose intervals don't add up to an octave, can't be walked
+     * downward across the first degree and return None.
+     */
+    pub fn get_scale_desc(
+        &self,
+        scale_kind: &ScaleKind,
+        octave: i16,
+        degree: u8,
+        number_of_pitches: u8,
+    ) -> Option<Vec<Pitch>> {
+        if degree < 1 || number_of_pitches == 0 {
+            return Some(vec![]);
+        }
+
+        let degrees_in_scale = scale_kind.get_degrees_in_scale() as i16;
+        // the degrees below the first one of the start octave, which lie in lower octaves
+        let degrees_below_first = number_of_pitches as i16 - degree as i16;
+        let octaves_down = match degrees_below_first > 0 {
+            true => (degrees_below_first + degrees_in_scale - 1) / degrees_in_scale,
+            false => 0,
+        };
+
+        if let ScaleKind::Custom(pattern) = scale_kind {
+            let repetition = pattern.intervals().iter().map(|i| *i as u16).sum::<u16>();
+            if octaves_down > 0 && repetition != OCTAVE_ADDITIVE as u16 {
+                return None;
+            }
+        }
+
+        // the lowest pitch, from which the scale is calculated upward
+        let lowest_degree =
+            degree as i16 + octaves_down * degrees_in_scale - (number_of_pitches as i16 - 1);
+        let mut pitches = self.get_scale(
+            scale_kind,
+            octave - octaves_down,
+            u8::try_from(lowest_degree).ok()?,
+            number_of_pitches,
+        )?;
+        pitches.reverse();
+
+        Some(pitches)
+    }
+
+    /**
+     * Calculate the pitches of a scale given by the semitones between its consecutive tones.
+     * The degrees are counted across repetitions of the intervals, so any number of tones
+     * per repetition is supported.
+     */
+    fn get_interval_scale(
+        &self,
+        intervals: &[u8],
+        octave: i16,
+        degree: u8,
+        number_of_pitches: u8,
+    ) -> Option<Vec<Pitch>> {
+        if intervals.is_empty() || degree < 1 {
+            return None;
+        }
+
+        let tonic = self.get_position(1) as i16;
+        let repetition = intervals.iter().map(|i| *i as i16).sum::<i16>();
+        let mut pitches: Vec<Pitch> = vec![];
+
+        for degree in (degree as usize - 1)..(degree as usize - 1 + number_of_pitches as usize) {
+            let repetitions = (degree / intervals.len()) as i16;
+            let interval = intervals[0..(degree % intervals.len())]
+                .iter()
+                .map(|i| *i as i16)
+                .sum::<i16>();
+
+            match self
+                .temperament
+                .get_pitch(octave, tonic + repetitions * repetition + interval)
+            {
+                Some(pitch) => pitches.push(pitch),
+                None => return None,
+            }
+        }
+
+        Some(pitches)
+    }
+}
+
+impl Key<temperament::EqualTemperament> {
+    /**
+     * Create a Key using twelve-tone equal temperament tuned to the given pitch standard.
+     */
+    pub fn equal_tempered(
+        note: &'static Note,
+        accidental: &'static Accidental,
+        pitch_standard: f64,
+    ) -> Self {
+        Key::new(
+            note,
+            accidental,
+            Arc::new(
+                <temperament::EqualTemperament as temperament::Temperament>::new(pitch_standard),
+            ),
+        )
+    }
+}
+
+impl<T> std::fmt::Display for Key<T>
+where
+    T: temperament::Temperament,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.tonic())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        harmonic_series, temperament::EqualTemperament, temperament::Temperament,
+        temperament::BAROQUE_PITCH, temperament::STUTTGART_PITCH, Accidental, IntervalName,
+        IntervalPattern, Key, Note, Pitch, ScaleKind, Tone,
+    };
+
+    use std::sync::Arc;
+
+    fn format_tones(tones: &[Tone]) -> String {
+        tones
+            .iter()
+            .map(|tone| format!("{}", tone))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_equal_tempered_key() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::equal_tempered(&Note::E, &Accidental::Flat, STUTTGART_PITCH);
+
+        assert_eq!(format!("{}", key), "Eb");
+        assert_eq!(
+            format!("{:.3?}", key.get_scale(&ScaleKind::Major, 4, 1, 7)),
+            format!(
+                "{:.3?}",
+                Key::new(&Note::E, &Accidental::Flat, temp).get_scale(&ScaleKind::Major, 4, 1, 7)
+            )
+        );
+    }
+
+    #[test]
+    fn test_passing_tones() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, temp);
+
+        let format_passing_tones = |passing_tones: Vec<(Tone, Pitch)>| {
+            passing_tones
+                .iter()
+                .map(|(tone, pitch)| format!("{} {:.3?}", tone, pitch))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        assert_eq!(
+            format_passing_tones(key.get_passing_tones(1, 2, 4)),
+            "C# Pitch(277.183)"
+        );
+        assert_eq!(
+            format_passing_tones(key.get_passing_tones(2, 1, 4)),
+            "Db Pitch(277.183)"
+        );
+        assert!(key.get_passing_tones(3, 4, 4).is_empty());
+        assert!(key.get_passing_tones(4, 3, 4).is_empty());
+        assert!(key.get_passing_tones(5, 5, 4).is_empty());
+        assert!(key.get_passing_tones(0, 2, 4).is_empty());
+
+        // E to G is a minor third
+        assert_eq!(
+            format_passing_tones(key.get_passing_tones(3, 5, 4)),
+            "F Pitch(349.228), F# Pitch(369.994)"
+        );
+        assert_eq!(
+            format_passing_tones(key.get_passing_tones(5, 3, 3)),
+            "Gb Pitch(184.997), F Pitch(174.614)"
+        );
+
+        // C and D of Eb major lie in the octave above the tonic
+        let key = Key::new(&Note::E, &Accidental::Flat, Arc::clone(&key.temperament));
+        assert_eq!(
+            format_passing_tones(key.get_passing_tones(6, 7, 4)),
+            "C# Pitch(554.365)"
+        );
+    }
+
+    #[test]
+    fn test_parse_chord() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::G, &Accidental::Natural, temp);
+
+        assert_eq!(
+            format!("{:.3?}", key.parse_chord("Cmaj7")),
+            /*C_4, E_4, G_4, B_4*/
+            "Some([Pitch(261.626), Pitch(329.628), Pitch(391.995), Pitch(493.883)])"
+        );
+        assert_eq!(
+            format!("{:.3?}", key.parse_chord("Am")),
+            /*A_4, C_5, E_5*/
+            "Some([Pitch(440.000), Pitch(523.251), Pitch(659.255)])"
+        );
+        assert_eq!(
+            format!("{:.3?}", key.parse_chord("Bbdim")),
+            /*Bb_4, Db_5, Fb_5*/
+            "Some([Pitch(466.164), Pitch(554.365), Pitch(659.255)])"
+        );
+        assert_eq!(
+            key.parse_chord("F#aug7").map(|pitches| pitches.len()),
+            Some(4)
+        );
+
+        // Cb4 is B3 and B#4 is C5
+        assert_eq!(
+            format!("{:.3?}", key.parse_chord("Cb")),
+            /*Cb_4, Eb_4, Gb_4*/
+            "Some([Pitch(246.942), Pitch(311.127), Pitch(369.994)])"
+        );
+        assert_eq!(
+            format!("{:.3?}", key.parse_chord("B#m")),
+            /*B#_4, D#_5, F##_5*/
+            "Some([Pitch(523.251), Pitch(622.254), Pitch(783.991)])"
+        );
+
+        for symbol in ["", "H", "c", "Cmajor", "C##", "Amm"] {
+            assert!(key.parse_chord(symbol).is_none(), "{}", symbol);
+        }
+    }
+
+    #[test]
+    fn test_nearest_degree() {
+        let temperament = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let nearest = |tonic: &str, hz: f64| {
+            Key::from_tonic(tonic.parse().unwrap(), Arc::clone(&temperament))
+                .nearest_degree(Pitch(hz))
+                .map(|(degree, octave, cents)| format!("{} {} {:+.1}", degree, octave, cents))
+        };
+
+        assert_eq!(nearest("C", 440.0).as_deref(), Some("6 4 +0.0"));
+        assert_eq!(nearest("C", 1050.0).as_deref(), Some("1 6 +5.8"));
+        // C#4 lies exactly between C4 and D4
+        let c_sharp = 440.0 * 2f64.powf(-8.0 / 12.0);
+        assert_eq!(nearest("C", c_sharp).as_deref(), Some("1 4 +100.0"));
+        assert_eq!(nearest("D", 280.0).as_deref(), Some("7 4 +17.5"));
+        // the second degree of B major is C#, which lies in the octave above B
+        assert_eq!(nearest("B", 560.0).as_deref(), Some("2 5 +17.5"));
+        assert_eq!(nearest("C", 0.0), None);
+    }
+
+    #[test]
+    fn test_spell() {
+        let temperament = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let spelled = |tonic: &str, hz: f64, prefer_flats: bool| {
+            let (tone, octave) = Key::from_tonic(tonic.parse().unwrap(), Arc::clone(&temperament))
+                .spell(Pitch(hz), prefer_flats)
+                .unwrap();
+            format!("{}{}", tone, octave)
+        };
+
+        assert_eq!(spelled("C", 466.164, false), "A#4");
+        assert_eq!(spelled("C", 466.164, true), "Bb4");
+        assert_eq!(spelled("C", 440.0, true), "A4");
+        // tones of the scale keep their spelling
+        assert_eq!(spelled("F", 466.164, false), "Bb4");
+        assert_eq!(spelled("E", 415.305, true), "G#4");
+        assert_eq!(spelled("C#", 523.251, true), "B#4");
+        assert_eq!(spelled("Gb", 493.883, false), "Cb5");
+    }
+
+    #[test]
+    fn test_key_from_parsed_tonic() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::from_tonic("C".parse().unwrap(), temp);
+        assert_eq!(
+            format!("{:.3?}", key.get_scale(&ScaleKind::Major, 4, 1, 1)),
+            "Some([Pitch(261.626)])"
+        );
+
+        let temp = Arc::new(EqualTemperament::new(BAROQUE_PITCH));
+        let key = Key::from_tonic("F#".parse().unwrap(), temp);
+        assert_eq!(key.tonic().to_string(), "F#");
+        assert!(key.is_enharmonic_to(&Key::new(
+            &Note::G,
+            &Accidental::Flat,
+            Arc::new(EqualTemperament::new(BAROQUE_PITCH))
+        )));
+    }
+
+    #[test]
+    fn test_harmonic_series() {
+        assert_eq!(
+            harmonic_series(Pitch(65.0), 4),
+            vec![Pitch(65.0), Pitch(130.0), Pitch(195.0), Pitch(260.0)]
+        );
+        assert!(harmonic_series(Pitch(65.0), 0).is_empty());
+    }
+
+    #[test]
+    fn test_interval_to() {
+        let tone = |s: &str| s.parse::<Tone>().unwrap();
+
+        assert_eq!(tone("C").interval_to(tone("E")), IntervalName::MajorThird);
+        assert_eq!(
+            tone("C").interval_to(tone("F#")),
+            IntervalName::AugmentedFourth
+        );
+        assert_eq!(
+            tone("C").interval_to(tone("Gb")),
+            IntervalName::DiminishedFifth
+        );
+        assert_eq!(tone("E").interval_to(tone("C")), IntervalName::MinorSixth);
+        assert_eq!(tone("G").interval_to(tone("D")), IntervalName::PerfectFifth);
+        assert_eq!(tone("B").interval_to(tone("C")), IntervalName::MinorSecond);
+        assert_eq!(
+            tone("A").interval_to(tone("A")),
+            IntervalName::PerfectUnison
+        );
+        assert_eq!(
+            tone("C").interval_to(tone("Cb")),
+            IntervalName::DiminishedOctave
+        );
+        assert_eq!(
+            tone("Cb").interval_to(tone("F#")),
+            IntervalName::Other {
+                number: 4,
+                semitones: 7
+            }
+        );
+    }
+
+    #[test]
+    fn test_modulate() {
+        let c_major = Key::new(
+            &Note::C,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        );
+
+        let modulated = |key: &Key<EqualTemperament>, interval| {
+            key.modulate(interval).unwrap().tonic().to_string()
+        };
+        assert_eq!(modulated(&c_major, IntervalName::PerfectFifth), "G");
+        assert_eq!(modulated(&c_major, IntervalName::PerfectFourth), "F");
+        assert_eq!(modulated(&c_major, IntervalName::MinorThird), "Eb");
+        assert_eq!(modulated(&c_major, IntervalName::AugmentedFourth), "F#");
+        assert_eq!(modulated(&c_major, IntervalName::DiminishedFifth), "Gb");
+        assert_eq!(modulated(&c_major, IntervalName::PerfectUnison), "C");
+
+        let b_major = c_major.modulate(IntervalName::MajorSeventh).unwrap();
+        assert_eq!(b_major.tonic().to_string(), "B");
+        assert_eq!(modulated(&b_major, IntervalName::PerfectFifth), "F#");
+        assert_eq!(modulated(&b_major, IntervalName::MajorSecond), "C#");
+
+        // Gb major modulates to B major rather than Cb major
+        let g_flat_major = c_major.modulate(IntervalName::DiminishedFifth).unwrap();
+        assert_eq!(modulated(&g_flat_major, IntervalName::PerfectFourth), "B");
+
+        assert!(c_major
+            .modulate(IntervalName::Other {
+                number: 9,
+                semitones: 14
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_all_keys() {
+        assert_eq!(Tone::all().count(), 21);
+        assert_eq!(
+            Tone::all()
+                .map(|tone| tone.to_string())
+                .collect::<Vec<String>>()
+                .join(" "),
+            "Fb Cb Gb Db Ab Eb Bb F C G D A E B F# C# G# D# A# E# B#"
+        );
+
+        let major: Vec<Key<EqualTemperament>> = Key::all_major().collect();
+        assert_eq!(major.len(), 15);
+        assert_eq!(major[0].tonic().to_string(), "Cb");
+        assert_eq!(major[7].tonic().to_string(), "C");
+        assert_eq!(major[14].tonic().to_string(), "C#");
+        // neighbouring keys are a fifth apart
+        for keys in major.windows(2) {
+            assert_eq!(
+                (keys[1].tonic().get_position() + 12 - keys[0].tonic().get_position()) % 12,
+                7
+            );
+        }
+
+        let minor: Vec<Key<EqualTemperament>> = Key::all_minor().collect();
+        assert_eq!(minor.len(), 15);
+        assert_eq!(minor[0].tonic().to_string(), "Ab");
+        assert_eq!(minor[7].tonic().to_string(), "A");
+        assert_eq!(minor[14].tonic().to_string(), "A#");
+
+        // every key calculates its scales, which rise from the tonic
+        for key in major.iter().chain(&minor) {
+            for scale_kind in [&ScaleKind::Major, &ScaleKind::Minor] {
+                let scale = key.get_scale(scale_kind, 4, 1, 8).unwrap();
+                assert!(
+                    scale
+                        .windows(2)
+                        .all(|pitches| pitches[0].get_hz() < pitches[1].get_hz()),
+                    "{}",
+                    key
+                );
+                assert_eq!(
+                    key.spell(scale[0], key.tonic().accidental == Accidental::Flat)
+                        .map(|(tone, _)| tone.get_position()),
+                    Some(key.tonic().get_position()),
+                    "{}",
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_degree_of() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, temp);
+
+        assert_eq!(key.degree_of("C".parse().unwrap()), Some(1));
+        assert_eq!(key.degree_of("B".parse().unwrap()), Some(7));
+        assert_eq!(key.degree_of("F#".parse().unwrap()), None);
+        assert_eq!(key.position_of("E".parse().unwrap()), Some(2));
+        assert_eq!(key.position_of("Eb".parse().unwrap()), None);
+
+        let key = Key::new(&Note::G, &Accidental::Natural, Arc::clone(&key.temperament));
+        assert_eq!(key.degree_of("F#".parse().unwrap()), Some(7));
+        assert_eq!(key.degree_of("Gb".parse().unwrap()), Some(7));
+        assert_eq!(key.degree_of("F".parse().unwrap()), None);
+
+        for (position, tone) in key.get_tones().into_iter().enumerate() {
+            assert_eq!(key.position_of(tone), Some(position as u8));
+        }
+    }
+
+    #[test]
+    fn test_triad() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, temp);
+
+        assert_eq!(
+            format!("{:.3?}", key.triad(&ScaleKind::Major, 4, 1)),
+            /*C_4, E_4, G_4*/
+            "Some([Pitch(261.626), Pitch(329.628), Pitch(391.995)])"
+        );
+        assert_eq!(
+            format!("{:.3?}", key.triad(&ScaleKind::Major, 4, 6)),
+            /*A_4, C_5, E_5*/
+            "Some([Pitch(440.000), Pitch(523.251), Pitch(659.255)])"
+        );
+        assert_eq!(
+            key.triad(&ScaleKind::Major, 4, 8),
+            key.triad(&ScaleKind::Major, 5, 1)
+        );
+        assert!(key.triad(&ScaleKind::Major, 4, 0).is_none());
+    }
+
+    #[test]
+    fn test_scale_kind_names() {
+        assert_eq!(ScaleKind::ALL.len(), 6);
+
+        for scale_kind in ScaleKind::ALL {
+            assert_eq!(scale_kind.name().parse::<ScaleKind>().unwrap(), *scale_kind);
+        }
+
+        assert_eq!(ScaleKind::custom([2, 2]).unwrap().name(), "custom");
+        assert_eq!(
+            "custom".parse::<ScaleKind>().unwrap_err().to_string(),
+            "Unknown scale kind 'custom', expected one of major, minor, relative-minor, \
+             chromatic, blues, full-blues or the semitones between the tones like '2,1,3,1,1,3,1'."
+        );
+    }
+
+    #[test]
+    fn test_custom_scale_kind_parsing() {
+        let hungarian_minor = ScaleKind::custom([2, 1, 3, 1, 1, 3, 1]).unwrap();
+        assert_eq!(
+            "2,1,3,1,1,3,1".parse::<ScaleKind>().unwrap(),
+            hungarian_minor
+        );
+        assert_eq!(
+            " 2, 1,3,1,1,3 ,1".parse::<ScaleKind>().unwrap(),
+            hungarian_minor
+        );
+        assert_eq!(hungarian_minor.to_string(), "2,1,3,1,1,3,1");
+        assert_eq!(ScaleKind::Minor.to_string(), "minor");
+
+        assert!("2,0,3".parse::<ScaleKind>().is_err());
+        assert!("2,,3".parse::<ScaleKind>().is_err());
+        assert!("2,256".parse::<ScaleKind>().is_err());
+    }
+
+    #[test]
+    fn test_interval_pattern() {
+        let pattern = IntervalPattern::new(vec![3, 2, 1, 1, 3, 2]).unwrap();
+        assert_eq!(pattern.intervals(), &[3, 2, 1, 1, 3, 2]);
+        assert_eq!(pattern.clone(), pattern);
+
+        assert_eq!(
+            IntervalPattern::new([]).unwrap_err().to_string(),
+            "The intervals [] of a scale have to be at least one positive number of semitones."
+        );
+        assert_eq!(
+            IntervalPattern::new([2, 0, 2]).unwrap_err().to_string(),
+            "The intervals [2, 0, 2] of a scale have to be at least one positive number of semitones."
+        );
+    }
+
+    #[test]
+    fn test_scale_kinds_cover_every_variant() {
+        // a new scale kind has to be given its position in ALL here, unless it's as generic as Custom
+        let position = |scale_kind: &ScaleKind| match scale_kind {
+            ScaleKind::Major => Some(0),
+            ScaleKind::Minor => Some(1),
+            ScaleKind::RelativeMinor => Some(2),
+            ScaleKind::Chromatic => Some(3),
+            ScaleKind::Blues => Some(4),
+            ScaleKind::FullBlues => Some(5),
+            ScaleKind::Custom(_) => None,
+        };
+
+        for (index, scale_kind) in ScaleKind::ALL.iter().enumerate() {
+            assert_eq!(position(scale_kind), Some(index), "{}", scale_kind.name());
+        }
+        assert_eq!(ScaleKind::ALL.len(), 6);
+        assert_eq!(position(&ScaleKind::custom([1]).unwrap()), None);
+    }
+
+    #[test]
+    fn test_custom_major_scale() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let major_pattern = ScaleKind::custom([2, 2, 1, 2, 2, 2, 1]).unwrap();
+
+        for (note, accidental) in [
+            (&Note::C, &Accidental::Natural),
+            (&Note::G, &Accidental::Flat),
+            (&Note::A, &Accidental::Natural),
+        ] {
+            let key = Key::new(note, accidental, Arc::clone(&temp));
+            let major = key.get_scale(&ScaleKind::Major, 4, 1, 15).unwrap();
+            let custom = key.get_scale(&major_pattern, 4, 1, 15).unwrap();
+            assert_eq!(format!("{:.3?}", custom), format!("{:.3?}", major));
+
+            let major = key.get_scale(&ScaleKind::Major, 3, 5, 4).unwrap();
+            let custom = key.get_scale(&major_pattern, 3, 5, 4).unwrap();
+            assert_eq!(format!("{:.3?}", custom), format!("{:.3?}", major));
+        }
+    }
+
+    #[test]
+    fn test_custom_blues_scale() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, temp);
+        let blues = ScaleKind::custom([3, 2, 1, 1, 3, 2]).unwrap();
+
+        match key.get_scale(&blues, 4, 1, 7) {
+            Some(pitches) => {
+                assert_eq!(pitches.len(), 7);
+                assert_eq!(format!("{:.3?}", pitches[0]), "Pitch(261.626)" /*C_4*/);
+                assert_eq!(
+                    format!("{:.3?}", pitches[1]),
+                    "Pitch(311.127)" /*Eb_4*/
+                );
+                assert_eq!(format!("{:.3?}", pitches[2]), "Pitch(349.228)" /*F_4*/);
+                assert_eq!(
+                    format!("{:.3?}", pitches[3]),
+                    "Pitch(369.994)" /*Gb_4*/
+                );
+                assert_eq!(format!("{:.3?}", pitches[4]), "Pitch(391.995)" /*G_4*/);
+                assert_eq!(
+                    format!("{:.3?}", pitches[5]),
+                    "Pitch(466.164)" /*Bb_4*/
+                );
+                assert_eq!(format!("{:.3?}", pitches[6]), "Pitch(523.251)" /*C_5*/);
+            }
+            None => panic!("expected some pitches"),
+        }
+
+        assert_eq!(
+            format_tones(&key.get_custom_tones(&[3, 2, 1, 1, 3, 2])),
+            "C Eb F Gb G Bb"
+        );
+        assert_eq!(blues.get_degrees_in_scale(), 6);
+    }
+
+    #[test]
+    fn test_blues_scale() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, temp);
 
-        let temperament: Rc<T> = Rc::clone(&self.temperament);
+        let pitches = key.get_scale(&ScaleKind::Blues, 4, 1, 8).unwrap();
+        assert_eq!(pitches.len(), 8);
+        assert_eq!(
+            format!("{:.3?}", &pitches[0..6]),
+            /*C_4, Eb_4, F_4, Gb_4, G_4, Bb_4*/
+            "[Pitch(261.626), Pitch(311.127), Pitch(349.228), Pitch(369.994), Pitch(391.995), Pitch(466.164)]"
+        );
+        // the next repetition starts an octave higher
+        assert_eq!(
+            format!("{:.3?}", pitches[6]),
+            format!("{:.3?}", Pitch(pitches[0].0 * 2.0))
+        );
+        assert_eq!(
+            format!("{:.3?}", pitches[7]),
+            format!("{:.3?}", Pitch(pitches[1].0 * 2.0))
+        );
 
-        let key = match position {
-            1 => Some(Key::new(&Note::C, &Accidental::Natural, temperament)),
-            2 => Some(match major {
-                true => Key::new(&Note::C, &Accidental::Sharp, temperament),
-                false => Key::new(&Note::D, &Accidental::Flat, temperament),
-            }),
-            3 => Some(Key::new(&Note::D, &Accidental::Natural, temperament)),
-            4 => Some(match major {
-                true => Key::new(&Note::D, &Accidental::Sharp, temperament),
-                false => Key::new(&Note::E, &Accidental::Flat, temperament),
-            }),
-            5 => Some(Key::new(&Note::E, &Accidental::Natural, temperament)),
-            6 => Some(Key::new(&Note::F, &Accidental::Natural, temperament)),
-            7 => Some(match major {
-                true => Key::new(&Note::F, &Accidental::Sharp, temperament),
-                false => Key::new(&Note::G, &Accidental::Flat, temperament),
-            }),
-            8 => Some(Key::new(&Note::G, &Accidental::Natural, temperament)),
-            9 => Some(match major {
-                true => Key::new(&Note::G, &Accidental::Sharp, temperament),
-                false => Key::new(&Note::A, &Accidental::Flat, temperament),
-            }),
-            10 => Some(Key::new(&Note::A, &Accidental::Natural, temperament)),
-            11 => Some(match major {
-                true => Key::new(&Note::A, &Accidental::Sharp, temperament),
-                false => Key::new(&Note::B, &Accidental::Flat, temperament),
-            }),
-            12 => Some(Key::new(&Note::B, &Accidental::Natural, temperament)),
-            _ => None,
-        };
+        // starting on the fifth degree crosses the octave after two tones
+        let pitches = key.get_scale(&ScaleKind::Blues, 4, 5, 3).unwrap();
+        assert_eq!(
+            format!("{:.3?}", pitches),
+            /*G_4, Bb_4, C_5*/
+            "[Pitch(391.995), Pitch(466.164), Pitch(523.251)]"
+        );
 
-        return key;
+        assert_eq!(ScaleKind::Blues.get_degrees_in_scale(), 6);
+        assert_eq!(
+            format_tones(&key.get_custom_tones(&super::SEMITONES_IN_BLUES_SCALE)),
+            "C Eb F Gb G Bb"
+        );
     }
 
-    fn get_degree(&self, position: u8) -> Option<u8> {
-        let mut position = position - 1;
-        position %= OCTAVE_ADDITIVE;
-        position += 1;
+    #[test]
+    fn test_full_blues_scale() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, temp);
 
-        for degree in 1..(DEGREES_IN_SCALE + 1) {
-            let mut position_of_degree = self.get_position(degree) - 1;
-            position_of_degree %= OCTAVE_ADDITIVE;
-            position_of_degree += 1;
+        let pitches = key.get_scale(&ScaleKind::FullBlues, 4, 1, 10).unwrap();
+        assert_eq!(
+            format!("{:.3?}", pitches),
+            /*C_4, D_4, Eb_4, E_4, F_4, Gb_4, G_4, A_4, Bb_4, C_5*/
+            "[Pitch(261.626), Pitch(293.665), Pitch(311.127), Pitch(329.628), Pitch(349.228), \
+             Pitch(369.994), Pitch(391.995), Pitch(440.000), Pitch(466.164), Pitch(523.251)]"
+        );
+        assert_eq!(ScaleKind::FullBlues.get_degrees_in_scale(), 9);
+    }
 
-            if position == position_of_degree {
-                return Some(degree);
+    #[test]
+    fn test_custom_scale_tones() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+
+        let key = Key::new(&Note::A, &Accidental::Natural, Arc::clone(&temp));
+        // hungarian minor
+        assert_eq!(
+            format_tones(&key.get_custom_tones(&[2, 1, 3, 1, 1, 3, 1])),
+            "A B C D# E F G#"
+        );
+
+        let key = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
+        // major pentatonic
+        assert_eq!(
+            format_tones(&key.get_custom_tones(&[2, 2, 3, 2, 3])),
+            "C D E G A"
+        );
+    }
+
+    #[test]
+    fn test_custom_non_octave_scale() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, temp);
+
+        // a scale of fifths repeating every two fifths
+        match key.get_scale(&ScaleKind::custom([7]).unwrap(), 4, 1, 3) {
+            Some(pitches) => {
+                assert_eq!(format!("{:.3?}", pitches[0]), "Pitch(261.626)" /*C_4*/);
+                assert_eq!(format!("{:.3?}", pitches[1]), "Pitch(391.995)" /*G_4*/);
+                assert_eq!(format!("{:.3?}", pitches[2]), "Pitch(587.330)" /*D_5*/);
             }
+            None => panic!("expected some pitches"),
         }
+    }
 
-        return None;
+    #[test]
+    fn test_fold_below() {
+        assert_eq!(
+            format!("{:.3?}", Pitch(16744.036).fold_below(Pitch(10000.0))),
+            "Pitch(8372.018)"
+        );
+        assert_eq!(
+            format!("{:.3?}", Pitch(16744.036).fold_below(Pitch(1000.0))),
+            "Pitch(523.251)"
+        );
+        assert_eq!(
+            format!("{:.3?}", Pitch(440.0).fold_below(Pitch(1000.0))),
+            "Pitch(440.000)"
+        );
+        assert_eq!(
+            format!("{:.3?}", Pitch(880.0).fold_below(Pitch(880.0))),
+            "Pitch(880.000)"
+        );
     }
 
-    /**
-     * Get the position of the tone in the twelve-tone system based
-     * on the given scale-degree of the major scale.
-     * For the Key of Mi the positions for the
-     * degrees from 1 to 7 would be the following.
-     * degree:   1  2  3  4  5  6  7 |  8 / 1
-     * position: 4  6  8  9 11 13 15 | 16 (-12 = 4)
-     *             +2 +2 +1 +2 +2 +2 | +1
-     */
-    fn get_position(&self, degree: u8) -> u8 {
-        let mut end: u8 = degree - 1;
+    #[test]
+    fn test_shift_octaves() {
+        assert_eq!(Pitch(440.0).shift_octaves(2), Pitch(1760.0));
+        assert_eq!(Pitch(440.0).shift_octaves(-3), Pitch(55.0));
+        assert_eq!(Pitch(440.0).shift_octaves(0), Pitch(440.0));
+    }
 
-        let mut position: u8 = 0;
+    #[test]
+    fn test_cents_from() {
+        assert_eq!(
+            format!("{:.3?}", Pitch(880.0).cents_from(Pitch(440.0))),
+            "1200.000"
+        );
+        assert_eq!(
+            format!("{:.3?}", Pitch(440.0).cents_from(Pitch(880.0))),
+            "-1200.000"
+        );
+        assert_eq!(
+            format!("{:.3?}", Pitch(466.1638).cents_from(Pitch(440.0))),
+            "100.000"
+        );
+        assert_eq!(Pitch(440.0).cents_from(Pitch(440.0)), 0.0);
+    }
 
-        if end > DEGREES_IN_SCALE {
-            end -= DEGREES_IN_SCALE;
-            let octaves: u8 = end / DEGREES_IN_SCALE;
-            end %= DEGREES_IN_SCALE;
-            position += (octaves + 1) * OCTAVE_ADDITIVE;
-            position += SEMITONES_IN_MAJOR_SCALE[0..end as usize].iter().sum::<u8>();
-        } else {
-            position = SEMITONES_IN_MAJOR_SCALE[0..end as usize].iter().sum::<u8>();
+    #[test]
+    fn test_tone_position() {
+        assert_eq!(Tone::new(Note::C, Accidental::Natural).get_position(), 1);
+        assert_eq!(Tone::new(Note::C, Accidental::Flat).get_position(), 12);
+        assert_eq!(Tone::new(Note::B, Accidental::Sharp).get_position(), 1);
+        assert_eq!(Tone::new(Note::G, Accidental::Flat).get_position(), 7);
+        assert_eq!(Tone::new(Note::F, Accidental::Sharp).get_position(), 7);
+        assert_eq!(Tone::new(Note::A, Accidental::Natural).get_position(), 10);
+
+        let in_octave = |note, accidental| Tone::new(note, accidental).get_position_in_octave();
+        assert_eq!(in_octave(Note::C, Accidental::Flat), 0);
+        assert_eq!(in_octave(Note::B, Accidental::Sharp), 13);
+        assert_eq!(in_octave(Note::G, Accidental::Flat), 7);
+        assert_eq!(in_octave(Note::A, Accidental::Natural), 10);
+    }
+
+    #[test]
+    fn test_parse_tone() {
+        assert_eq!(
+            "C".parse::<Tone>().unwrap(),
+            Tone::new(Note::C, Accidental::Natural)
+        );
+        assert_eq!(
+            "F#".parse::<Tone>().unwrap(),
+            Tone::new(Note::F, Accidental::Sharp)
+        );
+        assert_eq!(
+            "Bb".parse::<Tone>().unwrap(),
+            Tone::new(Note::B, Accidental::Flat)
+        );
+
+        for invalid in ["", "H", "c", "C##", "Bx"] {
+            assert!(invalid.parse::<Tone>().is_err(), "{}", invalid);
         }
+        assert_eq!(
+            "H".parse::<Tone>().unwrap_err().to_string(),
+            "'H' is not a tone like 'C', 'F#' or 'Bb'."
+        );
 
-        let offset = SEMITONES_IN_MAJOR_SCALE[0..self.note.get_index() as usize]
-            .iter()
-            .sum::<u8>();
-        position += offset;
+        let key = Key::from_tonic(
+            "Eb".parse().unwrap(),
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        );
+        assert_eq!(key.tonic(), Tone::new(Note::E, Accidental::Flat));
+    }
 
-        position = match self.accidental {
-            Accidental::Flat => position - 1,
-            Accidental::Natural => position,
-            Accidental::Sharp => position + 1,
-        };
+    #[test]
+    fn test_get_tones() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+
+        let key = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
+        assert_eq!(format_tones(&key.get_tones()), "C D E F G A B");
+
+        let key = Key::new(&Note::E, &Accidental::Flat, Arc::clone(&temp));
+        assert_eq!(format_tones(&key.get_tones()), "Eb F G Ab Bb C D");
 
-        return position + 1;
+        let key = Key::new(&Note::F, &Accidental::Sharp, Arc::clone(&temp));
+        assert_eq!(format_tones(&key.get_tones()), "F# G# A# B C# D# E#");
     }
 
-    /**
-     * Calculate an array of consecutive pitches of the given scale using the given Temperament.
-     * The Pitches will start in the given octave with the given scale-degree and comprise the given
-     * number of pitches.
-     */
-    pub fn get_scale(
-        &self,
-        scale_kind: &'static ScaleKind,
-        octave: i16,
-        degree: u8,
-        number_of_pitches: u8,
-    ) -> Option<Vec<Pitch>> {
-        match scale_kind {
-            ScaleKind::Major => {
-                let mut pitches: Vec<Pitch> = vec![];
+    #[test]
+    fn test_chromatic_neighbours() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
 
-                for degree in degree..(degree + number_of_pitches) {
-                    match self
-                        .temperament
-                        .get_pitch(octave, self.get_position(degree) as i16)
-                    {
-                        Some(pitch) => pitches.push(pitch),
-                        None => return None,
-                    }
-                }
+        let b = Tone::new(Note::B, Accidental::Natural);
+        let c = Tone::new(Note::C, Accidental::Natural);
+        let e = Tone::new(Note::E, Accidental::Natural);
+        let f = Tone::new(Note::F, Accidental::Natural);
+        let d = Tone::new(Note::D, Accidental::Natural);
 
-                return Some(pitches);
-            }
-            ScaleKind::RelativeMinor => {
-                let mut degree = degree - 1;
-                degree -= 5;
-                degree %= DEGREES_IN_SCALE;
-                degree += 1;
+        assert_eq!(format!("{}", key.chromatic_neighbour_above(b)), "C");
+        assert_eq!(format!("{}", key.chromatic_neighbour_below(c)), "B");
+        assert_eq!(format!("{}", key.chromatic_neighbour_above(e)), "F");
+        assert_eq!(format!("{}", key.chromatic_neighbour_above(f)), "F#");
+        assert_eq!(format!("{}", key.chromatic_neighbour_below(d)), "Db");
 
-                let submediant = self.get_position(1 + 5);
+        let (above, below) = key.chromatic_approach_tones(d);
+        assert_eq!(format!("{} {}", above, below), "D# Db");
 
-                match self.key_by_position(submediant, false) {
-                    Some(relative_minor) => relative_minor.get_scale(
-                        &ScaleKind::Major,
-                        octave,
-                        degree as u8,
-                        number_of_pitches,
-                    ),
-                    None => None,
-                }
-            }
-            ScaleKind::Minor => {
-                let tonic = self.get_position(1);
-                match self.key_by_position(tonic + 3, false) {
-                    Some(minor) => {
-                        let mapped_tonic_degree = minor.get_degree(tonic).unwrap();
-                        let mapped_tonic = minor.get_position(mapped_tonic_degree);
+        // the spelling of the key takes precedence
+        let key = Key::new(&Note::F, &Accidental::Natural, Arc::clone(&temp));
+        let a = Tone::new(Note::A, Accidental::Natural);
+        assert_eq!(format!("{}", key.chromatic_neighbour_above(a)), "Bb");
 
-                        let octave = octave
-                            + ((tonic as i8 - mapped_tonic as i8) / OCTAVE_ADDITIVE as i8) as i16;
+        let key = Key::new(&Note::D, &Accidental::Natural, Arc::clone(&temp));
+        let g = Tone::new(Note::G, Accidental::Natural);
+        assert_eq!(format!("{}", key.chromatic_neighbour_below(g)), "F#");
+    }
 
-                        return minor.get_scale(
-                            &ScaleKind::Major,
-                            octave,
-                            mapped_tonic_degree + (degree - 1),
-                            number_of_pitches,
-                        );
-                    }
-                    None => None,
-                }
-            }
-            ScaleKind::Chromatic => {
-                let mut pitches: Vec<Pitch> = vec![];
+    #[test]
+    fn test_secondary_dominant_key() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
 
-                for degree in degree..(degree + number_of_pitches) {
-                    match self.temperament.get_pitch(octave, degree as i16) {
-                        Some(pitch) => pitches.push(pitch),
-                        None => return None,
-                    }
-                }
+        let secondary_dominants: Vec<String> = (1..=7)
+            .map(|degree| format!("{}", key.get_secondary_dominant_key(degree).unwrap()))
+            .collect();
+        assert_eq!(secondary_dominants, ["G", "A", "B", "C", "D", "E", "F#"]);
+
+        let key = Key::new(&Note::D, &Accidental::Flat, Arc::clone(&temp));
+        assert_eq!(
+            format!("{}", key.get_secondary_dominant_key(1).unwrap()),
+            "Ab"
+        );
+        assert_eq!(
+            format!("{}", key.get_secondary_dominant_key(7).unwrap()),
+            "G"
+        );
+
+        let key = Key::new(&Note::F, &Accidental::Sharp, Arc::clone(&temp));
+        // the fifth above E# is B#
+        assert_eq!(
+            format!("{}", key.get_secondary_dominant_key(7).unwrap()),
+            "B#"
+        );
 
-                return Some(pitches);
+        for degree in [0, 8] {
+            match key.get_secondary_dominant_key(degree) {
+                Err(e) => assert_eq!(
+                    format!("{}", e),
+                    format!("The scale degree {} is not between 1 and 7.", degree)
+                ),
+                Ok(_) => panic!("Created the secondary dominant of an invalid degree."),
             }
         }
     }
-}
 
-impl<T> std::fmt::Display for Key<T>
-where
-    T: temperament::Temperament,
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.accidental {
-            Accidental::Flat => write!(f, "{:?}b", self.note),
-            Accidental::Natural => write!(f, "{:?}", self.note),
-            Accidental::Sharp => write!(f, "{:?}#", self.note),
+    #[test]
+    fn test_tritone_substitution() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
+
+        let substitution = key.get_tritone_substitution().unwrap();
+        assert_eq!(format!("{}", substitution), "Gb");
+        assert_eq!(
+            format!("{}", substitution.get_tritone_substitution().unwrap()),
+            "C"
+        );
+
+        let tritones: Vec<String> = ["C", "F#", "Gb", "F", "B", "A#", "B#", "E"]
+            .iter()
+            .map(|tone| format!("{}", key.tritone_of_tone(tone.parse().unwrap())))
+            .collect();
+        assert_eq!(tritones, ["Gb", "C", "C", "B", "F", "E", "F#", "Bb"]);
+        assert_eq!(
+            key.tritone_of_tone(Tone::new(Note::F, Accidental::Natural)),
+            Tone::new(Note::F, Accidental::Natural).get_tritone()
+        );
+
+        // twice the tritone away is the same pitch class for every tonic
+        for position in 1..=12 {
+            let key = Key::from_tonic(Tone::from_position(position, false), Arc::clone(&temp));
+            let substitution = key.get_tritone_substitution().unwrap();
+            assert_eq!(
+                substitution.tonic(),
+                key.tritone_of_tone(key.tonic()),
+                "{}",
+                key
+            );
+
+            let twice = substitution.get_tritone_substitution().unwrap();
+            assert!(twice.is_enharmonic_to(&key), "{}", key);
+            assert!(twice.get_scale(&ScaleKind::Major, 4, 1, 7).is_some());
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::{
-        temperament::EqualTemperament, temperament::Temperament, temperament::STUTTGART_PITCH,
-        Accidental, Key, Note, ScaleKind,
-    };
+    #[test]
+    fn test_invert_scale() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+
+        let key = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
+        assert_eq!(format_tones(&key.invert_scale()), "C Bb Ab G F Eb Db");
+
+        let key = Key::new(&Note::D, &Accidental::Natural, Arc::clone(&temp));
+        assert_eq!(format_tones(&key.invert_scale()), "D C Bb A G F Eb");
 
-    use std::rc::Rc;
+        // Gb would need the double flats Ebb and Bbb
+        let key = Key::new(&Note::G, &Accidental::Flat, Arc::clone(&temp));
+        assert_eq!(format_tones(&key.invert_scale()), "Gb Fb D Db Cb A G");
+    }
 
     #[test]
     fn test_get_position() {
-        let temp = Rc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
 
-        let key = Key::new(&Note::C, &Accidental::Natural, Rc::clone(&temp));
+        let key = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
         assert_eq!(key.get_position(1), 1); // c
         assert_eq!(key.get_position(2), 3); // d
         assert_eq!(key.get_position(3), 5); // e
@@ -310,7 +2255,7 @@ mod tests {
         assert_eq!(key.get_position(14), 24); // b
         assert_eq!(key.get_position(15), 25); // c
 
-        let key = Key::new(&Note::G, &Accidental::Natural, Rc::clone(&temp));
+        let key = Key::new(&Note::G, &Accidental::Natural, Arc::clone(&temp));
         assert_eq!(key.get_position(1), 8); // g
         assert_eq!(key.get_position(2), 10); // a
         assert_eq!(key.get_position(3), 12); // b
@@ -330,7 +2275,7 @@ mod tests {
 
     #[test]
     fn test_key_c_natural_major() {
-        let temp = Rc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
         let key = Key::new(&Note::C, &Accidental::Natural, temp);
         match key.get_scale(&ScaleKind::Major, 4, 1, 8) {
             Some(pitches) => {
@@ -350,7 +2295,7 @@ mod tests {
 
     #[test]
     fn test_key_g_flat_minor() {
-        let temp = Rc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
         let key = Key::new(&Note::G, &Accidental::Flat, temp);
         match key.get_scale(&ScaleKind::Minor, 4, 1, 8) {
             Some(pitches) => {
@@ -398,7 +2343,7 @@ mod tests {
 
     #[test]
     fn test_key_f_sharp_minor() {
-        let temp = Rc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
         let key = Key::new(&Note::F, &Accidental::Sharp, temp);
         match key.get_scale(&ScaleKind::Minor, 4, 1, 8) {
             Some(pitches) => {
@@ -443,4 +2388,246 @@ mod tests {
             None => panic!("expected some pitches"),
         }
     }
+
+    /**
+     * Compare the natural minor scale of the key starting with the given degree in octave 4
+     * to the pitches of twelve-tone equal temperament at the given positions.
+     */
+    fn assert_minor_scale(
+        note: &'static Note,
+        accidental: &'static Accidental,
+        degree: u8,
+        positions: &[i16],
+    ) {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(note, accidental, Arc::clone(&temp));
+
+        let expected: Vec<String> = positions
+            .iter()
+            .map(|position| format!("{:.3?}", temp.get_pitch(4, *position).unwrap()))
+            .collect();
+        let pitches: Vec<String> = key
+            .get_scale(&ScaleKind::Minor, 4, degree, positions.len() as u8)
+            .unwrap()
+            .iter()
+            .map(|pitch| format!("{:.3?}", pitch))
+            .collect();
+
+        assert_eq!(pitches, expected, "{} minor from degree {}", key, degree);
+    }
+
+    #[test]
+    fn test_octave_boundaries_of_keys_not_starting_on_c() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let format = |pitches: Vec<Pitch>| -> Vec<String> {
+            pitches
+                .iter()
+                .map(|pitch| format!("{:.3}", pitch.get_hz()))
+                .collect()
+        };
+
+        // G4 A4 B4 C5 D5 E5 F#5 G5, the octave changes between B4 and C5
+        let g = Key::new(&Note::G, &Accidental::Natural, Arc::clone(&temp));
+        assert_eq!(
+            format(g.get_scale(&ScaleKind::Major, 4, 1, 8).unwrap()),
+            vec![
+                "391.995", "440.000", "493.883", "523.251", "587.330", "659.255", "739.989",
+                "783.991"
+            ]
+        );
+
+        // Gb4 Ab4 Bb4 Cb5 Db5 Eb5 F5 Gb5, Cb5 sounds like B4 but belongs to the upper octave
+        let g_flat = Key::new(&Note::G, &Accidental::Flat, Arc::clone(&temp));
+        assert_eq!(
+            format(g_flat.get_scale(&ScaleKind::Major, 4, 1, 8).unwrap()),
+            vec![
+                "369.994", "415.305", "466.164", "493.883", "554.365", "622.254", "698.456",
+                "739.989"
+            ]
+        );
+
+        // B4 C#5 D#5 E5 F#5 G#5 A#5 B5, the octave changes right after the tonic
+        let b = Key::new(&Note::B, &Accidental::Natural, Arc::clone(&temp));
+        assert_eq!(
+            format(b.get_scale(&ScaleKind::Major, 4, 1, 8).unwrap()),
+            vec![
+                "493.883", "554.365", "622.254", "659.255", "739.989", "830.609", "932.328",
+                "987.767"
+            ]
+        );
+
+        // the pitches rise through every boundary over two octaves
+        for key in [&g, &g_flat, &b] {
+            let pitches = key.get_scale(&ScaleKind::Major, 3, 1, 15).unwrap();
+            assert!(pitches
+                .windows(2)
+                .all(|pair| pair[0].get_hz() < pair[1].get_hz()));
+            assert!((pitches[14].get_hz() / pitches[0].get_hz() - 4.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_descending_scales() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let key = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
+        let format = |pitches: Vec<Pitch>| -> Vec<String> {
+            pitches
+                .iter()
+                .map(|pitch| format!("{:.3}", pitch.get_hz()))
+                .collect()
+        };
+
+        // C5 B4 A4 G4 F4 E4 D4 C4
+        assert_eq!(
+            format(key.get_scale_desc(&ScaleKind::Major, 5, 1, 8).unwrap()),
+            vec![
+                "523.251", "493.883", "440.000", "391.995", "349.228", "329.628", "293.665",
+                "261.626"
+            ]
+        );
+        // E5 D5 C5 B4 A4 down across two octave boundaries to A3
+        assert_eq!(
+            format(key.get_scale_desc(&ScaleKind::Major, 5, 3, 10).unwrap()),
+            vec![
+                "659.255", "587.330", "523.251", "493.883", "440.000", "391.995", "349.228",
+                "329.628", "293.665", "261.626"
+            ]
+        );
+        assert_eq!(
+            format(key.get_scale_desc(&ScaleKind::Major, 4, 5, 3).unwrap()),
+            format(
+                key.get_scale(&ScaleKind::Major, 4, 3, 3)
+                    .unwrap()
+                    .into_iter()
+                    .rev()
+                    .collect()
+            )
+        );
+
+        // A5 G5 F5 E5 D5 C5 B4 A4
+        let a_minor = Key::new(&Note::A, &Accidental::Natural, Arc::clone(&temp));
+        let descending = a_minor.get_scale_desc(&ScaleKind::Minor, 5, 1, 8).unwrap();
+        let mut ascending = a_minor.get_scale(&ScaleKind::Minor, 4, 1, 8).unwrap();
+        ascending.reverse();
+        assert_eq!(format(descending), format(ascending));
+
+        // a custom scale repeating after a fifth can't be walked below its first degree
+        let fifths = ScaleKind::custom([4, 3]).unwrap();
+        assert!(key.get_scale_desc(&fifths, 4, 2, 2).is_some());
+        assert!(key.get_scale_desc(&fifths, 4, 1, 2).is_none());
+    }
+
+    #[test]
+    fn test_minor_scales() {
+        // A4 B4 C5 D5 E5 F5 G5 A5
+        assert_minor_scale(
+            &Note::A,
+            &Accidental::Natural,
+            1,
+            &[10, 12, 13, 15, 17, 18, 20, 22],
+        );
+        // E4 F#4 G4 A4 B4 C5 D5 E5
+        assert_minor_scale(
+            &Note::E,
+            &Accidental::Natural,
+            1,
+            &[5, 7, 8, 10, 12, 13, 15, 17],
+        );
+        // Bb4 C5 Db5 Eb5 F5 Gb5 Ab5 Bb5
+        assert_minor_scale(
+            &Note::B,
+            &Accidental::Flat,
+            1,
+            &[11, 13, 14, 16, 18, 19, 21, 23],
+        );
+        // F#4 G#4 A4 B4 C#5 D5 E5 F#5
+        assert_minor_scale(
+            &Note::F,
+            &Accidental::Sharp,
+            1,
+            &[7, 9, 10, 12, 14, 15, 17, 19],
+        );
+        // C4 D4 Eb4 F4 G4 Ab4 Bb4 C5
+        assert_minor_scale(
+            &Note::C,
+            &Accidental::Natural,
+            1,
+            &[1, 3, 4, 6, 8, 9, 11, 13],
+        );
+    }
+
+    #[test]
+    fn test_minor_scales_from_later_degrees() {
+        // C5 D5 E5 F5 G5 A5 B5 C6 D6 of A minor
+        assert_minor_scale(
+            &Note::A,
+            &Accidental::Natural,
+            3,
+            &[13, 15, 17, 18, 20, 22, 24, 25, 27],
+        );
+        // A4 B4 C5 of E minor
+        assert_minor_scale(&Note::E, &Accidental::Natural, 4, &[10, 12, 13]);
+        // Bb5 C6 of Bb minor, an octave above the tonic
+        assert_minor_scale(&Note::B, &Accidental::Flat, 8, &[23, 25]);
+        // E5 F#5 G#5 of F# minor
+        assert_minor_scale(&Note::F, &Accidental::Sharp, 7, &[17, 19, 21]);
+    }
+
+    #[test]
+    fn test_is_enharmonic_to() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let c_sharp = Key::new(&Note::C, &Accidental::Sharp, Arc::clone(&temp));
+        let d_flat = Key::new(&Note::D, &Accidental::Flat, Arc::clone(&temp));
+        let c = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
+        let g = Key::new(&Note::G, &Accidental::Natural, Arc::clone(&temp));
+
+        assert!(c_sharp.is_enharmonic_to(&d_flat));
+        assert!(d_flat.is_enharmonic_to(&c_sharp));
+        assert!(c.is_enharmonic_to(&c));
+        assert!(!c.is_enharmonic_to(&g));
+        assert!(Key::new(&Note::B, &Accidental::Sharp, Arc::clone(&temp)).is_enharmonic_to(&c));
+
+        // the pitches are compared, not the names of the tonics
+        let baroque_c = Key::equal_tempered(&Note::C, &Accidental::Natural, BAROQUE_PITCH);
+        assert!(!c.is_enharmonic_to(&baroque_c));
+
+        // a semitone of 19-EDO is smaller
+        let edo_19 = Arc::new(EqualTemperament::with_octave_additive(STUTTGART_PITCH, 19));
+        assert!(!c_sharp.is_enharmonic_to(&Key::new(&Note::D, &Accidental::Flat, edo_19)));
+    }
+
+    #[test]
+    fn test_enharmonic_equivalent() {
+        let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+        let equivalent = |note: &'static Note, accidental: &'static Accidental| {
+            Key::new(note, accidental, Arc::clone(&temp))
+                .enharmonic_equivalent()
+                .map(|key| key.tonic().to_string())
+        };
+
+        assert_eq!(
+            equivalent(&Note::C, &Accidental::Sharp),
+            Some(String::from("Db"))
+        );
+        assert_eq!(
+            equivalent(&Note::G, &Accidental::Flat),
+            Some(String::from("F#"))
+        );
+        assert_eq!(
+            equivalent(&Note::C, &Accidental::Natural),
+            Some(String::from("B#"))
+        );
+        assert_eq!(
+            equivalent(&Note::E, &Accidental::Sharp),
+            Some(String::from("F"))
+        );
+        assert_eq!(
+            equivalent(&Note::B, &Accidental::Natural),
+            Some(String::from("Cb"))
+        );
+        assert_eq!(equivalent(&Note::D, &Accidental::Natural), None);
+
+        let key = Key::new(&Note::A, &Accidental::Sharp, Arc::clone(&temp));
+        assert!(key.is_enharmonic_to(&key.enharmonic_equivalent().unwrap()));
+    }
 }
@@ -0,0 +1,114 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub struct KeyCreationError {
+    degree: u8,
+    degrees_in_scale: u8,
+}
+
+impl KeyCreationError {
+    pub fn new(degree: u8, degrees_in_scale: u8) -> Self {
+        KeyCreationError {
+            degree,
+            degrees_in_scale,
+        }
+    }
+}
+
+impl fmt::Display for KeyCreationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The scale degree {} is not between 1 and {}.",
+            self.degree, self.degrees_in_scale
+        )
+    }
+}
+
+impl Error for KeyCreationError {}
+
+#[derive(Debug)]
+pub struct ToneParseError {
+    representation: String,
+}
+
+impl ToneParseError {
+    pub fn new(representation: &str) -> Self {
+        ToneParseError {
+            representation: representation.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ToneParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' is not a tone like 'C', 'F#' or 'Bb'.",
+            self.representation
+        )
+    }
+}
+
+impl Error for ToneParseError {}
+
+#[derive(Debug)]
+pub struct ScaleKindParseError {
+    representation: String,
+}
+
+impl ScaleKindParseError {
+    pub fn new(representation: &str) -> Self {
+        ScaleKindParseError {
+            representation: representation.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ScaleKindParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = super::ScaleKind::ALL
+            .iter()
+            .map(|scale_kind| scale_kind.name())
+            .collect();
+
+        write!(
+            f,
+            "Unknown scale kind '{}', expected one of {} \
+             or the semitones between the tones like '2,1,3,1,1,3,1'.",
+            self.representation,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for ScaleKindParseError {}
+
+/**
+ * The intervals of a custom scale are missing or one of them is zero.
+ */
+#[derive(Debug)]
+pub struct IntervalPatternError {
+    intervals: Vec<u8>,
+}
+
+impl IntervalPatternError {
+    pub fn new(intervals: &[u8]) -> Self {
+        IntervalPatternError {
+            intervals: intervals.to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for IntervalPatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The intervals {:?} of a scale have to be at least one positive number of semitones.",
+            self.intervals
+        )
+    }
+}
+
+impl Error for IntervalPatternError {}
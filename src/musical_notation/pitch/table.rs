@@ -0,0 +1,112 @@
+use super::temperament::Temperament;
+use super::{Key, Pitch, Tone, DEGREES_IN_SCALE, OCTAVE_ADDITIVE};
+
+use std::ops::RangeInclusive;
+
+// the MIDI note number of A4
+const MIDI_REFERENCE_NOTE: i16 = 69;
+
+/**
+ * One tone of the major scale of a key in one octave of a pitch table.
+ * The octave is the one of scientific pitch notation the tone sounds in,
+ * the MIDI note is the one of the twelve-tone system the tone is spelled as and
+ * cents is the deviation of the pitch from twelve-tone equal temperament
+ * with the same reference pitch.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitchRow {
+    pub tone: Tone,
+    pub octave: i16,
+    pub pitch: Pitch,
+    pub midi_note: i16,
+    pub cents: f64,
+}
+
+/**
+ * Calculate the pitches of the major scale of the key in each of the given octaves,
+ * the scale starting with the tonic of the octave as in `Key::get_scale`.
+ * Tones the temperament can't calculate a pitch for are left out.
+ */
+pub fn pitch_table<T: Temperament>(key: &Key<T>, octaves: RangeInclusive<i16>) -> Vec<PitchRow> {
+    let tones = key.get_tones();
+    let reference = key
+        .temperament
+        .get_pitch(4, T::get_reference_pitch_degree() as i16);
+    let mut rows = vec![];
+
+    for octave in octaves {
+        for degree in 1..=DEGREES_IN_SCALE {
+            let position = key.get_position(degree) as i16;
+
+            let (pitch, reference) = match (key.temperament.get_pitch(octave, position), reference)
+            {
+                (Some(pitch), Some(reference)) => (pitch, reference),
+                _ => continue,
+            };
+
+            let semitones_above_c0 = OCTAVE_ADDITIVE as i16 * octave + position - 1;
+            // the MIDI note number of C0 is 12
+            let midi_note = semitones_above_c0 + OCTAVE_ADDITIVE as i16;
+            let equal_tempered = Pitch(
+                reference.get_hz()
+                    * 2f64.powf((midi_note - MIDI_REFERENCE_NOTE) as f64 / OCTAVE_ADDITIVE as f64),
+            );
+
+            rows.push(PitchRow {
+                tone: tones[degree as usize - 1],
+                octave: semitones_above_c0.div_euclid(OCTAVE_ADDITIVE as i16),
+                pitch,
+                midi_note,
+                cents: pitch.cents_from(equal_tempered),
+            });
+        }
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pitch_table;
+    use crate::musical_notation::{Accidental, EqualTemperament, Key, Note, Tone, STUTTGART_PITCH};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn d_major_test() {
+        let key = Key::equal_tempered(&Note::D, &Accidental::Natural, STUTTGART_PITCH);
+        let rows = pitch_table(&key, 3..=5);
+
+        assert_eq!(rows.len(), 21);
+        assert_eq!(rows[0].tone, Tone::new(Note::D, Accidental::Natural));
+        assert_eq!(rows[0].octave, 3);
+        assert_eq!(rows[0].midi_note, 50);
+        assert_eq!(format!("{:.3}", rows[0].pitch.get_hz()), "146.832");
+
+        // B4 and C#5 belong to the scale starting on D4
+        assert_eq!(rows[12].octave, 4);
+        assert_eq!(rows[12].midi_note, 71);
+        assert_eq!(rows[13].tone, Tone::new(Note::C, Accidental::Sharp));
+        assert_eq!(rows[13].octave, 5);
+        assert_eq!(rows[13].midi_note, 73);
+
+        assert!(rows.iter().all(|row| row.cents.abs() < 1e-9));
+    }
+
+    #[test]
+    fn deviation_test() {
+        // in 24-EDO every step is a quarter tone
+        let key = Key::new(
+            &Note::A,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::with_octave_additive(STUTTGART_PITCH, 24)),
+        );
+        let rows = pitch_table(&key, 4..=4);
+
+        assert_eq!(rows[0].midi_note, 69);
+        assert!(rows[0].cents.abs() < 1e-9);
+        // B is two positions above A, which is a semitone in 24-EDO
+        assert_eq!(rows[1].midi_note, 71);
+        assert_eq!(format!("{:.3}", rows[1].cents), "-100.000");
+    }
+}
@@ -1,6 +1,6 @@
-use super::{Pitch, OCTAVE_MULTIPLICATIVE};
+use super::{Note, Pitch, Tone, OCTAVE_MULTIPLICATIVE};
 
-mod proportionen;
+pub mod proportionen;
 
 /* Different pitch standards.
  * The number always referes to
@@ -13,7 +13,81 @@ pub const BAROQUE_PITCH: f64 = 415.0;
 pub const CHORTON_PITCH: f64 = 466.0;
 pub const CLASSICAL_PITCH: f64 = 429.5; // 427–430
 
+/**
+ * The names of the pitch standards together with the frequency of A_4 in Herz.
+ */
+pub const PITCH_STANDARDS: [(&str, f64); 4] = [
+    ("baroque", BAROQUE_PITCH),
+    ("chorton", CHORTON_PITCH),
+    ("classical", CLASSICAL_PITCH),
+    ("stuttgart", STUTTGART_PITCH),
+];
+
+/**
+ * The names of the temperaments together with a short description.
+ */
+pub const TEMPERAMENTS: [(&str, &str); 2] = [
+    (
+        "equal-temperament",
+        "the octave divided into twelve equal semitones",
+    ),
+    (
+        "just-intonation",
+        "seven tones tuned by whole number ratios",
+    ),
+];
+
 const REFERENCE_PITCH_OCTAVE: u8 = 4;
+// pitches whose distances in cents differ by less than this are equally near
+const NEAREST_TIE_TOLERANCE_CENTS: f64 = 1e-6;
+
+/**
+ * Find the candidate position whose pitch is nearest to the given pitch, searching the
+ * octave the pitch lies in and its neighbours. Returns the position, its octave and the
+ * distance of the pitch from the position's pitch in cents, positive if the pitch is
+ * sharper. A pitch exactly between two candidates belongs to the lower one.
+ * Returns None for pitches that aren't positive or if no candidate has a pitch.
+ */
+pub(super) fn nearest_position(
+    pitch: Pitch,
+    positions: &[i16],
+    get_pitch: impl Fn(i16, i16) -> Option<Pitch>,
+) -> Option<(i16, i16, f64)> {
+    if !(pitch.0 > 0.0 && pitch.0.is_finite()) {
+        return None;
+    }
+
+    let reference = get_pitch(REFERENCE_PITCH_OCTAVE as i16, *positions.first()?)?;
+    let estimated_octave =
+        REFERENCE_PITCH_OCTAVE as i16 + (pitch.cents_from(reference) / 1200.0).floor() as i16;
+
+    let mut nearest: Option<(i16, i16, f64)> = None;
+
+    for octave in estimated_octave - 1..=estimated_octave + 1 {
+        for position in positions {
+            let cents = match get_pitch(octave, *position) {
+                Some(candidate) => pitch.cents_from(candidate),
+                None => continue,
+            };
+
+            let is_nearer = match nearest {
+                None => true,
+                Some((_, _, nearest_cents)) => {
+                    let difference = cents.abs() - nearest_cents.abs();
+                    // on a tie the candidate the pitch is above is the lower one
+                    difference < -NEAREST_TIE_TOLERANCE_CENTS
+                        || (difference.abs() <= NEAREST_TIE_TOLERANCE_CENTS
+                            && cents > nearest_cents)
+                }
+            };
+            if is_nearer {
+                nearest = Some((*position, octave, cents));
+            }
+        }
+    }
+
+    nearest
+}
 
 /*
  * twelve tone temperament
@@ -41,10 +115,30 @@ pub trait Temperament {
     /**
      * returns the number of notes in an octave
      */
-    fn get_octave_additive() -> u8 {
+    fn octave_additive(&self) -> u16 {
         12
     }
 
+    /**
+     * Find the tone of this Temperament nearest to the pitch, searching the octaves around
+     * the one the pitch lies in. Returns the tone, spelled with a sharp if it is a black key,
+     * its octave and the distance of the pitch from the tone in cents, positive if the pitch
+     * is sharper. A pitch exactly between two tones belongs to the lower one. Returns None
+     * for pitches that aren't positive and for temperaments without twelve tones per octave.
+     */
+    fn nearest_tone(&self, pitch: Pitch) -> Option<(Tone, i16, f64)> {
+        if self.octave_additive() != 12 {
+            return None;
+        }
+
+        let positions: Vec<i16> = (1..=12).collect();
+        let (position, octave, cents) = nearest_position(pitch, &positions, |octave, position| {
+            self.get_pitch(octave, position)
+        })?;
+
+        Some((Tone::from_position(position as u8, false), octave, cents))
+    }
+
     /**
      * returns the degree of the reference pitch
      */
@@ -83,9 +177,29 @@ pub trait SevenToneTemperament {
     /**
      * returns the number of notes in an octave
      */
-    fn get_octave_additive() -> u8 {
+    fn octave_additive(&self) -> u16 {
         7
     }
+
+    /**
+     * Find the tone of this Temperament nearest to the pitch like `Temperament::nearest_tone`.
+     * The tones are the seven natural tones.
+     */
+    fn nearest_tone(&self, pitch: Pitch) -> Option<(Tone, i16, f64)> {
+        let positions: Vec<i16> = (1..=7).collect();
+        let (position, octave, cents) = nearest_position(pitch, &positions, |octave, position| {
+            self.get_pitch(octave, position)
+        })?;
+
+        Some((
+            Tone::new(
+                Note::from_index(position as u8 - 1),
+                super::Accidental::Natural,
+            ),
+            octave,
+            cents,
+        ))
+    }
 }
 
 /**
@@ -118,7 +232,7 @@ impl SevenToneTemperament for JustIntonation {
         if position < 1 {
             position -= 1; // 0 -> -1; -6 -> -7
             position *= -1; // -1 -> 1; -7 -> 7
-            octave = octave - (1 + ((position - 1) / 7));
+            octave -= 1 + ((position - 1) / 7);
             position = ((position - 1) % 7) + 1;
             // 1 -> 7, 2 -> 6, 3 -> 5, 4 -> 4, 5 -> 3, 6 -> 2, 7 -> 1
             position = 7 - position + 1;
@@ -148,7 +262,7 @@ impl SevenToneTemperament for JustIntonation {
                 position_proportion = position_proportion.fusion(&self.proportionen[i as usize]);
             }
         } else if relative_a < 0 {
-            position = position - 1; // 1 -> 0; 5 -> 4; 4 -> 3
+            position -= 1; // 1 -> 0; 5 -> 4; 4 -> 3
             for i in position..(4 + 1) {
                 // i = 0, 1, 2, 3, 4; i = 4; i = 3, 4
                 // position + 4 - i = 4, 3, 2, 1, 0; position + 4 - i = 4; position + 4 - i = 4, 3
@@ -158,42 +272,229 @@ impl SevenToneTemperament for JustIntonation {
             position_proportion = position_proportion.invert();
         }
 
-        return Some(Pitch(
+        Some(Pitch(
             octave_proportion
                 .fusion(&position_proportion)
                 .scale(self.pitch_standard),
-        ));
+        ))
     }
 }
 
 pub struct EqualTemperament {
     pitch_standard: f64,
+    octave_additive: u16,
+    reference_pitch_degree: u8,
+}
+
+impl EqualTemperament {
+    /**
+     * Create an equal temperament dividing the octave into the given number of
+     * equal steps, e.g. 19 for 19-EDO. The reference pitch keeps its position.
+     */
+    pub fn with_octave_additive(pitch_standard: f64, octave_additive: u16) -> EqualTemperament {
+        EqualTemperament {
+            pitch_standard,
+            octave_additive,
+            reference_pitch_degree: Self::get_reference_pitch_degree(),
+        }
+    }
+
+    /**
+     * Create an equal temperament whose pitch standard is the frequency of the
+     * given tone in octave 4 instead of A_4, e.g. 261.626 for C_4. Cb and B# are
+     * taken for their position in the twelve-tone system, i.e. B and C.
+     */
+    pub fn with_reference_tone(pitch_standard: f64, reference_tone: Tone) -> EqualTemperament {
+        EqualTemperament {
+            pitch_standard,
+            octave_additive: 12,
+            reference_pitch_degree: reference_tone.get_position(),
+        }
+    }
 }
 
 impl Temperament for EqualTemperament {
     fn new(pitch_standard: f64) -> EqualTemperament {
-        EqualTemperament { pitch_standard }
+        EqualTemperament::with_octave_additive(pitch_standard, 12)
+    }
+
+    fn octave_additive(&self) -> u16 {
+        self.octave_additive
     }
 
     fn get_pitch(&self, octave: i16, position: i16) -> Option<Pitch> {
         let octave_intervall =
-            (octave - REFERENCE_PITCH_OCTAVE as i16) * Self::get_octave_additive() as i16;
-        let relative_a = position - Self::get_reference_pitch_degree() as i16;
+            (octave - REFERENCE_PITCH_OCTAVE as i16) * self.octave_additive() as i16;
+        let relative_a = position - self.reference_pitch_degree as i16;
         let intervall_size = relative_a + octave_intervall;
-        return Some(Pitch(
+        Some(Pitch(
             self.pitch_standard
                 * (OCTAVE_MULTIPLICATIVE as f64)
-                    .powf(intervall_size as f64 / Self::get_octave_additive() as f64),
-        ));
+                    .powf(intervall_size as f64 / self.octave_additive() as f64),
+        ))
+    }
+}
+
+/**
+ * The position of the step of the temperament nearest to the tone of the twelve-tone
+ * system, counted from the reference pitch, which keeps its position in every division
+ * of the octave. For twelve steps per octave this is the position of the tone.
+ */
+fn nearest_step_position<T: Temperament>(temperament: &T, tone: &Tone) -> i16 {
+    let reference = T::get_reference_pitch_degree() as i16;
+    let semitones = tone.get_position() as i16 - reference;
+
+    match temperament.octave_additive() {
+        12 => tone.get_position() as i16,
+        steps => reference + (semitones as f64 * steps as f64 / 12.0).round() as i16,
     }
 }
 
+fn compare_pitches(
+    scale: &[Tone],
+    tempered: impl Fn(&Tone) -> Option<Pitch>,
+    equal: impl Fn(&Tone) -> Option<Pitch>,
+) -> Vec<(Tone, f64)> {
+    scale
+        .iter()
+        .filter_map(|tone| match (tempered(tone), equal(tone)) {
+            (Some(tempered), Some(equal)) => Some((*tone, tempered.cents_from(equal))),
+            _ => None,
+        })
+        .collect()
+}
+
+/**
+ * Compare the pitches of the given tones in the given octave of the tempered
+ * temperament to the ones of the equal temperament. Returns the deviation in
+ * cents of every tone, positive if the tempered pitch is sharper than the equal
+ * one and negative if it's flatter. A temperament dividing the octave into other
+ * than twelve steps plays every tone with its nearest step. Tones which one of
+ * the temperaments can't calculate a pitch for are left out.
+ */
+pub fn compare_to_equal_temperament<T: Temperament, U: Temperament>(
+    tempered: &T,
+    equal: &U,
+    scale: &[Tone],
+    octave: i16,
+) -> Vec<(Tone, f64)> {
+    compare_pitches(
+        scale,
+        |tone| tempered.get_pitch(octave, nearest_step_position(tempered, tone)),
+        |tone| equal.get_pitch(octave, nearest_step_position(equal, tone)),
+    )
+}
+
+/**
+ * Compare the pitches of a seven tone temperament like JustIntonation to the ones of
+ * the equal temperament like `compare_to_equal_temperament`. A seven tone temperament
+ * only has the natural tones, all other tones of the scale are left out.
+ */
+pub fn compare_seven_tone_to_equal_temperament<T: SevenToneTemperament, U: Temperament>(
+    tempered: &T,
+    equal: &U,
+    scale: &[Tone],
+    octave: i16,
+) -> Vec<(Tone, f64)> {
+    compare_pitches(
+        scale,
+        |tone| match tone.accidental {
+            super::Accidental::Natural => {
+                tempered.get_pitch(octave, tone.note.get_index() as i16 + 1)
+            }
+            _ => None,
+        },
+        |tone| equal.get_pitch(octave, nearest_step_position(equal, tone)),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        proportionen, EqualTemperament, JustIntonation, SevenToneTemperament, Temperament,
+        compare_seven_tone_to_equal_temperament, compare_to_equal_temperament, proportionen,
+        EqualTemperament, JustIntonation, SevenToneTemperament, Temperament, BAROQUE_PITCH,
         STUTTGART_PITCH,
     };
+    use crate::musical_notation::{Accidental, Key, Note, Pitch, Tone};
+
+    #[test]
+    fn nearest_tone_test() {
+        let temp = EqualTemperament::new(STUTTGART_PITCH);
+        let nearest = |hz: f64| {
+            temp.nearest_tone(Pitch(hz))
+                .map(|(tone, octave, cents)| format!("{}{} {:+.1}", tone, octave, cents))
+        };
+
+        assert_eq!(nearest(440.0).as_deref(), Some("A4 +0.0"));
+        assert_eq!(nearest(450.0).as_deref(), Some("A4 +38.9"));
+        assert_eq!(nearest(453.0).as_deref(), Some("A#4 -49.6"));
+        assert_eq!(nearest(261.626).as_deref(), Some("C4 +0.0"));
+        assert_eq!(nearest(254.0).as_deref(), Some("B3 +48.8"));
+        // exactly between A4 and A#4
+        assert_eq!(
+            nearest((440.0f64 * 466.1637615180899).sqrt()).as_deref(),
+            Some("A4 +50.0")
+        );
+        // outside of the octaves -1 to 10
+        assert_eq!(nearest(4.0879).as_deref(), Some("C-2 +0.0"));
+        assert_eq!(nearest(64000.0).as_deref(), Some("B11 +21.3"));
+
+        assert_eq!(nearest(0.0), None);
+        assert_eq!(nearest(-440.0), None);
+        assert_eq!(
+            EqualTemperament::with_octave_additive(STUTTGART_PITCH, 19).nearest_tone(Pitch(440.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn nearest_tone_just_intonation_test() {
+        let proportionen = [
+            proportionen::Proportion::new(8, 9),
+            proportionen::Proportion::new(9, 10),
+            proportionen::Proportion::new(15, 16),
+            proportionen::Proportion::new(8, 9),
+            proportionen::Proportion::new(8, 9),
+            proportionen::Proportion::new(9, 10),
+            proportionen::Proportion::new(15, 16),
+        ];
+        let temp = JustIntonation::new(STUTTGART_PITCH, 6, proportionen);
+
+        let (tone, octave, cents) = temp.nearest_tone(Pitch(262.0)).unwrap();
+        assert_eq!((tone, octave), (Tone::new(Note::C, Accidental::Natural), 4));
+        assert_eq!(format!("{:.1}", cents), "8.3");
+
+        let (tone, octave, cents) = temp.nearest_tone(Pitch(880.0)).unwrap();
+        assert_eq!((tone, octave), (Tone::new(Note::A, Accidental::Natural), 5));
+        assert!(cents.abs() < 1e-9);
+
+        // F#4 lies between F4 and G4 and is nearer to G4 here
+        let (tone, octave, _) = temp.nearest_tone(Pitch(380.0)).unwrap();
+        assert_eq!((tone, octave), (Tone::new(Note::G, Accidental::Natural), 4));
+    }
+
+    #[test]
+    fn reference_tone_test() {
+        let temp =
+            EqualTemperament::with_reference_tone(261.626, Tone::new(Note::C, Accidental::Natural));
+        assert_eq!(
+            format!("{:.2?}", temp.get_pitch(4, 10)),
+            "Some(Pitch(440.00))"
+        );
+        assert_eq!(
+            format!("{:.3?}", temp.get_pitch(4, 1)),
+            "Some(Pitch(261.626))"
+        );
+
+        let temp = EqualTemperament::with_reference_tone(
+            STUTTGART_PITCH,
+            Tone::new(Note::A, Accidental::Natural),
+        );
+        assert_eq!(
+            temp.get_pitch(3, 4),
+            EqualTemperament::new(STUTTGART_PITCH).get_pitch(3, 4)
+        );
+    }
 
     #[test]
     fn equal_temperament_test() {
@@ -224,6 +525,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn octave_additive_test() {
+        assert_eq!(EqualTemperament::new(STUTTGART_PITCH).octave_additive(), 12);
+
+        let temp = EqualTemperament::with_octave_additive(STUTTGART_PITCH, 19);
+        assert_eq!(temp.octave_additive(), 19);
+        assert_eq!(
+            format!("{:.3?}", temp.get_pitch(4, 10)),
+            "Some(Pitch(440.000))"
+        );
+        assert_eq!(
+            format!("{:.3?}", temp.get_pitch(4, 29)),
+            "Some(Pitch(880.000))"
+        );
+        assert_eq!(
+            format!("{:.3?}", temp.get_pitch(5, 10)),
+            "Some(Pitch(880.000))"
+        );
+        // one step of 19-EDO is about 63.16 cents
+        assert_eq!(
+            format!("{:.3?}", temp.get_pitch(4, 11)),
+            "Some(Pitch(456.348))"
+        );
+    }
+
     #[test]
     fn just_intonation_test() {
         let proportionen: [proportionen::Proportion; 7] = [
@@ -289,4 +615,83 @@ mod tests {
             "Some(Pitch(260.741))"
         );
     }
+
+    #[test]
+    fn compare_to_equal_temperament_test() {
+        let equal = EqualTemperament::new(STUTTGART_PITCH);
+        let scale =
+            Key::equal_tempered(&Note::C, &Accidental::Natural, STUTTGART_PITCH).get_tones();
+
+        let deviations = compare_to_equal_temperament(&equal, &equal, &scale, 4);
+        assert_eq!(deviations.len(), 7);
+        assert!(deviations
+            .iter()
+            .zip(scale.iter())
+            .all(|((tone, cents), expected)| tone == expected && *cents == 0.0));
+
+        // a baroque pitch of 415 Hz is a little more than a semitone flatter
+        let baroque = EqualTemperament::new(BAROQUE_PITCH);
+        let deviations = compare_to_equal_temperament(&baroque, &equal, &scale, 4);
+        assert!(deviations
+            .iter()
+            .all(|(_, cents)| format!("{:.2}", cents) == "-101.27"));
+
+        let format = |deviations: Vec<(Tone, f64)>| {
+            deviations
+                .iter()
+                .map(|(tone, cents)| format!("{} {:+.1}", tone, cents))
+                .collect::<Vec<String>>()
+        };
+
+        // 24-EDO contains every semitone of 12-EDO as every second of its steps
+        let quarter_tones = EqualTemperament::with_octave_additive(STUTTGART_PITCH, 24);
+        let deviations = compare_to_equal_temperament(&quarter_tones, &equal, &scale, 4);
+        assert!(deviations.iter().all(|(_, cents)| cents.abs() < 1e-9));
+
+        // 19-EDO plays every tone with its nearest step of about 63.2 cents from A
+        let nineteen = EqualTemperament::with_octave_additive(STUTTGART_PITCH, 19);
+        assert_eq!(
+            format(compare_to_equal_temperament(&nineteen, &equal, &scale, 4)),
+            vec!["C +15.8", "D +5.3", "E -5.3", "F +21.1", "G +10.5", "A +0.0", "B -10.5"]
+        );
+    }
+
+    #[test]
+    fn compare_just_intonation_to_equal_temperament_test() {
+        // C major in just intonation with C4 as the reference pitch
+        let proportionen = [
+            proportionen::Proportion::new(8, 9),   // D
+            proportionen::Proportion::new(9, 10),  // E
+            proportionen::Proportion::new(15, 16), // F
+            proportionen::Proportion::new(8, 9),   // G
+            proportionen::Proportion::new(9, 10),  // A
+            proportionen::Proportion::new(8, 9),   // B
+            proportionen::Proportion::new(15, 16), // C
+        ];
+        let c4 = 261.626;
+        let just = JustIntonation::new(c4, 1, proportionen);
+        let equal =
+            EqualTemperament::with_reference_tone(c4, Tone::new(Note::C, Accidental::Natural));
+        let scale =
+            Key::equal_tempered(&Note::C, &Accidental::Natural, STUTTGART_PITCH).get_tones();
+
+        let deviations: Vec<String> =
+            compare_seven_tone_to_equal_temperament(&just, &equal, &scale, 4)
+                .iter()
+                .map(|(tone, cents)| format!("{} {:+.1}", tone, cents))
+                .collect();
+        // the major third 5:4 is 386.3 cents wide and the fifth 3:2 701.955 cents
+        assert_eq!(
+            deviations,
+            vec!["C +0.0", "D +3.9", "E -13.7", "F -2.0", "G +2.0", "A -15.6", "B -11.7"]
+        );
+
+        // a seven tone temperament has no pitches for the black keys
+        let d_major =
+            Key::equal_tempered(&Note::D, &Accidental::Natural, STUTTGART_PITCH).get_tones();
+        assert_eq!(
+            compare_seven_tone_to_equal_temperament(&just, &equal, &d_major, 4).len(),
+            5
+        );
+    }
 }
@@ -1,6 +1,16 @@
 use super::super::OCTAVE_MULTIPLICATIVE;
+use std::cmp::Ordering;
 use std::fmt;
 
+/*
+ * A Proportion a:b scales a frequency by b/a, so "up" means
+ * a greater factor b/a. The constants below are therefore ordered
+ * OCTAVE_DOWN < UNIT < OCTAVE_UP.
+ */
+
+/**
+ * The proportion 1:2 which doubles a frequency.
+ */
 pub const OCTAVE_UP: Proportion = Proportion {
     magnitude_a: 1,
     magnitude_b: OCTAVE_MULTIPLICATIVE as u32,
@@ -8,6 +18,9 @@ pub const OCTAVE_UP: Proportion = Proportion {
     magnitude_b_norm: OCTAVE_MULTIPLICATIVE as u32,
 };
 
+/**
+ * The proportion 2:1 which halves a frequency.
+ */
 pub const OCTAVE_DOWN: Proportion = Proportion {
     magnitude_a: OCTAVE_MULTIPLICATIVE as u32,
     magnitude_b: 1,
@@ -15,6 +28,9 @@ pub const OCTAVE_DOWN: Proportion = Proportion {
     magnitude_b_norm: 1,
 };
 
+/**
+ * The proportion 1:1 which leaves a frequency unchanged.
+ */
 pub const UNIT: Proportion = Proportion {
     magnitude_a: 1,
     magnitude_b: 1,
@@ -41,7 +57,7 @@ impl Proportion {
 
         prop.normalize();
 
-        return prop;
+        prop
     }
 
     fn normalize(&mut self) {
@@ -49,7 +65,7 @@ impl Proportion {
         let mut a = self.magnitude_a;
         let mut b = self.magnitude_b;
 
-        while (a % b) > 0 {
+        while !a.is_multiple_of(b) {
             r = a % b;
             a = b;
             b = r;
@@ -76,15 +92,15 @@ impl Proportion {
         } else {
             if power < 0 {
                 Proportion::new(
-                    self.magnitude_b.pow(power.abs() as u32),
-                    self.magnitude_a.pow(power.abs() as u32),
+                    self.magnitude_b.pow(power.unsigned_abs()),
+                    self.magnitude_a.pow(power.unsigned_abs()),
                 )
             } else
             /* power > 0 */
             {
                 Proportion::new(
-                    self.magnitude_a.pow(power.abs() as u32),
-                    self.magnitude_b.pow(power.abs() as u32),
+                    self.magnitude_a.pow(power.unsigned_abs()),
+                    self.magnitude_b.pow(power.unsigned_abs()),
                 )
             }
         }
@@ -93,6 +109,36 @@ impl Proportion {
     pub fn scale(&self, number: f64) -> f64 {
         (number * self.magnitude_b as f64) / self.magnitude_a as f64
     }
+
+    /**
+     * The factor b/a this Proportion scales a frequency by.
+     */
+    pub fn to_f64(&self) -> f64 {
+        self.magnitude_b as f64 / self.magnitude_a as f64
+    }
+
+    /**
+     * The size of the interval in cents, i.e. 1200 * log2(b/a).
+     * Intervals going up are positive, intervals going down negative.
+     */
+    pub fn to_cents(&self) -> f64 {
+        1200.0 * self.to_f64().log2()
+    }
+}
+
+impl Ord for Proportion {
+    fn cmp(&self, other: &Proportion) -> Ordering {
+        // b1/a1 <=> b2/a2 is equivalent to b1*a2 <=> b2*a1 as a1, a2 > 0
+        let lhs = self.magnitude_b_norm as u64 * other.magnitude_a_norm as u64;
+        let rhs = other.magnitude_b_norm as u64 * self.magnitude_a_norm as u64;
+        lhs.cmp(&rhs)
+    }
+}
+
+impl PartialOrd for Proportion {
+    fn partial_cmp(&self, other: &Proportion) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl PartialEq<Proportion> for Proportion {
@@ -110,7 +156,7 @@ impl fmt::Display for Proportion {
 
 #[cfg(test)]
 mod tests {
-    use super::Proportion;
+    use super::{Proportion, OCTAVE_DOWN, OCTAVE_UP, UNIT};
 
     #[test]
     fn display_test() {
@@ -188,4 +234,65 @@ mod tests {
         assert_eq!(format!("{:.3?}", a.scale(3.333)), "2.222");
         assert_eq!(format!("{:.3?}", b.scale(3.333)), "2.222");
     }
+
+    #[test]
+    fn to_f64_test() {
+        assert_eq!(format!("{:.3?}", Proportion::new(1, 2).to_f64()), "2.000");
+        assert_eq!(format!("{:.3?}", Proportion::new(4, 5).to_f64()), "1.250");
+        assert_eq!(format!("{:.3?}", Proportion::new(3, 2).to_f64()), "0.667");
+    }
+
+    #[test]
+    fn to_cents_test() {
+        assert_eq!(
+            format!("{:.3?}", Proportion::new(1, 2).to_cents()),
+            "1200.000"
+        );
+        assert_eq!(
+            format!("{:.3?}", Proportion::new(2, 3).to_cents()),
+            "701.955"
+        );
+        assert_eq!(
+            format!("{:.3?}", Proportion::new(4, 5).to_cents()),
+            "386.314"
+        );
+        assert_eq!(
+            format!("{:.3?}", Proportion::new(2, 1).to_cents()),
+            "-1200.000"
+        );
+        assert_eq!(format!("{:.3?}", UNIT.to_cents()), "0.000");
+    }
+
+    #[test]
+    fn constants_ordering_test() {
+        assert!(OCTAVE_DOWN < UNIT);
+        assert!(UNIT < OCTAVE_UP);
+        assert!(Proportion::new(2, 4) == OCTAVE_UP);
+        assert!(Proportion::new(2, 4) <= OCTAVE_UP);
+    }
+
+    #[test]
+    fn sort_test() {
+        let mut intervals = vec![
+            Proportion::new(2, 3),   // fifth
+            Proportion::new(15, 16), // minor second
+            Proportion::new(1, 2),   // octave
+            Proportion::new(5, 6),   // minor third
+            Proportion::new(1, 1),   // unison
+            Proportion::new(3, 4),   // fourth
+            Proportion::new(8, 9),   // major second
+            Proportion::new(3, 5),   // major sixth
+            Proportion::new(4, 5),   // major third
+        ];
+        intervals.sort();
+
+        assert_eq!(
+            intervals
+                .iter()
+                .map(|p| format!("{}", p))
+                .collect::<Vec<String>>()
+                .join(", "),
+            "1:1, 15:16, 8:9, 5:6, 4:5, 3:4, 2:3, 3:5, 1:2"
+        );
+    }
 }
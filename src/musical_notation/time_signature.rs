@@ -0,0 +1,51 @@
+use super::Duration;
+
+/**
+ * A time signature consisting of the number of beats in a bar
+ * and the note value of one beat, e.g. 3/4 or 6/8.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeSignature {
+    pub beats: u8,
+    pub beat_value: u8,
+}
+
+impl TimeSignature {
+    /**
+     * Panics if beats or beat_value is zero.
+     */
+    pub fn new(beats: u8, beat_value: u8) -> Self {
+        assert!(
+            beats > 0 && beat_value > 0,
+            "A TimeSignature needs at least one beat and a beat value greater than zero"
+        );
+        TimeSignature { beats, beat_value }
+    }
+
+    /**
+     * The Duration of one beat.
+     */
+    pub fn beat_duration(&self) -> Duration {
+        Duration::new(1, self.beat_value as u32)
+    }
+
+    /**
+     * The Duration of a whole bar.
+     */
+    pub fn bar_duration(&self) -> Duration {
+        Duration::new(self.beats as u32, self.beat_value as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Duration, TimeSignature};
+
+    #[test]
+    fn bar_duration_test() {
+        assert_eq!(TimeSignature::new(4, 4).bar_duration(), Duration::whole());
+        assert_eq!(TimeSignature::new(3, 4).bar_duration(), Duration::new(3, 4));
+        assert_eq!(TimeSignature::new(6, 8).bar_duration(), Duration::new(3, 4));
+        assert_eq!(TimeSignature::new(6, 8).beat_duration(), Duration::eighth());
+    }
+}
@@ -1,15 +1,159 @@
-#[derive(Debug, Copy, Clone)]
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Volume(u8);
 
+const MAX_MIDI_VELOCITY: u8 = 127;
+
 impl Volume {
+    /**
+     * Create a volume from its raw value, which is clamped to the
+     * range from SILENT (0) to FFF (252).
+     */
+    pub fn new(value: u8) -> Volume {
+        Volume(value.min(FFF.0))
+    }
+
+    /**
+     * Create the volume at the given fraction of FFF, e.g. 0.5 for half of its amplitude.
+     * The fraction is clamped to the range from 0.0 (SILENT) to 1.0 (FFF).
+     */
+    pub fn percent(fraction: f64) -> Volume {
+        let fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        Volume((fraction * FFF.0 as f64).round() as u8)
+    }
+
     pub fn get(&self) -> u8 {
         self.0
     }
+
+    /**
+     * Step the given number of dynamics up (positive steps) or down (negative steps)
+     * the ladder SILENT, PPP, PP, P, MP, M, MF, F, FF, FFF. A volume in between
+     * two dynamics is first rounded to the nearest one. The result is clamped to
+     * the ladder, so stepping up from FFF stays at FFF.
+     */
+    pub fn step(&self, steps: i8) -> Volume {
+        let index = (self.0 as i16 + STEP_SIZE as i16 / 2) / STEP_SIZE as i16;
+        let index = (index + steps as i16).clamp(0, LADDER.len() as i16 - 1);
+        LADDER[index as usize]
+    }
+
+    /**
+     * The volume as a linear amplitude between 0.0 (SILENT) and 1.0 (FFF).
+     */
+    pub fn as_amplitude(&self) -> f64 {
+        self.as_amplitude_with_curve(1.0)
+    }
+
+    /**
+     * The volume as an amplitude between 0.0 (SILENT) and 1.0 (FFF), where the
+     * linear amplitude is raised to the power of the given exponent. An exponent
+     * greater than 1.0 makes the quiet dynamics quieter, one less than 1.0 louder.
+     */
+    pub fn as_amplitude_with_curve(&self, exponent: f64) -> f64 {
+        (self.0 as f64 / FFF.0 as f64).powf(exponent)
+    }
+
+    /**
+     * Map a MIDI velocity between 0 and 127 to a volume between SILENT and FFF.
+     * Velocities above 127 are treated as 127.
+     */
+    pub fn from_midi_velocity(velocity: u8) -> Volume {
+        let velocity = velocity.min(MAX_MIDI_VELOCITY) as u16;
+        Volume(
+            ((velocity * FFF.0 as u16 + MAX_MIDI_VELOCITY as u16 / 2) / MAX_MIDI_VELOCITY as u16)
+                as u8,
+        )
+    }
+
+    /**
+     * Map this volume to a MIDI velocity between 0 and 127.
+     */
+    pub fn to_midi_velocity(&self) -> u8 {
+        let volume = self.0.min(FFF.0) as u16;
+        ((volume * MAX_MIDI_VELOCITY as u16 + FFF.0 as u16 / 2) / FFF.0 as u16) as u8
+    }
+
+    /**
+     * Linearly interpolate between this volume (fraction 0.0) and the other
+     * volume (fraction 1.0). The fraction is clamped to that range.
+     */
+    pub fn interpolate(&self, other: Volume, fraction: f64) -> Volume {
+        let fraction = fraction.clamp(0.0, 1.0);
+        Volume((self.0 as f64 + (other.0 as f64 - self.0 as f64) * fraction).round() as u8)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        LADDER
+            .iter()
+            .zip(NAMES.iter())
+            .find(|(volume, _)| volume.0 == self.0)
+            .map(|(_, name)| *name)
+    }
+}
+
+/**
+ * Defines how a Volume is converted to the gain of an instrument.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum VolumeCurve {
+    /**
+     * The gain is proportional to the volume.
+     */
+    #[default]
+    Linear,
+    /**
+     * Every dynamic step changes the gain by the same number of decibels,
+     * which is closer to the perceived loudness. FFF has a gain of 1.0 and
+     * PPP is quieter by EXPONENTIAL_DYNAMIC_RANGE decibels. SILENT stays silent.
+     */
+    Exponential,
+    /**
+     * Smoothly flattens the gain towards SILENT and FFF and
+     * makes the middle dynamics more distinct.
+     */
+    SCurve,
+}
+
+/**
+ * The difference in decibels between PPP and FFF under the exponential curve.
+ */
+const EXPONENTIAL_DYNAMIC_RANGE: f64 = 40.0;
+
+impl VolumeCurve {
+    /**
+     * The gain between 0.0 and 1.0 of the given volume under this curve.
+     */
+    pub fn gain(&self, volume: Volume) -> f64 {
+        let amplitude = volume.as_amplitude().min(1.0);
+
+        match self {
+            VolumeCurve::Linear => amplitude,
+            VolumeCurve::Exponential => {
+                if volume.0 == 0 {
+                    0.0
+                } else {
+                    let steps_below_fff = (FFF.0 as f64 - volume.0 as f64) / STEP_SIZE as f64;
+                    let decibels =
+                        -EXPONENTIAL_DYNAMIC_RANGE * steps_below_fff / (LADDER.len() - 2) as f64;
+                    10.0_f64.powf(decibels / 20.0)
+                }
+            }
+            VolumeCurve::SCurve => amplitude * amplitude * (3.0 - 2.0 * amplitude),
+        }
+    }
 }
 
 const STEP_SIZE: u8 = 28;
 pub const SILENT: Volume = Volume(0);
-pub const PPP: Volume = Volume(1 * STEP_SIZE);
+pub const PPP: Volume = Volume(STEP_SIZE);
 pub const PP: Volume = Volume(2 * STEP_SIZE);
 pub const P: Volume = Volume(3 * STEP_SIZE);
 pub const MP: Volume = Volume(4 * STEP_SIZE);
@@ -18,3 +162,197 @@ pub const MF: Volume = Volume(6 * STEP_SIZE);
 pub const F: Volume = Volume(7 * STEP_SIZE);
 pub const FF: Volume = Volume(8 * STEP_SIZE);
 pub const FFF: Volume = Volume(9 * STEP_SIZE);
+
+const LADDER: [Volume; 10] = [SILENT, PPP, PP, P, MP, M, MF, F, FF, FFF];
+const NAMES: [&str; 10] = [
+    "silent", "ppp", "pp", "p", "mp", "m", "mf", "f", "ff", "fff",
+];
+
+/**
+ * Displays the name of the dynamic, e.g. "mf", or the raw value
+ * if the volume lies in between two dynamics.
+ */
+impl fmt::Display for Volume {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VolumeParseError {
+    representation: String,
+}
+
+impl fmt::Display for VolumeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown dynamic '{}', expected one of {}.",
+            self.representation,
+            NAMES.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for VolumeParseError {}
+
+impl FromStr for Volume {
+    type Err = VolumeParseError;
+
+    fn from_str(s: &str) -> Result<Volume, VolumeParseError> {
+        let representation = s.trim().to_lowercase();
+
+        match NAMES.iter().position(|name| *name == representation) {
+            Some(index) => Ok(LADDER[index]),
+            None => Err(VolumeParseError {
+                representation: s.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Volume, VolumeCurve, FF, FFF, LADDER, M, MF, P, PP, PPP, SILENT};
+
+    #[test]
+    fn new_and_percent_test() {
+        assert_eq!(Volume::new(100).get(), 100);
+        assert_eq!(Volume::new(255), FFF);
+        assert_eq!(Volume::new(0), SILENT);
+
+        assert_eq!(Volume::percent(1.5), FFF);
+        assert_eq!(Volume::percent(1.0), FFF);
+        assert_eq!(Volume::percent(-0.5), SILENT);
+        assert_eq!(Volume::percent(f64::NAN), SILENT);
+        // half of FFF lies between MP and M, rounded to M
+        assert_eq!(Volume::percent(0.5).get(), 126);
+        assert_eq!(Volume::percent(0.5).step(0), M);
+        assert_eq!(Volume::percent(M.as_amplitude()), M);
+    }
+
+    #[test]
+    fn parse_and_display_test() {
+        for volume in LADDER {
+            let name = format!("{}", volume);
+            assert_eq!(name.parse::<Volume>().unwrap().get(), volume.get());
+        }
+
+        assert_eq!("mf".parse::<Volume>().unwrap().get(), MF.get());
+        assert_eq!(" FFF ".parse::<Volume>().unwrap().get(), FFF.get());
+        assert_eq!(format!("{}", PP), "pp");
+        assert_eq!(format!("{}", SILENT), "silent");
+        assert_eq!(format!("{}", Volume(100)), "100");
+
+        match "loud".parse::<Volume>() {
+            Err(e) => assert_eq!(
+                format!("{}", e),
+                "Unknown dynamic 'loud', expected one of silent, ppp, pp, p, mp, m, mf, f, ff, fff."
+            ),
+            Ok(_) => panic!("Parsed an unknown dynamic."),
+        }
+    }
+
+    #[test]
+    fn step_test() {
+        assert_eq!(M.step(1).get(), MF.get());
+        assert_eq!(M.step(-2).get(), P.get());
+        assert_eq!(P.step(0).get(), P.get());
+        assert_eq!(FF.step(3).get(), FFF.get());
+        assert_eq!(PPP.step(-5).get(), SILENT.get());
+        assert_eq!(SILENT.step(-1).get(), SILENT.get());
+        assert_eq!(FFF.step(i8::MAX).get(), FFF.get());
+        assert_eq!(FFF.step(i8::MIN).get(), SILENT.get());
+        // 100 lies between MP (112) and P (84) and is rounded to MP
+        assert_eq!(Volume(100).step(1).get(), M.get());
+        assert_eq!(Volume(255).step(0).get(), FFF.get());
+    }
+
+    #[test]
+    fn amplitude_test() {
+        assert_eq!(format!("{:.3?}", SILENT.as_amplitude()), "0.000");
+        assert_eq!(format!("{:.3?}", FFF.as_amplitude()), "1.000");
+        assert_eq!(format!("{:.3?}", M.as_amplitude()), "0.556");
+        assert_eq!(format!("{:.3?}", M.as_amplitude_with_curve(2.0)), "0.309");
+        assert_eq!(format!("{:.3?}", FFF.as_amplitude_with_curve(2.0)), "1.000");
+    }
+
+    #[test]
+    fn midi_velocity_test() {
+        assert_eq!(SILENT.to_midi_velocity(), 0);
+        assert_eq!(FFF.to_midi_velocity(), 127);
+        assert_eq!(M.to_midi_velocity(), 71);
+        assert_eq!(Volume::from_midi_velocity(0).get(), SILENT.get());
+        assert_eq!(Volume::from_midi_velocity(127).get(), FFF.get());
+        assert_eq!(Volume::from_midi_velocity(200).get(), FFF.get());
+
+        // the velocity has a lower resolution, but the dynamic is retained
+        for volume in LADDER {
+            assert_eq!(
+                Volume::from_midi_velocity(volume.to_midi_velocity())
+                    .step(0)
+                    .get(),
+                volume.get()
+            );
+        }
+
+        for velocity in 0..=127 {
+            assert_eq!(
+                Volume::from_midi_velocity(velocity).to_midi_velocity(),
+                velocity
+            );
+        }
+    }
+
+    #[test]
+    fn interpolate_test() {
+        assert_eq!(P.interpolate(FF, 0.0), P);
+        assert_eq!(P.interpolate(FF, 1.0), FF);
+        assert_eq!(P.interpolate(FF, 0.5).get(), 154);
+        assert_eq!(FF.interpolate(P, 0.25).get(), 189);
+        assert_eq!(P.interpolate(FF, 2.0), FF);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_test() {
+        for volume in LADDER {
+            let json = serde_json::to_string(&volume).unwrap();
+            assert_eq!(json, format!("{}", volume.get()));
+            assert_eq!(serde_json::from_str::<Volume>(&json).unwrap(), volume);
+        }
+    }
+
+    #[test]
+    fn volume_curve_test() {
+        assert_eq!(format!("{:.3?}", VolumeCurve::Linear.gain(MF)), "0.667");
+        // three steps below FFF, i.e. 15 dB quieter
+        assert_eq!(
+            format!("{:.3?}", VolumeCurve::Exponential.gain(MF)),
+            "0.178"
+        );
+        assert!(VolumeCurve::Exponential.gain(MF) < VolumeCurve::Linear.gain(MF));
+        assert_eq!(format!("{:.3?}", VolumeCurve::SCurve.gain(MF)), "0.741");
+
+        for curve in [
+            VolumeCurve::Linear,
+            VolumeCurve::Exponential,
+            VolumeCurve::SCurve,
+        ] {
+            assert_eq!(curve.gain(SILENT), 0.0);
+            assert_eq!(format!("{:.3?}", curve.gain(FFF)), "1.000");
+
+            for (quieter, louder) in LADDER.iter().zip(LADDER.iter().skip(1)) {
+                assert!(curve.gain(*quieter) < curve.gain(*louder));
+            }
+        }
+
+        assert_eq!(
+            format!("{:.3?}", VolumeCurve::Exponential.gain(PPP)),
+            "0.010"
+        );
+    }
+}
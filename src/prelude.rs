@@ -0,0 +1,37 @@
+//! Re-exports the types commonly needed to generate a Voice,
+//! so that a single `use music_generator::prelude::*;` suffices.
+//!
+//! ```
+//! use music_generator::prelude::*;
+//! use std::collections::HashMap;
+//! use std::sync::Arc;
+//!
+//! let mut axiom = Axiom::from("AB").unwrap();
+//! let ruleset = RuleSet::from(vec![Rule::from("A->ABC").unwrap()]).unwrap();
+//! axiom.apply_ruleset(&ruleset);
+//!
+//! let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+//! let key = Key::new(&Note::D, &Accidental::Natural, temp);
+//! let action: Arc<dyn Action<_> + Send + Sync> = Arc::new(SimpleAction::new(key, &ScaleKind::Major));
+//!
+//! let mut atom_types: HashMap<&Atom, AtomType<NeutralActionState>> = HashMap::new();
+//! for atom in axiom.atoms() {
+//!     atom_types.insert(atom, AtomType::HasAction { action: Arc::clone(&action) });
+//! }
+//!
+//! let voice = Voice::from(&axiom, atom_types).unwrap();
+//! assert_eq!(voice.total_duration(), Duration::time_units(4));
+//! ```
+
+pub use crate::l_system::{Atom, Axiom, Rule, RuleSet};
+pub use crate::musical_notation::{
+    Accidental, Articulation, Duration, EqualTemperament, Key, MusicalElement, Note, Pitch,
+    ScaleKind, Temperament, TimeSignature, Tone, Volume, VolumeCurve,
+};
+pub use crate::musical_notation::{BAROQUE_PITCH, CHORTON_PITCH, CLASSICAL_PITCH, STUTTGART_PITCH};
+pub use crate::musical_notation::{F, FF, FFF, M, MF, MP, P, PP, PPP, SILENT};
+pub use crate::voice::action::{
+    Action, ActionKind, ActionMap, AtomType, MusicalState, NeutralActionState, SimpleAction,
+};
+pub use crate::voice::groove::GroovePattern;
+pub use crate::voice::{Score, Voice};
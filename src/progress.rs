@@ -0,0 +1,103 @@
+/* This module contains the reporting of progress
+ * during the long running stages of creating a piece:
+ * the expansion of an axiom, the generation of a voice
+ * from it and the rendering of the voice to audio.
+ */
+
+use std::fmt;
+
+/**
+ * A stage of the creation of a piece, in the order the stages run.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// applying the ruleset to the axiom, counted in iterations
+    Expansion,
+    /// creating the MusicalElements of a voice, counted in atoms
+    Generation,
+    /// rendering the sequenced voices, counted in samples
+    Rendering,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Expansion => write!(f, "expansion"),
+            Stage::Generation => write!(f, "generation"),
+            Stage::Rendering => write!(f, "rendering"),
+        }
+    }
+}
+
+/**
+ * Receives the progress of a stage. `on_stage` is called with a done count
+ * of zero when the stage starts and then with increasing counts up to the
+ * total when the stage is finished.
+ */
+pub trait Progress {
+    fn on_stage(&self, stage: Stage, done: u64, total: u64);
+}
+
+/**
+ * A Progress ignoring all reports, used by the functions without a progress parameter.
+ */
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn on_stage(&self, _stage: Stage, _done: u64, _total: u64) {}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{Progress, Stage};
+
+    use std::cell::RefCell;
+
+    /**
+     * A Progress remembering every report, for the tests of the reporting functions.
+     */
+    #[derive(Default)]
+    pub(crate) struct CollectingProgress {
+        pub(crate) reports: RefCell<Vec<(Stage, u64, u64)>>,
+    }
+
+    impl Progress for CollectingProgress {
+        fn on_stage(&self, stage: Stage, done: u64, total: u64) {
+            self.reports.borrow_mut().push((stage, done, total));
+        }
+    }
+
+    impl CollectingProgress {
+        /**
+         * Assert that the stages arrive in order, each one starting at zero
+         * and counting up strictly to its total.
+         */
+        pub(crate) fn assert_in_order(&self) {
+            let reports = self.reports.borrow();
+            assert!(!reports.is_empty());
+
+            for pair in reports.windows(2) {
+                let ((stage, done, total), (next_stage, next_done, next_total)) =
+                    (pair[0], pair[1]);
+                if stage == next_stage {
+                    assert!(next_done > done, "{:?}", pair);
+                    assert_eq!(total, next_total);
+                } else {
+                    assert!(next_stage > stage, "{:?}", pair);
+                    assert_eq!(done, total, "{:?}", pair);
+                    assert_eq!(next_done, 0, "{:?}", pair);
+                }
+            }
+
+            let (_, done, total) = reports[reports.len() - 1];
+            assert_eq!(done, total);
+        }
+    }
+
+    #[test]
+    fn stage_order_test() {
+        assert!(Stage::Expansion < Stage::Generation);
+        assert!(Stage::Generation < Stage::Rendering);
+        assert_eq!(Stage::Rendering.to_string(), "rendering");
+    }
+}
@@ -0,0 +1,523 @@
+/* This module describes a whole piece in a
+ * TOML project file: the L-system, the key,
+ * the voices and how they are rendered.
+ */
+
+use crate::l_system::{Axiom, RuleSet};
+use crate::musical_notation as notation;
+use crate::musical_notation::{EqualTemperament, Key, Temperament, Tone};
+use crate::voice::action::{Action, ActionMap, MusicalState, SimpleAction};
+use crate::voice::Voice;
+
+pub use crate::voice::action::ActionKind;
+
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub mod error {
+    use std::error::Error;
+    use std::fmt;
+
+    #[derive(Debug)]
+    pub struct ProjectError {
+        message: String,
+    }
+
+    impl ProjectError {
+        pub fn new(message: &str) -> ProjectError {
+            ProjectError {
+                message: message.to_string(),
+            }
+        }
+
+        /**
+         * An error caused by the value of the given key in the given table.
+         */
+        pub fn in_key(table: &str, key: &str, message: &str) -> ProjectError {
+            ProjectError::new(&format!("[{}] {}: {}", table, key, message))
+        }
+    }
+
+    impl fmt::Display for ProjectError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid project: {}.", self.message)
+        }
+    }
+
+    impl Error for ProjectError {}
+}
+
+use error::ProjectError;
+
+/**
+ * A piece described by a TOML file like the following. Only the axiom is required.
+ *
+ * ```toml
+ * [lsystem]
+ * axiom = "A"
+ * rules = ["A->AB", "B->CA"]
+ * iterations = 3
+ *
+ * [key]
+ * tonic = "F#"
+ * scale = "minor"
+ * pitch_standard = 440.0
+ *
+ * [voices.melody]
+ * mapping = { "[" = "push", "]" = "pop", "+" = "octave_up" }
+ * instrument = "triangle"
+ * pan = -0.5
+ * gain = 0.8
+ *
+ * [render]
+ * bpm = 90
+ * sample_rate = 44100.0
+ * output = "piece.wav"
+ * ```
+ *
+ * The mapping of a voice names the ActionKinds like an ActionMap file,
+ * atoms missing in it play notes.
+ * Without any voice table the piece consists of a single default voice.
+ */
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Project {
+    pub lsystem: LSystemTable,
+    #[serde(default)]
+    pub key: KeyTable,
+    #[serde(default)]
+    pub voices: BTreeMap<String, VoiceTable>,
+    #[serde(default)]
+    pub render: RenderTable,
+    #[serde(skip)]
+    axiom: Axiom,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LSystemTable {
+    pub axiom: String,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub iterations: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct KeyTable {
+    pub tonic: String,
+    pub scale: String,
+    pub pitch_standard: f64,
+}
+
+impl Default for KeyTable {
+    fn default() -> Self {
+        KeyTable {
+            tonic: String::from("C"),
+            scale: String::from("major"),
+            pitch_standard: notation::STUTTGART_PITCH,
+        }
+    }
+}
+
+/**
+ * The sound a voice is played with.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Instrument {
+    #[default]
+    Sine,
+    Square,
+    Saw,
+    Triangle,
+}
+
+impl Instrument {
+    pub const ALL: &'static [Instrument] = &[
+        Instrument::Sine,
+        Instrument::Square,
+        Instrument::Saw,
+        Instrument::Triangle,
+    ];
+
+    /**
+     * The name of the instrument as it's written in a project file.
+     */
+    pub fn name(&self) -> &'static str {
+        match self {
+            Instrument::Sine => "sine",
+            Instrument::Square => "square",
+            Instrument::Saw => "saw",
+            Instrument::Triangle => "triangle",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Instrument::Sine => "a pure sine wave",
+            Instrument::Square => "a hollow square wave",
+            Instrument::Saw => "a bright sawtooth wave",
+            Instrument::Triangle => "a soft triangle wave",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct VoiceTable {
+    pub mapping: BTreeMap<String, ActionKind>,
+    pub instrument: Instrument,
+    pub pan: f64,
+    pub gain: f64,
+}
+
+impl Default for VoiceTable {
+    fn default() -> Self {
+        VoiceTable {
+            mapping: BTreeMap::new(),
+            instrument: Instrument::Sine,
+            pan: 0.0,
+            gain: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct RenderTable {
+    pub bpm: u16,
+    pub sample_rate: f64,
+    pub output: Option<PathBuf>,
+}
+
+impl Default for RenderTable {
+    fn default() -> Self {
+        RenderTable {
+            bpm: 120,
+            sample_rate: 44100.0,
+            output: None,
+        }
+    }
+}
+
+impl Project {
+    /**
+     * Read and validate the project file at the given path.
+     */
+    pub fn from_toml(path: &Path, max_atoms: usize) -> Result<Project, ProjectError> {
+        match std::fs::read_to_string(path) {
+            Ok(toml) => Project::from_toml_str(&toml, max_atoms),
+            Err(e) => Err(ProjectError::new(&format!(
+                "The file '{}' couldn't be read: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    /**
+     * Parse and validate a project from its TOML representation. Its axiom is expanded
+     * once, failing if it would grow beyond max_atoms atoms.
+     */
+    pub fn from_toml_str(toml: &str, max_atoms: usize) -> Result<Project, ProjectError> {
+        let mut project: Project =
+            toml::from_str(toml).map_err(|e| ProjectError::new(&e.to_string()))?;
+
+        if project.voices.is_empty() {
+            project
+                .voices
+                .insert(String::from("1"), VoiceTable::default());
+        }
+
+        project.validate()?;
+        project.axiom = project.expand_axiom(max_atoms)?;
+        Ok(project)
+    }
+
+    fn validate(&self) -> Result<(), ProjectError> {
+        self.tonic()?;
+        self.scale_kind()?;
+
+        if self.key.pitch_standard <= 0.0 {
+            return Err(ProjectError::in_key(
+                "key",
+                "pitch_standard",
+                "must be greater than 0",
+            ));
+        }
+
+        for (name, voice) in &self.voices {
+            let table = format!("voices.{}", name);
+
+            for atom in voice.mapping.keys() {
+                if atom.chars().count() != 1 {
+                    return Err(ProjectError::in_key(
+                        &table,
+                        "mapping",
+                        &format!("'{}' is not a single atom", atom),
+                    ));
+                }
+            }
+            if !(-1.0..=1.0).contains(&voice.pan) {
+                return Err(ProjectError::in_key(
+                    &table,
+                    "pan",
+                    &format!("{} is not between -1 and 1", voice.pan),
+                ));
+            }
+            if voice.gain < 0.0 {
+                return Err(ProjectError::in_key(
+                    &table,
+                    "gain",
+                    &format!("{} is negative", voice.gain),
+                ));
+            }
+        }
+
+        if self.render.bpm == 0 {
+            return Err(ProjectError::in_key(
+                "render",
+                "bpm",
+                "must be greater than 0",
+            ));
+        }
+        if self.render.sample_rate <= 0.0 {
+            return Err(ProjectError::in_key(
+                "render",
+                "sample_rate",
+                "must be greater than 0",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /**
+     * The axiom of the L-system with its rules applied.
+     */
+    pub fn axiom(&self) -> &Axiom {
+        &self.axiom
+    }
+
+    fn expand_axiom(&self, max_atoms: usize) -> Result<Axiom, ProjectError> {
+        let axiom = Axiom::from(&self.lsystem.axiom).map_err(|e| {
+            ProjectError::in_key("lsystem", "axiom", e.to_string().trim_end_matches('.'))
+        })?;
+
+        if self.lsystem.rules.is_empty() {
+            return Ok(axiom);
+        }
+
+        let ruleset: RuleSet = self.lsystem.rules.join("\n").parse().map_err(
+            |e: crate::error::RepresentationError| {
+                ProjectError::in_key("lsystem", "rules", e.to_string().trim_end_matches('.'))
+            },
+        )?;
+
+        ruleset
+            .expand_bounded(&axiom, self.lsystem.iterations, max_atoms)
+            .map_err(|e| {
+                ProjectError::in_key("lsystem", "iterations", e.to_string().trim_end_matches('.'))
+            })
+    }
+
+    fn tonic(&self) -> Result<Tone, ProjectError> {
+        self.key
+            .tonic
+            .parse()
+            .map_err(|e: crate::error::ToneParseError| {
+                ProjectError::in_key("key", "tonic", e.to_string().trim_end_matches('.'))
+            })
+    }
+
+    fn scale_kind(&self) -> Result<notation::ScaleKind, ProjectError> {
+        self.key
+            .scale
+            .parse()
+            .map_err(|e: notation::ScaleKindParseError| {
+                ProjectError::in_key("key", "scale", e.to_string().trim_end_matches('.'))
+            })
+    }
+
+    /**
+     * Build the voices of the project from the expanded axiom, ordered by their names.
+     */
+    pub fn voices(&self) -> crate::Result<Vec<(&str, &VoiceTable, Voice)>> {
+        let axiom = &self.axiom;
+        let key = Key::from_tonic(
+            self.tonic()?,
+            Arc::new(EqualTemperament::new(self.key.pitch_standard)),
+        );
+        let action: Arc<dyn Action<MusicalState> + Send + Sync> =
+            Arc::new(SimpleAction::new(key, &self.scale_kind()?));
+
+        let mut voices = vec![];
+
+        for (name, voice_table) in &self.voices {
+            let action_map = voice_table
+                .mapping
+                .iter()
+                .filter_map(|(symbol, action_kind)| {
+                    symbol.chars().next().map(|symbol| (symbol, *action_kind))
+                })
+                .collect::<ActionMap>();
+            let atom_types = action_map.atom_types(axiom, Arc::clone(&action));
+
+            voices.push((name.as_str(), voice_table, Voice::from(axiom, atom_types)?));
+        }
+
+        Ok(voices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActionKind, Instrument, Project, VoiceTable};
+    use crate::l_system::DEFAULT_MAX_ATOMS;
+
+    const PROJECT: &str = r#"
+        [lsystem]
+        axiom = "A"
+        rules = ["A->AB[C]", "B->A"]
+        iterations = 2
+
+        [key]
+        tonic = "D"
+        scale = "minor"
+
+        [voices.bass]
+        mapping = { "[" = "push", "]" = "pop", "C" = "none" }
+        instrument = "saw"
+        pan = -0.5
+        gain = 0.5
+
+        [voices.melody]
+        mapping = { "[" = "none", "]" = "none" }
+
+        [render]
+        bpm = 90
+        output = "piece.wav"
+    "#;
+
+    #[test]
+    fn from_toml_str_test() {
+        let project = Project::from_toml_str(PROJECT, DEFAULT_MAX_ATOMS).unwrap();
+
+        assert_eq!(format!("{:?}", project.axiom()), "AB[C]A[C]");
+        assert_eq!(project.render.bpm, 90);
+        assert_eq!(project.render.sample_rate, 44100.0);
+        assert_eq!(project.voices["bass"].instrument, Instrument::Saw);
+        assert_eq!(project.voices["bass"].mapping["["], ActionKind::Push);
+        assert_eq!(project.voices["melody"].instrument, Instrument::Sine);
+        assert_eq!(project.voices["melody"].gain, 1.0);
+
+        let voices = project.voices().unwrap();
+        assert_eq!(voices.len(), 2);
+        assert_eq!(voices[0].0, "bass");
+        assert_eq!(voices[0].2.musical_elements().len(), 3);
+        assert_eq!(voices[1].0, "melody");
+        assert_eq!(voices[1].2.musical_elements().len(), 5);
+    }
+
+    #[test]
+    fn default_voice_test() {
+        let project =
+            Project::from_toml_str("[lsystem]\naxiom = \"ABC\"\n", DEFAULT_MAX_ATOMS).unwrap();
+        let voices = project.voices().unwrap();
+
+        assert_eq!(voices.len(), 1);
+        assert_eq!(voices[0].2.musical_elements().len(), 3);
+        assert_eq!(project.render.bpm, 120);
+    }
+
+    #[test]
+    fn validation_error_test() {
+        for (toml, message) in [
+            (
+                "[lsystem]\naxiom = \"A\"\n[key]\ntonic = \"H\"\n",
+                "Invalid project: [key] tonic: 'H' is not a tone like 'C', 'F#' or 'Bb'.",
+            ),
+            (
+                "[lsystem]\naxiom = \"A\"\n[voices.lead]\npan = 2.0\n",
+                "Invalid project: [voices.lead] pan: 2 is not between -1 and 1.",
+            ),
+            (
+                "[lsystem]\naxiom = \"A\"\n[voices.lead]\nmapping = { AB = \"push\" }\n",
+                "Invalid project: [voices.lead] mapping: 'AB' is not a single atom.",
+            ),
+            (
+                "[lsystem]\naxiom = \"A\"\nrules = [\"A\"]\n",
+                "Invalid project: [lsystem] rules: There was an Error with the Representation \
+                 of an L-System Element: Rule didn't contain a '->'.",
+            ),
+            (
+                "[lsystem]\naxiom = \"A\"\n[render]\nbpm = 0\n",
+                "Invalid project: [render] bpm: must be greater than 0.",
+            ),
+        ] {
+            assert_eq!(
+                Project::from_toml_str(toml, DEFAULT_MAX_ATOMS)
+                    .unwrap_err()
+                    .to_string(),
+                message
+            );
+        }
+
+        // errors found by the TOML parser name the key as well
+        let error = Project::from_toml_str(
+            "[lsystem]\naxiom = \"A\"\n[render]\nbpm = \"fast\"\n",
+            DEFAULT_MAX_ATOMS,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("render.bpm"), "{}", error);
+
+        let error = Project::from_toml_str(
+            "[lsystem]\naxiom = \"A\"\n[voices.lead]\nmapping = { A = \"jump\" }\n",
+            DEFAULT_MAX_ATOMS,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("voices.lead.mapping"), "{}", error);
+    }
+
+    #[test]
+    fn max_atoms_test() {
+        let toml = "[lsystem]\naxiom = \"A\"\nrules = [\"A->AA\"]\niterations = 3\n";
+
+        assert_eq!(
+            Project::from_toml_str(toml, 8)
+                .unwrap()
+                .axiom()
+                .atoms()
+                .len(),
+            8
+        );
+        assert_eq!(
+            Project::from_toml_str(toml, 7).unwrap_err().to_string(),
+            "Invalid project: [lsystem] iterations: Expanding the Axiom would produce 8 atoms, \
+             more than the maximum of 7."
+        );
+    }
+
+    #[test]
+    fn instruments_cover_every_variant() {
+        for instrument in Instrument::ALL {
+            // a new instrument has to be added here and to ALL
+            match instrument {
+                Instrument::Sine | Instrument::Square | Instrument::Saw | Instrument::Triangle => {}
+            }
+
+            let voice: VoiceTable =
+                toml::from_str(&format!("instrument = \"{}\"", instrument.name())).unwrap();
+            assert_eq!(voice.instrument, *instrument);
+        }
+
+        assert_eq!(Instrument::ALL.len(), 4);
+    }
+}
@@ -0,0 +1,251 @@
+/* This module lets a performer control the
+ * expansion of an L-system in real time with
+ * the messages of a MIDI controller. It doesn't
+ * open MIDI ports itself, a MIDI library like
+ * midir passes the messages of its input
+ * connection on to the handler.
+ */
+
+use crate::l_system::{Axiom, RuleSet, DEFAULT_MAX_ATOMS};
+use crate::voice::Voice;
+
+const NOTE_ON: u8 = 0x90;
+const CONTROL_CHANGE: u8 = 0xB0;
+const STATUS_MASK: u8 = 0xF0;
+const MAX_DATA_VALUE: u8 = 127;
+
+/**
+ * The controller adjusting the tempo by default, the modulation wheel.
+ */
+pub const MOD_WHEEL: u8 = 1;
+pub const DEFAULT_BPM: u16 = 120;
+
+/**
+ * The MIDI messages a MidiInputHandler reacts to, on any channel.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiEvent {
+    NoteOn { note: u8, velocity: u8 },
+    ControlChange { controller: u8, value: u8 },
+}
+
+impl MidiEvent {
+    /**
+     * Parse the bytes of a MIDI message. A note-on with a velocity of 0
+     * is a note-off and like all other messages not an event.
+     */
+    pub fn parse(message: &[u8]) -> Option<MidiEvent> {
+        match *message {
+            [status, note, velocity]
+                if status & STATUS_MASK == NOTE_ON
+                    && note <= MAX_DATA_VALUE
+                    && (1..=MAX_DATA_VALUE).contains(&velocity) =>
+            {
+                Some(MidiEvent::NoteOn { note, velocity })
+            }
+            [status, controller, value]
+                if status & STATUS_MASK == CONTROL_CHANGE
+                    && controller <= MAX_DATA_VALUE
+                    && value <= MAX_DATA_VALUE =>
+            {
+                Some(MidiEvent::ControlChange { controller, value })
+            }
+            _ => None,
+        }
+    }
+}
+
+/**
+ * A MidiInputHandler applies the ruleset once to the axiom for every note a
+ * performer plays and creates the voice of the expanded axiom, which is played
+ * next. Turning the tempo controller sets the tempo between the lowest and the
+ * highest bpm. The handler doesn't depend on how the messages arrive, e.g. the
+ * callback of a MIDI input connection passes the bytes of every message on to
+ * `handle_message`.
+ */
+pub struct MidiInputHandler<F: Fn(&Axiom) -> crate::Result<Voice>> {
+    axiom: Axiom,
+    ruleset: RuleSet,
+    create_voice: F,
+    bpm: u16,
+    bpm_range: (u16, u16),
+    tempo_controller: u8,
+    max_atoms: usize,
+}
+
+impl<F: Fn(&Axiom) -> crate::Result<Voice>> MidiInputHandler<F> {
+    /**
+     * Create a MidiInputHandler expanding the axiom with the ruleset and creating the
+     * voices with the given function. The tempo starts at 120 bpm and the modulation
+     * wheel sets it between 60 and 240 bpm. The axiom may grow to DEFAULT_MAX_ATOMS atoms.
+     */
+    pub fn new(axiom: Axiom, ruleset: RuleSet, create_voice: F) -> Self {
+        MidiInputHandler {
+            axiom,
+            ruleset,
+            create_voice,
+            bpm: DEFAULT_BPM,
+            bpm_range: (60, 240),
+            tempo_controller: MOD_WHEEL,
+            max_atoms: DEFAULT_MAX_ATOMS,
+        }
+    }
+
+    /**
+     * Set the maximum number of atoms the axiom may grow to.
+     */
+    pub fn set_max_atoms(&mut self, max_atoms: usize) {
+        self.max_atoms = max_atoms;
+    }
+
+    /**
+     * Set the controller adjusting the tempo and the tempo at its lowest and highest value.
+     */
+    pub fn set_tempo_controller(&mut self, controller: u8, lowest_bpm: u16, highest_bpm: u16) {
+        self.tempo_controller = controller;
+        self.bpm_range = (lowest_bpm.min(highest_bpm), lowest_bpm.max(highest_bpm));
+    }
+
+    pub fn axiom(&self) -> &Axiom {
+        &self.axiom
+    }
+
+    pub fn bpm(&self) -> u16 {
+        self.bpm
+    }
+
+    /**
+     * React to the bytes of a MIDI message. A note-on expands the axiom and returns the
+     * voice to play next, a change of the tempo controller sets the tempo. All other
+     * messages are ignored. A note-on that would expand the axiom beyond the maximum
+     * number of atoms is an error and leaves the axiom unchanged.
+     */
+    pub fn handle_message(&mut self, message: &[u8]) -> crate::Result<Option<Voice>> {
+        match MidiEvent::parse(message) {
+            Some(MidiEvent::NoteOn { .. }) => {
+                self.axiom
+                    .apply_ruleset_bounded(&self.ruleset, self.max_atoms)?;
+                Ok(Some((self.create_voice)(&self.axiom)?))
+            }
+            Some(MidiEvent::ControlChange { controller, value })
+                if controller == self.tempo_controller =>
+            {
+                let (lowest_bpm, highest_bpm) = self.bpm_range;
+                let span = (highest_bpm - lowest_bpm) as f64;
+                self.bpm =
+                    lowest_bpm + (span * value as f64 / MAX_DATA_VALUE as f64).round() as u16;
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MidiEvent, MidiInputHandler, DEFAULT_BPM};
+    use crate::l_system::{Axiom, Rule, RuleSet};
+    use crate::musical_notation::{
+        Accidental, EqualTemperament, Key, Note, ScaleKind, STUTTGART_PITCH,
+    };
+    use crate::voice::action::SimpleAction;
+    use crate::voice::Voice;
+
+    use std::sync::Arc;
+
+    fn handler() -> MidiInputHandler<impl Fn(&Axiom) -> crate::Result<Voice>> {
+        MidiInputHandler::new(
+            Axiom::from("A").unwrap(),
+            RuleSet::from(vec![Rule::from("A->AB").unwrap()]).unwrap(),
+            |axiom: &Axiom| {
+                let key = Key::equal_tempered(&Note::C, &Accidental::Natural, STUTTGART_PITCH);
+                Voice::from_axiom_with_default_action(
+                    axiom,
+                    Arc::new(SimpleAction::<EqualTemperament>::new(
+                        key,
+                        &ScaleKind::Major,
+                    )),
+                )
+            },
+        )
+    }
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(
+            MidiEvent::parse(&[0x90, 60, 100]),
+            Some(MidiEvent::NoteOn {
+                note: 60,
+                velocity: 100
+            })
+        );
+        assert_eq!(
+            MidiEvent::parse(&[0xB3, 1, 64]),
+            Some(MidiEvent::ControlChange {
+                controller: 1,
+                value: 64
+            })
+        );
+
+        // note-off, a note-on without velocity, program change and invalid data
+        for message in [
+            &[0x80, 60, 0][..],
+            &[0x90, 60, 0],
+            &[0xC0, 5],
+            &[0x90, 200, 100],
+            &[],
+        ] {
+            assert_eq!(MidiEvent::parse(message), None, "{:?}", message);
+        }
+    }
+
+    #[test]
+    fn note_on_test() {
+        let mut handler = handler();
+
+        let voice = handler.handle_message(&[0x90, 60, 100]).unwrap().unwrap();
+        assert_eq!(format!("{:?}", handler.axiom()), "AB");
+        assert_eq!(voice.get_note_count(), 2);
+
+        let voice = handler.handle_message(&[0x91, 64, 1]).unwrap().unwrap();
+        assert_eq!(format!("{:?}", handler.axiom()), "ABB");
+        assert_eq!(voice.get_note_count(), 3);
+
+        assert!(handler.handle_message(&[0x80, 60, 0]).unwrap().is_none());
+        assert_eq!(format!("{:?}", handler.axiom()), "ABB");
+    }
+
+    #[test]
+    fn max_atoms_test() {
+        let mut handler = handler();
+        handler.set_max_atoms(2);
+
+        assert!(handler.handle_message(&[0x90, 60, 100]).is_ok());
+        let error = handler.handle_message(&[0x90, 60, 100]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Expanding the Axiom would produce 3 atoms, more than the maximum of 2."
+        );
+        assert_eq!(format!("{:?}", handler.axiom()), "AB");
+    }
+
+    #[test]
+    fn tempo_test() {
+        let mut handler = handler();
+        assert_eq!(handler.bpm(), DEFAULT_BPM);
+
+        assert!(handler.handle_message(&[0xB0, 1, 127]).unwrap().is_none());
+        assert_eq!(handler.bpm(), 240);
+        handler.handle_message(&[0xB0, 1, 0]).unwrap();
+        assert_eq!(handler.bpm(), 60);
+
+        // other controllers leave the tempo alone
+        handler.handle_message(&[0xB0, 7, 127]).unwrap();
+        assert_eq!(handler.bpm(), 60);
+
+        handler.set_tempo_controller(7, 100, 80);
+        handler.handle_message(&[0xB0, 7, 127]).unwrap();
+        assert_eq!(handler.bpm(), 100);
+        assert_eq!(format!("{:?}", handler.axiom()), "A");
+    }
+}
@@ -0,0 +1,106 @@
+/**
+ * A small deterministic pseudo random number generator, so that every
+ * stochastic feature produces the same output when given the same seed.
+ * The numbers are generated with the SplitMix64 algorithm, which isn't
+ * suitable for cryptographic purposes.
+ */
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn from_seed(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /**
+     * A uniformly distributed number in the range [0.0, 1.0).
+     */
+    pub fn next_f64(&mut self) -> f64 {
+        // the 53 most significant bits fill the mantissa of a f64
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /**
+     * Choose an index into weights, where the probability of each index is
+     * proportional to its weight. Negative weights are treated as zero.
+     * Returns None if no weight is greater than zero.
+     */
+    pub fn choose_weighted(&mut self, weights: &[f64]) -> Option<usize> {
+        let total: f64 = weights.iter().map(|weight| weight.max(0.0)).sum();
+
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut target = self.next_f64() * total;
+
+        for (index, weight) in weights.iter().enumerate() {
+            let weight = weight.max(0.0);
+
+            if target < weight {
+                return Some(index);
+            }
+
+            target -= weight;
+        }
+
+        // rounding errors can leave target slightly above the last weight
+        weights.iter().rposition(|weight| *weight > 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn same_seed_test() {
+        let mut rng_1 = Rng::from_seed(42);
+        let mut rng_2 = Rng::from_seed(42);
+
+        for _ in 0..5 {
+            assert_eq!(rng_1.next_u64(), rng_2.next_u64());
+            assert_eq!(rng_1.next_f64(), rng_2.next_f64());
+        }
+
+        let mut rng_3 = Rng::from_seed(43);
+        assert_ne!(Rng::from_seed(42).next_u64(), rng_3.next_u64());
+    }
+
+    #[test]
+    fn next_f64_range_test() {
+        let mut rng = Rng::from_seed(0);
+
+        for _ in 0..1000 {
+            let number = rng.next_f64();
+            assert!((0.0..1.0).contains(&number));
+        }
+    }
+
+    #[test]
+    fn choose_weighted_test() {
+        let mut rng = Rng::from_seed(7);
+        let mut counts = [0; 3];
+
+        for _ in 0..1000 {
+            counts[rng.choose_weighted(&[1.0, 0.0, 3.0]).unwrap()] += 1;
+        }
+
+        assert_eq!(counts[1], 0);
+        assert!(counts[2] > 2 * counts[0]);
+
+        assert_eq!(rng.choose_weighted(&[]), None);
+        assert_eq!(rng.choose_weighted(&[0.0, -1.0]), None);
+        assert_eq!(rng.choose_weighted(&[-1.0, 2.0]), Some(1));
+    }
+}
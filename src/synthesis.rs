@@ -0,0 +1,848 @@
+/* This module contains sound generators which
+ * aren't part of fundsp, e.g. the playback of a
+ * wavetable loaded from a WAV file.
+ */
+
+use crate::musical_notation::{Pitch, Volume};
+use crate::progress::{Progress, Stage};
+use crate::rng::Rng;
+
+use fundsp::hacker::*;
+
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+pub mod error;
+
+use error::WaveTableError;
+
+/**
+ * The peak a clipping wave is attenuated to, just below full scale.
+ */
+const CLIP_GUARD_PEAK: f64 = 0.99;
+// the odd constant of the splitmix generator, spreading consecutive indices over all bits
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/**
+ * One cycle of a waveform, which is played faster or slower for different pitches.
+ * The sample rate is the one of the file the cycle was loaded from, so the cycle
+ * sounds at `sample_rate / samples.len()` Hz if it's played unchanged.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveTable {
+    samples: Vec<f32>,
+    sample_rate: f32,
+}
+
+impl WaveTable {
+    /**
+     * Create a WaveTable of the cycle given by the samples, which were recorded at the
+     * sample rate. The cycle needs at least two samples and the sample rate has to be positive.
+     */
+    pub fn new(samples: Vec<f32>, sample_rate: f32) -> Result<WaveTable, WaveTableError> {
+        if samples.len() < 2 {
+            return Err(WaveTableError::new("the cycle needs at least two samples"));
+        }
+        if sample_rate.is_nan() || sample_rate <= 0.0 {
+            return Err(WaveTableError::new("the sample rate has to be positive"));
+        }
+
+        Ok(WaveTable {
+            samples,
+            sample_rate,
+        })
+    }
+
+    /**
+     * Load the first cycle of the first channel of a 16 bit PCM or 32 bit float WAV file.
+     * The cycle lasts from the first to the second upward zero crossing, where a file
+     * starting with a zero followed by a positive sample starts with a crossing. A file
+     * without two upward zero crossings is taken as a whole, assuming it holds exactly one cycle.
+     */
+    pub fn from_wav(path: &Path) -> Result<WaveTable, WaveTableError> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            WaveTableError::new(&format!(
+                "The file '{}' couldn't be read: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let (samples, sample_rate) = read_wav(&bytes)?;
+        let upward_zero_crossings: Vec<usize> = (0..samples.len())
+            .filter(|index| match index {
+                // a file starting with silence rising to a positive sample starts with a cycle
+                0 => samples[0] == 0.0 && samples.get(1).is_some_and(|next| *next > 0.0),
+                _ => samples[index - 1] < 0.0 && samples[*index] >= 0.0,
+            })
+            .take(2)
+            .collect();
+
+        let samples = match upward_zero_crossings[..] {
+            [start, end] => samples[start..end].to_vec(),
+            _ => samples,
+        };
+
+        WaveTable::new(samples, sample_rate)
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /**
+     * Read the cycle at the given phase between 0 and 1, interpolating linearly between the samples.
+     */
+    pub fn at(&self, phase: f64) -> f64 {
+        let position = phase.rem_euclid(1.0) * self.samples.len() as f64;
+        let index = position.floor() as usize % self.samples.len();
+        let next = (index + 1) % self.samples.len();
+        let weight = position.fract();
+
+        self.samples[index] as f64 * (1.0 - weight) + self.samples[next] as f64 * weight
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+/**
+ * Read the samples of the first channel and the sample rate of a WAV file.
+ */
+fn read_wav(bytes: &[u8]) -> Result<(Vec<f32>, f32), WaveTableError> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(WaveTableError::new("the file is not a WAV file"));
+    }
+
+    let mut format: Option<(u16, usize, u32, u16)> = None;
+    let mut offset = 12;
+
+    while offset + 8 <= bytes.len() {
+        let chunk_id = &bytes[offset..offset + 4];
+        let chunk_size = read_u32(bytes, offset + 4) as usize;
+        let chunk = &bytes[offset + 8..Ord::min(offset + 8 + chunk_size, bytes.len())];
+
+        match chunk_id {
+            b"fmt " if chunk.len() >= 16 => {
+                format = Some((
+                    read_u16(chunk, 0),
+                    read_u16(chunk, 2) as usize,
+                    read_u32(chunk, 4),
+                    read_u16(chunk, 14),
+                ));
+            }
+            b"data" => {
+                let samples = match format {
+                    Some((1, channels, _, 16)) if channels > 0 => chunk
+                        .chunks_exact(2 * channels)
+                        .map(|frame| read_u16(frame, 0) as i16 as f32 / i16::MAX as f32)
+                        .collect(),
+                    Some((3, channels, _, 32)) if channels > 0 => chunk
+                        .chunks_exact(4 * channels)
+                        .map(|frame| f32::from_bits(read_u32(frame, 0)))
+                        .collect(),
+                    Some(_) => {
+                        return Err(WaveTableError::new(
+                            "only 16 bit PCM and 32 bit float samples are supported",
+                        ))
+                    }
+                    None => {
+                        return Err(WaveTableError::new(
+                            "the format has to be given before the samples",
+                        ))
+                    }
+                };
+
+                let (_, _, sample_rate, _) = format.unwrap();
+                return Ok((samples, sample_rate as f32));
+            }
+            _ => {}
+        }
+
+        // chunks are padded to an even size
+        offset += 8 + chunk_size + chunk_size % 2;
+    }
+
+    Err(WaveTableError::new("the file contains no samples"))
+}
+
+/**
+ * A fundsp node playing a WaveTable at a fixed pitch.
+ */
+pub struct WaveTableOscillator {
+    table: Arc<WaveTable>,
+    hz: f64,
+    phase: f64,
+    sample_duration: f64,
+}
+
+impl WaveTableOscillator {
+    pub fn new(pitch: Pitch, table: &Arc<WaveTable>) -> WaveTableOscillator {
+        WaveTableOscillator {
+            table: Arc::clone(table),
+            hz: pitch.get_hz(),
+            phase: 0.0,
+            sample_duration: 1.0 / DEFAULT_SR,
+        }
+    }
+}
+
+impl AudioNode for WaveTableOscillator {
+    const ID: u64 = 0x5761_7665;
+    type Sample = f64;
+    type Inputs = U0;
+    type Outputs = U1;
+
+    fn reset(&mut self, sample_rate: Option<f64>) {
+        self.phase = 0.0;
+        if let Some(sample_rate) = sample_rate {
+            self.sample_duration = 1.0 / sample_rate;
+        }
+    }
+
+    fn tick(&mut self, _input: &Frame<f64, U0>) -> Frame<f64, U1> {
+        let sample = self.table.at(self.phase);
+        self.phase = (self.phase + self.hz * self.sample_duration).fract();
+        [sample].into()
+    }
+}
+
+/**
+ * Create a stereo unit playing the wavetable at the given pitch and volume, panned to the center.
+ */
+pub fn build_wavetable_audio_unit(
+    pitch: Pitch,
+    volume: Volume,
+    table: &Arc<WaveTable>,
+) -> Box<dyn AudioUnit64> {
+    Box::new((volume.as_amplitude() * An(WaveTableOscillator::new(pitch, table))) >> pan(0.0))
+}
+
+/**
+ * Slow periodic and random per-note deviations of the pitch an instrument plays.
+ * The vibrato swings the pitch by up to vibrato_cents above and below the note
+ * vibrato_hz times a second. Every note is detuned by a random number of cents
+ * between -detune_cents and detune_cents. A slide note glides in from the previous
+ * pitch during the first slide_portion of its duration, evenly in cents.
+ * The default modulation keeps the pitch and lets slides jump.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PitchModulation {
+    pub vibrato_hz: f64,
+    pub vibrato_cents: f64,
+    pub detune_cents: f64,
+    pub slide_portion: f64,
+}
+
+impl PitchModulation {
+    /**
+     * The random detune in cents of the note with the given index. The same seed
+     * and index always give the same detune, while neighbouring seeds don't share
+     * the detunes of shifted indices.
+     */
+    pub fn detune_of(&self, seed: u64, index: usize) -> f64 {
+        if self.detune_cents == 0.0 {
+            return 0.0;
+        }
+
+        let mut rng = Rng::from_seed(seed ^ (index as u64 + 1).wrapping_mul(GOLDEN_GAMMA));
+        (2.0 * rng.next_f64() - 1.0) * self.detune_cents
+    }
+
+    /**
+     * A node putting out the frequency in Hz of the pitch detuned by the given cents
+     * and modulated by the vibrato, to be fed into an oscillator like `sine()`.
+     */
+    pub fn frequency(
+        &self,
+        pitch: Pitch,
+        detune_cents: f64,
+    ) -> An<impl AudioNode<Sample = f64, Inputs = U0, Outputs = U1>> {
+        self.sliding_frequency(None, pitch, 0.0, detune_cents)
+    }
+
+    /**
+     * A node putting out the frequency like `frequency`, which first glides from
+     * slide_from to the pitch if the note of the given duration in seconds is a slide.
+     */
+    pub fn sliding_frequency(
+        &self,
+        slide_from: Option<Pitch>,
+        pitch: Pitch,
+        duration_secs: f64,
+        detune_cents: f64,
+    ) -> An<impl AudioNode<Sample = f64, Inputs = U0, Outputs = U1>> {
+        let hz = pitch.get_hz() * 2f64.powf(detune_cents / 1200.0);
+        let slide_cents = slide_from.map_or(0.0, |slide_from| slide_from.cents_from(pitch));
+        let slide_secs = self.slide_portion.clamp(0.0, 1.0) * duration_secs;
+        let PitchModulation {
+            vibrato_hz,
+            vibrato_cents,
+            ..
+        } = *self;
+
+        lfo(move |t: f64| {
+            let remaining_slide_cents = if t < slide_secs {
+                slide_cents * (1.0 - t / slide_secs)
+            } else {
+                0.0
+            };
+            hz * 2f64.powf(
+                (remaining_slide_cents + vibrato_cents * (TAU * vibrato_hz * t).sin()) / 1200.0,
+            )
+        })
+    }
+}
+
+/**
+ * Where the notes of a voice are placed in the stereo field. Fixed plays every note
+ * at the pan of the voice. ByPitch spreads the notes around it by their pitch like the
+ * keys of a piano seen from the player, the lowest pitch of the voice width / 2 to the
+ * left and the highest width / 2 to the right, evenly in cents in between.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SpatializeMode {
+    #[default]
+    Fixed,
+    ByPitch {
+        width: f64,
+    },
+}
+
+impl SpatializeMode {
+    /**
+     * The pan of a note with the given pitch in a voice at the given pan, whose notes
+     * lie in the range. A voice with a single pitch or without range is played at its pan.
+     * The pan is clamped to [-1, 1].
+     */
+    pub fn pan_of(&self, pan: f64, pitch: Pitch, range: Option<(Pitch, Pitch)>) -> f64 {
+        match (self, range) {
+            (SpatializeMode::ByPitch { width }, Some((lowest, highest)))
+                if highest.get_hz() > lowest.get_hz() =>
+            {
+                let position = pitch.cents_from(lowest) / highest.cents_from(lowest);
+                (pan + width * (position - 0.5)).clamp(-1.0, 1.0)
+            }
+            _ => pan,
+        }
+    }
+}
+
+/**
+ * Render the node like `Wave64::render`, reporting the rendered samples as the Rendering
+ * stage to the progress once per second of audio and when the rendering is finished.
+ */
+pub fn render_with_progress(
+    sample_rate: f64,
+    duration: f64,
+    node: &mut dyn AudioUnit64,
+    progress: &dyn Progress,
+) -> Wave64 {
+    node.reset(Some(sample_rate));
+    let length = (duration * sample_rate).round() as usize;
+    let report_interval = Ord::max(sample_rate as usize, 1);
+    let mut wave = Wave64::with_capacity(node.outputs(), sample_rate, length);
+    let mut buffers = vec![vec![0.0; MAX_BUFFER_SIZE]; node.outputs()];
+    let mut done = 0;
+
+    progress.on_stage(Stage::Rendering, 0, length as u64);
+
+    while done < length {
+        let size = Ord::min(length - done, MAX_BUFFER_SIZE);
+        let mut output: Vec<&mut [f64]> = buffers
+            .iter_mut()
+            .map(|buffer| &mut buffer[..size])
+            .collect();
+        node.process(size, &[], &mut output);
+
+        for (channel, buffer) in buffers.iter().enumerate() {
+            wave.channel_mut(channel).extend_from_slice(&buffer[..size]);
+        }
+
+        let reports_before = done / report_interval;
+        done += size;
+        if done / report_interval > reports_before || done == length {
+            progress.on_stage(Stage::Rendering, done as u64, length as u64);
+        }
+    }
+
+    wave
+}
+
+/**
+ * Attenuate the wave if its peak exceeds full scale, which would distort it when it is saved.
+ * The wave is scaled so that its peak lies just below full scale.
+ * Returns the factor the wave was scaled by or None if it doesn't clip.
+ */
+pub fn guard_clipping(wave: &mut Wave64) -> Option<f64> {
+    let peak = wave.amplitude();
+    if peak <= 1.0 {
+        return None;
+    }
+
+    let factor = CLIP_GUARD_PEAK / peak;
+    for channel in 0..wave.channels() {
+        for sample in wave.channel_mut(channel).iter_mut() {
+            *sample *= factor;
+        }
+    }
+
+    Some(factor)
+}
+
+/**
+ * Write the samples of the wave to the sink as raw PCM, interleaved frame by frame
+ * as little-endian 32 bit floats, e.g. to pipe them into another tool.
+ */
+pub fn write_pcm_f32(wave: &Wave64, out: &mut impl Write) -> std::io::Result<()> {
+    for index in 0..wave.length() {
+        for channel in 0..wave.channels() {
+            out.write_all(&(wave.at(channel, index) as f32).to_le_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Get the number of samples per channel in the data chunk of the WAV file,
+ * dividing its size by the block alignment given in the fmt chunk.
+ */
+fn wav_length(bytes: &[u8]) -> Option<usize> {
+    let mut block_align = None;
+    let mut at = 12;
+
+    while at + 8 <= bytes.len() {
+        let size = u32::from_le_bytes(bytes[at + 4..at + 8].try_into().ok()?) as usize;
+        let data = at + 8;
+        match &bytes[at..at + 4] {
+            b"fmt " => {
+                block_align = Some(u16::from_le_bytes(
+                    bytes.get(data + 12..data + 14)?.try_into().ok()?,
+                ))
+            }
+            b"data" => return size.checked_div(usize::from(block_align?)),
+            _ => {}
+        }
+        // chunks are padded to an even size
+        at = data + size + size % 2;
+    }
+
+    None
+}
+
+/**
+ * Add a `smpl` chunk to the WAV file at the path, marking the samples from start up to but
+ * excluding end as a forward loop, which samplers and players honor when looping the file.
+ * The loop has to end within the samples of the file.
+ * The file is rewritten, as the WAV saver of fundsp can't write custom chunks.
+ */
+pub fn write_loop_points(
+    path: &Path,
+    sample_rate: f64,
+    start: usize,
+    end: usize,
+) -> std::io::Result<()> {
+    let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+    let mut bytes = std::fs::read(path)?;
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid("The file is not a WAV file."));
+    }
+    if start >= end {
+        return Err(invalid("The loop has to start before it ends."));
+    }
+    let length = wav_length(&bytes).ok_or_else(|| invalid("The WAV file holds no samples."))?;
+    if end > length {
+        return Err(invalid(&format!(
+            "The loop ends after sample {}, beyond the {} samples of the file.",
+            end, length
+        )));
+    }
+    let offset = |samples: usize| {
+        u32::try_from(samples)
+            .map_err(|_| invalid("The loop lies beyond the samples a WAV file can hold."))
+    };
+    let (start, last) = (offset(start)?, offset(end - 1)?);
+
+    let sample_period_ns = (1e9 / sample_rate).round() as u32;
+    let fields: [u32; 15] = [
+        // manufacturer, product, sample period, MIDI unity note and pitch fraction
+        0,
+        0,
+        sample_period_ns,
+        60,
+        0,
+        // SMPTE format and offset, the number of loops and of additional sampler data
+        0,
+        0,
+        1,
+        0,
+        // the loop: its cue point id, a forward loop, its first and last sample,
+        // the fraction and an infinite play count
+        0,
+        0,
+        start,
+        last,
+        0,
+        0,
+    ];
+
+    bytes.extend_from_slice(b"smpl");
+    bytes.extend_from_slice(&(4 * fields.len() as u32).to_le_bytes());
+    for field in fields {
+        bytes.extend_from_slice(&field.to_le_bytes());
+    }
+
+    let riff_size = offset(bytes.len() - 8)?;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    std::fs::write(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_wavetable_audio_unit, guard_clipping, render_with_progress, write_loop_points,
+        write_pcm_f32, PitchModulation, SpatializeMode, WaveTable,
+    };
+    use crate::musical_notation::{Duration, MusicalElement, Pitch, M};
+    use crate::progress::tests::CollectingProgress;
+    use crate::progress::Stage;
+    use crate::test_helpers;
+    use crate::voice::{with_context, NoteContext, Voice};
+
+    use fundsp::hacker::*;
+    use std::sync::Arc;
+
+    fn decibels(wave: &Wave64) -> f64 {
+        let samples = wave.channel(0);
+        let rms = (samples.iter().map(|sample| sample * sample).sum::<f64>()
+            / samples.len() as f64)
+            .sqrt();
+        20.0 * rms.log10()
+    }
+
+    #[test]
+    fn sine_cycle_test() {
+        let path = std::env::temp_dir().join("music_generator_sine_cycle.wav");
+        let mut cycle = Wave64::new(1, 44100.0);
+        // two and a half cycles of a sine lasting 100 samples each
+        cycle.resize(250);
+        for index in 0..250 {
+            cycle.set(0, index, (index as f64 * TAU / 100.0).sin());
+        }
+        cycle.save_wav16(&path).unwrap();
+
+        let table = Arc::new(WaveTable::from_wav(&path).unwrap());
+        assert_eq!(table.sample_rate(), 44100.0);
+        assert!((99..=101).contains(&table.samples().len()));
+
+        let mut wavetable = build_wavetable_audio_unit(Pitch(330.0), M, &table);
+        let mut sine = (M.as_amplitude() * sine_hz(330.0)) >> pan(0.0);
+
+        let difference = decibels(&Wave64::render(44100.0, 1.0, wavetable.as_mut()))
+            - decibels(&Wave64::render(44100.0, 1.0, &mut sine));
+        assert!(difference.abs() < 1.0, "{} dB", difference);
+    }
+
+    #[test]
+    fn new_test() {
+        let table = WaveTable::new(vec![0.0, 1.0, 0.0, -1.0], 400.0).unwrap();
+        assert_eq!(table.samples(), &[0.0, 1.0, 0.0, -1.0]);
+        assert_eq!(table.at(0.25), 1.0);
+
+        assert_eq!(
+            WaveTable::new(vec![], 44100.0).unwrap_err().to_string(),
+            "Invalid wavetable: the cycle needs at least two samples."
+        );
+        assert!(WaveTable::new(vec![0.0, 1.0], 0.0).is_err());
+        assert!(WaveTable::new(vec![0.0, 1.0], f32::NAN).is_err());
+    }
+
+    #[test]
+    fn invalid_wav_test() {
+        let path = std::env::temp_dir().join("music_generator_invalid_cycle.wav");
+        std::fs::write(&path, "not a wav file").unwrap();
+
+        assert_eq!(
+            WaveTable::from_wav(&path).unwrap_err().to_string(),
+            "Invalid wavetable: the file is not a WAV file."
+        );
+    }
+
+    #[test]
+    fn render_with_progress_test() {
+        let mut sine = sine_hz(440.0) >> pan(0.0);
+        let progress = CollectingProgress::default();
+
+        let wave = render_with_progress(1000.0, 2.5, &mut sine, &progress);
+
+        assert_eq!(wave.channels(), 2);
+        assert_eq!(wave.len(), 2500);
+        assert_eq!(
+            wave.channel(0),
+            Wave64::render(1000.0, 2.5, &mut sine).channel(0)
+        );
+
+        let reports = progress.reports.borrow();
+        assert_eq!(reports.first(), Some(&(Stage::Rendering, 0, 2500)));
+        assert_eq!(reports.last(), Some(&(Stage::Rendering, 2500, 2500)));
+        // one report per second of audio besides the first and the last
+        assert_eq!(reports.len(), 4);
+        drop(reports);
+        progress.assert_in_order();
+    }
+
+    /**
+     * The frequencies of the wave in consecutive windows of the given number of seconds.
+     */
+    fn windowed_frequencies(wave: &Wave64, window: f64) -> Vec<f64> {
+        test_helpers::windowed_frequencies(
+            wave.channel(0),
+            wave.sample_rate(),
+            (window * wave.sample_rate()) as usize,
+        )
+    }
+
+    #[test]
+    fn vibrato_test() {
+        let vibrato = PitchModulation {
+            vibrato_hz: 5.0,
+            vibrato_cents: 50.0,
+            detune_cents: 0.0,
+            slide_portion: 0.0,
+        };
+        let mut node = vibrato.frequency(Pitch(440.0), 0.0) >> sine();
+        let frequencies = windowed_frequencies(&Wave64::render(44100.0, 1.0, &mut node), 0.025);
+
+        // 50 cents above and below 440 Hz are 452.9 Hz and 427.5 Hz
+        let highest = frequencies.iter().cloned().fold(0.0, f64::max);
+        let lowest = frequencies.iter().cloned().fold(f64::MAX, f64::min);
+        assert!(highest > 448.0 && highest < 454.0, "{}", highest);
+        assert!(lowest < 432.0 && lowest > 426.0, "{}", lowest);
+
+        // the pitch rises and falls five times a second
+        let rises = frequencies
+            .windows(2)
+            .filter(|pair| pair[0] < 440.0 && pair[1] >= 440.0)
+            .count();
+        assert!((4..=6).contains(&rises), "{}", rises);
+
+        let steady = PitchModulation {
+            vibrato_hz: 5.0,
+            ..PitchModulation::default()
+        };
+        let mut node = steady.frequency(Pitch(440.0), 0.0) >> sine();
+        let frequencies = windowed_frequencies(&Wave64::render(44100.0, 1.0, &mut node), 0.025);
+        assert!(
+            frequencies.iter().all(|hz| (hz - 440.0).abs() < 1.0),
+            "{:?}",
+            frequencies
+        );
+    }
+
+    #[test]
+    fn slide_test() {
+        let modulation = PitchModulation {
+            slide_portion: 0.5,
+            ..PitchModulation::default()
+        };
+        let mut node =
+            modulation.sliding_frequency(Some(Pitch(220.0)), Pitch(440.0), 1.0, 0.0) >> sine();
+        let frequencies = windowed_frequencies(&Wave64::render(44100.0, 1.0, &mut node), 0.05);
+
+        // the note starts near the previous pitch, rises steadily and holds its own pitch after half a second
+        assert!(frequencies[0] < 250.0, "{:?}", frequencies);
+        assert!(frequencies[..10].windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(
+            frequencies[11..].iter().all(|hz| (hz - 440.0).abs() < 1.0),
+            "{:?}",
+            frequencies
+        );
+
+        // a note without a previous pitch or without a slide portion doesn't slide
+        for (slide_from, modulation) in [
+            (None, modulation),
+            (Some(Pitch(220.0)), PitchModulation::default()),
+        ] {
+            let mut node =
+                modulation.sliding_frequency(slide_from, Pitch(440.0), 1.0, 0.0) >> sine();
+            let frequencies = windowed_frequencies(&Wave64::render(44100.0, 0.5, &mut node), 0.1);
+            assert!(frequencies.iter().all(|hz| (hz - 440.0).abs() < 1.0));
+        }
+    }
+
+    #[test]
+    fn guard_clipping_test() {
+        // a sine at three times full scale
+        let mut hot = 3.0 * sine_hz(440.0);
+        let mut wave = Wave64::render(44100.0, 0.5, &mut hot);
+        assert!(wave.amplitude() > 2.9);
+
+        let factor = guard_clipping(&mut wave).unwrap();
+        assert!(wave.amplitude() < 1.0 && wave.amplitude() > 0.98);
+        assert!((factor * 3.0 - 0.99).abs() < 0.01, "{}", factor);
+
+        // a wave within full scale is left alone
+        let mut quiet = 0.5 * sine_hz(440.0);
+        let mut wave = Wave64::render(44100.0, 0.5, &mut quiet);
+        let samples = wave.channel(0).clone();
+        assert_eq!(guard_clipping(&mut wave), None);
+        assert_eq!(wave.channel(0), &samples);
+    }
+
+    #[test]
+    fn write_pcm_f32_test() {
+        let mut stereo = sine_hz(440.0) >> pan(0.5);
+        let wave = Wave64::render(44100.0, 0.1, &mut stereo);
+
+        let mut bytes = vec![];
+        write_pcm_f32(&wave, &mut bytes).unwrap();
+        assert_eq!(bytes.len(), wave.length() * wave.channels() * 4);
+
+        // the second sample is the first one of the right channel
+        let sample =
+            |index: usize| f32::from_le_bytes(bytes[4 * index..4 * index + 4].try_into().unwrap());
+        assert_eq!(sample(1), wave.at(1, 0) as f32);
+        assert_eq!(sample(2), wave.at(0, 1) as f32);
+    }
+
+    #[test]
+    fn write_loop_points_test() {
+        let path = std::env::temp_dir().join(format!(
+            "music_generator_loop_points_{}.wav",
+            std::process::id()
+        ));
+        let mut sine = sine_hz(440.0) >> pan(0.0);
+        Wave64::render(44100.0, 1.0, &mut sine)
+            .save_wav16(&path)
+            .unwrap();
+        let length = std::fs::metadata(&path).unwrap().len() as usize;
+
+        write_loop_points(&path, 44100.0, 11025, 33075).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let field = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        assert_eq!(bytes.len(), length + 68);
+        assert_eq!(field(4) as usize, bytes.len() - 8);
+        assert_eq!(&bytes[length..length + 4], b"smpl");
+        assert_eq!(field(length + 4), 60);
+        // the sample period in nanoseconds and the number of loops
+        assert_eq!(field(length + 16), 22676);
+        assert_eq!(field(length + 36), 1);
+        // the first and the last sample of the loop
+        assert_eq!(field(length + 52), 11025);
+        assert_eq!(field(length + 56), 33074);
+
+        // the saved wave is still readable
+        assert!(WaveTable::from_wav(&path).is_ok());
+        assert!(write_loop_points(&path, 44100.0, 10, 10).is_err());
+
+        // the loop may end with the last sample but not beyond it
+        assert!(write_loop_points(&path, 44100.0, 0, 44100).is_ok());
+        assert_eq!(
+            write_loop_points(&path, 44100.0, 0, 44101)
+                .unwrap_err()
+                .to_string(),
+            "The loop ends after sample 44101, beyond the 44100 samples of the file."
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn spatialize_test() {
+        let range = Some((Pitch(110.0), Pitch(440.0)));
+        let by_pitch = SpatializeMode::ByPitch { width: 1.0 };
+
+        assert_eq!(by_pitch.pan_of(0.0, Pitch(110.0), range), -0.5);
+        assert_eq!(by_pitch.pan_of(0.0, Pitch(220.0), range), 0.0);
+        assert_eq!(by_pitch.pan_of(0.0, Pitch(440.0), range), 0.5);
+        assert_eq!(by_pitch.pan_of(0.8, Pitch(440.0), range), 1.0);
+        assert_eq!(
+            by_pitch.pan_of(0.3, Pitch(440.0), Some((Pitch(440.0), Pitch(440.0)))),
+            0.3
+        );
+        assert_eq!(SpatializeMode::Fixed.pan_of(0.3, Pitch(110.0), range), 0.3);
+    }
+
+    #[test]
+    fn spatialize_stereo_balance_test() {
+        let scale: Vec<MusicalElement> = [261.626, 293.665, 329.628, 349.228, 391.995, 440.0]
+            .iter()
+            .map(|hz| MusicalElement::note(Pitch(*hz), Duration::quarter(), M))
+            .collect();
+        let voice = Voice::from_musical_elements(scale);
+        let range = voice.pitch_range();
+        let by_pitch = SpatializeMode::ByPitch { width: 2.0 };
+
+        let mut sequencer = Sequencer::new(44100.0, 2);
+        voice.sequence_with(
+            &mut sequencer,
+            120,
+            with_context(|context: &NoteContext| -> Box<dyn AudioUnit64> {
+                Box::new(
+                    sine_hz(context.pitch.get_hz())
+                        >> pan(by_pitch.pan_of(0.0, context.pitch, range)),
+                )
+            }),
+        );
+        let wave = Wave64::render(44100.0, voice.get_duration(120), &mut sequencer);
+
+        // a quarter note lasts one second at 120 eighths per minute
+        let energy = |channel: usize, note: usize| -> f64 {
+            wave.channel(channel)[note * 44100 + 4410..(note + 1) * 44100 - 4410]
+                .iter()
+                .map(|sample| sample * sample)
+                .sum()
+        };
+        assert!(energy(0, 0) > 10.0 * energy(1, 0));
+        assert!(energy(0, 1) > energy(1, 1));
+        assert!(energy(1, 4) > energy(0, 4));
+        assert!(energy(1, 5) > 10.0 * energy(0, 5));
+    }
+
+    #[test]
+    fn detune_test() {
+        let modulation = PitchModulation {
+            detune_cents: 20.0,
+            ..PitchModulation::default()
+        };
+
+        let detunes: Vec<f64> = (0..100)
+            .map(|index| modulation.detune_of(7, index))
+            .collect();
+        assert!(detunes.iter().all(|cents| cents.abs() <= 20.0));
+        assert!(detunes.iter().any(|cents| *cents > 10.0));
+        assert!(detunes.iter().any(|cents| *cents < -10.0));
+        assert_eq!(modulation.detune_of(7, 3), detunes[3]);
+        assert_ne!(modulation.detune_of(8, 3), detunes[3]);
+        assert_ne!(modulation.detune_of(8, 3), detunes[4]);
+        assert_ne!(modulation.detune_of(0, 0), modulation.detune_of(1, 0));
+        assert_eq!(PitchModulation::default().detune_of(7, 3), 0.0);
+
+        let mut node = modulation.frequency(Pitch(440.0), 100.0) >> sine();
+        let frequencies = windowed_frequencies(&Wave64::render(44100.0, 0.5, &mut node), 0.1);
+        // a hundred cents above A4 is A#4
+        assert!(
+            frequencies.iter().all(|hz| (hz - 466.164).abs() < 1.0),
+            "{:?}",
+            frequencies
+        );
+    }
+}
@@ -0,0 +1,23 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub struct WaveTableError {
+    message: String,
+}
+
+impl WaveTableError {
+    pub fn new(message: &str) -> Self {
+        WaveTableError {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for WaveTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid wavetable: {}.", self.message)
+    }
+}
+
+impl Error for WaveTableError {}
@@ -0,0 +1,96 @@
+/* This module contains helpers building the
+ * MusicalElements of expected voices in tests
+ * from tones instead of raw frequencies. It is
+ * available to other crates with the feature "test-utils".
+ */
+
+use crate::musical_notation::{
+    Duration, EqualTemperament, MusicalElement, Pitch, Temperament, Tone, Volume, STUTTGART_PITCH,
+};
+
+/**
+ * Parse a tone like "C", "F#" or "Bb". Panics if the tone is invalid.
+ */
+pub fn tone(representation: &str) -> Tone {
+    representation
+        .parse()
+        .unwrap_or_else(|error| panic!("{}", error))
+}
+
+/**
+ * Get the pitch of the tone in the octave in equal temperament tuned to the Stuttgart pitch.
+ * Cb and B# lie in the octave of their note name, e.g. B#4 is C5.
+ */
+pub fn pitch(tone: Tone, octave: i16) -> Pitch {
+    EqualTemperament::new(STUTTGART_PITCH)
+        .get_pitch(octave, tone.get_position_in_octave())
+        .expect("Every position has a pitch in equal temperament")
+}
+
+/**
+ * Create a note of the tone in the octave, e.g. `note(tone("C"), 4, Duration::quarter(), M)`.
+ */
+pub fn note(tone: Tone, octave: i16, duration: Duration, volume: Volume) -> MusicalElement {
+    MusicalElement::note(pitch(tone, octave), duration, volume)
+}
+
+pub fn rest(duration: Duration) -> MusicalElement {
+    MusicalElement::Rest { duration }
+}
+
+/**
+ * The frequencies of the samples measured between upward zero crossings
+ * in consecutive windows of the given number of samples.
+ */
+pub fn windowed_frequencies(samples: &[f64], sample_rate: f64, window_length: usize) -> Vec<f64> {
+    samples
+        .chunks_exact(window_length)
+        .map(|window| {
+            let crossings: Vec<usize> = (1..window.len())
+                .filter(|index| window[index - 1] < 0.0 && window[*index] >= 0.0)
+                .collect();
+            let (first, last) = (crossings[0], crossings[crossings.len() - 1]);
+            (crossings.len() - 1) as f64 * sample_rate / (last - first) as f64
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{note, pitch, rest, tone, windowed_frequencies};
+    use crate::musical_notation::{Duration, MusicalElement, Pitch, M};
+
+    #[test]
+    fn note_test() {
+        assert_eq!(
+            format!("{:?}", note(tone("C"), 4, Duration::quarter(), M)),
+            format!(
+                "{:?}",
+                MusicalElement::note(Pitch(261.6255653005986), Duration::quarter(), M)
+            )
+        );
+        assert_eq!(
+            rest(Duration::eighth()),
+            MusicalElement::Rest {
+                duration: Duration::eighth()
+            }
+        );
+
+        assert_eq!(pitch(tone("A"), 4), Pitch(440.0));
+        assert_eq!(pitch(tone("B#"), 4), pitch(tone("C"), 5));
+        assert_eq!(pitch(tone("Cb"), 4), pitch(tone("B"), 3));
+    }
+
+    #[test]
+    fn windowed_frequencies_test() {
+        // a square wave of 100 Hz, which rises through zero every 40 samples
+        let samples: Vec<f64> = (0..400)
+            .map(|index| if index % 40 < 20 { 1.0 } else { -1.0 })
+            .collect();
+
+        assert_eq!(
+            windowed_frequencies(&samples, 4000.0, 200),
+            vec![100.0, 100.0]
+        );
+    }
+}
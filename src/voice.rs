@@ -1,19 +1,111 @@
 use crate::musical_notation as notation;
 
 use fundsp::audiounit::AudioUnit64;
-use fundsp::math::bpm_hz;
 use fundsp::sequencer::Sequencer;
 
+use std::fmt;
+
 pub mod action;
+pub mod grid;
+pub mod groove;
+pub mod pitch_class;
+pub mod score;
+
+pub use pitch_class::PitchClassSet;
+pub use score::Score;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum ErrorKind {
     UndefinedAtomType,
     PopOnEmptyStack,
     GenerationError,
 }
 
-#[derive(Debug)]
+/**
+ * The number of beats in a whole note when sequencing a Voice.
+ * One beat equals one time unit, i.e. an eighth note.
+ */
+pub const BEATS_PER_WHOLE: u32 = 8;
+
+/**
+ * The fraction of its duration a staccato note is played for.
+ */
+const STACCATO_LENGTH: f64 = 0.5;
+
+/**
+ * The fraction of its duration a legato note is extended into the following element.
+ */
+const LEGATO_OVERLAP: f64 = 0.1;
+
+/**
+ * The melodic tension of the notes on the degrees one to seven of the major scale.
+ */
+const DEGREE_TENSIONS: [f64; 7] = [0.0, 0.5, 0.3, 0.6, 0.4, 0.5, 0.9];
+
+/**
+ * The melodic tension of a note outside the major scale.
+ */
+const CHROMATIC_TENSION: f64 = 1.0;
+
+/**
+ * What an instrument knows about the note it plays when a Voice is sequenced.
+ * The duration is the time the note sounds for, e.g. half of its duration for a
+ * staccato note, and the index is the one of its MusicalElement in the voice.
+ * A slide note glides from the pitch slide_from to its own pitch.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteContext {
+    pub pitch: notation::Pitch,
+    pub volume: notation::Volume,
+    pub duration_secs: f64,
+    pub index: usize,
+    pub start_secs: f64,
+    pub slide_from: Option<notation::Pitch>,
+}
+
+/**
+ * Creates the audio unit playing a note of a Voice. Every closure taking the pitch
+ * and the volume of the note is an instrument, a closure taking the whole NoteContext
+ * becomes one by wrapping it with `with_context`.
+ */
+pub trait NoteInstrument {
+    fn create_audio_unit(&self, context: &NoteContext) -> Box<dyn AudioUnit64>;
+}
+
+impl<F> NoteInstrument for F
+where
+    F: Fn(notation::Pitch, notation::Volume) -> Box<dyn AudioUnit64>,
+{
+    fn create_audio_unit(&self, context: &NoteContext) -> Box<dyn AudioUnit64> {
+        self(context.pitch, context.volume)
+    }
+}
+
+/**
+ * An instrument creating the audio units with a closure taking the NoteContext.
+ */
+pub struct ContextInstrument<F>(pub F);
+
+impl<F> NoteInstrument for ContextInstrument<F>
+where
+    F: Fn(&NoteContext) -> Box<dyn AudioUnit64>,
+{
+    fn create_audio_unit(&self, context: &NoteContext) -> Box<dyn AudioUnit64> {
+        (self.0)(context)
+    }
+}
+
+/**
+ * Turn a closure taking the NoteContext into an instrument.
+ */
+pub fn with_context<F>(create_audio_unit: F) -> ContextInstrument<F>
+where
+    F: Fn(&NoteContext) -> Box<dyn AudioUnit64>,
+{
+    ContextInstrument(create_audio_unit)
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Voice {
     musical_elements: Vec<notation::MusicalElement>,
 }
@@ -23,50 +115,1802 @@ impl Voice {
         Voice { musical_elements }
     }
 
+    pub fn musical_elements(&self) -> &[notation::MusicalElement] {
+        &self.musical_elements
+    }
+
+    /**
+     * Write the voice as JSON to the file at the given path.
+     */
+    #[cfg(feature = "serde")]
+    pub fn save_json(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+
+    /**
+     * Read a voice previously written by `save_json` from the file at the given path.
+     */
+    #[cfg(feature = "serde")]
+    pub fn load_json(path: &std::path::Path) -> std::io::Result<Voice> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /**
+     * Append a rest of the given duration to the voice.
+     */
+    pub fn push_rest(&mut self, duration: notation::Duration) {
+        self.musical_elements
+            .push(notation::MusicalElement::Rest { duration });
+    }
+
+    /**
+     * Get the number of seconds the voice lasts at the given tempo, including its TimedNotes.
+     */
     pub fn get_duration(&self, bpm: u16) -> f64 {
-        let length = self.get_len();
-        return length as f64 / bpm_hz(bpm as f64);
+        self.total_duration().to_seconds(bpm, BEATS_PER_WHOLE) + self.timed_seconds()
+    }
+
+    /**
+     * Get the number of seconds taken by the TimedNotes of the voice.
+     */
+    fn timed_seconds(&self) -> f64 {
+        self.musical_elements
+            .iter()
+            .map(|musical_element| match musical_element {
+                notation::MusicalElement::TimedNote { seconds, .. } => *seconds,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    /**
+     * Get the musical duration of the voice. TimedNotes last a fixed number of
+     * seconds instead of a musical duration and add nothing to it, `get_duration`
+     * includes them at a given tempo.
+     */
+    pub fn total_duration(&self) -> notation::Duration {
+        self.musical_elements
+            .iter()
+            .map(|musical_element| musical_element.get_duration())
+            .sum()
+    }
+
+    /**
+     * Get the lowest and the highest pitch of the voice.
+     * Returns None if the voice contains no notes.
+     */
+    pub fn pitch_range(&self) -> Option<(notation::Pitch, notation::Pitch)> {
+        let mut range: Option<(notation::Pitch, notation::Pitch)> = None;
+
+        for musical_element in &self.musical_elements {
+            if let notation::MusicalElement::Note { pitch, .. }
+            | notation::MusicalElement::TimedNote { pitch, .. } = musical_element
+            {
+                range = match range {
+                    None => Some((*pitch, *pitch)),
+                    Some((low, high)) => Some((
+                        if pitch.get_hz() < low.get_hz() {
+                            *pitch
+                        } else {
+                            low
+                        },
+                        if pitch.get_hz() > high.get_hz() {
+                            *pitch
+                        } else {
+                            high
+                        },
+                    )),
+                };
+            }
+        }
+
+        range
+    }
+
+    /**
+     * Transpose the whole voice by as few octaves as possible, so that all of its
+     * pitches lie between low and high. Returns false and leaves the voice
+     * unchanged if the range of the voice doesn't fit between low and high.
+     */
+    pub fn fit_to_range(&mut self, low: notation::Pitch, high: notation::Pitch) -> bool {
+        // in octaves, about a hundredth of a cent
+        const TOLERANCE: f64 = 1e-5;
+
+        let (lowest, highest) = match self.pitch_range() {
+            Some(range) => range,
+            None => return true,
+        };
+
+        let min_octaves = ((low.get_hz() / lowest.get_hz()).log2() - TOLERANCE).ceil() as i32;
+        let max_octaves = ((high.get_hz() / highest.get_hz()).log2() + TOLERANCE).floor() as i32;
+
+        if min_octaves > max_octaves {
+            return false;
+        }
+
+        let octaves = 0.clamp(min_octaves, max_octaves);
+        let factor = 2.0_f64.powi(octaves);
+
+        for musical_element in &mut self.musical_elements {
+            if let notation::MusicalElement::Note { pitch, .. }
+            | notation::MusicalElement::TimedNote { pitch, .. } = musical_element
+            {
+                *pitch = notation::Pitch(pitch.get_hz() * factor);
+            }
+        }
+
+        true
+    }
+
+    /**
+     * Fold every note lying outside of the range between low and high by as few
+     * octaves as possible into it, leaving the notes inside the range untouched.
+     * Unlike `fit_to_range` each note is moved on its own, which keeps every
+     * note audible but can break up the contour of the melody. A note without an
+     * octave inside the range, which is only possible if the range spans less than
+     * an octave, is left unchanged.
+     */
+    pub fn fold_into_range(&self, low: notation::Pitch, high: notation::Pitch) -> Voice {
+        // in octaves, about a hundredth of a cent
+        const TOLERANCE: f64 = 1e-5;
+
+        self.map_pitches(|pitch| {
+            let min_octaves = ((low.get_hz() / pitch.get_hz()).log2() - TOLERANCE).ceil() as i32;
+            let max_octaves = ((high.get_hz() / pitch.get_hz()).log2() + TOLERANCE).floor() as i32;
+
+            match min_octaves <= max_octaves {
+                true => pitch.shift_octaves(0.clamp(min_octaves, max_octaves) as i16),
+                false => pitch,
+            }
+        })
+    }
+
+    /**
+     * Count the notes of the voice, i.e. all of its elements but the rests.
+     */
+    pub fn get_note_count(&self) -> usize {
+        self.musical_elements
+            .iter()
+            .filter(|musical_element| {
+                matches!(
+                    musical_element,
+                    notation::MusicalElement::Note { .. }
+                        | notation::MusicalElement::TimedNote { .. }
+                )
+            })
+            .count()
+    }
+
+    /**
+     * Count the rests of the voice.
+     */
+    pub fn get_rest_count(&self) -> usize {
+        self.musical_elements
+            .iter()
+            .filter(|musical_element| {
+                matches!(musical_element, notation::MusicalElement::Rest { .. })
+            })
+            .count()
+    }
+
+    /**
+     * Count all elements of the voice, unlike `total_duration`,
+     * which measures how long they last.
+     */
+    pub fn get_element_count(&self) -> usize {
+        self.musical_elements.len()
+    }
+
+    /**
+     * List the index and pitch of every note most adults can't hear, as its pitch
+     * lies below 20 Hz or above 20 kHz. Such notes usually come from a voice that
+     * is scaled to the wrong octaves.
+     */
+    pub fn audibility_report(&self) -> Vec<(usize, notation::Pitch)> {
+        const LOWEST_AUDIBLE_HZ: f64 = 20.0;
+        const HIGHEST_AUDIBLE_HZ: f64 = 20000.0;
+
+        self.musical_elements
+            .iter()
+            .enumerate()
+            .filter_map(|(index, musical_element)| match musical_element {
+                notation::MusicalElement::Note { pitch, .. }
+                | notation::MusicalElement::TimedNote { pitch, .. }
+                    if pitch.get_hz() < LOWEST_AUDIBLE_HZ
+                        || pitch.get_hz() > HIGHEST_AUDIBLE_HZ =>
+                {
+                    Some((index, *pitch))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /**
+     * Get the melodic tension of every MusicalElement of the voice by the degree of the
+     * major scale of the key the note lies on. The tonic is at rest, the leading tone
+     * is the most tense degree and notes outside the scale are tenser still.
+     * Rests have no tension.
+     */
+    pub fn get_melodic_tension_curve<T: notation::Temperament>(
+        &self,
+        key: &notation::Key<T>,
+    ) -> Vec<f64> {
+        self.musical_elements
+            .iter()
+            .map(|musical_element| match musical_element {
+                notation::MusicalElement::Note { pitch, .. }
+                | notation::MusicalElement::TimedNote { pitch, .. } => {
+                    match key.get_degree_of_pitch(*pitch) {
+                        Some(degree) => DEGREE_TENSIONS[degree as usize - 1],
+                        None => CHROMATIC_TENSION,
+                    }
+                }
+                _ => 0.0,
+            })
+            .collect()
+    }
+
+    /**
+     * Transpose every pitch of the voice up (positive semitones) or down (negative
+     * semitones) by the given number of equal tempered semitones.
+     */
+    pub fn transpose(&self, semitones: i16) -> Voice {
+        let factor = 2.0_f64.powf(semitones as f64 / 12.0);
+        self.map_pitches(|pitch| notation::Pitch(pitch.get_hz() * factor))
+    }
+
+    /**
+     * Create a voice harmonizing this one at the given interval: every pitch is moved
+     * interval_degrees degrees up (positive) or down (negative) the major scale of the key,
+     * e.g. 2 for a third above. Rests remain rests and the rhythm stays the same.
+     * Fails if a pitch doesn't belong to the scale.
+     */
+    pub fn harmonize<T: notation::Temperament>(
+        &self,
+        key: &notation::Key<T>,
+        interval_degrees: i8,
+    ) -> Result<Voice, action::error::ActionError> {
+        let mut musical_elements = self.musical_elements.clone();
+
+        for musical_element in &mut musical_elements {
+            if let notation::MusicalElement::Note { pitch, .. }
+            | notation::MusicalElement::TimedNote { pitch, .. } = musical_element
+            {
+                *pitch = key
+                    .shift_by_degrees(*pitch, interval_degrees)
+                    .ok_or_else(|| {
+                        action::error::ActionError::from_generation_error(
+                            action::error::OutOfKeyError::new(*pitch, key),
+                        )
+                    })?;
+            }
+        }
+
+        Ok(Voice { musical_elements })
+    }
+
+    /**
+     * Create the follower of a canon or an echo of the voice: a copy starting after a rest
+     * of delay_units time units, transposed by the given semitones and with every volume
+     * multiplied by volume_scale, clamped between SILENT and FFF. A delay longer than the
+     * voice makes the copy start after the voice ended.
+     */
+    pub fn canon(&self, delay_units: u16, transpose_semitones: i16, volume_scale: f64) -> Voice {
+        let mut canon = Voice {
+            musical_elements: vec![],
+        };
+
+        if delay_units > 0 {
+            canon.push_rest(notation::Duration::time_units(delay_units));
+        }
+
+        for mut musical_element in self.transpose(transpose_semitones).musical_elements {
+            if let notation::MusicalElement::Note { volume, .. }
+            | notation::MusicalElement::TimedNote { volume, .. } = &mut musical_element
+            {
+                *volume = notation::Volume::percent(
+                    volume.get() as f64 / notation::FFF.get() as f64 * volume_scale,
+                );
+            }
+            canon.musical_elements.push(musical_element);
+        }
+
+        canon
+    }
+
+    /**
+     * Apply a crescendo or diminuendo to the MusicalElements from from_index to
+     * to_index (both inclusive). The volume of each note is linearly interpolated
+     * between start and end by its position in the span. Rests keep their place
+     * in the span but are left unchanged. An index past the end of the voice is
+     * treated as the last index.
+     */
+    pub fn apply_hairpin(
+        &mut self,
+        from_index: usize,
+        to_index: usize,
+        start: notation::Volume,
+        end: notation::Volume,
+    ) {
+        let to_index = to_index.min(self.musical_elements.len().saturating_sub(1));
+
+        if self.musical_elements.is_empty() || from_index > to_index {
+            return;
+        }
+
+        let span = (to_index - from_index).max(1) as f64;
+
+        for (index, musical_element) in self.musical_elements[from_index..=to_index]
+            .iter_mut()
+            .enumerate()
+        {
+            if let notation::MusicalElement::Note { volume, .. }
+            | notation::MusicalElement::TimedNote { volume, .. } = musical_element
+            {
+                *volume = start.interpolate(end, index as f64 / span);
+            }
+        }
+    }
+
+    /**
+     * Accent the notes starting on a beat by stepping their volume by the value of the
+     * pattern for that beat of the bar, e.g. [2, 0, 1, 0] for a strong first and a
+     * weaker third beat in 4/4. A pattern shorter than the bar is repeated within it.
+     * Notes starting between the beats keep their volume, rests are skipped but still
+     * advance the position in the bar. TimedNotes take no time in the bar.
+     */
+    pub fn apply_accents(&self, time_signature: notation::TimeSignature, pattern: &[i8]) -> Voice {
+        let bar_duration = time_signature.bar_duration();
+        let beat_duration = time_signature.beat_duration();
+        let mut musical_elements = self.musical_elements.clone();
+        let mut position_in_bar = notation::Duration::zero();
+
+        for musical_element in &mut musical_elements {
+            let mut beat = 0;
+            let mut beat_start = notation::Duration::zero();
+            while beat_start + beat_duration <= position_in_bar {
+                beat_start = beat_start + beat_duration;
+                beat += 1;
+            }
+
+            if let notation::MusicalElement::Note { volume, .. }
+            | notation::MusicalElement::TimedNote { volume, .. } = musical_element
+            {
+                if beat_start == position_in_bar && !pattern.is_empty() {
+                    *volume = volume.step(pattern[beat % pattern.len()]);
+                }
+            }
+
+            position_in_bar = position_in_bar + musical_element.get_duration();
+            while position_in_bar >= bar_duration {
+                position_in_bar = position_in_bar - bar_duration;
+            }
+        }
+
+        Voice { musical_elements }
+    }
+
+    /**
+     * Get the indices of the MusicalElements that sound at the start of each bar.
+     * This is the element starting on the barline, or the element
+     * crossing it if no element starts there.
+     */
+    pub fn bar_positions(&self, time_signature: notation::TimeSignature) -> Vec<usize> {
+        let bar_duration = time_signature.bar_duration();
+        let mut bar_positions: Vec<usize> = vec![];
+        let mut next_barline = notation::Duration::zero();
+        let mut elapsed = notation::Duration::zero();
+
+        for (index, musical_element) in self.musical_elements.iter().enumerate() {
+            elapsed = elapsed + musical_element.get_duration();
+
+            while next_barline < elapsed {
+                bar_positions.push(index);
+                next_barline = next_barline + bar_duration;
+            }
+        }
+
+        bar_positions
+    }
+
+    /**
+     * Get the indices of the MusicalElements that start before a barline and end after it.
+     * Notation of these elements has to be split with a tie at the barline.
+     */
+    pub fn barline_crossings(&self, time_signature: notation::TimeSignature) -> Vec<usize> {
+        let bar_duration = time_signature.bar_duration();
+        let mut barline_crossings: Vec<usize> = vec![];
+        let mut next_barline = bar_duration;
+        let mut elapsed = notation::Duration::zero();
+
+        for (index, musical_element) in self.musical_elements.iter().enumerate() {
+            elapsed = elapsed + musical_element.get_duration();
+
+            if next_barline < elapsed {
+                barline_crossings.push(index);
+            }
+
+            while next_barline <= elapsed {
+                next_barline = next_barline + bar_duration;
+            }
+        }
+
+        barline_crossings
+    }
+
+    /**
+     * Append a rest so that the voice ends on a barline.
+     * Nothing is appended if the voice already does.
+     */
+    pub fn pad_to_bar(&mut self, time_signature: notation::TimeSignature) {
+        let bar_duration = time_signature.bar_duration();
+        let total_duration = self.total_duration();
+        let mut end_of_bar = notation::Duration::zero();
+
+        while end_of_bar < total_duration {
+            end_of_bar = end_of_bar + bar_duration;
+        }
+
+        if end_of_bar > total_duration {
+            self.musical_elements.push(notation::MusicalElement::Rest {
+                duration: end_of_bar - total_duration,
+            });
+        }
+    }
+
+    /**
+     * Compare two voices element by element. Pitches are considered equal if they
+     * differ by at most cents_tolerance cents, everything else has to match exactly.
+     */
+    pub fn approx_eq(&self, other: &Voice, cents_tolerance: f64) -> bool {
+        self.musical_elements.len() == other.musical_elements.len()
+            && self
+                .musical_elements
+                .iter()
+                .zip(other.musical_elements.iter())
+                .all(|pair| match pair {
+                    (
+                        notation::MusicalElement::Note {
+                            pitch,
+                            duration,
+                            volume,
+                            articulation,
+                        },
+                        notation::MusicalElement::Note {
+                            pitch: other_pitch,
+                            duration: other_duration,
+                            volume: other_volume,
+                            articulation: other_articulation,
+                        },
+                    ) => {
+                        pitch.cents_from(*other_pitch).abs() <= cents_tolerance
+                            && duration == other_duration
+                            && volume == other_volume
+                            && articulation == other_articulation
+                    }
+                    (musical_element, other_musical_element) => {
+                        musical_element == other_musical_element
+                    }
+                })
     }
 
-    fn get_len(&self) -> u16 {
-        let mut len: u16 = 0;
+    /**
+     * Merge consecutive notes of equal pitch and volume into one longer note.
+     * The merged note keeps the articulation of the last note it was merged from,
+     * as this determines how it connects to the following element. Staccato notes
+     * are never merged, as the merged note would sound for only half of its length.
+     */
+    pub fn merge_ties(&self) -> Voice {
+        let mut musical_elements: Vec<notation::MusicalElement> = vec![];
 
         for musical_element in &self.musical_elements {
-            len += musical_element.get_duration().get_time_units();
+            if let (
+                Some(notation::MusicalElement::Note {
+                    pitch: previous_pitch,
+                    duration: previous_duration,
+                    volume: previous_volume,
+                    articulation: previous_articulation,
+                }),
+                notation::MusicalElement::Note {
+                    pitch,
+                    duration,
+                    volume,
+                    articulation,
+                },
+            ) = (musical_elements.last_mut(), musical_element)
+            {
+                let staccato = [*previous_articulation, *articulation]
+                    .contains(&notation::Articulation::Staccato);
+                if previous_pitch == pitch && previous_volume == volume && !staccato {
+                    *previous_duration = *previous_duration + *duration;
+                    // a slide between equal pitches is inaudible, only the slide into the first note is kept
+                    *previous_articulation = match (*previous_articulation, *articulation) {
+                        (
+                            notation::Articulation::Slide,
+                            notation::Articulation::Normal | notation::Articulation::Slide,
+                        ) => notation::Articulation::Slide,
+                        (_, notation::Articulation::Slide) => notation::Articulation::Normal,
+                        (_, articulation) => articulation,
+                    };
+                    continue;
+                }
+            }
+
+            musical_elements.push(*musical_element);
+        }
+
+        Voice { musical_elements }
+    }
+
+    /**
+     * Merge adjacent rests into a single rest.
+     */
+    pub fn coalesce_rests(&self) -> Voice {
+        let mut musical_elements: Vec<notation::MusicalElement> = vec![];
+
+        for musical_element in &self.musical_elements {
+            if let (
+                Some(notation::MusicalElement::Rest {
+                    duration: previous_duration,
+                }),
+                notation::MusicalElement::Rest { duration },
+            ) = (musical_elements.last_mut(), musical_element)
+            {
+                *previous_duration = *previous_duration + *duration;
+                continue;
+            }
+
+            musical_elements.push(*musical_element);
+        }
+
+        Voice { musical_elements }
+    }
+
+    /**
+     * Remove the rests at the start and at the end of the voice.
+     * All notes are moved forward by the length of the leading rests.
+     */
+    pub fn trim(&self) -> Voice {
+        let is_note = |musical_element: &notation::MusicalElement| {
+            matches!(
+                musical_element,
+                notation::MusicalElement::Note { .. } | notation::MusicalElement::TimedNote { .. }
+            )
+        };
+
+        let first = self.musical_elements.iter().position(is_note);
+        let last = self.musical_elements.iter().rposition(is_note);
+
+        match (first, last) {
+            (Some(first), Some(last)) => Voice {
+                musical_elements: self.musical_elements[first..=last].to_vec(),
+            },
+            _ => Voice {
+                musical_elements: vec![],
+            },
+        }
+    }
+
+    /**
+     * Shorten every rest to at most max_units time units. Adjacent rests are
+     * merged first, so a run of rests is capped as a whole. All notes
+     * following a shortened rest are moved forward accordingly.
+     */
+    pub fn strip_silence_over(&self, max_units: u16) -> Voice {
+        let max_duration = notation::Duration::time_units(max_units);
+        let mut voice = self.coalesce_rests();
+
+        for musical_element in &mut voice.musical_elements {
+            if let notation::MusicalElement::Rest { duration } = musical_element {
+                *duration = (*duration).min(max_duration);
+            }
+        }
+
+        voice
+    }
+
+    /**
+     * Create a voice of the elements the function returns for the elements of this voice.
+     */
+    pub fn map(&self, f: impl Fn(&notation::MusicalElement) -> notation::MusicalElement) -> Voice {
+        Voice {
+            musical_elements: self.musical_elements.iter().map(f).collect(),
+        }
+    }
+
+    /**
+     * Create a voice of the elements of this voice the predicate holds for. The removed
+     * elements no longer occupy time, so all following elements move forward. Use
+     * `replace_with_rests` to keep the timing.
+     */
+    pub fn retain(&self, pred: impl Fn(&notation::MusicalElement) -> bool) -> Voice {
+        Voice {
+            musical_elements: self
+                .musical_elements
+                .iter()
+                .filter(|musical_element| pred(musical_element))
+                .copied()
+                .collect(),
+        }
+    }
+
+    /**
+     * Create a voice with every pitch of this voice replaced by the one the function returns.
+     */
+    pub fn map_pitches(&self, f: impl Fn(notation::Pitch) -> notation::Pitch) -> Voice {
+        self.map(|musical_element| {
+            let mut musical_element = *musical_element;
+            if let notation::MusicalElement::Note { pitch, .. }
+            | notation::MusicalElement::TimedNote { pitch, .. } = &mut musical_element
+            {
+                *pitch = f(*pitch);
+            }
+            musical_element
+        })
+    }
+
+    /**
+     * Create a voice with the notes the predicate holds for replaced by rests of the same
+     * duration, so all other elements keep their timing. TimedNotes are kept, as their
+     * length in seconds can't be expressed as the Duration of a rest.
+     */
+    pub fn replace_with_rests(&self, pred: impl Fn(&notation::MusicalElement) -> bool) -> Voice {
+        self.map(|musical_element| match musical_element {
+            notation::MusicalElement::Note { duration, .. } if pred(musical_element) => {
+                notation::MusicalElement::Rest {
+                    duration: *duration,
+                }
+            }
+            _ => *musical_element,
+        })
+    }
+
+    /**
+     * Calculate the start and stop time in seconds of every note of the voice.
+     * Staccato notes stop after half of their duration,
+     * legato notes are extended into the following element.
+     * TimedNotes last their number of seconds at any tempo.
+     */
+    fn schedule(&self, bpm: u16) -> Vec<(f64, f64, notation::Pitch, notation::Volume)> {
+        self.note_contexts(bpm)
+            .into_iter()
+            .map(|context| {
+                (
+                    context.start_secs,
+                    context.start_secs + context.duration_secs,
+                    context.pitch,
+                    context.volume,
+                )
+            })
+            .collect()
+    }
+
+    /**
+     * Calculate the NoteContext of every note of the voice, timed like in `schedule`.
+     * A slide note slides from the pitch of the element right before it, if that is a note.
+     */
+    fn note_contexts(&self, bpm: u16) -> Vec<NoteContext> {
+        let mut note_contexts = vec![];
+        let mut elapsed = notation::Duration::zero();
+        let mut elapsed_timed_seconds = 0.0;
+        let mut previous_pitch: Option<notation::Pitch> = None;
+
+        for (index, musical_element) in self.musical_elements.iter().enumerate() {
+            let time_note_starts: f64 =
+                elapsed.to_seconds(bpm, BEATS_PER_WHOLE) + elapsed_timed_seconds;
+
+            match musical_element {
+                notation::MusicalElement::TimedNote {
+                    pitch,
+                    seconds,
+                    volume,
+                } => {
+                    note_contexts.push(NoteContext {
+                        pitch: *pitch,
+                        volume: *volume,
+                        duration_secs: *seconds,
+                        index,
+                        start_secs: time_note_starts,
+                        slide_from: None,
+                    });
+                    elapsed_timed_seconds += seconds;
+                    previous_pitch = Some(*pitch);
+                }
+                notation::MusicalElement::Note {
+                    pitch,
+                    duration,
+                    volume,
+                    articulation,
+                } => {
+                    let length: f64 = duration.to_seconds(bpm, BEATS_PER_WHOLE);
+                    let sounding_length: f64 = match articulation {
+                        notation::Articulation::Legato => length * (1.0 + LEGATO_OVERLAP),
+                        notation::Articulation::Normal | notation::Articulation::Slide => length,
+                        notation::Articulation::Staccato => length * STACCATO_LENGTH,
+                    };
+                    note_contexts.push(NoteContext {
+                        pitch: *pitch,
+                        volume: *volume,
+                        duration_secs: sounding_length,
+                        index,
+                        start_secs: time_note_starts,
+                        slide_from: match articulation {
+                            notation::Articulation::Slide => previous_pitch,
+                            _ => None,
+                        },
+                    });
+                    previous_pitch = Some(*pitch);
+                }
+                notation::MusicalElement::Rest { .. } => previous_pitch = None,
+            }
+
+            elapsed = elapsed + musical_element.get_duration();
         }
 
-        return len;
+        note_contexts
     }
 
-    pub fn sequence<T>(&self, sequencer: &mut Sequencer, bpm: u16, create_audio_unit: T)
+    /**
+     * Add every note of the voice to the sequencer at the given tempo,
+     * playing it with the audio unit created from its pitch and volume.
+     */
+    pub fn sequence<F>(&self, sequencer: &mut Sequencer, bpm: u16, create_audio_unit: F)
     where
-        T: Fn(notation::Pitch, notation::Volume) -> Box<dyn AudioUnit64>,
+        F: Fn(notation::Pitch, notation::Volume) -> Box<dyn AudioUnit64>,
     {
-        let bpm_in_hz: f64 = bpm_hz(bpm as f64);
-        let mut last_time_unit: u16 = 0;
+        self.sequence_with(sequencer, bpm, create_audio_unit);
+    }
+
+    /**
+     * Add every note of the voice to the sequencer like `sequence`, playing it with
+     * the audio unit the instrument creates for it, e.g. one knowing the whole
+     * NoteContext when wrapped with `with_context`.
+     */
+    pub fn sequence_with<I: NoteInstrument>(
+        &self,
+        sequencer: &mut Sequencer,
+        bpm: u16,
+        instrument: I,
+    ) {
+        for context in self.note_contexts(bpm) {
+            sequencer.add64(
+                context.start_secs,
+                context.start_secs + context.duration_secs,
+                0.2,
+                0.2,
+                instrument.create_audio_unit(&context),
+            );
+        }
+    }
+}
+
+impl fmt::Debug for Voice {
+    /**
+     * Lists every MusicalElement on its own line. The precision of the
+     * formatter determines the number of decimal places of the pitches
+     * and defaults to three. The articulation of a note is only listed if it isn't Normal.
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(3);
+
+        writeln!(f, "Voice[")?;
 
         for musical_element in &self.musical_elements {
             match musical_element {
                 notation::MusicalElement::Rest { duration } => {
-                    last_time_unit += duration.get_time_units();
+                    writeln!(f, "  Rest {{ duration: {} }}", duration)?
                 }
                 notation::MusicalElement::Note {
                     pitch,
                     duration,
                     volume,
+                    articulation,
                 } => {
-                    let time_note_starts: f64 = last_time_unit as f64 / bpm_in_hz;
-                    last_time_unit += duration.get_time_units();
-                    let time_note_stops: f64 = last_time_unit as f64 / bpm_in_hz;
-                    sequencer.add64(
-                        time_note_starts,
-                        time_note_stops,
-                        0.2,
-                        0.2,
-                        create_audio_unit(*pitch, *volume),
-                    );
+                    write!(
+                        f,
+                        "  Note {{ pitch: {:.*} Hz, duration: {}, volume: {}",
+                        precision,
+                        pitch.get_hz(),
+                        duration,
+                        volume.get()
+                    )?;
+                    if *articulation != notation::Articulation::Normal {
+                        write!(f, ", articulation: {:?}", articulation)?;
+                    }
+                    writeln!(f, " }}")?
+                }
+                notation::MusicalElement::TimedNote {
+                    pitch,
+                    seconds,
+                    volume,
+                } => writeln!(
+                    f,
+                    "  TimedNote {{ pitch: {:.*} Hz, seconds: {}, volume: {} }}",
+                    precision,
+                    pitch.get_hz(),
+                    seconds,
+                    volume.get()
+                )?,
+            }
+        }
+
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{with_context, NoteContext, Voice};
+    use crate::musical_notation::{
+        Accidental, Articulation, Duration, Key, MusicalElement, Note, Pitch, ScaleKind,
+        TimeSignature, Volume, F, FFF, M, P, SILENT, STUTTGART_PITCH,
+    };
+    use crate::test_helpers::rest;
+    use fundsp::hacker::{pan, sine_hz, AudioUnit64};
+    use fundsp::sequencer::Sequencer;
+    use std::cell::RefCell;
+
+    // a quarter note of a raw frequency, unlike `test_helpers::note` taking a tone
+    fn note(frequency: f64, volume: Volume, articulation: Articulation) -> MusicalElement {
+        MusicalElement::Note {
+            pitch: Pitch(frequency),
+            duration: Duration::quarter(),
+            volume,
+            articulation,
+        }
+    }
+
+    fn quarter_note() -> MusicalElement {
+        MusicalElement::Note {
+            pitch: Pitch(440.0),
+            duration: Duration::quarter(),
+            volume: M,
+            articulation: Articulation::Normal,
+        }
+    }
+
+    #[test]
+    fn debug_single_note_test() {
+        let voice = Voice::from_musical_elements(vec![MusicalElement::Note {
+            pitch: Pitch(261.6255653),
+            duration: Duration::quarter(),
+            volume: M,
+            articulation: Articulation::Normal,
+        }]);
+
+        assert_eq!(
+            format!("{:?}", voice),
+            "Voice[\n  Note { pitch: 261.626 Hz, duration: 1/4, volume: 140 }\n]"
+        );
+        assert_eq!(
+            format!("{:.1?}", voice),
+            "Voice[\n  Note { pitch: 261.6 Hz, duration: 1/4, volume: 140 }\n]"
+        );
+    }
+
+    #[test]
+    fn get_element_count_test() {
+        let voice = Voice::from_musical_elements(vec![
+            quarter_note(),
+            MusicalElement::rest(1),
+            quarter_note(),
+            quarter_note(),
+            MusicalElement::rest(4),
+            MusicalElement::TimedNote {
+                pitch: Pitch(440.0),
+                seconds: 0.5,
+                volume: M,
+            },
+        ]);
+
+        assert_eq!(voice.get_note_count(), 4);
+        assert_eq!(voice.get_rest_count(), 2);
+        assert_eq!(voice.get_element_count(), 6);
+        assert_eq!(
+            voice.get_note_count() + voice.get_rest_count(),
+            voice.get_element_count()
+        );
+        assert_eq!(Voice::from_musical_elements(vec![]).get_element_count(), 0);
+    }
+
+    #[test]
+    fn debug_notes_and_rests_test() {
+        let voice = Voice::from_musical_elements(vec![
+            MusicalElement::Note {
+                pitch: Pitch(440.0),
+                duration: Duration::dotted(Duration::quarter()),
+                volume: P,
+                articulation: Articulation::Normal,
+            },
+            MusicalElement::Rest {
+                duration: Duration::eighth(),
+            },
+        ]);
+
+        assert_eq!(
+            format!("{:.3?}", voice),
+            "Voice[\n  Note { pitch: 440.000 Hz, duration: 3/8, volume: 84 }\n  Rest { duration: 1/8 }\n]"
+        );
+        assert_eq!(
+            format!("{:?}", Voice::from_musical_elements(vec![])),
+            "Voice[\n]"
+        );
+    }
+
+    #[test]
+    fn pad_to_bar_test() {
+        let mut voice = Voice::from_musical_elements((0..7).map(|_| quarter_note()).collect());
+        let time_signature = TimeSignature::new(4, 4);
+
+        assert_eq!(voice.bar_positions(time_signature), vec![0, 4]);
+
+        voice.pad_to_bar(time_signature);
+        assert_eq!(voice.musical_elements.len(), 8);
+        assert_eq!(voice.total_duration(), Duration::new(2, 1));
+        assert_eq!(
+            format!("{:?}", voice.musical_elements[7]),
+            "Rest { duration: Duration { num: 1, den: 4 } }"
+        );
+        assert_eq!(voice.bar_positions(time_signature), vec![0, 4]);
+
+        voice.pad_to_bar(time_signature);
+        assert_eq!(voice.musical_elements.len(), 8);
+    }
+
+    #[test]
+    fn pad_empty_voice_to_bar_test() {
+        let mut voice = Voice::from_musical_elements(vec![]);
+        voice.pad_to_bar(TimeSignature::new(3, 4));
+        assert_eq!(voice.musical_elements.len(), 0);
+        assert!(voice.bar_positions(TimeSignature::new(3, 4)).is_empty());
+    }
+
+    #[test]
+    fn barline_crossing_test() {
+        let voice = Voice::from_musical_elements(vec![
+            quarter_note(),
+            quarter_note(),
+            quarter_note(),
+            MusicalElement::Note {
+                pitch: Pitch(440.0),
+                duration: Duration::half(),
+                volume: M,
+                articulation: Articulation::Normal,
+            },
+            quarter_note(),
+            quarter_note(),
+        ]);
+        let time_signature = TimeSignature::new(4, 4);
+
+        assert_eq!(voice.bar_positions(time_signature), vec![0, 3]);
+        assert_eq!(voice.barline_crossings(time_signature), vec![3]);
+
+        let time_signature = TimeSignature::new(3, 4);
+        assert_eq!(voice.bar_positions(time_signature), vec![0, 3, 5]);
+        assert!(voice.barline_crossings(time_signature).is_empty());
+    }
+
+    #[test]
+    fn element_longer_than_bar_test() {
+        let voice = Voice::from_musical_elements(vec![
+            quarter_note(),
+            MusicalElement::Rest {
+                duration: Duration::new(2, 1),
+            },
+            quarter_note(),
+        ]);
+        let time_signature = TimeSignature::new(4, 4);
+
+        assert_eq!(voice.bar_positions(time_signature), vec![0, 1, 1]);
+        assert_eq!(voice.barline_crossings(time_signature), vec![1]);
+    }
+
+    #[test]
+    fn pitch_range_test() {
+        let voice = Voice::from_musical_elements(vec![
+            MusicalElement::Note {
+                pitch: Pitch(440.0),
+                duration: Duration::quarter(),
+                volume: M,
+                articulation: Articulation::Normal,
+            },
+            MusicalElement::Rest {
+                duration: Duration::quarter(),
+            },
+            MusicalElement::Note {
+                pitch: Pitch(261.626),
+                duration: Duration::quarter(),
+                volume: M,
+                articulation: Articulation::Normal,
+            },
+            MusicalElement::Note {
+                pitch: Pitch(523.251),
+                duration: Duration::quarter(),
+                volume: M,
+                articulation: Articulation::Normal,
+            },
+        ]);
+
+        assert_eq!(
+            format!("{:.3?}", voice.pitch_range()),
+            "Some((Pitch(261.626), Pitch(523.251)))"
+        );
+
+        let rests = Voice::from_musical_elements(vec![MusicalElement::Rest {
+            duration: Duration::quarter(),
+        }]);
+        assert_eq!(rests.pitch_range(), None);
+    }
+
+    #[test]
+    fn apply_accents_test() {
+        let key = Key::equal_tempered(&Note::C, &Accidental::Natural, STUTTGART_PITCH);
+        let mut musical_elements: Vec<MusicalElement> = key
+            .get_scale(&ScaleKind::Major, 4, 1, 6)
+            .unwrap()
+            .into_iter()
+            .map(|pitch| MusicalElement::note(pitch, Duration::quarter(), M))
+            .collect();
+        musical_elements.insert(1, rest(Duration::quarter()));
+        musical_elements.push(MusicalElement::note(Pitch(440.0), Duration::eighth(), M));
+        musical_elements.push(MusicalElement::note(Pitch(440.0), Duration::eighth(), M));
+        let voice = Voice::from_musical_elements(musical_elements);
+
+        let accented = voice.apply_accents(TimeSignature::new(3, 4), &[2, -1, 0]);
+        assert_eq!(
+            accented.musical_elements().len(),
+            voice.musical_elements().len()
+        );
+        assert_eq!(accented.total_duration(), voice.total_duration());
+
+        let volumes: Vec<Option<Volume>> = accented
+            .musical_elements()
+            .iter()
+            .map(|musical_element| match musical_element {
+                MusicalElement::Note { volume, .. } => Some(*volume),
+                _ => None,
+            })
+            .collect();
+        // the rest still takes the second beat, the last eighth starts between two beats
+        assert_eq!(
+            volumes,
+            vec![
+                Some(M.step(2)),
+                None,
+                Some(M),
+                Some(M.step(2)),
+                Some(M.step(-1)),
+                Some(M),
+                Some(M.step(2)),
+                Some(M.step(-1)),
+                Some(M),
+            ]
+        );
+    }
+
+    #[test]
+    fn fold_into_range_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(16744.036, M, Articulation::Normal), // C_10
+            rest(Duration::eighth()),
+            note(440.0, M, Articulation::Normal),
+            note(27.5, M, Articulation::Normal), // A_0
+        ]);
+        assert_eq!(voice.get_note_count(), 3);
+
+        let folded = voice.fold_into_range(Pitch(55.0), Pitch(2093.005));
+        assert_eq!(folded.get_note_count(), 3);
+        assert_eq!(folded.musical_elements()[1], voice.musical_elements()[1]);
+        assert_eq!(folded.musical_elements()[2], voice.musical_elements()[2]);
+        assert_eq!(
+            format!("{:.3?}", folded.pitch_range()),
+            "Some((Pitch(55.000), Pitch(2093.005)))"
+        );
+
+        // a range smaller than an octave leaves notes without an octave inside it alone
+        let folded = voice.fold_into_range(Pitch(400.0), Pitch(420.0));
+        assert!(folded.approx_eq(&voice, 0.0));
+    }
+
+    #[test]
+    fn fit_to_range_test() {
+        let mut voice = Voice::from_musical_elements(vec![
+            MusicalElement::Note {
+                pitch: Pitch(4186.009), // C_8
+                duration: Duration::quarter(),
+                volume: M,
+                articulation: Articulation::Normal,
+            },
+            MusicalElement::Rest {
+                duration: Duration::quarter(),
+            },
+            MusicalElement::Note {
+                pitch: Pitch(5274.041), // E_8
+                duration: Duration::quarter(),
+                volume: M,
+                articulation: Articulation::Normal,
+            },
+        ]);
+
+        assert!(voice.fit_to_range(Pitch(261.626), Pitch(523.251)));
+        assert_eq!(
+            format!("{:.3?}", voice),
+            "Voice[\n  Note { pitch: 261.626 Hz, duration: 1/4, volume: 140 }\n  Rest { duration: 1/4 }\n  Note { pitch: 329.628 Hz, duration: 1/4, volume: 140 }\n]"
+        );
+
+        // already in range
+        assert!(voice.fit_to_range(Pitch(200.0), Pitch(2000.0)));
+        assert_eq!(
+            format!("{:.3?}", voice.pitch_range()),
+            "Some((Pitch(261.626), Pitch(329.628)))"
+        );
+
+        // a major third doesn't fit into a semitone
+        assert!(!voice.fit_to_range(Pitch(261.626), Pitch(277.183)));
+        assert_eq!(
+            format!("{:.3?}", voice.pitch_range()),
+            "Some((Pitch(261.626), Pitch(329.628)))"
+        );
+
+        // a low voice is shifted up
+        assert!(voice.fit_to_range(Pitch(1000.0), Pitch(3000.0)));
+        assert_eq!(
+            format!("{:.3?}", voice.pitch_range()),
+            "Some((Pitch(1046.502), Pitch(1318.510)))"
+        );
+    }
+
+    #[test]
+    fn audibility_report_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(440.0, M, Articulation::Normal),
+            note(21096.164, M, Articulation::Normal), // E_10
+            rest(Duration::eighth()),
+            note(16.352, M, Articulation::Normal), // C_0
+            note(20.0, M, Articulation::Normal),
+            note(16744.036, M, Articulation::Normal), // C_10
+            note(20000.0, M, Articulation::Normal),
+        ]);
+
+        assert_eq!(
+            voice.audibility_report(),
+            vec![(1, Pitch(21096.164)), (3, Pitch(16.352))]
+        );
+        assert!(Voice::from_musical_elements(vec![])
+            .audibility_report()
+            .is_empty());
+    }
+
+    #[test]
+    fn melodic_tension_curve_test() {
+        let key = Key::equal_tempered(&Note::C, &Accidental::Natural, STUTTGART_PITCH);
+        let scale = key.get_scale(&ScaleKind::Major, 4, 1, 8).unwrap();
+        let voice = Voice::from_musical_elements(
+            scale
+                .iter()
+                .map(|pitch| note(pitch.get_hz(), M, Articulation::Normal))
+                .collect(),
+        );
+
+        assert_eq!(
+            voice.get_melodic_tension_curve(&key),
+            vec![0.0, 0.5, 0.3, 0.6, 0.4, 0.5, 0.9, 0.0]
+        );
+
+        // the passing tone C#4 between C and D, a rest and G in another octave
+        let voice = Voice::from_musical_elements(vec![
+            note(261.626, M, Articulation::Normal),
+            note(277.183, M, Articulation::Normal),
+            rest(Duration::eighth()),
+            note(98.0, M, Articulation::Normal),
+        ]);
+        assert_eq!(
+            voice.get_melodic_tension_curve(&key),
+            vec![0.0, 1.0, 0.0, 0.4]
+        );
+    }
+
+    #[test]
+    fn transpose_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(440.0, M, Articulation::Normal),
+            rest(Duration::eighth()),
+            note(261.626, M, Articulation::Staccato),
+        ]);
+
+        let up = voice.transpose(3);
+        assert_eq!(up.musical_elements()[1], voice.musical_elements()[1]);
+        assert_eq!(
+            format!("{:.3?}", up.pitch_range()),
+            "Some((Pitch(311.128), Pitch(523.251)))"
+        );
+        assert!(up.transpose(-3).approx_eq(&voice, 0.001));
+        assert!(voice.transpose(0).approx_eq(&voice, 0.0));
+    }
+
+    #[test]
+    fn harmonize_test() {
+        let key = Key::equal_tempered(&Note::C, &Accidental::Natural, STUTTGART_PITCH);
+        let scale = key.get_scale(&ScaleKind::Major, 4, 1, 10).unwrap();
+        let mut musical_elements: Vec<MusicalElement> = scale[..8]
+            .iter()
+            .map(|pitch| MusicalElement::note(*pitch, Duration::eighth(), M))
+            .collect();
+        musical_elements.insert(3, rest(Duration::eighth()));
+        let voice = Voice::from_musical_elements(musical_elements);
+
+        // a third above C D E F G A B C is E F G A B C D E
+        let harmony = voice.harmonize(&key, 2).unwrap();
+        assert_eq!(
+            harmony.musical_elements().len(),
+            voice.musical_elements().len()
+        );
+        assert_eq!(harmony.musical_elements()[3], voice.musical_elements()[3]);
+        let pitches: Vec<Pitch> = harmony
+            .musical_elements()
+            .iter()
+            .filter_map(|musical_element| match musical_element {
+                MusicalElement::Note { pitch, .. } => Some(*pitch),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(pitches.len(), 8);
+        for (pitch, expected) in pitches.iter().zip(&scale[2..10]) {
+            assert!((pitch.get_hz() - expected.get_hz()).abs() < 0.001);
+        }
+
+        assert!(harmony
+            .harmonize(&key, -2)
+            .unwrap()
+            .approx_eq(&voice, 0.001));
+        assert!(voice.harmonize(&key, 0).unwrap().approx_eq(&voice, 0.001));
+
+        // C# is not in the scale of C major
+        let voice = Voice::from_musical_elements(vec![note(277.183, M, Articulation::Normal)]);
+        assert!(voice.harmonize(&key, 2).is_err());
+    }
+
+    #[test]
+    fn map_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(440.0, M, Articulation::Normal),
+            rest(Duration::eighth()),
+            note(220.0, P, Articulation::Staccato),
+        ]);
+
+        let louder = voice.map(|musical_element| match *musical_element {
+            MusicalElement::Note {
+                pitch,
+                duration,
+                articulation,
+                ..
+            } => MusicalElement::Note {
+                pitch,
+                duration,
+                volume: F,
+                articulation,
+            },
+            other => other,
+        });
+        assert_eq!(louder.musical_elements()[1], voice.musical_elements()[1]);
+        assert_eq!(
+            louder.musical_elements()[2],
+            note(220.0, F, Articulation::Staccato)
+        );
+
+        let doubled = voice.map_pitches(|pitch| Pitch(pitch.get_hz() * 2.0));
+        assert_eq!(
+            doubled.musical_elements()[0],
+            note(880.0, M, Articulation::Normal)
+        );
+        assert_eq!(doubled.musical_elements()[1], voice.musical_elements()[1]);
+        assert_eq!(
+            doubled.musical_elements()[2],
+            note(440.0, P, Articulation::Staccato)
+        );
+    }
+
+    #[test]
+    fn retain_and_replace_with_rests_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(440.0, M, Articulation::Normal),
+            rest(Duration::eighth()),
+            note(880.0, M, Articulation::Normal),
+            note(220.0, M, Articulation::Normal),
+        ]);
+        let too_high = |musical_element: &MusicalElement| match musical_element {
+            MusicalElement::Note { pitch, .. } => pitch.get_hz() > 500.0,
+            _ => false,
+        };
+
+        // the removed note no longer occupies time
+        let retained = voice.retain(|musical_element| !too_high(musical_element));
+        assert_eq!(retained.musical_elements().len(), 3);
+        assert_eq!(
+            retained.total_duration(),
+            voice.total_duration() - Duration::quarter()
+        );
+
+        // the note replaced by a rest keeps the timing of the others
+        let replaced = voice.replace_with_rests(too_high);
+        assert_eq!(replaced.musical_elements().len(), 4);
+        assert_eq!(replaced.musical_elements()[2], rest(Duration::quarter()));
+        assert_eq!(replaced.musical_elements()[3], voice.musical_elements()[3]);
+        assert_eq!(replaced.total_duration(), voice.total_duration());
+    }
+
+    #[test]
+    fn note_context_test() {
+        let voice = Voice::from_musical_elements(vec![
+            MusicalElement::Note {
+                pitch: Pitch(440.0),
+                duration: Duration::half(),
+                volume: M,
+                articulation: Articulation::Normal,
+            },
+            rest(Duration::quarter()),
+            note(220.0, F, Articulation::Staccato),
+            MusicalElement::TimedNote {
+                pitch: Pitch(330.0),
+                seconds: 0.75,
+                volume: P,
+            },
+            note(110.0, M, Articulation::Legato),
+        ]);
+        let contexts = RefCell::new(vec![]);
+
+        let mut sequencer = Sequencer::new(44100.0, 2);
+        voice.sequence_with(
+            &mut sequencer,
+            120,
+            with_context(|context: &NoteContext| -> Box<dyn AudioUnit64> {
+                contexts.borrow_mut().push(*context);
+                Box::new(sine_hz(context.pitch.get_hz()) >> pan(0.0))
+            }),
+        );
+
+        // a half note lasts two seconds at 120 bpm, a quarter note one second
+        let summary: Vec<String> = contexts
+            .borrow()
+            .iter()
+            .map(|context| {
+                format!(
+                    "{} {:.0} {:.2}+{:.2}",
+                    context.index,
+                    context.pitch.get_hz(),
+                    context.start_secs,
+                    context.duration_secs
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                "0 440 0.00+2.00",
+                "2 220 3.00+0.50",
+                "3 330 4.00+0.75",
+                "4 110 4.75+1.10"
+            ]
+        );
+        assert_eq!(contexts.borrow()[1].volume, F);
+
+        // closures taking the pitch and the volume still are instruments
+        voice.sequence_with(
+            &mut sequencer,
+            120,
+            |pitch: Pitch, _volume: Volume| -> Box<dyn AudioUnit64> {
+                Box::new(sine_hz(pitch.get_hz()) >> pan(0.0))
+            },
+        );
+        // and sequence infers the types of their arguments
+        voice.sequence(&mut sequencer, 120, |pitch, _volume| {
+            Box::new(sine_hz(pitch.get_hz()) >> pan(0.0))
+        });
+    }
+
+    #[test]
+    fn apply_hairpin_test() {
+        let mut voice = Voice::from_musical_elements((0..4).map(|_| quarter_note()).collect());
+        voice.apply_hairpin(0, 3, P, F);
+
+        let volumes: Vec<Volume> = voice
+            .musical_elements
+            .iter()
+            .map(|musical_element| match musical_element {
+                MusicalElement::Note { volume, .. } => *volume,
+                _ => panic!("expected only notes"),
+            })
+            .collect();
+
+        assert_eq!(volumes.first(), Some(&P));
+        assert_eq!(volumes.last(), Some(&F));
+        assert!(volumes.windows(2).all(|pair| pair[0].get() < pair[1].get()));
+    }
+
+    #[test]
+    fn apply_hairpin_with_rests_test() {
+        let mut voice = Voice::from_musical_elements(vec![
+            quarter_note(),
+            quarter_note(),
+            rest(Duration::quarter()),
+            quarter_note(),
+            quarter_note(),
+        ]);
+        voice.apply_hairpin(1, 10, F, P);
+
+        let volumes: Vec<Option<u8>> = voice
+            .musical_elements
+            .iter()
+            .map(|musical_element| match musical_element {
+                MusicalElement::Note { volume, .. } => Some(volume.get()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            volumes,
+            vec![Some(M.get()), Some(F.get()), None, Some(121), Some(P.get())]
+        );
+    }
+
+    #[test]
+    fn merge_ties_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(440.0, M, Articulation::Normal),
+            note(440.0, M, Articulation::Normal),
+            note(440.0, M, Articulation::Staccato),
+            note(440.0, P, Articulation::Normal),
+            MusicalElement::Rest {
+                duration: Duration::quarter(),
+            },
+            note(440.0, P, Articulation::Normal),
+            note(523.251, P, Articulation::Normal),
+        ]);
+        let merged = voice.merge_ties();
+
+        assert_eq!(merged.musical_elements.len(), 6);
+        assert_eq!(merged.total_duration(), voice.total_duration());
+        assert_eq!(
+            format!("{:.3?}", merged),
+            "Voice[\n  Note { pitch: 440.000 Hz, duration: 1/2, volume: 140 }\n  Note { pitch: 440.000 Hz, duration: 1/4, volume: 140, articulation: Staccato }\n  Note { pitch: 440.000 Hz, duration: 1/4, volume: 84 }\n  Rest { duration: 1/4 }\n  Note { pitch: 440.000 Hz, duration: 1/4, volume: 84 }\n  Note { pitch: 523.251 Hz, duration: 1/4, volume: 84 }\n]"
+        );
+        assert_eq!(
+            Voice::from_musical_elements(vec![])
+                .merge_ties()
+                .musical_elements
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn merge_ties_articulation_test() {
+        let merged = |first: Articulation, second: Articulation| {
+            Voice::from_musical_elements(vec![note(440.0, M, first), note(440.0, M, second)])
+                .merge_ties()
+                .musical_elements
+        };
+
+        // a normal note tied to a staccato one would sound for only half of their length
+        assert_eq!(
+            merged(Articulation::Normal, Articulation::Staccato).len(),
+            2
+        );
+        assert_eq!(
+            merged(Articulation::Staccato, Articulation::Normal).len(),
+            2
+        );
+        assert_eq!(
+            merged(Articulation::Staccato, Articulation::Staccato).len(),
+            2
+        );
+        assert_eq!(merged(Articulation::Legato, Articulation::Legato).len(), 1);
+        assert_eq!(
+            merged(Articulation::Slide, Articulation::Normal),
+            vec![MusicalElement::Note {
+                pitch: Pitch(440.0),
+                duration: Duration::half(),
+                volume: M,
+                articulation: Articulation::Slide,
+            }]
+        );
+    }
+
+    #[test]
+    fn articulation_schedule_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(440.0, M, Articulation::Normal),
+            note(440.0, M, Articulation::Staccato),
+            MusicalElement::Rest {
+                duration: Duration::quarter(),
+            },
+            note(440.0, M, Articulation::Legato),
+        ]);
+        // a quarter note lasts two beats, i.e. one second at 120 bpm
+        let times: Vec<String> = voice
+            .schedule(120)
+            .iter()
+            .map(|(start, stop, _, _)| format!("{:.2}-{:.2}", start, stop))
+            .collect();
+
+        assert_eq!(times, vec!["0.00-1.00", "1.00-1.50", "3.00-4.10"]);
+    }
+
+    #[test]
+    fn slide_schedule_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(220.0, M, Articulation::Slide),
+            note(440.0, M, Articulation::Slide),
+            note(330.0, M, Articulation::Normal),
+            rest(Duration::quarter()),
+            note(440.0, M, Articulation::Slide),
+            MusicalElement::TimedNote {
+                pitch: Pitch(550.0),
+                seconds: 0.5,
+                volume: M,
+            },
+            note(660.0, M, Articulation::Slide),
+        ]);
+
+        // only a note right before a slide note is slid from, a slide note lasts like a normal one
+        let contexts = voice.note_contexts(120);
+        assert_eq!(
+            contexts
+                .iter()
+                .map(|context| context.slide_from)
+                .collect::<Vec<Option<Pitch>>>(),
+            vec![
+                None,
+                Some(Pitch(220.0)),
+                None,
+                None,
+                None,
+                Some(Pitch(550.0))
+            ]
+        );
+        assert_eq!(contexts[1].duration_secs, 1.0);
+
+        // merging a slide into an equal pitch keeps only the slide into the first note
+        let merged = Voice::from_musical_elements(vec![
+            note(220.0, M, Articulation::Normal),
+            note(440.0, M, Articulation::Slide),
+            note(440.0, M, Articulation::Slide),
+            note(330.0, M, Articulation::Normal),
+            note(330.0, M, Articulation::Slide),
+        ])
+        .merge_ties();
+        assert_eq!(
+            format!("{:.0?}", merged),
+            "Voice[\n  Note { pitch: 220 Hz, duration: 1/4, volume: 140 }\n  Note { pitch: 440 Hz, duration: 1/2, volume: 140, articulation: Slide }\n  Note { pitch: 330 Hz, duration: 1/2, volume: 140 }\n]"
+        );
+    }
+
+    #[test]
+    fn canon_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(440.0, F, Articulation::Normal),
+            rest(Duration::eighth()),
+            note(330.0, M, Articulation::Staccato),
+        ]);
+        let canon = voice.canon(8, -12, 0.5);
+
+        assert_eq!(canon.musical_elements.len(), 4);
+        assert_eq!(canon.musical_elements[0], rest(Duration::whole()));
+        assert_eq!(
+            canon.total_duration(),
+            voice.total_duration() + Duration::whole()
+        );
+        for (original, copy) in voice
+            .musical_elements
+            .iter()
+            .zip(&canon.musical_elements[1..])
+        {
+            match (original, copy) {
+                (
+                    MusicalElement::Note { pitch, volume, .. },
+                    MusicalElement::Note {
+                        pitch: copy_pitch,
+                        volume: copy_volume,
+                        ..
+                    },
+                ) => {
+                    assert!((pitch.get_hz() / copy_pitch.get_hz() - 2.0).abs() < 1e-9);
+                    assert_eq!(copy_volume.get(), (volume.get() as f64 / 2.0).round() as u8);
                 }
+                (original, copy) => assert_eq!(original, copy),
             }
         }
+
+        // the volume is clamped and a delay of zero adds no rest
+        let canon = voice.canon(0, 0, 10.0);
+        assert_eq!(canon.musical_elements.len(), 3);
+        assert_eq!(
+            canon.musical_elements[0].get_duration(),
+            Duration::quarter()
+        );
+        assert!(
+            matches!(canon.musical_elements[0], MusicalElement::Note { volume, .. } if volume == FFF)
+        );
+        assert!(
+            matches!(voice.canon(0, 0, -1.0).musical_elements[2], MusicalElement::Note { volume, .. } if volume == SILENT)
+        );
+
+        // a delay longer than the voice starts the copy after its end
+        let canon = voice.canon(100, 0, 1.0);
+        assert!(canon.total_duration() > voice.total_duration() + voice.total_duration());
+        assert_eq!(
+            canon.musical_elements[0].get_duration(),
+            Duration::time_units(100)
+        );
+    }
+
+    #[test]
+    fn coalesce_rests_test() {
+        let voice = Voice::from_musical_elements(vec![
+            rest(Duration::eighth()),
+            rest(Duration::quarter()),
+            quarter_note(),
+            rest(Duration::eighth()),
+            quarter_note(),
+            rest(Duration::eighth()),
+            rest(Duration::eighth()),
+        ]);
+        let coalesced = voice.coalesce_rests();
+
+        assert_eq!(coalesced.musical_elements.len(), 5);
+        assert_eq!(coalesced.total_duration(), voice.total_duration());
+        assert_eq!(
+            format!("{:.3?}", coalesced),
+            "Voice[\n  Rest { duration: 3/8 }\n  Note { pitch: 440.000 Hz, duration: 1/4, volume: 140 }\n  Rest { duration: 1/8 }\n  Note { pitch: 440.000 Hz, duration: 1/4, volume: 140 }\n  Rest { duration: 1/4 }\n]"
+        );
+    }
+
+    #[test]
+    fn trim_test() {
+        let voice = Voice::from_musical_elements(vec![
+            rest(Duration::eighth()),
+            quarter_note(),
+            rest(Duration::eighth()),
+            quarter_note(),
+            rest(Duration::half()),
+        ]);
+        let trimmed = voice.trim();
+
+        assert_eq!(trimmed.musical_elements.len(), 3);
+        assert_eq!(trimmed.total_duration(), Duration::new(5, 8));
+    }
+
+    #[test]
+    fn strip_silence_over_test() {
+        let voice = Voice::from_musical_elements(vec![
+            quarter_note(),
+            rest(Duration::whole()),
+            rest(Duration::whole()),
+            quarter_note(),
+            rest(Duration::eighth()),
+            quarter_note(),
+        ]);
+        let stripped = voice.strip_silence_over(2);
+
+        assert_eq!(
+            format!("{:.3?}", stripped),
+            "Voice[\n  Note { pitch: 440.000 Hz, duration: 1/4, volume: 140 }\n  Rest { duration: 1/4 }\n  Note { pitch: 440.000 Hz, duration: 1/4, volume: 140 }\n  Rest { duration: 1/8 }\n  Note { pitch: 440.000 Hz, duration: 1/4, volume: 140 }\n]"
+        );
+    }
+
+    #[test]
+    fn only_rests_test() {
+        let voice =
+            Voice::from_musical_elements(vec![rest(Duration::quarter()), rest(Duration::eighth())]);
+
+        let trimmed = voice.trim();
+        assert_eq!(trimmed.musical_elements.len(), 0);
+        assert_eq!(trimmed.total_duration(), Duration::zero());
+        assert_eq!(trimmed.get_duration(120), 0.0);
+        assert!(trimmed.schedule(120).is_empty());
+
+        assert_eq!(voice.coalesce_rests().musical_elements.len(), 1);
+        assert_eq!(
+            voice.strip_silence_over(0).total_duration(),
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    fn approx_eq_test() {
+        let voice = Voice::from_musical_elements(vec![quarter_note(), rest(Duration::eighth())]);
+
+        let close = Voice::from_musical_elements(vec![
+            note(440.01, M, Articulation::Normal),
+            rest(Duration::eighth()),
+        ]);
+        assert!(voice.approx_eq(&close, 0.1));
+        assert!(!voice.approx_eq(&close, 0.01));
+
+        let louder = Voice::from_musical_elements(vec![
+            note(440.0, P, Articulation::Normal),
+            rest(Duration::eighth()),
+        ]);
+        assert!(!voice.approx_eq(&louder, 0.1));
+
+        let longer_rest =
+            Voice::from_musical_elements(vec![quarter_note(), rest(Duration::quarter())]);
+        assert!(!voice.approx_eq(&longer_rest, 0.1));
+
+        let shorter = Voice::from_musical_elements(vec![quarter_note()]);
+        assert!(!voice.approx_eq(&shorter, 0.1));
+        assert!(voice.approx_eq(&voice, 0.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(440.0, P, Articulation::Staccato),
+            rest(Duration::dotted(Duration::eighth())),
+            quarter_note(),
+        ]);
+
+        let json = serde_json::to_string(&voice).unwrap();
+        assert_eq!(
+            json,
+            r#"{"musical_elements":[{"Note":{"pitch":440.0,"duration":{"num":1,"den":4},"volume":84,"articulation":"Staccato"}},{"Rest":{"duration":{"num":3,"den":16}}},{"Note":{"pitch":440.0,"duration":{"num":1,"den":4},"volume":140,"articulation":"Normal"}}]}"#
+        );
+        assert!(serde_json::from_str::<Voice>(&json)
+            .unwrap()
+            .approx_eq(&voice, 0.0));
+
+        let path = std::env::temp_dir().join("music_generator_voice_round_trip.json");
+        voice.save_json(&path).unwrap();
+        assert!(Voice::load_json(&path).unwrap().approx_eq(&voice, 0.0));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn push_rest_test() {
+        let mut voice = Voice::from_musical_elements(vec![quarter_note()]);
+        let schedule = voice.schedule(120);
+
+        voice.push_rest(Duration::dotted(Duration::quarter()));
+        voice.musical_elements.push(MusicalElement::rest(3));
+
+        assert_eq!(voice.total_duration(), Duration::new(1, 1));
+        assert_eq!(format!("{:.3?}", voice.get_duration(120)), "4.000");
+        // the rests don't add any sounding notes
+        assert_eq!(voice.schedule(120), schedule);
+    }
+
+    #[test]
+    fn timed_note_test() {
+        let timed_note = MusicalElement::TimedNote {
+            pitch: Pitch(220.0),
+            seconds: 0.5,
+            volume: M,
+        };
+        let voice = Voice::from_musical_elements(vec![quarter_note(), timed_note, quarter_note()]);
+
+        for bpm in [60, 240] {
+            let schedule = voice.schedule(bpm);
+            let quarter_seconds = Duration::quarter().to_seconds(bpm, super::BEATS_PER_WHOLE);
+
+            // the slot of the timed note lasts half a second at any tempo
+            assert_eq!(schedule[1].0, quarter_seconds);
+            assert_eq!(schedule[1].1 - schedule[1].0, 0.5);
+            assert_eq!(schedule[2].0, quarter_seconds + 0.5);
+            assert_eq!(voice.get_duration(bpm), 2.0 * quarter_seconds + 0.5);
+        }
+
+        // the timed note has no musical duration, only its seconds
+        assert_eq!(timed_note.get_duration(), Duration::zero());
+        assert_eq!(voice.total_duration(), Duration::new(1, 2));
+        assert_eq!(timed_note.to_seconds(60, super::BEATS_PER_WHOLE), 0.5);
+        assert_eq!(
+            format!("{:?}", Voice::from_musical_elements(vec![timed_note])),
+            "Voice[\n  TimedNote { pitch: 220.000 Hz, seconds: 0.5, volume: 140 }\n]"
+        );
     }
 }
@@ -3,12 +3,13 @@
  * build a Voice, can do.
  */
 
-use crate::l_system::{Atom, Axiom};
+use crate::l_system::{Atom, Axiom, Symbol};
 use crate::musical_notation as notation;
+use crate::progress::{NoProgress, Progress, Stage};
 
 use std::cell::{RefCell, RefMut};
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub mod error;
 
@@ -29,48 +30,261 @@ pub trait ActionState {
 pub trait Action<S: ActionState> {
     fn gen_next_musical_element(
         &self,
-        symbol: char,
+        symbol: Symbol,
         state: RefMut<S>,
     ) -> Result<notation::MusicalElement, error::ActionError>;
+
+    /**
+     * Create all MusicalElements an Atom adds to a Voice. Most Actions
+     * add a single element, Actions like the ArpeggioAction add several.
+     */
+    fn gen_next_musical_elements(
+        &self,
+        symbol: Symbol,
+        state: RefMut<S>,
+    ) -> Result<Vec<notation::MusicalElement>, error::ActionError> {
+        Ok(vec![self.gen_next_musical_element(symbol, state)?])
+    }
 }
 
+/**
+ * What an Atom does when a Voice is built. An Atom with an Action adds a
+ * MusicalElement to the Voice, all other types only act on the ActionState:
+ * ChangeState applies its function to the current state.
+ */
 pub enum AtomType<S: ActionState> {
     NoAction,
-    HasAction { action: Rc<dyn Action<S>> },
+    HasAction {
+        action: Arc<dyn Action<S> + Send + Sync>,
+    },
+    ChangeState {
+        change: fn(&mut S),
+    },
     PushStack,
     PopStack,
 }
 
+/**
+ * Where a MusicalElement of a traced Voice comes from: the index and the symbol
+ * of the atom in the axiom that generated it, and the index of the element in the Voice.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElementOrigin {
+    pub atom_index: usize,
+    pub symbol: Symbol,
+    pub element_index: usize,
+}
+
 impl super::Voice {
     pub fn from<S: ActionState>(
         axiom: &Axiom,
         atom_types: HashMap<&Atom, AtomType<S>>,
-    ) -> Result<super::Voice, error::ActionError> {
+    ) -> crate::Result<super::Voice> {
+        super::Voice::from_with_progress(axiom, atom_types, &NoProgress)
+    }
+
+    /**
+     * Create the Voice like `from`, reporting each interpreted Atom
+     * as the Generation stage to the progress.
+     */
+    pub fn from_with_progress<S: ActionState>(
+        axiom: &Axiom,
+        atom_types: HashMap<&Atom, AtomType<S>>,
+        progress: &dyn Progress,
+    ) -> crate::Result<super::Voice> {
+        super::Voice::build(axiom, atom_types, progress, |_, _, _| {})
+    }
+
+    /**
+     * Create the Voice like `from`, recording the origin of every MusicalElement,
+     * e.g. to find out which atom generated a suspicious note.
+     */
+    pub fn from_traced<S: ActionState>(
+        axiom: &Axiom,
+        atom_types: HashMap<&Atom, AtomType<S>>,
+    ) -> crate::Result<(super::Voice, Vec<ElementOrigin>)> {
+        let mut origins = vec![];
+
+        let voice = super::Voice::build(
+            axiom,
+            atom_types,
+            &NoProgress,
+            |atom_index, symbol, element_indices| {
+                origins.extend(element_indices.map(|element_index| ElementOrigin {
+                    atom_index,
+                    symbol,
+                    element_index,
+                }))
+            },
+        )?;
+
+        Ok((voice, origins))
+    }
+
+    /**
+     * Interpret the atoms of the axiom one after another, passing the index and the symbol
+     * of every atom with an Action along with the indices of the elements it added to
+     * on_elements.
+     */
+    fn build<S: ActionState>(
+        axiom: &Axiom,
+        atom_types: HashMap<&Atom, AtomType<S>>,
+        progress: &dyn Progress,
+        mut on_elements: impl FnMut(usize, Symbol, std::ops::Range<usize>),
+    ) -> crate::Result<super::Voice> {
+        let total = axiom.atom_list.len() as u64;
+        progress.on_stage(Stage::Generation, 0, total);
+
         let mut voice = super::Voice {
             musical_elements: vec![],
         };
 
         let current_state: RefCell<S> = RefCell::new(S::get_neutral_state());
 
-        for atom in axiom.atoms() {
+        for (index, atom) in axiom.atoms().enumerate() {
             match atom_types.get(&atom) {
                 Some(atom_type) => match atom_type {
-                    AtomType::HasAction { action } => voice.musical_elements.push(
-                        action.gen_next_musical_element(atom.symbol, current_state.borrow_mut())?,
-                    ),
+                    AtomType::HasAction { action } => {
+                        let start = voice.musical_elements.len();
+                        voice.musical_elements.extend(
+                            action
+                                .gen_next_musical_elements(atom.symbol, current_state.borrow_mut())
+                                .map_err(|error| error.at_atom(index, atom.symbol))?,
+                        );
+                        on_elements(index, atom.symbol, start..voice.musical_elements.len());
+                    }
+                    AtomType::ChangeState { change } => change(&mut current_state.borrow_mut()),
                     AtomType::PushStack => current_state.borrow().push(),
-                    AtomType::PopStack => current_state.borrow_mut().pop()?,
+                    AtomType::PopStack => current_state
+                        .borrow_mut()
+                        .pop()
+                        .map_err(|error| error.at_atom(index, atom.symbol))?,
                     AtomType::NoAction => {}
                 },
-                None => {
-                    return Err(error::ActionError::from_error_kind(
-                        &super::ErrorKind::UndefinedAtomType,
-                    ))
-                }
+                None => return Err(error::ActionError::from_undefined_atom(atom.symbol).into()),
             };
+            progress.on_stage(Stage::Generation, index as u64 + 1, total);
         }
 
-        return Ok(voice);
+        Ok(voice)
+    }
+
+    /**
+     * Create a Voice letting the action generate a MusicalElement for every Atom
+     * of the axiom, which saves setting up the AtomTypes for quick experiments.
+     */
+    pub fn from_axiom_with_default_action(
+        axiom: &Axiom,
+        action: Arc<dyn Action<NeutralActionState> + Send + Sync>,
+    ) -> crate::Result<super::Voice> {
+        let atom_types = axiom
+            .atoms()
+            .map(|atom| {
+                (
+                    atom,
+                    AtomType::HasAction {
+                        action: Arc::clone(&action),
+                    },
+                )
+            })
+            .collect();
+
+        super::Voice::from(axiom, atom_types)
+    }
+
+    /**
+     * Create a Voice like `from_axiom_with_default_action`, except that the
+     * atoms with the rest symbol become eighth rests instead of being passed to the action.
+     */
+    pub fn from_axiom_with_rest_symbol(
+        axiom: &Axiom,
+        action: Arc<dyn Action<NeutralActionState> + Send + Sync>,
+        rest_symbol: impl Into<Symbol>,
+    ) -> crate::Result<super::Voice> {
+        let rest_symbol = rest_symbol.into();
+        let rest_action: Arc<dyn Action<NeutralActionState> + Send + Sync> = Arc::new(RestAction);
+
+        let atom_types = axiom
+            .atoms()
+            .map(|atom| {
+                let action = if atom.symbol == rest_symbol {
+                    &rest_action
+                } else {
+                    &action
+                };
+                (
+                    atom,
+                    AtomType::HasAction {
+                        action: Arc::clone(action),
+                    },
+                )
+            })
+            .collect();
+
+        super::Voice::from(axiom, atom_types)
+    }
+
+    /**
+     * Create one Voice per Axiom like `Voice::from`, building the voices concurrently
+     * on one thread each. The AtomTypes of every Axiom are created by the factory.
+     * Returns the voices in the order of the axioms or the first error encountered.
+     */
+    pub fn from_parallel<S, F>(
+        axioms: &[Axiom],
+        atom_type_factory: F,
+    ) -> crate::Result<Vec<super::Voice>>
+    where
+        S: ActionState,
+        F: for<'a> Fn(&'a Axiom) -> HashMap<&'a Atom, AtomType<S>> + Sync,
+    {
+        let atom_type_factory = &atom_type_factory;
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = axioms
+                .iter()
+                .map(|axiom| {
+                    scope.spawn(move || super::Voice::from(axiom, atom_type_factory(axiom)))
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("A thread building a Voice panicked"))
+                .collect()
+        })
+    }
+}
+
+/**
+ * Get the number of a letter, counting the letters A to Z and a to w from 1 to 49 like a
+ * SimpleAction. The letter x stands for an eighth rest and has no number, any other symbol
+ * is not mapped.
+ */
+pub(crate) fn letter_number(symbol: Symbol) -> Result<Option<u8>, error::ActionError> {
+    match symbol {
+        Symbol::Char('x') => Ok(None),
+        Symbol::Char(character @ 'A'..='Z') => Ok(Some(character as u8 - b'A' + 1)),
+        Symbol::Char(character @ 'a'..='w') => Ok(Some(character as u8 - b'a' + 27)),
+        _ => Err(error::ActionError::from_generation_error(
+            simple_action::error::MappingError::new(symbol),
+        )),
+    }
+}
+
+/**
+ * An Action generating an eighth rest for every symbol.
+ */
+pub(crate) struct RestAction;
+
+impl<S: ActionState> Action<S> for RestAction {
+    fn gen_next_musical_element(
+        &self,
+        _symbol: Symbol,
+        _state: RefMut<S>,
+    ) -> Result<notation::MusicalElement, error::ActionError> {
+        Ok(notation::MusicalElement::Rest {
+            duration: notation::Duration::eighth(),
+        })
     }
 }
 
@@ -94,12 +308,150 @@ impl ActionState for NeutralActionState {
     }
 }
 
+/**
+ * This is an ActionState holding the octave shift, the volume and the tonic
+ * of the following notes. Pushing saves these, popping restores the last
+ * saved ones. The slide flag marks the next note as a slide and is cleared
+ * by the action generating it. A tonic of None keeps the key of the action,
+ * a ModulatingAction sets it when it modulates.
+ */
+pub struct MusicalState {
+    pub octave_shift: i16,
+    pub volume: notation::Volume,
+    pub slide: bool,
+    pub tonic: Option<notation::Tone>,
+    stack: RefCell<Vec<(i16, notation::Volume, Option<notation::Tone>)>>,
+}
+
+impl MusicalState {
+    pub fn octave_up(&mut self) {
+        self.octave_shift += 1;
+    }
+
+    pub fn octave_down(&mut self) {
+        self.octave_shift -= 1;
+    }
+
+    pub fn louder(&mut self) {
+        self.volume = self.volume.step(1);
+    }
+
+    pub fn softer(&mut self) {
+        self.volume = self.volume.step(-1);
+    }
+
+    pub fn slide(&mut self) {
+        self.slide = true;
+    }
+
+    /**
+     * Clear the slide flag and make the element a slide if the flag was set and it is a note.
+     */
+    pub(crate) fn use_up_slide(&mut self, musical_element: &mut notation::MusicalElement) {
+        if std::mem::take(&mut self.slide) {
+            if let notation::MusicalElement::Note { articulation, .. } = musical_element {
+                *articulation = notation::Articulation::Slide;
+            }
+        }
+    }
+}
+
+impl ActionState for MusicalState {
+    fn get_neutral_state() -> MusicalState {
+        MusicalState {
+            octave_shift: 0,
+            volume: notation::M,
+            slide: false,
+            tonic: None,
+            stack: RefCell::new(vec![]),
+        }
+    }
+    fn push(&self) {
+        self.stack
+            .borrow_mut()
+            .push((self.octave_shift, self.volume, self.tonic));
+    }
+    fn pop(&mut self) -> Result<(), error::ActionError> {
+        match self.stack.get_mut().pop() {
+            Some((octave_shift, volume, tonic)) => {
+                self.octave_shift = octave_shift;
+                self.volume = volume;
+                self.tonic = tonic;
+                Ok(())
+            }
+            None => Err(error::ActionError::from_error_kind(
+                &super::ErrorKind::PopOnEmptyStack,
+            )),
+        }
+    }
+}
+
+/**
+ * An AtomTypeMap builds the AtomTypes of an axiom,
+ * registering the brackets as PushStack and PopStack.
+ */
+pub mod atom_type_map;
+
+pub use atom_type_map::AtomTypeMap;
+
+/**
+ * An ActionMap assigns an ActionKind to the symbols of an Axiom,
+ * e.g. read from a TOML file.
+ */
+pub mod action_map;
+
+pub use action_map::{ActionKind, ActionMap};
+
 /**
  * A SimpleAction is an Action, that maps the 26 upper case
  * letters A to Z and the 23 lower case letters a to w in that
  * order to the notes of seven consecutive octaves of the given key.
- * The letter x will be mapped to a rest.
+ * The starting octave and the number of octaves can be configured.
+ * For scales with more or less than seven degrees the letters are mapped
+ * to the consecutive degrees of the scale in the same way.
+ * The letter x will be mapped to a rest. Any other character,
+ * e.g. a musical symbol like '♩', can be mapped to a degree explicitly.
  */
 pub mod simple_action;
 
 pub use simple_action::SimpleAction;
+
+/**
+ * A ZipRhythmAction takes the pitches of the generated elements
+ * from another Action and their durations from a parallel rhythm string.
+ */
+pub mod zip_rhythm_action;
+
+pub use zip_rhythm_action::{RhythmState, ZipRhythmAction};
+
+/**
+ * A MarkovAction chooses the pitch of every note from the one
+ * before it, weighted by a transition matrix of scale degrees.
+ */
+pub mod markov_action;
+
+pub use markov_action::{MarkovAction, MarkovState};
+
+/**
+ * An ArpeggioAction maps the letters to the degrees of the scale and
+ * plays the triad on each degree as a run of short notes.
+ */
+pub mod arpeggio_action;
+
+pub use arpeggio_action::{ArpeggioAction, ArpeggioPattern};
+
+/**
+ * A ModulatingAction maps the letters to the degrees of the scale in the
+ * key of the MusicalState and changes that key on its modulation symbol.
+ */
+pub mod modulating_action;
+
+pub use modulating_action::ModulatingAction;
+
+/**
+ * An OvertoneAction maps the letters to the partials of the
+ * harmonic series of a fundamental instead of the degrees of a scale.
+ */
+pub mod overtone_action;
+
+pub use overtone_action::OvertoneAction;
@@ -0,0 +1,439 @@
+use super::{Action, AtomType, MusicalState, RestAction};
+use crate::l_system::{Atom, Axiom, Symbol};
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub mod error;
+
+/**
+ * What the atoms with a symbol do when a Voice is built. A Note is generated by the
+ * note action, a Rest lasts an eighth note. Push and Pop save and restore the
+ * MusicalState, the other kinds change it for the following notes. Slide makes
+ * the next note glide in from the pitch of the note before it.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ActionKind {
+    Note,
+    Rest,
+    Push,
+    Pop,
+    None,
+    OctaveUp,
+    OctaveDown,
+    Louder,
+    Softer,
+    Slide,
+}
+
+const ACTION_KIND_NAMES: [(&str, ActionKind); 10] = [
+    ("note", ActionKind::Note),
+    ("rest", ActionKind::Rest),
+    ("push", ActionKind::Push),
+    ("pop", ActionKind::Pop),
+    ("none", ActionKind::None),
+    ("octave_up", ActionKind::OctaveUp),
+    ("octave_down", ActionKind::OctaveDown),
+    ("louder", ActionKind::Louder),
+    ("softer", ActionKind::Softer),
+    ("slide", ActionKind::Slide),
+];
+
+impl FromStr for ActionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ActionKind, String> {
+        match ACTION_KIND_NAMES.iter().find(|(name, _)| *name == s) {
+            Some((_, action_kind)) => Ok(*action_kind),
+            None => Err(format!(
+                "unknown action '{}', expected one of {}",
+                s,
+                ACTION_KIND_NAMES
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/**
+ * An ActionMap assigns an ActionKind to symbols. Symbols without an
+ * ActionKind are notes, so the empty map turns every atom into a note.
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ActionMap {
+    action_kinds: BTreeMap<Symbol, ActionKind>,
+}
+
+impl ActionMap {
+    pub fn new() -> ActionMap {
+        ActionMap::default()
+    }
+
+    pub fn insert(&mut self, symbol: impl Into<Symbol>, action_kind: ActionKind) {
+        self.action_kinds.insert(symbol.into(), action_kind);
+    }
+
+    pub fn get(&self, symbol: impl Into<Symbol>) -> ActionKind {
+        self.action_kinds
+            .get(&symbol.into())
+            .copied()
+            .unwrap_or(ActionKind::Note)
+    }
+
+    /**
+     * Read the mapping from a TOML file assigning the name of an ActionKind to each symbol:
+     *
+     * ```toml
+     * "A" = "note"
+     * "x" = "rest"
+     * "[" = "push"
+     * "]" = "pop"
+     * "+" = "octave_up"
+     * "-" = "octave_down"
+     * "(" = "louder"
+     * ")" = "softer"
+     * " " = "none"
+     * "~" = "slide"
+     * "{up}" = "octave_up"
+     * ```
+     *
+     * A symbol is a single character or a multi-character token in braces.
+     */
+    pub fn from_toml(path: &Path) -> Result<ActionMap, error::ActionMapError> {
+        match std::fs::read_to_string(path) {
+            Ok(toml) => ActionMap::from_toml_str(&toml),
+            Err(e) => Err(error::ActionMapError::new(&format!(
+                "The file '{}' couldn't be read: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    /**
+     * Parse the mapping from its TOML representation. Errors name the line of the invalid entry.
+     */
+    pub fn from_toml_str(toml: &str) -> Result<ActionMap, error::ActionMapError> {
+        let action_names: BTreeMap<String, toml::Spanned<String>> =
+            toml::from_str(toml).map_err(|e| error::ActionMapError::new(&e.to_string()))?;
+
+        let mut action_map = ActionMap::new();
+
+        for (symbol, action_name) in action_names {
+            let line = toml[..action_name.start()].matches('\n').count() + 1;
+            let in_line = |message: String| {
+                error::ActionMapError::new(&format!("line {}: {}", line, message))
+            };
+
+            let action_kind = action_name.get_ref().parse().map_err(in_line)?;
+            match symbol.parse::<Symbol>() {
+                Ok(parsed_symbol) => action_map.insert(parsed_symbol, action_kind),
+                Err(_) => return Err(in_line(format!("'{}' is not a single symbol", symbol))),
+            }
+        }
+
+        Ok(action_map)
+    }
+
+    /**
+     * Get the AtomTypes of all atoms of the axiom to pass them to `Voice::from`.
+     * The atoms mapped to a note use the given action.
+     */
+    pub fn atom_types<'a>(
+        &self,
+        axiom: &'a Axiom,
+        note_action: Arc<dyn Action<MusicalState> + Send + Sync>,
+    ) -> HashMap<&'a Atom, AtomType<MusicalState>> {
+        let rest_action: Arc<dyn Action<MusicalState> + Send + Sync> = Arc::new(RestAction);
+
+        axiom
+            .atoms()
+            .map(|atom| {
+                let atom_type = match self.get(atom.symbol) {
+                    ActionKind::Note => AtomType::HasAction {
+                        action: Arc::clone(&note_action),
+                    },
+                    ActionKind::Rest => AtomType::HasAction {
+                        action: Arc::clone(&rest_action),
+                    },
+                    ActionKind::Push => AtomType::PushStack,
+                    ActionKind::Pop => AtomType::PopStack,
+                    ActionKind::None => AtomType::NoAction,
+                    ActionKind::OctaveUp => AtomType::ChangeState {
+                        change: MusicalState::octave_up,
+                    },
+                    ActionKind::OctaveDown => AtomType::ChangeState {
+                        change: MusicalState::octave_down,
+                    },
+                    ActionKind::Louder => AtomType::ChangeState {
+                        change: MusicalState::louder,
+                    },
+                    ActionKind::Softer => AtomType::ChangeState {
+                        change: MusicalState::softer,
+                    },
+                    ActionKind::Slide => AtomType::ChangeState {
+                        change: MusicalState::slide,
+                    },
+                };
+
+                (atom, atom_type)
+            })
+            .collect()
+    }
+}
+
+impl<S: Into<Symbol>> FromIterator<(S, ActionKind)> for ActionMap {
+    fn from_iter<I: IntoIterator<Item = (S, ActionKind)>>(iter: I) -> Self {
+        ActionMap {
+            action_kinds: iter
+                .into_iter()
+                .map(|(symbol, action_kind)| (symbol.into(), action_kind))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActionKind, ActionMap};
+    use crate::l_system::Axiom;
+    use crate::musical_notation::{
+        Accidental, Articulation, EqualTemperament, Key, MusicalElement, Note, ScaleKind,
+        Temperament, F, M, MF, STUTTGART_PITCH,
+    };
+    use crate::voice::action::{Action, MusicalState, SimpleAction};
+    use crate::voice::Voice;
+
+    use std::sync::Arc;
+
+    fn pitches_and_volumes(voice: &Voice) -> Vec<Option<(String, u8)>> {
+        voice
+            .musical_elements()
+            .iter()
+            .map(|musical_element| match musical_element {
+                MusicalElement::Note { pitch, volume, .. }
+                | MusicalElement::TimedNote { pitch, volume, .. } => {
+                    Some((format!("{:.3}", pitch.get_hz()), volume.get()))
+                }
+                MusicalElement::Rest { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn from_toml_str_test() {
+        let action_map = ActionMap::from_toml_str(
+            "\"A\" = \"note\"\n\"x\" = \"rest\"\n\"[\" = \"push\"\n\"]\" = \"pop\"\n\
+             \"+\" = \"octave_up\"\n\"-\" = \"octave_down\"\n\"(\" = \"louder\"\n\
+             \")\" = \"softer\"\n\" \" = \"none\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(action_map.get('A'), ActionKind::Note);
+        assert_eq!(action_map.get('x'), ActionKind::Rest);
+        assert_eq!(action_map.get('['), ActionKind::Push);
+        assert_eq!(action_map.get(']'), ActionKind::Pop);
+        assert_eq!(action_map.get('+'), ActionKind::OctaveUp);
+        assert_eq!(action_map.get('-'), ActionKind::OctaveDown);
+        assert_eq!(action_map.get('('), ActionKind::Louder);
+        assert_eq!(action_map.get(')'), ActionKind::Softer);
+        assert_eq!(action_map.get(' '), ActionKind::None);
+        // unmapped symbols are notes
+        assert_eq!(action_map.get('B'), ActionKind::Note);
+    }
+
+    #[test]
+    fn invalid_toml_test() {
+        let error = ActionMap::from_toml_str("\"A\" = \"note\"\n\"+\" = \"octave_upp\"\n")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Invalid action mapping: line 2: unknown action 'octave_upp', expected one of note, \
+             rest, push, pop, none, octave_up, octave_down, louder, softer, slide."
+        );
+
+        assert_eq!(
+            ActionMap::from_toml_str("\"A\" = \"note\"\n\n\"AB\" = \"note\"\n")
+                .unwrap_err()
+                .to_string(),
+            "Invalid action mapping: line 3: 'AB' is not a single symbol."
+        );
+        assert!(ActionMap::from_toml_str("\"A\" = 1\n").is_err());
+    }
+
+    #[test]
+    fn atom_types_test() {
+        let action_map: ActionMap = [
+            ('[', ActionKind::Push),
+            (']', ActionKind::Pop),
+            ('+', ActionKind::OctaveUp),
+            ('-', ActionKind::OctaveDown),
+            ('(', ActionKind::Louder),
+            (')', ActionKind::Softer),
+            ('.', ActionKind::Rest),
+            (' ', ActionKind::None),
+        ]
+        .into_iter()
+        .collect();
+
+        let key = Key::new(
+            &Note::A,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        );
+        let action: Arc<dyn Action<MusicalState> + Send + Sync> =
+            Arc::new(SimpleAction::new(key, &ScaleKind::Major));
+
+        let axiom = Axiom::from("A [+(A] A. -)A").unwrap();
+        let voice =
+            Voice::from(&axiom, action_map.atom_types(&axiom, Arc::clone(&action))).unwrap();
+
+        assert_eq!(
+            pitches_and_volumes(&voice),
+            vec![
+                Some((String::from("440.000"), M.get())),
+                Some((String::from("880.000"), MF.get())),
+                Some((String::from("440.000"), M.get())),
+                None,
+                Some((String::from("220.000"), M.step(-1).get())),
+            ]
+        );
+
+        // the state is kept until it is changed again
+        let axiom = Axiom::from("((A)A").unwrap();
+        let voice =
+            Voice::from(&axiom, action_map.atom_types(&axiom, Arc::clone(&action))).unwrap();
+        assert_eq!(
+            pitches_and_volumes(&voice),
+            vec![
+                Some((String::from("440.000"), F.get())),
+                Some((String::from("440.000"), MF.get())),
+            ]
+        );
+
+        // the error names the atom it came from
+        let axiom = Axiom::from("A]").unwrap();
+        assert_eq!(
+            Voice::from(&axiom, action_map.atom_types(&axiom, action))
+                .unwrap_err()
+                .to_string(),
+            "PopOnEmptyStack: Tried to pop an empty state stack (atom ']' at index 1)."
+        );
+    }
+
+    #[test]
+    fn traced_test() {
+        let action_map: ActionMap = [
+            ('+', ActionKind::OctaveUp),
+            ('.', ActionKind::Rest),
+            (' ', ActionKind::None),
+        ]
+        .into_iter()
+        .collect();
+
+        let key = Key::new(
+            &Note::A,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        );
+        let action: Arc<dyn Action<MusicalState> + Send + Sync> =
+            Arc::new(SimpleAction::new(key, &ScaleKind::Major));
+
+        let axiom = Axiom::from("A +B. C").unwrap();
+        let (voice, origins) =
+            Voice::from_traced(&axiom, action_map.atom_types(&axiom, action)).unwrap();
+
+        assert_eq!(origins.len(), voice.musical_elements().len());
+        assert_eq!(
+            origins
+                .iter()
+                .map(|origin| (origin.atom_index, origin.symbol, origin.element_index))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 'A'.into(), 0),
+                (3, 'B'.into(), 1),
+                (4, '.'.into(), 2),
+                (6, 'C'.into(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn slide_test() {
+        let action_map = ActionMap::from_toml_str("\"~\" = \"slide\"\n\"[\" = \"push\"\n").unwrap();
+        let key = Key::new(
+            &Note::A,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        );
+        let action: Arc<dyn Action<MusicalState> + Send + Sync> =
+            Arc::new(SimpleAction::new(key, &ScaleKind::Major));
+
+        // the slide is used up by the next element, even if it is a rest
+        let axiom = Axiom::from("A~BA~[xA").unwrap();
+        let voice = Voice::from(&axiom, action_map.atom_types(&axiom, action)).unwrap();
+        let articulations: Vec<Option<Articulation>> = voice
+            .musical_elements()
+            .iter()
+            .map(|musical_element| match musical_element {
+                MusicalElement::Note { articulation, .. } => Some(*articulation),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            articulations,
+            vec![
+                Some(Articulation::Normal),
+                Some(Articulation::Slide),
+                Some(Articulation::Normal),
+                None,
+                Some(Articulation::Normal),
+            ]
+        );
+    }
+
+    #[test]
+    fn token_symbols_test() {
+        let action_map =
+            ActionMap::from_toml_str("\"{up}\" = \"octave_up\"\n\"{pause}\" = \"rest\"\n").unwrap();
+
+        let key = Key::new(
+            &Note::A,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        );
+        let action: Arc<dyn Action<MusicalState> + Send + Sync> =
+            Arc::new(SimpleAction::new(key, &ScaleKind::Major));
+
+        let axiom = Axiom::from("A{up}A{pause}").unwrap();
+        let voice =
+            Voice::from(&axiom, action_map.atom_types(&axiom, Arc::clone(&action))).unwrap();
+        assert_eq!(
+            pitches_and_volumes(&voice),
+            vec![
+                Some((String::from("440.000"), M.get())),
+                Some((String::from("880.000"), M.get())),
+                None,
+            ]
+        );
+
+        // an unmapped token is a note, but the simple action can't map it to a pitch
+        let axiom = Axiom::from("A{unknown}").unwrap();
+        let error = Voice::from(&axiom, action_map.atom_types(&axiom, action)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "GenerationError: Unexpected symbol: '{unknown}' (atom '{unknown}' at index 1)."
+        );
+    }
+}
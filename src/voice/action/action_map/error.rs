@@ -0,0 +1,23 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub struct ActionMapError {
+    message: String,
+}
+
+impl ActionMapError {
+    pub fn new(message: &str) -> Self {
+        ActionMapError {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid action mapping: {}.", self.message)
+    }
+}
+
+impl Error for ActionMapError {}
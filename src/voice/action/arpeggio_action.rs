@@ -0,0 +1,249 @@
+use super::simple_action::{error::PitchError, DEFAULT_BASE_OCTAVE};
+use super::{error::ActionError, letter_number, Action, MusicalState, NeutralActionState};
+use crate::l_system::Symbol;
+use crate::musical_notation as notation;
+
+use std::cell::RefMut;
+
+/**
+ * The order in which an ArpeggioAction plays the pitches of a triad.
+ * UpDown plays the root, the third, the fifth and the third again,
+ * so repeating the arpeggio doesn't play the root twice in a row.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArpeggioPattern {
+    Up,
+    Down,
+    UpDown,
+}
+
+impl ArpeggioPattern {
+    fn order(&self) -> &'static [usize] {
+        match self {
+            ArpeggioPattern::Up => &[0, 1, 2],
+            ArpeggioPattern::Down => &[2, 1, 0],
+            ArpeggioPattern::UpDown => &[0, 1, 2, 1],
+        }
+    }
+}
+
+pub struct ArpeggioAction<T: notation::Temperament> {
+    key: notation::Key<T>,
+    scale_kind: notation::ScaleKind,
+    pattern: ArpeggioPattern,
+    duration: notation::Duration,
+}
+
+impl<T: notation::Temperament> ArpeggioAction<T> {
+    /**
+     * Create an ArpeggioAction mapping the letters A to Z and a to w in that order
+     * to the degrees of the scale starting with the first degree in octave 4, like
+     * a SimpleAction. Every arpeggio lasts an eighth note.
+     */
+    pub fn new(
+        key: notation::Key<T>,
+        scale_kind: &notation::ScaleKind,
+        pattern: ArpeggioPattern,
+    ) -> Self {
+        ArpeggioAction {
+            key,
+            scale_kind: scale_kind.clone(),
+            pattern,
+            duration: notation::Duration::eighth(),
+        }
+    }
+
+    /**
+     * Set the duration of a whole arpeggio. Each of its notes gets an
+     * equal part of it, so the arpeggios keep the rhythm of the axiom.
+     */
+    pub fn set_duration(&mut self, duration: notation::Duration) {
+        self.duration = duration;
+    }
+
+    /**
+     * Map the symbol to the notes of an arpeggio shifted by the given number of octaves,
+     * or to a single rest for the letter x.
+     */
+    fn gen_arpeggio(
+        &self,
+        symbol: Symbol,
+        octave_shift: i16,
+        volume: notation::Volume,
+    ) -> Result<Vec<notation::MusicalElement>, ActionError> {
+        let degree = match letter_number(symbol)? {
+            Some(degree) => degree,
+            None => {
+                return Ok(vec![notation::MusicalElement::Rest {
+                    duration: self.duration,
+                }])
+            }
+        };
+
+        let triad = self
+            .key
+            .triad(&self.scale_kind, DEFAULT_BASE_OCTAVE, degree)
+            .ok_or_else(|| {
+                ActionError::from_generation_error(PitchError::new(&self.key, &self.scale_kind))
+            })?;
+
+        let order = self.pattern.order();
+        let duration = self.duration / order.len() as u32;
+
+        Ok(order
+            .iter()
+            .map(|index| {
+                notation::MusicalElement::note(
+                    triad[*index].shift_octaves(octave_shift),
+                    duration,
+                    volume,
+                )
+            })
+            .collect())
+    }
+}
+
+/**
+ * A single MusicalElement can only hold the first note of the arpeggio,
+ * `Voice::from` adds all of them.
+ */
+impl<T: notation::Temperament> Action<NeutralActionState> for ArpeggioAction<T> {
+    fn gen_next_musical_element(
+        &self,
+        symbol: Symbol,
+        state: RefMut<NeutralActionState>,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        Ok(self.gen_next_musical_elements(symbol, state)?[0])
+    }
+
+    fn gen_next_musical_elements(
+        &self,
+        symbol: Symbol,
+        _state: RefMut<NeutralActionState>,
+    ) -> Result<Vec<notation::MusicalElement>, ActionError> {
+        self.gen_arpeggio(symbol, 0, notation::M)
+    }
+}
+
+/**
+ * With a MusicalState the arpeggios are shifted by its octave shift and played with its
+ * volume. If the slide flag is set, the first note of the next arpeggio uses it up.
+ */
+impl<T: notation::Temperament> Action<MusicalState> for ArpeggioAction<T> {
+    fn gen_next_musical_element(
+        &self,
+        symbol: Symbol,
+        state: RefMut<MusicalState>,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        Ok(self.gen_next_musical_elements(symbol, state)?[0])
+    }
+
+    fn gen_next_musical_elements(
+        &self,
+        symbol: Symbol,
+        mut state: RefMut<MusicalState>,
+    ) -> Result<Vec<notation::MusicalElement>, ActionError> {
+        let mut arpeggio = self.gen_arpeggio(symbol, state.octave_shift, state.volume)?;
+        state.use_up_slide(&mut arpeggio[0]);
+
+        Ok(arpeggio)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArpeggioAction, ArpeggioPattern};
+    use crate::l_system::Axiom;
+    use crate::musical_notation::{
+        Accidental, Duration, EqualTemperament, Key, MusicalElement, Note, Pitch, ScaleKind,
+        Temperament, STUTTGART_PITCH,
+    };
+    use crate::voice::action::{Action, ActionKind, ActionMap, MusicalState};
+    use crate::voice::Voice;
+
+    use std::sync::Arc;
+
+    fn c_major() -> Key<EqualTemperament> {
+        Key::new(
+            &Note::C,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        )
+    }
+
+    fn arpeggiate(axiom: &str, pattern: ArpeggioPattern) -> Voice {
+        let action = Arc::new(ArpeggioAction::new(c_major(), &ScaleKind::Major, pattern));
+        Voice::from_axiom_with_default_action(&Axiom::from(axiom).unwrap(), action).unwrap()
+    }
+
+    fn pitches(voice: &Voice) -> Vec<Option<Pitch>> {
+        voice
+            .musical_elements()
+            .iter()
+            .map(|musical_element| match musical_element {
+                MusicalElement::Note { pitch, .. } => Some(*pitch),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn arpeggio_test() {
+        let triad = c_major().triad(&ScaleKind::Major, 4, 1).unwrap();
+
+        let voice = arpeggiate("A", ArpeggioPattern::Up);
+        assert_eq!(
+            pitches(&voice),
+            vec![Some(triad[0]), Some(triad[1]), Some(triad[2])]
+        );
+        assert!(voice
+            .musical_elements()
+            .iter()
+            .all(|musical_element| musical_element.get_duration() == Duration::new(1, 24)));
+        assert_eq!(voice.total_duration(), Duration::eighth());
+
+        let voice = arpeggiate("A", ArpeggioPattern::Down);
+        assert_eq!(
+            pitches(&voice),
+            vec![Some(triad[2]), Some(triad[1]), Some(triad[0])]
+        );
+
+        let voice = arpeggiate("A", ArpeggioPattern::UpDown);
+        assert_eq!(
+            pitches(&voice),
+            vec![
+                Some(triad[0]),
+                Some(triad[1]),
+                Some(triad[2]),
+                Some(triad[1])
+            ]
+        );
+        assert_eq!(voice.total_duration(), Duration::eighth());
+    }
+
+    #[test]
+    fn rests_and_octave_shifts_test() {
+        let action_map: ActionMap = [('+', ActionKind::OctaveUp)].into_iter().collect();
+        let mut action = ArpeggioAction::new(c_major(), &ScaleKind::Major, ArpeggioPattern::Up);
+        action.set_duration(Duration::quarter());
+        let action: Arc<dyn Action<MusicalState> + Send + Sync> = Arc::new(action);
+
+        let axiom = Axiom::from("Ex+E").unwrap();
+        let voice = Voice::from(&axiom, action_map.atom_types(&axiom, action)).unwrap();
+        let pitches = pitches(&voice);
+
+        assert_eq!(pitches.len(), 7);
+        assert_eq!(pitches[3], None);
+        assert_eq!(
+            voice.musical_elements()[3].get_duration(),
+            Duration::quarter()
+        );
+        for index in 0..3 {
+            assert_eq!(
+                pitches[index + 4],
+                pitches[index].map(|pitch| pitch.shift_octaves(1))
+            );
+        }
+        assert_eq!(voice.total_duration(), Duration::quarter() * 3);
+    }
+}
@@ -0,0 +1,144 @@
+use super::{Action, ActionState, AtomType};
+use crate::l_system::{Atom, Axiom, Symbol};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/**
+ * The symbols an AtomTypeMap registers as PushStack and PopStack.
+ */
+pub const PUSH_SYMBOL: char = '[';
+pub const POP_SYMBOL: char = ']';
+
+/**
+ * An AtomTypeMap builds the AtomTypes of the atoms of an axiom for `Voice::from`.
+ * The brackets '[' and ']' of the axiom are registered as PushStack and PopStack
+ * right away, as most grammars use them to save and restore the state.
+ *
+ * ```
+ * use music_generator::prelude::*;
+ * use music_generator::voice::action::AtomTypeMap;
+ * use std::sync::Arc;
+ *
+ * let axiom = Axiom::from("A[B]x").unwrap();
+ * let key = Key::equal_tempered(&Note::C, &Accidental::Natural, STUTTGART_PITCH);
+ * let action: Arc<dyn Action<NeutralActionState> + Send + Sync> =
+ *     Arc::new(SimpleAction::new(key, &ScaleKind::Major));
+ *
+ * let atom_types = AtomTypeMap::new(&axiom).with_default_action(action).build();
+ * assert_eq!(Voice::from(&axiom, atom_types).unwrap().get_note_count(), 2);
+ * ```
+ */
+pub struct AtomTypeMap<'a, S: ActionState> {
+    axiom: &'a Axiom,
+    atom_types: HashMap<&'a Atom, AtomType<S>>,
+}
+
+impl<'a, S: ActionState> AtomTypeMap<'a, S> {
+    pub fn new(axiom: &'a Axiom) -> Self {
+        AtomTypeMap {
+            axiom,
+            atom_types: HashMap::new(),
+        }
+        .insert(PUSH_SYMBOL, AtomType::PushStack)
+        .insert(POP_SYMBOL, AtomType::PopStack)
+    }
+
+    /**
+     * Give the atoms with the symbol the AtomType, replacing the one they had.
+     * A symbol the axiom doesn't contain is ignored.
+     */
+    pub fn insert(mut self, symbol: impl Into<Symbol>, atom_type: AtomType<S>) -> Self {
+        let symbol = symbol.into();
+
+        if let Some(atom) = self.axiom.atoms().find(|atom| atom.symbol == symbol) {
+            self.atom_types.insert(atom, atom_type);
+        }
+
+        self
+    }
+
+    /**
+     * Let the action generate the MusicalElements of all atoms without an AtomType yet.
+     */
+    pub fn with_default_action(mut self, action: Arc<dyn Action<S> + Send + Sync>) -> Self {
+        for atom in self.axiom.atoms() {
+            self.atom_types
+                .entry(atom)
+                .or_insert_with(|| AtomType::HasAction {
+                    action: Arc::clone(&action),
+                });
+        }
+
+        self
+    }
+
+    pub fn build(self) -> HashMap<&'a Atom, AtomType<S>> {
+        self.atom_types
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AtomTypeMap;
+    use crate::l_system::Axiom;
+    use crate::voice::action::{AtomType, MusicalState, NeutralActionState, RestAction};
+    use crate::voice::Voice;
+
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn brackets_test() {
+        let axiom = Axiom::from("A[+A]A").unwrap();
+        let atom_types = AtomTypeMap::new(&axiom)
+            .insert(
+                '+',
+                AtomType::ChangeState {
+                    change: MusicalState::octave_up,
+                },
+            )
+            .with_default_action(Arc::new(RestAction))
+            .build();
+
+        assert_eq!(atom_types.len(), 4);
+        assert_eq!(
+            Voice::from(&axiom, atom_types)
+                .unwrap()
+                .musical_elements()
+                .len(),
+            3
+        );
+
+        // symbols the axiom doesn't contain aren't registered
+        let axiom = Axiom::from("AB").unwrap();
+        let atom_types = AtomTypeMap::<NeutralActionState>::new(&axiom)
+            .insert('C', AtomType::NoAction)
+            .build();
+        assert!(atom_types.is_empty());
+    }
+
+    #[test]
+    fn unregistered_bracket_hint_test() {
+        let axiom = Axiom::from("A[A]").unwrap();
+        let atom_types: HashMap<_, AtomType<NeutralActionState>> = axiom
+            .atoms()
+            .filter(|atom| atom.symbol == 'A'.into())
+            .map(|atom| {
+                (
+                    atom,
+                    AtomType::HasAction {
+                        action: Arc::new(RestAction),
+                    },
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            Voice::from(&axiom, atom_types).unwrap_err().to_string(),
+            "UndefinedAtomType: The type of atom '[' is left undefined, \
+             register it as AtomType::PushStack or build the AtomTypes with an AtomTypeMap, \
+             which registers '[' and ']' as PushStack and PopStack."
+        );
+    }
+}
@@ -2,18 +2,59 @@ use std::error::Error;
 use std::fmt;
 
 use super::super::ErrorKind;
+use crate::l_system::Symbol;
+use crate::musical_notation::{Key, Pitch, Temperament};
 
 #[derive(Debug)]
 pub struct ActionError {
     kind: &'static ErrorKind,
     message: String,
+    source: Option<Box<dyn Error + Send + Sync>>,
 }
 
 impl ActionError {
-    pub fn from_generation_error<T: Error>(generation_error: &T) -> ActionError {
+    /**
+     * Wrap the error that occurred while generating a MusicalElement.
+     * It stays accessible through `Error::source`. Its closing period
+     * is dropped, as the ActionError ends the message with its own.
+     */
+    pub fn from_generation_error<T: Error + Send + Sync + 'static>(
+        generation_error: T,
+    ) -> ActionError {
         ActionError {
             kind: &ErrorKind::GenerationError,
-            message: format!("{}", generation_error),
+            message: generation_error
+                .to_string()
+                .trim_end_matches('.')
+                .to_string(),
+            source: Some(Box::new(generation_error)),
+        }
+    }
+
+    /**
+     * The message of an undefined bracket suggests registering it,
+     * as forgetting to do so is a common mistake.
+     */
+    pub fn from_undefined_atom(symbol: impl Into<Symbol>) -> ActionError {
+        let symbol = symbol.into();
+        let hint = match symbol {
+            Symbol::Char('[') => Some("PushStack"),
+            Symbol::Char(']') => Some("PopStack"),
+            _ => None,
+        };
+
+        ActionError {
+            kind: &ErrorKind::UndefinedAtomType,
+            message: match hint {
+                Some(atom_type) => format!(
+                    "The type of atom '{}' is left undefined, register it as AtomType::{} \
+                     or build the AtomTypes with an AtomTypeMap, which registers '[' and ']' \
+                     as PushStack and PopStack",
+                    symbol, atom_type
+                ),
+                None => format!("The type of atom '{}' is left undefined", symbol),
+            },
+            source: None,
         }
     }
 
@@ -29,18 +70,147 @@ impl ActionError {
                     String::from("General error while generating a MusicalElement")
                 }
             },
+            source: None,
         }
     }
+
+    /**
+     * Name the atom of the axiom the error came from in the message,
+     * so that a mistake in a long axiom can be found.
+     */
+    pub fn at_atom(mut self, atom_index: usize, symbol: Symbol) -> ActionError {
+        self.message = format!(
+            "{} (atom '{}' at index {})",
+            self.message, symbol, atom_index
+        );
+        self
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        self.kind
+    }
 }
 
 impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}.", self.kind, self.message)
+    }
+}
+
+impl Error for ActionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| source.as_ref() as &(dyn Error + 'static))
+    }
+}
+
+/**
+ * A pitch that is not a tone of the scale of a key.
+ */
+#[derive(Debug)]
+pub struct OutOfKeyError {
+    hz: f64,
+    key_msg: String,
+}
+
+impl OutOfKeyError {
+    pub fn new<T: Temperament>(pitch: Pitch, key: &Key<T>) -> Self {
+        OutOfKeyError {
+            hz: pitch.get_hz(),
+            key_msg: format!("{}", key),
+        }
+    }
+}
+
+impl fmt::Display for OutOfKeyError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "There was an Error while interpreting the Axiom: {}.",
-            self.message
+            "The pitch {:.3} Hz is not in the scale of the {} key",
+            self.hz, self.key_msg
         )
     }
 }
 
-impl Error for ActionError {}
+impl Error for OutOfKeyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::ErrorKind;
+    use super::ActionError;
+
+    #[test]
+    fn display_error_kind_test() {
+        assert_eq!(
+            format!("{}", ActionError::from_undefined_atom('X')),
+            "UndefinedAtomType: The type of atom 'X' is left undefined."
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                ActionError::from_error_kind(&ErrorKind::UndefinedAtomType)
+            ),
+            "UndefinedAtomType: The type of an atom is left undefined."
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                ActionError::from_error_kind(&ErrorKind::PopOnEmptyStack)
+            ),
+            "PopOnEmptyStack: Tried to pop an empty state stack."
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                ActionError::from_error_kind(&ErrorKind::GenerationError)
+            ),
+            "GenerationError: General error while generating a MusicalElement."
+        );
+        assert_eq!(
+            format!("{}", ActionError::from_generation_error(std::fmt::Error)),
+            "GenerationError: an error occurred when formatting an argument."
+        );
+    }
+
+    #[test]
+    fn kind_test() {
+        assert_eq!(
+            ActionError::from_error_kind(&ErrorKind::PopOnEmptyStack).kind(),
+            &ErrorKind::PopOnEmptyStack
+        );
+        assert_eq!(
+            ActionError::from_undefined_atom('X').kind(),
+            &ErrorKind::UndefinedAtomType
+        );
+    }
+
+    #[test]
+    fn source_test() {
+        use std::error::Error;
+
+        assert!(ActionError::from_undefined_atom('X').source().is_none());
+        assert_eq!(
+            format!(
+                "{}",
+                ActionError::from_generation_error(std::fmt::Error)
+                    .source()
+                    .unwrap()
+            ),
+            "an error occurred when formatting an argument"
+        );
+    }
+
+    #[test]
+    fn into_anyhow_test() {
+        let result: anyhow::Result<()> = (|| {
+            Err(ActionError::from_error_kind(&ErrorKind::PopOnEmptyStack))?;
+            Ok(())
+        })();
+
+        assert_eq!(
+            format!("{}", result.unwrap_err()),
+            "PopOnEmptyStack: Tried to pop an empty state stack."
+        );
+    }
+}
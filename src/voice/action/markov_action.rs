@@ -0,0 +1,206 @@
+use super::{error::ActionError, simple_action::error::PitchError, Action, ActionState};
+use crate::l_system::Symbol;
+use crate::musical_notation as notation;
+use crate::rng::Rng;
+
+use std::cell::{RefCell, RefMut};
+
+const BASE_OCTAVE: i16 = 4;
+
+/**
+ * This is an ActionState holding the index of the last degree a MarkovAction
+ * emitted and the random numbers of its choices, seeded by the action on its
+ * first note. Pushing saves the last degree, popping returns to the last saved one.
+ */
+pub struct MarkovState {
+    pub degree: Option<usize>,
+    rng: Option<Rng>,
+    stack: RefCell<Vec<Option<usize>>>,
+}
+
+impl ActionState for MarkovState {
+    fn get_neutral_state() -> MarkovState {
+        MarkovState {
+            degree: None,
+            rng: None,
+            stack: RefCell::new(vec![]),
+        }
+    }
+    fn push(&self) {
+        self.stack.borrow_mut().push(self.degree);
+    }
+    fn pop(&mut self) -> Result<(), ActionError> {
+        match self.stack.get_mut().pop() {
+            Some(degree) => {
+                self.degree = degree;
+                Ok(())
+            }
+            None => Err(ActionError::from_error_kind(
+                &super::super::ErrorKind::PopOnEmptyStack,
+            )),
+        }
+    }
+}
+
+pub struct MarkovAction<T: notation::Temperament, const N: usize> {
+    key: notation::Key<T>,
+    scale_kind: notation::ScaleKind,
+    matrix: [[f64; N]; N],
+    seed: u64,
+}
+
+impl<T: notation::Temperament, const N: usize> MarkovAction<T, N> {
+    /**
+     * Create a MarkovAction choosing among the first N degrees of the scale, counted
+     * from the first degree in octave 4 on through the following octaves. The entry
+     * matrix[i][j] is the weight of a step from the i-th to the j-th of these degrees.
+     */
+    pub fn new(
+        key: notation::Key<T>,
+        scale_kind: &notation::ScaleKind,
+        matrix: [[f64; N]; N],
+        seed: u64,
+    ) -> Self {
+        MarkovAction {
+            key,
+            scale_kind: scale_kind.clone(),
+            matrix,
+            seed,
+        }
+    }
+
+    /**
+     * Choose the index of the degree following the last one. The chain starts on the
+     * first degree and stays on a degree whose row has no positive weight. The random
+     * numbers start anew with the seed for every Voice, so the same seed always gives
+     * the same melody.
+     */
+    fn next_degree(&self, state: &mut MarkovState) -> usize {
+        let rng = state.rng.get_or_insert_with(|| Rng::from_seed(self.seed));
+
+        match state.degree {
+            Some(degree) => rng.choose_weighted(&self.matrix[degree]).unwrap_or(degree),
+            None => 0,
+        }
+    }
+}
+
+/**
+ * Every symbol emits the next note of the chain as an eighth note, so the
+ * axiom controls the rhythm and the structure while the matrix controls the contour.
+ */
+impl<T: notation::Temperament, const N: usize> Action<MarkovState> for MarkovAction<T, N> {
+    fn gen_next_musical_element(
+        &self,
+        _symbol: Symbol,
+        mut state: RefMut<MarkovState>,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        let degree = self.next_degree(&mut state);
+
+        let pitch = u8::try_from(N)
+            .ok()
+            .and_then(|n| self.key.get_scale(&self.scale_kind, BASE_OCTAVE, 1, n))
+            .and_then(|pitches| pitches.get(degree).copied())
+            .ok_or_else(|| {
+                ActionError::from_generation_error(PitchError::new(&self.key, &self.scale_kind))
+            })?;
+
+        state.degree = Some(degree);
+
+        Ok(notation::MusicalElement::note(
+            pitch,
+            notation::Duration::eighth(),
+            notation::M,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MarkovAction, MarkovState};
+    use crate::l_system::{Atom, Axiom};
+    use crate::musical_notation::{
+        Accidental, EqualTemperament, Key, MusicalElement, Note, ScaleKind, Temperament,
+        STUTTGART_PITCH,
+    };
+    use crate::voice::action::AtomType;
+    use crate::voice::Voice;
+
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    const STEPWISE: [[f64; 4]; 4] = [
+        [0.0, 1.0, 0.0, 0.0],
+        [1.0, 0.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0, 1.0],
+        [0.0, 0.0, 1.0, 0.0],
+    ];
+
+    /**
+     * The degrees of the C major scale starting at 1 the chain walks through for the axiom.
+     */
+    fn degrees<const N: usize>(axiom: &str, matrix: [[f64; N]; N], seed: u64) -> Vec<usize> {
+        let key = Key::new(
+            &Note::C,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        );
+        let scale = key.get_scale(&ScaleKind::Major, 4, 1, 8).unwrap();
+        let action = Arc::new(MarkovAction::new(key, &ScaleKind::Major, matrix, seed));
+
+        let axiom = Axiom::from(axiom).unwrap();
+        let atom_types: HashMap<&Atom, AtomType<MarkovState>> = axiom
+            .atoms()
+            .map(|atom| {
+                let atom_type = match atom.symbol.to_string().as_str() {
+                    "[" => AtomType::PushStack,
+                    "]" => AtomType::PopStack,
+                    _ => AtomType::HasAction {
+                        action: action.clone(),
+                    },
+                };
+                (atom, atom_type)
+            })
+            .collect();
+
+        Voice::from(&axiom, atom_types)
+            .unwrap()
+            .musical_elements()
+            .iter()
+            .map(|musical_element| match musical_element {
+                MusicalElement::Note { pitch, .. } => {
+                    scale.iter().position(|degree| degree == pitch).unwrap() + 1
+                }
+                _ => panic!("The chain emitted a rest."),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identity_matrix_test() {
+        let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        assert_eq!(degrees("AAAAA", identity, 7), vec![1; 5]);
+
+        // a row without a positive weight keeps the degree as well
+        assert_eq!(degrees("AAA", [[0.0; 2]; 2], 7), vec![1; 3]);
+    }
+
+    #[test]
+    fn seeded_sequence_test() {
+        let sequence = degrees("AAAAAAAA", STEPWISE, 42);
+        assert_eq!(sequence, vec![1, 2, 1, 2, 1, 2, 3, 2]);
+        assert_eq!(degrees("AAAAAAAA", STEPWISE, 42), sequence);
+        assert!(sequence
+            .windows(2)
+            .all(|pair| pair[0].abs_diff(pair[1]) == 1));
+        assert_ne!(degrees("AAAAAAAA", STEPWISE, 43), sequence);
+    }
+
+    #[test]
+    fn push_and_pop_test() {
+        // the chain continues from the degree saved before the brackets
+        let sequence = degrees("AA[AA]A", STEPWISE, 42);
+        assert_eq!(sequence.len(), 5);
+        assert_eq!(sequence[4].abs_diff(sequence[1]), 1);
+    }
+}
@@ -0,0 +1,207 @@
+use super::simple_action::error::MappingError;
+use super::{error::ActionError, Action, MusicalState, SimpleAction};
+use crate::l_system::Symbol;
+use crate::musical_notation as notation;
+
+use std::cell::RefMut;
+
+/**
+ * A ModulatingAction plays the symbols like its SimpleAction, but in the key of the
+ * MusicalState. The modulation symbol changes that key by the interval of the action,
+ * so the following notes are resolved against the new key. The state starts in the
+ * key of the SimpleAction.
+ */
+pub struct ModulatingAction<T: notation::Temperament> {
+    notes: SimpleAction<T>,
+    modulation_symbol: Symbol,
+    interval: notation::IntervalName,
+}
+
+impl<T: notation::Temperament> ModulatingAction<T> {
+    /**
+     * Create a ModulatingAction playing the notes with the SimpleAction, keeping its
+     * range, pitch ceiling and mapped symbols. Every modulation symbol moves the key
+     * up by the interval, e.g. a perfect fifth modulates to the dominant.
+     */
+    pub fn new(
+        notes: SimpleAction<T>,
+        modulation_symbol: impl Into<Symbol>,
+        interval: notation::IntervalName,
+    ) -> Self {
+        ModulatingAction {
+            notes,
+            modulation_symbol: modulation_symbol.into(),
+            interval,
+        }
+    }
+
+    fn current_key(&self, state: &MusicalState) -> notation::Key<T> {
+        let key = self.notes.key();
+        key.with_tonic(state.tonic.unwrap_or_else(|| key.tonic()))
+    }
+}
+
+/**
+ * The notes are shifted by the octave shift of the state and played with its volume.
+ * If the slide flag is set, the next note uses it up. A modulation adds no
+ * MusicalElement, so `gen_next_musical_element` doesn't map the modulation symbol.
+ */
+impl<T: notation::Temperament> Action<MusicalState> for ModulatingAction<T> {
+    fn gen_next_musical_element(
+        &self,
+        symbol: Symbol,
+        mut state: RefMut<MusicalState>,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        if symbol == self.modulation_symbol {
+            return Err(ActionError::from_generation_error(MappingError::new(
+                symbol,
+            )));
+        }
+
+        let mut musical_element = self.notes.gen_musical_element_in_key(
+            &self.current_key(&state),
+            symbol,
+            state.octave_shift,
+            state.volume,
+        )?;
+        state.use_up_slide(&mut musical_element);
+
+        Ok(musical_element)
+    }
+
+    fn gen_next_musical_elements(
+        &self,
+        symbol: Symbol,
+        mut state: RefMut<MusicalState>,
+    ) -> Result<Vec<notation::MusicalElement>, ActionError> {
+        if symbol == self.modulation_symbol {
+            let key = self
+                .current_key(&state)
+                .modulate(self.interval)
+                .map_err(ActionError::from_generation_error)?;
+            state.tonic = Some(key.tonic());
+            return Ok(vec![]);
+        }
+
+        Ok(vec![self.gen_next_musical_element(symbol, state)?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ModulatingAction;
+    use crate::l_system::Axiom;
+    use crate::musical_notation::{
+        Accidental, EqualTemperament, IntervalName, Key, MusicalElement, Note, Pitch, ScaleKind,
+        STUTTGART_PITCH,
+    };
+    use crate::voice::action::{Action, ActionState, AtomTypeMap, MusicalState, SimpleAction};
+    use crate::voice::Voice;
+
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn c_major() -> SimpleAction<EqualTemperament> {
+        let key = Key::equal_tempered(&Note::C, &Accidental::Natural, STUTTGART_PITCH);
+        SimpleAction::new(key, &ScaleKind::Major)
+    }
+
+    fn frequencies_with(notes: SimpleAction<EqualTemperament>, axiom: &str) -> Vec<String> {
+        let action: Arc<dyn Action<MusicalState> + Send + Sync> = Arc::new(ModulatingAction::new(
+            notes,
+            '^',
+            IntervalName::PerfectFifth,
+        ));
+
+        let axiom = Axiom::from(axiom).unwrap();
+        let atom_types = AtomTypeMap::new(&axiom).with_default_action(action).build();
+
+        Voice::from(&axiom, atom_types)
+            .unwrap()
+            .musical_elements()
+            .iter()
+            .map(|musical_element| match musical_element {
+                MusicalElement::Note { pitch, .. } => format!("{:.3}", pitch.get_hz()),
+                musical_element => format!("{:?}", musical_element),
+            })
+            .collect()
+    }
+
+    fn frequencies(axiom: &str) -> Vec<String> {
+        frequencies_with(c_major(), axiom)
+    }
+
+    #[test]
+    fn modulation_test() {
+        assert_eq!(
+            frequencies("ABCD^ABCD"),
+            vec![
+                "261.626", "293.665", "329.628", "349.228", // C D E F in C major
+                "391.995", "440.000", "493.883", "523.251", // G A B C in G major
+            ]
+        );
+
+        // modulating twice leads to D major, where the third degree is F#
+        assert_eq!(frequencies("^^C"), vec!["369.994"]);
+
+        // popping the stack returns to the key saved with it
+        assert_eq!(frequencies("[^A]A"), vec!["391.995", "261.626"]);
+
+        // the modulation adds nothing to the voice, x is still a rest
+        assert_eq!(
+            frequencies("^x^A"),
+            vec!["Rest { duration: Duration { num: 1, den: 8 } }", "293.665"]
+        );
+    }
+
+    #[test]
+    fn modulation_to_cb_test() {
+        // a perfect fourth above Gb is spelled as B and plays B4 as the first degree
+        let key = Key::equal_tempered(&Note::G, &Accidental::Flat, STUTTGART_PITCH);
+        let action = ModulatingAction::new(
+            SimpleAction::new(key, &ScaleKind::Major),
+            '^',
+            IntervalName::PerfectFourth,
+        );
+        let state = RefCell::new(MusicalState::get_neutral_state());
+
+        assert!(action
+            .gen_next_musical_elements('^'.into(), state.borrow_mut())
+            .unwrap()
+            .is_empty());
+        assert_eq!(state.borrow().tonic.unwrap().to_string(), "B");
+        match action.gen_next_musical_element('A'.into(), state.borrow_mut()) {
+            Ok(MusicalElement::Note { pitch, .. }) => {
+                assert_eq!(format!("{:.3}", pitch.get_hz()), "493.883")
+            }
+            musical_element => panic!("{:?}", musical_element),
+        }
+    }
+
+    #[test]
+    fn simple_action_settings_test() {
+        // A4 in G major is folded below the ceiling, G4 isn't
+        let mut notes = c_major();
+        notes.set_pitch_ceiling(Some(Pitch(400.0)));
+        assert_eq!(frequencies_with(notes, "^AB"), vec!["391.995", "220.000"]);
+
+        let mut notes = c_major();
+        notes.set_symbol_degrees(HashMap::from([('♩', 3)]));
+        assert_eq!(frequencies_with(notes, "♩^♩"), vec!["329.628", "493.883"]);
+    }
+
+    #[test]
+    fn single_element_test() {
+        let action = ModulatingAction::new(c_major(), '^', IntervalName::PerfectFifth);
+        let state = RefCell::new(MusicalState::get_neutral_state());
+
+        assert!(action
+            .gen_next_musical_element('^'.into(), state.borrow_mut())
+            .is_err());
+        assert_eq!(state.borrow().tonic, None);
+        assert!(action
+            .gen_next_musical_element('A'.into(), state.borrow_mut())
+            .is_ok());
+    }
+}
@@ -0,0 +1,157 @@
+use super::{error::ActionError, letter_number, Action, MusicalState, NeutralActionState};
+use crate::l_system::Symbol;
+use crate::musical_notation as notation;
+
+use std::cell::RefMut;
+
+const PARTIALS: usize = 49;
+
+/**
+ * An OvertoneAction maps the letters to the partials of the harmonic series of its
+ * fundamental instead of the degrees of a scale, which gives melodies built on the
+ * overtones independent of any key.
+ */
+pub struct OvertoneAction {
+    partials: Vec<notation::Pitch>,
+    rolloff: bool,
+}
+
+impl OvertoneAction {
+    /**
+     * Create an OvertoneAction mapping the letters A to Z and a to w in that order to the
+     * partials 1 to 49 of the fundamental, so A plays the fundamental itself, and x to an
+     * eighth rest.
+     */
+    pub fn new(fundamental: notation::Pitch) -> Self {
+        OvertoneAction {
+            partials: notation::harmonic_series(fundamental, PARTIALS),
+            rolloff: false,
+        }
+    }
+
+    /**
+     * Let the volume roll off with the partial number, so partial n
+     * is played with 1/n of the volume. The rolloff is disabled by default.
+     */
+    pub fn set_rolloff(&mut self, rolloff: bool) {
+        self.rolloff = rolloff;
+    }
+
+    fn gen_musical_element(
+        &self,
+        symbol: Symbol,
+        octave_shift: i16,
+        volume: notation::Volume,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        let partial = match letter_number(symbol)? {
+            Some(partial) => partial,
+            None => {
+                return Ok(notation::MusicalElement::Rest {
+                    duration: notation::Duration::eighth(),
+                })
+            }
+        };
+
+        let volume = match self.rolloff {
+            true => notation::Volume::new((volume.get() as f64 / partial as f64).round() as u8),
+            false => volume,
+        };
+
+        Ok(notation::MusicalElement::note(
+            self.partials[partial as usize - 1].shift_octaves(octave_shift),
+            notation::Duration::eighth(),
+            volume,
+        ))
+    }
+}
+
+impl Action<NeutralActionState> for OvertoneAction {
+    fn gen_next_musical_element(
+        &self,
+        symbol: Symbol,
+        _state: RefMut<NeutralActionState>,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        self.gen_musical_element(symbol, 0, notation::M)
+    }
+}
+
+/**
+ * With a MusicalState the partials are shifted by its octave shift and played with its
+ * volume, rolled off if enabled. If the slide flag is set, the next note uses it up.
+ */
+impl Action<MusicalState> for OvertoneAction {
+    fn gen_next_musical_element(
+        &self,
+        symbol: Symbol,
+        mut state: RefMut<MusicalState>,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        let mut musical_element =
+            self.gen_musical_element(symbol, state.octave_shift, state.volume)?;
+        state.use_up_slide(&mut musical_element);
+
+        Ok(musical_element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OvertoneAction;
+    use crate::l_system::Axiom;
+    use crate::musical_notation::{
+        EqualTemperament, MusicalElement, Pitch, Temperament, STUTTGART_PITCH,
+    };
+    use crate::voice::Voice;
+
+    use std::sync::Arc;
+
+    fn c2() -> Pitch {
+        EqualTemperament::new(STUTTGART_PITCH)
+            .get_pitch(2, 1)
+            .unwrap()
+    }
+
+    fn partials(action: OvertoneAction, axiom: &str) -> Vec<(String, u8)> {
+        Voice::from_axiom_with_default_action(&Axiom::from(axiom).unwrap(), Arc::new(action))
+            .unwrap()
+            .musical_elements()
+            .iter()
+            .filter_map(|musical_element| match musical_element {
+                MusicalElement::Note { pitch, volume, .. } => {
+                    Some((format!("{:.3}", pitch.get_hz()), volume.get()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn partials_test() {
+        let partials = partials(OvertoneAction::new(c2()), "ABCDEFGxH");
+
+        assert_eq!(
+            partials
+                .iter()
+                .map(|(hz, _)| hz.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "65.406", "130.813", "196.219", "261.626", "327.032", "392.438", "457.845",
+                "523.251"
+            ]
+        );
+        assert!(partials.iter().all(|(_, volume)| *volume == 140));
+    }
+
+    #[test]
+    fn rolloff_test() {
+        let mut action = OvertoneAction::new(c2());
+        action.set_rolloff(true);
+
+        assert_eq!(
+            partials(action, "ABCDEFGH")
+                .into_iter()
+                .map(|(_, volume)| volume)
+                .collect::<Vec<u8>>(),
+            vec![140, 70, 47, 35, 28, 23, 20, 18]
+        );
+    }
+}
@@ -1,57 +1,229 @@
-use super::{error::ActionError, Action, NeutralActionState};
+use super::{error::ActionError, Action, MusicalState, NeutralActionState};
+use crate::l_system::Symbol;
 use crate::musical_notation as notation;
 use std::cell::RefMut;
+use std::collections::HashMap;
 
 pub mod error;
 
+pub(super) const DEFAULT_BASE_OCTAVE: i16 = 4;
+const MAX_OCTAVE_SPAN: u8 = 7;
+const NUMBER_OF_LETTERS: u16 = 26 + 23;
+
 pub struct SimpleAction<T: notation::Temperament> {
     key: notation::Key<T>,
-    scale_kind: &'static notation::ScaleKind,
+    scale_kind: notation::ScaleKind,
+    base_octave: i16,
+    octave_span: u8,
+    pitch_ceiling: Option<notation::Pitch>,
+    symbol_degrees: HashMap<char, usize>,
+    volume: notation::Volume,
 }
 
 impl<T: notation::Temperament> SimpleAction<T> {
-    pub fn new(key: notation::Key<T>, scale_kind: &'static notation::ScaleKind) -> Self {
-        SimpleAction { key, scale_kind }
+    /**
+     * Create a SimpleAction mapping the letters to the seven octaves
+     * starting at octave 4. The notes are played with the volume M.
+     */
+    pub fn new(key: notation::Key<T>, scale_kind: &notation::ScaleKind) -> Self {
+        SimpleAction {
+            key,
+            scale_kind: scale_kind.clone(),
+            base_octave: DEFAULT_BASE_OCTAVE,
+            octave_span: MAX_OCTAVE_SPAN,
+            pitch_ceiling: None,
+            symbol_degrees: HashMap::new(),
+            volume: notation::M,
+        }
+    }
+
+    /**
+     * Create a SimpleAction mapping the first letters to the notes
+     * of octave_span consecutive octaves starting with
+     * the first degree in base_octave. All other letters except x are
+     * left unmapped. The octave_span has to be between 1 and 7.
+     */
+    pub fn new_with_range(
+        key: notation::Key<T>,
+        scale_kind: &notation::ScaleKind,
+        base_octave: i16,
+        octave_span: u8,
+    ) -> Result<Self, error::RangeError> {
+        if !(1..=MAX_OCTAVE_SPAN).contains(&octave_span) {
+            return Err(error::RangeError::new(octave_span, MAX_OCTAVE_SPAN));
+        }
+
+        Ok(SimpleAction {
+            key,
+            scale_kind: scale_kind.clone(),
+            base_octave,
+            octave_span,
+            pitch_ceiling: None,
+            symbol_degrees: HashMap::new(),
+            volume: notation::M,
+        })
+    }
+
+    /**
+     * Map single character symbols to the degrees of the scale, counting from 1 for
+     * the first degree in the base octave on through the following octaves, e.g. to
+     * map '♩' to 1 and '♪' to 2. The mapped symbols take precedence over the letters
+     * and may reach beyond the octave span. Mapping a symbol to 0 leaves it unmapped.
+     */
+    pub fn set_symbol_degrees(&mut self, symbol_degrees: HashMap<char, usize>) {
+        self.symbol_degrees = symbol_degrees
+            .into_iter()
+            .filter(|(_, degree)| *degree > 0)
+            .collect();
+    }
+
+    /**
+     * Set the volume of the notes, which is M by default. With a MusicalState
+     * the notes are played with the volume of the state instead.
+     */
+    pub fn set_volume(&mut self, volume: notation::Volume) {
+        self.volume = volume;
+    }
+
+    /**
+     * Set a frequency ceiling. Pitches above the ceiling are folded
+     * down by octaves until they are below it, which keeps high letters
+     * audible. Passing None disables the folding.
+     */
+    pub fn set_pitch_ceiling(&mut self, pitch_ceiling: Option<notation::Pitch>) {
+        self.pitch_ceiling = pitch_ceiling;
     }
 }
 
-impl<T: notation::Temperament> Action<NeutralActionState> for SimpleAction<T> {
-    fn gen_next_musical_element(
+impl<T: notation::Temperament> SimpleAction<T> {
+    /**
+     * Map the symbol to a MusicalElement. The pitch of a note is shifted by the
+     * given number of octaves before it is folded below the pitch ceiling.
+     */
+    fn gen_musical_element(
         &self,
-        symbol: char,
-        _state: RefMut<NeutralActionState>,
+        symbol: Symbol,
+        octave_shift: i16,
+        volume: notation::Volume,
     ) -> Result<notation::MusicalElement, ActionError> {
-        if let Some(pitches) = self.key.get_scale(self.scale_kind, 4, 1, 7 * 7) {
-            let char_pos = symbol as u16;
-            const CHAR_POS_CAP_A: u16 = 'A' as u16;
-            const CHAR_POS_CAP_Z: u16 = 'Z' as u16;
-            const CHAR_POS_LOW_A: u16 = 'a' as u16;
-            const CHAR_POS_LOW_W: u16 = 'w' as u16;
-            const CHAR_POS_LOW_X: u16 = 'x' as u16;
-
-            match char_pos {
-                CHAR_POS_LOW_X => Ok(notation::MusicalElement::Rest {
-                    duration: notation::Duration(1),
-                }),
-                CHAR_POS_CAP_A..=CHAR_POS_CAP_Z => Ok(notation::MusicalElement::Note {
-                    pitch: pitches[(char_pos - CHAR_POS_CAP_A) as usize],
-                    duration: notation::Duration(1),
-                    volume: notation::M,
-                }),
-                CHAR_POS_LOW_A..=CHAR_POS_LOW_W => Ok(notation::MusicalElement::Note {
-                    pitch: pitches[(26 + char_pos - CHAR_POS_LOW_A) as usize],
-                    duration: notation::Duration(1),
-                    volume: notation::M,
+        self.gen_musical_element_in_key(&self.key, symbol, octave_shift, volume)
+    }
+
+    pub(super) fn key(&self) -> &notation::Key<T> {
+        &self.key
+    }
+
+    /**
+     * Map the symbol to a MusicalElement like `gen_musical_element`,
+     * but to the degrees of the scale in the given key.
+     */
+    pub(super) fn gen_musical_element_in_key(
+        &self,
+        key: &notation::Key<T>,
+        symbol: Symbol,
+        octave_shift: i16,
+        volume: notation::Volume,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        let number_of_pitches = NUMBER_OF_LETTERS
+            .min(self.octave_span as u16 * self.scale_kind.get_degrees_in_scale() as u16)
+            as u8;
+
+        // only single characters stand for notes or rests
+        let char_pos = match symbol {
+            Symbol::Char(character) => character as u32,
+            Symbol::Token(_) => u32::MAX,
+        };
+        const CHAR_POS_CAP_A: u32 = 'A' as u32;
+        const CHAR_POS_CAP_Z: u32 = 'Z' as u32;
+        const CHAR_POS_LOW_A: u32 = 'a' as u32;
+        const CHAR_POS_LOW_W: u32 = 'w' as u32;
+        const CHAR_POS_LOW_X: u32 = 'x' as u32;
+
+        let mapped_degree = match symbol {
+            Symbol::Char(character) => self.symbol_degrees.get(&character),
+            Symbol::Token(_) => None,
+        };
+
+        // the mapped symbols may reach beyond the octave span
+        let (index, number_of_pitches) = match (mapped_degree, char_pos) {
+            (Some(degree), _) => match u8::try_from(*degree) {
+                Ok(degree) if degree > 0 => ((degree - 1) as usize, degree),
+                _ => {
+                    return Err(ActionError::from_generation_error(
+                        error::MappingError::new(symbol),
+                    ))
+                }
+            },
+            (None, CHAR_POS_LOW_X) => {
+                return Ok(notation::MusicalElement::Rest {
+                    duration: notation::Duration::eighth(),
+                })
+            }
+            (None, CHAR_POS_CAP_A..=CHAR_POS_CAP_Z) => {
+                ((char_pos - CHAR_POS_CAP_A) as usize, number_of_pitches)
+            }
+            (None, CHAR_POS_LOW_A..=CHAR_POS_LOW_W) => {
+                ((26 + char_pos - CHAR_POS_LOW_A) as usize, number_of_pitches)
+            }
+            (None, _) => {
+                return Err(ActionError::from_generation_error(
+                    error::MappingError::new(symbol),
+                ))
+            }
+        };
+
+        if let Some(pitches) =
+            key.get_scale(&self.scale_kind, self.base_octave, 1, number_of_pitches)
+        {
+            match pitches.get(index) {
+                Some(pitch) => Ok(notation::MusicalElement::Note {
+                    pitch: match self.pitch_ceiling {
+                        Some(pitch_ceiling) => {
+                            pitch.shift_octaves(octave_shift).fold_below(pitch_ceiling)
+                        }
+                        None => pitch.shift_octaves(octave_shift),
+                    },
+                    duration: notation::Duration::eighth(),
+                    volume,
+                    articulation: notation::Articulation::Normal,
                 }),
-                _ => Err(ActionError::from_generation_error(
-                    &error::MappingError::new(symbol),
+                None => Err(ActionError::from_generation_error(
+                    error::MappingError::new(symbol),
                 )),
             }
         } else {
-            Err(ActionError::from_generation_error(&error::PitchError::new(
-                &self.key,
+            Err(ActionError::from_generation_error(error::PitchError::new(
+                key,
                 &self.scale_kind,
             )))
         }
     }
 }
+
+impl<T: notation::Temperament> Action<NeutralActionState> for SimpleAction<T> {
+    fn gen_next_musical_element(
+        &self,
+        symbol: Symbol,
+        _state: RefMut<NeutralActionState>,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        self.gen_musical_element(symbol, 0, self.volume)
+    }
+}
+
+/**
+ * With a MusicalState the notes are shifted by its octave shift and played with its volume.
+ * If the slide flag is set, the next element uses it up and is a slide if it is a note.
+ */
+impl<T: notation::Temperament> Action<MusicalState> for SimpleAction<T> {
+    fn gen_next_musical_element(
+        &self,
+        symbol: Symbol,
+        mut state: RefMut<MusicalState>,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        let mut musical_element =
+            self.gen_musical_element(symbol, state.octave_shift, state.volume)?;
+        state.use_up_slide(&mut musical_element);
+
+        Ok(musical_element)
+    }
+}
@@ -1,16 +1,19 @@
 use std::error::Error;
 use std::fmt;
 
+use crate::l_system::Symbol;
 use crate::musical_notation::{Key, ScaleKind, Temperament};
 
 #[derive(Debug)]
 pub struct MappingError {
-    symbol: char,
+    symbol: Symbol,
 }
 
 impl MappingError {
-    pub fn new(symbol: char) -> Self {
-        MappingError { symbol }
+    pub fn new(symbol: impl Into<Symbol>) -> Self {
+        MappingError {
+            symbol: symbol.into(),
+        }
     }
 }
 
@@ -22,16 +25,43 @@ impl fmt::Display for MappingError {
 
 impl Error for MappingError {}
 
+#[derive(Debug)]
+pub struct RangeError {
+    octave_span: u8,
+    max_octave_span: u8,
+}
+
+impl RangeError {
+    pub fn new(octave_span: u8, max_octave_span: u8) -> Self {
+        RangeError {
+            octave_span,
+            max_octave_span,
+        }
+    }
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The octave span {} is not between 1 and {}.",
+            self.octave_span, self.max_octave_span
+        )
+    }
+}
+
+impl Error for RangeError {}
+
 pub struct PitchError {
     key_msg: String,
-    scale_kind: &'static ScaleKind,
+    scale_kind: ScaleKind,
 }
 
 impl PitchError {
-    pub fn new<T: Temperament>(key: &Key<T>, scale_kind: &'static ScaleKind) -> Self {
+    pub fn new<T: Temperament>(key: &Key<T>, scale_kind: &ScaleKind) -> Self {
         PitchError {
             key_msg: format!("{}", key),
-            scale_kind,
+            scale_kind: scale_kind.clone(),
         }
     }
 }
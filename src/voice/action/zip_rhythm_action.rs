@@ -0,0 +1,240 @@
+use super::{error::ActionError, Action, ActionState, NeutralActionState};
+use crate::l_system::{Axiom, Symbol};
+use crate::musical_notation as notation;
+
+use std::cell::{RefCell, RefMut};
+
+pub mod error;
+
+/**
+ * This is an ActionState holding the index of the next
+ * duration of the rhythm. The rhythm runs parallel to the
+ * generated elements, so pushing and popping leave the index alone.
+ */
+pub struct RhythmState {
+    pub index: usize,
+}
+
+impl ActionState for RhythmState {
+    fn get_neutral_state() -> RhythmState {
+        RhythmState { index: 0 }
+    }
+    fn push(&self) {}
+    fn pop(&mut self) -> Result<(), ActionError> {
+        Ok(())
+    }
+}
+
+pub struct ZipRhythmAction<A: Action<NeutralActionState>> {
+    pitch_action: A,
+    rhythm: Vec<u16>,
+}
+
+impl<A: Action<NeutralActionState>> ZipRhythmAction<A> {
+    /**
+     * Create a ZipRhythmAction taking the pitches from the pitch action
+     * and the durations from the rhythm. The rhythm is a whitespace
+     * separated list of positive numbers of time units, e.g. "1 2 1 2",
+     * where one time unit is an eighth note. It needs one duration for
+     * every symbol of the pitch axiom apart from the brackets.
+     */
+    pub fn new(
+        pitch_action: A,
+        pitch_axiom: &Axiom,
+        rhythm: &str,
+    ) -> Result<Self, error::RhythmError> {
+        let rhythm = rhythm
+            .split_whitespace()
+            .map(|time_units| match time_units.parse::<u16>() {
+                Ok(time_units) if time_units > 0 => Ok(time_units),
+                _ => Err(error::RhythmError::new(&format!(
+                    "'{}' is not a positive number of time units",
+                    time_units
+                ))),
+            })
+            .collect::<Result<Vec<u16>, error::RhythmError>>()?;
+
+        if rhythm.is_empty() {
+            return Err(error::RhythmError::new("the rhythm is empty"));
+        }
+
+        let pitch_symbols = pitch_axiom
+            .atoms()
+            .filter(|atom| !matches!(atom.symbol, Symbol::Char('[') | Symbol::Char(']')))
+            .count();
+        if rhythm.len() != pitch_symbols {
+            return Err(error::RhythmError::from_length_mismatch(
+                rhythm.len(),
+                pitch_symbols,
+            ));
+        }
+
+        Ok(ZipRhythmAction {
+            pitch_action,
+            rhythm,
+        })
+    }
+}
+
+/**
+ * The i-th generated element gets the i-th duration of the rhythm.
+ * TimedNotes keep their length in seconds but still use up a duration.
+ */
+impl<A: Action<NeutralActionState>> Action<RhythmState> for ZipRhythmAction<A> {
+    fn gen_next_musical_element(
+        &self,
+        symbol: Symbol,
+        mut state: RefMut<RhythmState>,
+    ) -> Result<notation::MusicalElement, ActionError> {
+        let time_units = match self.rhythm.get(state.index) {
+            Some(time_units) => *time_units,
+            None => {
+                return Err(ActionError::from_generation_error(error::RhythmError::new(
+                    &format!(
+                        "the rhythm has {} durations but the voice has more elements",
+                        self.rhythm.len()
+                    ),
+                )))
+            }
+        };
+        state.index += 1;
+
+        let neutral_state = RefCell::new(NeutralActionState::get_neutral_state());
+        let duration = notation::Duration::time_units(time_units);
+
+        Ok(
+            match self
+                .pitch_action
+                .gen_next_musical_element(symbol, neutral_state.borrow_mut())?
+            {
+                notation::MusicalElement::Rest { .. } => {
+                    notation::MusicalElement::Rest { duration }
+                }
+                notation::MusicalElement::Note {
+                    pitch,
+                    volume,
+                    articulation,
+                    ..
+                } => notation::MusicalElement::Note {
+                    pitch,
+                    duration,
+                    volume,
+                    articulation,
+                },
+                timed_note @ notation::MusicalElement::TimedNote { .. } => timed_note,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RhythmState, ZipRhythmAction};
+    use crate::l_system::{Atom, Axiom, Symbol};
+    use crate::musical_notation::{
+        Accidental, Duration, EqualTemperament, Key, Note, ScaleKind, Temperament, STUTTGART_PITCH,
+    };
+    use crate::voice::action::{AtomType, SimpleAction};
+    use crate::voice::Voice;
+
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    type RhythmAction = ZipRhythmAction<SimpleAction<EqualTemperament>>;
+
+    fn rhythm_action(pitch_axiom: &Axiom, rhythm: &str) -> crate::Result<RhythmAction> {
+        let key = Key::new(
+            &Note::C,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        );
+        Ok(ZipRhythmAction::new(
+            SimpleAction::new(key, &ScaleKind::Major),
+            pitch_axiom,
+            rhythm,
+        )?)
+    }
+
+    fn voice(axiom: &Axiom, action: RhythmAction) -> crate::Result<Voice> {
+        let action = Arc::new(action);
+        let atom_types: HashMap<&Atom, AtomType<RhythmState>> = axiom
+            .atoms()
+            .map(|atom| {
+                let atom_type = match atom.symbol {
+                    Symbol::Char('[') => AtomType::PushStack,
+                    Symbol::Char(']') => AtomType::PopStack,
+                    _ => AtomType::HasAction {
+                        action: action.clone(),
+                    },
+                };
+                (atom, atom_type)
+            })
+            .collect();
+
+        Voice::from(axiom, atom_types)
+    }
+
+    fn zip(axiom: &str, rhythm: &str) -> crate::Result<Voice> {
+        let axiom = Axiom::from(axiom).unwrap();
+        voice(&axiom, rhythm_action(&axiom, rhythm)?)
+    }
+
+    #[test]
+    fn zip_rhythm_test() {
+        let voice = zip("ABCD", "1 2 1 2").unwrap();
+
+        assert_eq!(
+            voice
+                .musical_elements
+                .iter()
+                .map(|element| element.get_duration())
+                .collect::<Vec<Duration>>(),
+            vec![
+                Duration::time_units(1),
+                Duration::time_units(2),
+                Duration::time_units(1),
+                Duration::time_units(2),
+            ]
+        );
+
+        // rests take their duration from the rhythm too
+        let voice = zip("AxA", "3 4 1").unwrap();
+        assert_eq!(
+            voice.musical_elements[1].get_duration(),
+            Duration::time_units(4)
+        );
+    }
+
+    #[test]
+    fn mismatched_lengths_test() {
+        for (axiom, symbols) in [("ABCDE", 5), ("ABC", 3), ("A[BC]DE", 5)] {
+            assert_eq!(
+                zip(axiom, "1 2 1 2").unwrap_err().to_string(),
+                format!(
+                    "Invalid rhythm: the rhythm has 4 durations \
+                     but the pitch axiom has {} symbols.",
+                    symbols
+                )
+            );
+        }
+        assert!(zip("A[BC]D", "1 2 1 2").is_ok());
+
+        // an action used for a longer axiom than its own runs out of durations
+        let action = rhythm_action(&Axiom::from("AB").unwrap(), "1 2").unwrap();
+        assert_eq!(
+            voice(&Axiom::from("ABC").unwrap(), action)
+                .unwrap_err()
+                .to_string(),
+            "GenerationError: Invalid rhythm: the rhythm has 2 durations \
+             but the voice has more elements (atom 'C' at index 2)."
+        );
+    }
+
+    #[test]
+    fn invalid_rhythm_test() {
+        let axiom = Axiom::from("AB").unwrap();
+        for rhythm in ["", "1 0", "1 a"] {
+            assert!(rhythm_action(&axiom, rhythm).is_err());
+        }
+    }
+}
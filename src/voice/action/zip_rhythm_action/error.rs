@@ -0,0 +1,36 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub struct RhythmError {
+    message: String,
+}
+
+impl RhythmError {
+    pub fn new(message: &str) -> Self {
+        RhythmError {
+            message: message.to_string(),
+        }
+    }
+
+    /**
+     * The rhythm holds a different number of durations
+     * than there are pitch symbols to pair them with.
+     */
+    pub fn from_length_mismatch(rhythm_length: usize, pitch_symbols: usize) -> Self {
+        RhythmError {
+            message: format!(
+                "the rhythm has {} durations but the pitch axiom has {} symbols",
+                rhythm_length, pitch_symbols
+            ),
+        }
+    }
+}
+
+impl fmt::Display for RhythmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid rhythm: {}.", self.message)
+    }
+}
+
+impl Error for RhythmError {}
@@ -0,0 +1,193 @@
+/* This module draws a Voice as a piano roll
+ * made of text, to inspect it in a terminal.
+ */
+
+use crate::musical_notation as notation;
+
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+const SEMITONES_IN_OCTAVE: i32 = 12;
+// A4 is the tenth semitone of the fourth octave
+const A4_SEMITONES_ABOVE_C0: i32 = 4 * SEMITONES_IN_OCTAVE + 9;
+const LABEL_WIDTH: usize = 4;
+// tolerance for rounding the start and stop times of notes to columns
+const EPSILON: f64 = 1e-9;
+
+/**
+ * Round the pitch to the nearest semitone of twelve-tone equal temperament with
+ * A4 at 440 Hz and count the semitones from C0 up to it.
+ */
+fn semitones_above_c0(pitch: notation::Pitch) -> i32 {
+    (SEMITONES_IN_OCTAVE as f64 * (pitch.get_hz() / notation::STUTTGART_PITCH).log2()).round()
+        as i32
+        + A4_SEMITONES_ABOVE_C0
+}
+
+fn semitone_label(semitones_above_c0: i32) -> String {
+    format!(
+        "{}{}",
+        PITCH_CLASS_NAMES[semitones_above_c0.rem_euclid(SEMITONES_IN_OCTAVE) as usize],
+        semitones_above_c0.div_euclid(SEMITONES_IN_OCTAVE)
+    )
+}
+
+impl super::Voice {
+    /**
+     * Draw the voice as a piano roll. Every row is a semitone, labeled with its name
+     * and octave, from the C below the lowest to the B above the highest note of the
+     * voice, the highest row on top. Pitches are rounded to the nearest semitone.
+     * Every column is a time step, width_chars columns make up one bar of 4/4 time and
+     * the first line numbers the bars. Notes are drawn as `#` for as long as they sound
+     * at the given tempo, everything else as `.`. Returns an empty string for a voice
+     * without notes.
+     */
+    pub fn to_grid(&self, bpm: u16, width_chars: usize) -> String {
+        let schedule = self.schedule(bpm);
+
+        let semitones: Vec<i32> = schedule
+            .iter()
+            .map(|(_, _, pitch, _)| semitones_above_c0(*pitch))
+            .collect();
+
+        let (lowest, highest) = match (semitones.iter().min(), semitones.iter().max()) {
+            (Some(lowest), Some(highest)) if width_chars > 0 => (*lowest, *highest),
+            _ => return String::new(),
+        };
+        let lowest = lowest - lowest.rem_euclid(SEMITONES_IN_OCTAVE);
+        let highest = highest - highest.rem_euclid(SEMITONES_IN_OCTAVE) + SEMITONES_IN_OCTAVE - 1;
+
+        let bar_seconds = notation::TimeSignature::new(4, 4)
+            .bar_duration()
+            .to_seconds(bpm, super::BEATS_PER_WHOLE);
+        let column_seconds = bar_seconds / width_chars as f64;
+        let bars = ((self.get_duration(bpm) / bar_seconds - EPSILON).ceil() as usize).max(1);
+        let columns = bars * width_chars;
+
+        let mut rows = vec![vec!['.'; columns]; (highest - lowest + 1) as usize];
+
+        for ((start, stop, _, _), semitone) in schedule.iter().zip(semitones) {
+            let first_column = (start / column_seconds + EPSILON).floor() as usize;
+            let last_column = ((stop / column_seconds - EPSILON).ceil() as usize)
+                .max(first_column + 1)
+                .min(columns);
+
+            for cell in
+                &mut rows[(highest - semitone) as usize][first_column.min(columns)..last_column]
+            {
+                *cell = '#';
+            }
+        }
+
+        let mut grid = " ".repeat(LABEL_WIDTH);
+        for bar in 1..=bars {
+            let mut bar_number = format!("{:<width$}", bar, width = width_chars);
+            bar_number.truncate(width_chars);
+            grid.push_str(&bar_number);
+        }
+        let mut grid = grid.trim_end().to_string();
+
+        for (index, row) in rows.iter().enumerate() {
+            grid.push_str(&format!(
+                "\n{:<width$}{}",
+                semitone_label(highest - index as i32),
+                row.iter().collect::<String>(),
+                width = LABEL_WIDTH
+            ));
+        }
+
+        grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::musical_notation::{Articulation, Duration, MusicalElement, Pitch, M};
+    use crate::voice::Voice;
+
+    fn note(hz: f64, duration: Duration) -> MusicalElement {
+        MusicalElement::Note {
+            pitch: Pitch(hz),
+            duration,
+            volume: M,
+            articulation: Articulation::Normal,
+        }
+    }
+
+    #[test]
+    fn single_note_test() {
+        let voice = Voice::from_musical_elements(vec![note(261.626, Duration::quarter())]);
+        let grid = voice.to_grid(120, 4);
+        let lines: Vec<&str> = grid.lines().collect();
+
+        assert_eq!(lines.len(), 13);
+        assert_eq!(lines[0], "    1");
+        assert_eq!(lines[1], "B4  ....");
+        assert_eq!(lines[12], "C4  #...");
+        assert!(lines[1..12].iter().all(|line| &line[4..] == "...."));
+    }
+
+    #[test]
+    fn melody_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(440.0, Duration::quarter()),
+            MusicalElement::rest(2),
+            // slightly out of tune, rounded to C#5
+            note(556.0, Duration::dotted(Duration::half())),
+            note(246.942, Duration::half()),
+        ]);
+
+        assert_eq!(
+            voice.to_grid(90, 8),
+            "    1       2\n\
+             B5  ................\n\
+             A#5 ................\n\
+             A5  ................\n\
+             G#5 ................\n\
+             G5  ................\n\
+             F#5 ................\n\
+             F5  ................\n\
+             E5  ................\n\
+             D#5 ................\n\
+             D5  ................\n\
+             C#5 ....######......\n\
+             C5  ................\n\
+             B4  ................\n\
+             A#4 ................\n\
+             A4  ##..............\n\
+             G#4 ................\n\
+             G4  ................\n\
+             F#4 ................\n\
+             F4  ................\n\
+             E4  ................\n\
+             D#4 ................\n\
+             D4  ................\n\
+             C#4 ................\n\
+             C4  ................\n\
+             B3  ..........####..\n\
+             A#3 ................\n\
+             A3  ................\n\
+             G#3 ................\n\
+             G3  ................\n\
+             F#3 ................\n\
+             F3  ................\n\
+             E3  ................\n\
+             D#3 ................\n\
+             D3  ................\n\
+             C#3 ................\n\
+             C3  ................"
+        );
+    }
+
+    #[test]
+    fn empty_grid_test() {
+        assert_eq!(
+            Voice::from_musical_elements(vec![MusicalElement::rest(2)]).to_grid(120, 4),
+            ""
+        );
+        assert_eq!(
+            Voice::from_musical_elements(vec![note(440.0, Duration::quarter())]).to_grid(120, 0),
+            ""
+        );
+    }
+}
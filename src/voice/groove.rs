@@ -0,0 +1,217 @@
+/* This module shifts the notes of a Voice
+ * slightly off the grid, e.g. to play
+ * it with a swing feel.
+ */
+
+use crate::musical_notation as notation;
+
+use std::borrow::Cow;
+
+/**
+ * The delays applied to successive notes of a Voice. The delays are given in steps
+ * of a time unit, of which there are steps_per_time_unit in one time unit. A positive
+ * delay makes a note start later, a negative delay earlier. If the pattern is shorter
+ * than the voice it is repeated.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroovePattern {
+    pub delays_in_time_units: Cow<'static, [i16]>,
+    pub steps_per_time_unit: u16,
+}
+
+impl GroovePattern {
+    /**
+     * The common swing feel for eighth notes: the second of every two eighth notes
+     * is delayed by a third of a time unit, so that the pairs are played long-short
+     * in a ratio of 2:1.
+     */
+    pub const SWING_8TH: GroovePattern = GroovePattern {
+        delays_in_time_units: Cow::Borrowed(&[0, 1]),
+        steps_per_time_unit: 3,
+    };
+
+    pub fn new(delays_in_time_units: Vec<i16>, steps_per_time_unit: u16) -> GroovePattern {
+        GroovePattern {
+            delays_in_time_units: Cow::Owned(delays_in_time_units),
+            steps_per_time_unit,
+        }
+    }
+
+    fn delay(&self, note_index: usize) -> Option<(notation::Duration, bool)> {
+        if self.delays_in_time_units.is_empty() || self.steps_per_time_unit == 0 {
+            return None;
+        }
+
+        let delay = self.delays_in_time_units[note_index % self.delays_in_time_units.len()];
+        let duration = notation::Duration::tuplet(
+            notation::Duration::time_units(delay.unsigned_abs()),
+            self.steps_per_time_unit as u32,
+            1,
+        );
+
+        Some((duration, delay < 0))
+    }
+}
+
+impl super::Voice {
+    /**
+     * Shift the start of every note by the delay of the groove pattern. A delayed note
+     * is shortened and the element before it extended, a note played early is extended
+     * and the element before it shortened, so that all following elements keep their
+     * start. A delay of the first note inserts a rest before it. Delays that would leave
+     * a note without duration are ignored, rests shortened to nothing are removed.
+     */
+    pub fn apply_groove(&self, groove: &GroovePattern) -> super::Voice {
+        let mut musical_elements: Vec<notation::MusicalElement> = vec![];
+        let mut note_index = 0;
+
+        for musical_element in &self.musical_elements {
+            let mut musical_element = *musical_element;
+
+            if let notation::MusicalElement::Note { duration, .. } = &mut musical_element {
+                if let Some((delay, early)) = groove.delay(note_index) {
+                    match (early, musical_elements.last_mut()) {
+                        _ if delay.is_zero() => {}
+                        (false, _) if delay >= *duration => {}
+                        (false, Some(previous)) => {
+                            set_duration(previous, previous.get_duration() + delay);
+                            *duration = *duration - delay;
+                        }
+                        (false, None) => {
+                            musical_elements
+                                .push(notation::MusicalElement::Rest { duration: delay });
+                            *duration = *duration - delay;
+                        }
+                        (true, Some(previous @ notation::MusicalElement::Rest { .. }))
+                            if delay <= previous.get_duration() =>
+                        {
+                            set_duration(previous, previous.get_duration() - delay);
+                            *duration = *duration + delay;
+
+                            if previous.get_duration().is_zero() {
+                                musical_elements.pop();
+                            }
+                        }
+                        (true, Some(previous)) if delay < previous.get_duration() => {
+                            set_duration(previous, previous.get_duration() - delay);
+                            *duration = *duration + delay;
+                        }
+                        (true, _) => {}
+                    }
+                }
+
+                note_index += 1;
+            }
+
+            musical_elements.push(musical_element);
+        }
+
+        super::Voice { musical_elements }
+    }
+}
+
+fn set_duration(musical_element: &mut notation::MusicalElement, new_duration: notation::Duration) {
+    match musical_element {
+        notation::MusicalElement::Rest { duration } => *duration = new_duration,
+        notation::MusicalElement::Note { duration, .. } => *duration = new_duration,
+        notation::MusicalElement::TimedNote { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GroovePattern;
+    use crate::musical_notation::{Articulation, Duration, MusicalElement, Pitch, M};
+    use crate::voice::Voice;
+
+    fn note(duration: Duration) -> MusicalElement {
+        MusicalElement::Note {
+            pitch: Pitch(440.0),
+            duration,
+            volume: M,
+            articulation: Articulation::Normal,
+        }
+    }
+
+    fn start_times(voice: &Voice) -> Vec<f64> {
+        voice
+            .schedule(60)
+            .iter()
+            .map(|(start, _, _, _)| *start)
+            .collect()
+    }
+
+    #[test]
+    fn swing_8th_test() {
+        let voice =
+            Voice::from_musical_elements((0..4).map(|_| note(Duration::eighth())).collect());
+        let swung = voice.apply_groove(&GroovePattern::SWING_8TH);
+
+        assert_eq!(swung.total_duration(), voice.total_duration());
+        assert_eq!(
+            swung
+                .musical_elements
+                .iter()
+                .map(|musical_element| musical_element.get_duration())
+                .collect::<Vec<Duration>>(),
+            vec![
+                Duration::new(1, 6),
+                Duration::new(1, 12),
+                Duration::new(1, 6),
+                Duration::new(1, 12)
+            ]
+        );
+    }
+
+    #[test]
+    fn swing_quarter_notes_test() {
+        // swinging quarter notes delays every second one by two thirds of a time unit
+        let voice =
+            Voice::from_musical_elements((0..4).map(|_| note(Duration::quarter())).collect());
+        let swung = voice.apply_groove(&GroovePattern::new(vec![0, 2], 3));
+
+        let starts = start_times(&swung);
+        let gaps: Vec<f64> = starts.windows(2).map(|pair| pair[1] - pair[0]).collect();
+
+        assert_eq!(gaps.len(), 3);
+        assert!((gaps[0] / gaps[1] - 2.0).abs() < 1e-9);
+        assert!((gaps[2] / gaps[1] - 2.0).abs() < 1e-9);
+        assert_eq!(swung.total_duration(), voice.total_duration());
+    }
+
+    #[test]
+    fn groove_with_rests_test() {
+        let voice = Voice::from_musical_elements(vec![
+            note(Duration::eighth()),
+            MusicalElement::rest(1),
+            note(Duration::eighth()),
+            note(Duration::quarter()),
+        ]);
+
+        // the second note moves into the rest, the third one is delayed
+        let grooved = voice.apply_groove(&GroovePattern::new(vec![0, -1, 1], 1));
+        assert_eq!(
+            grooved.musical_elements,
+            vec![
+                note(Duration::eighth()),
+                note(Duration::dotted(Duration::quarter())),
+                note(Duration::eighth()),
+            ]
+        );
+    }
+
+    #[test]
+    fn delayed_first_note_test() {
+        let voice = Voice::from_musical_elements(vec![note(Duration::quarter())]);
+        let grooved = voice.apply_groove(&GroovePattern::new(vec![1], 1));
+
+        assert_eq!(
+            grooved.musical_elements,
+            vec![MusicalElement::rest(1), note(Duration::eighth())]
+        );
+
+        // a delay as long as the note is ignored
+        let grooved = voice.apply_groove(&GroovePattern::new(vec![2], 1));
+        assert_eq!(grooved.musical_elements, voice.musical_elements);
+    }
+}
@@ -0,0 +1,254 @@
+/* This module reduces the notes of a Voice to
+ * the set of their pitch classes, to analyse
+ * which of the twelve semitones a voice uses.
+ */
+
+use crate::musical_notation as notation;
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+const PITCH_CLASSES: u8 = 12;
+const MIDI_REFERENCE_NOTE: f64 = 69.0;
+
+/**
+ * A set of the pitch classes 0 to 11 of twelve-tone equal temperament,
+ * where 0 is C, 1 is C# and 11 is B.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PitchClassSet {
+    pitch_classes: BTreeSet<u8>,
+}
+
+impl PitchClassSet {
+    /**
+     * Create the set of the given pitch classes, which are taken modulo 12.
+     */
+    pub fn new(pitch_classes: impl IntoIterator<Item = u8>) -> PitchClassSet {
+        PitchClassSet {
+            pitch_classes: pitch_classes
+                .into_iter()
+                .map(|pitch_class| pitch_class % PITCH_CLASSES)
+                .collect(),
+        }
+    }
+
+    /**
+     * Get the pitch class of the pitch, rounded to the nearest MIDI note
+     * with A4 at the given frequency.
+     */
+    pub fn pitch_class_of(pitch: notation::Pitch, a4_hz: f64) -> u8 {
+        let midi_note = (MIDI_REFERENCE_NOTE
+            + PITCH_CLASSES as f64 * (pitch.get_hz() / a4_hz).log2())
+        .round() as i64;
+        midi_note.rem_euclid(PITCH_CLASSES as i64) as u8
+    }
+
+    /**
+     * Get the pitch classes in ascending order.
+     */
+    pub fn pitch_classes(&self) -> Vec<u8> {
+        self.pitch_classes.iter().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pitch_classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pitch_classes.is_empty()
+    }
+
+    pub fn contains(&self, pitch_class: u8) -> bool {
+        self.pitch_classes.contains(&(pitch_class % PITCH_CLASSES))
+    }
+
+    /**
+     * Transpose every pitch class by the number of semitones, which may be negative.
+     */
+    pub fn transpose(&self, semitones: i16) -> PitchClassSet {
+        PitchClassSet::new(self.pitch_classes.iter().map(|pitch_class| {
+            (*pitch_class as i16 + semitones).rem_euclid(PITCH_CLASSES as i16) as u8
+        }))
+    }
+
+    /**
+     * Invert every pitch class around C, i.e. map the pitch class n to 12 - n.
+     */
+    pub fn invert(&self) -> PitchClassSet {
+        PitchClassSet::new(
+            self.pitch_classes
+                .iter()
+                .map(|pitch_class| (PITCH_CLASSES - pitch_class) % PITCH_CLASSES),
+        )
+    }
+
+    /**
+     * Get the normal form of the set, its most compact rotation in ascending order.
+     * Rotations spanning the same interval are compared by the intervals from their
+     * first pitch class to the second last, third last and so on, after Rahn.
+     * Of rotations that are still equal the one starting on the lowest pitch class is taken.
+     */
+    pub fn normal_form(&self) -> Vec<u8> {
+        let pitch_classes = self.pitch_classes();
+        let span_from_first = |rotation: &[u8]| -> Vec<u8> {
+            rotation
+                .iter()
+                .rev()
+                .map(|pitch_class| (pitch_class + PITCH_CLASSES - rotation[0]) % PITCH_CLASSES)
+                .collect()
+        };
+
+        (0..pitch_classes.len())
+            .map(|start| {
+                let mut rotation = pitch_classes.clone();
+                rotation.rotate_left(start);
+                rotation
+            })
+            .min_by_key(|rotation| (span_from_first(rotation), rotation[0]))
+            .unwrap_or_default()
+    }
+
+    /**
+     * Count the pairs of pitch classes of the set for each of the six interval
+     * classes, from the minor second up to the tritone.
+     */
+    pub fn interval_vector(&self) -> [usize; 6] {
+        let pitch_classes = self.pitch_classes();
+        let mut interval_vector = [0; 6];
+
+        for (index, low) in pitch_classes.iter().enumerate() {
+            for high in &pitch_classes[index + 1..] {
+                let interval = high - low;
+                let interval_class = interval.min(PITCH_CLASSES - interval);
+                interval_vector[interval_class as usize - 1] += 1;
+            }
+        }
+
+        interval_vector
+    }
+}
+
+/**
+ * Formats the set like `{0,2,4}`.
+ */
+impl fmt::Display for PitchClassSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pitch_classes: Vec<String> = self
+            .pitch_classes
+            .iter()
+            .map(|pitch_class| pitch_class.to_string())
+            .collect();
+        write!(f, "{{{}}}", pitch_classes.join(","))
+    }
+}
+
+/**
+ * Format the interval vector like `<254361>`, writing the counts above 9 as the hexadecimal
+ * digits A to C, so that every interval class takes up one digit.
+ */
+pub fn format_interval_vector(interval_vector: &[usize; 6]) -> String {
+    let digits: String = interval_vector
+        .iter()
+        .map(|count| format!("{:X}", count))
+        .collect();
+    format!("<{}>", digits)
+}
+
+impl super::Voice {
+    /**
+     * Collect the pitch classes of all notes of the voice, rounding every pitch to
+     * the nearest MIDI note with A4 at the given frequency.
+     */
+    pub fn pitch_class_set(&self, a4_hz: f64) -> PitchClassSet {
+        PitchClassSet {
+            pitch_classes: self
+                .musical_elements()
+                .iter()
+                .filter_map(|musical_element| match musical_element {
+                    notation::MusicalElement::Note { pitch, .. }
+                    | notation::MusicalElement::TimedNote { pitch, .. } => {
+                        Some(PitchClassSet::pitch_class_of(*pitch, a4_hz))
+                    }
+                    notation::MusicalElement::Rest { .. } => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_interval_vector, PitchClassSet};
+    use crate::l_system::Axiom;
+    use crate::musical_notation::{
+        Accidental, EqualTemperament, Key, Note, Pitch, ScaleKind, BAROQUE_PITCH, STUTTGART_PITCH,
+    };
+    use crate::voice::action::SimpleAction;
+    use crate::voice::Voice;
+
+    use std::sync::Arc;
+
+    fn major_scale(pitch_standard: f64) -> Voice {
+        let key = Key::equal_tempered(&Note::C, &Accidental::Natural, pitch_standard);
+        Voice::from_axiom_with_default_action(
+            &Axiom::from("ABCDEFGxH").unwrap(),
+            Arc::new(SimpleAction::<EqualTemperament>::new(
+                key,
+                &ScaleKind::Major,
+            )),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn major_scale_test() {
+        let set = major_scale(STUTTGART_PITCH).pitch_class_set(STUTTGART_PITCH);
+        assert_eq!(set.to_string(), "{0,2,4,5,7,9,11}");
+        assert_eq!(set.interval_vector(), [2, 5, 4, 3, 6, 1]);
+        assert_eq!(format_interval_vector(&set.interval_vector()), "<254361>");
+        assert_eq!(set.normal_form(), vec![11, 0, 2, 4, 5, 7, 9]);
+
+        // the pitch classes are relative to the pitch standard of the voice
+        assert_eq!(
+            major_scale(BAROQUE_PITCH).pitch_class_set(BAROQUE_PITCH),
+            set
+        );
+        assert_eq!(
+            major_scale(BAROQUE_PITCH).pitch_class_set(STUTTGART_PITCH),
+            set.transpose(-1)
+        );
+    }
+
+    #[test]
+    fn transpose_and_invert_test() {
+        let major_triad = PitchClassSet::new([0, 4, 7]);
+
+        assert_eq!(major_triad.transpose(2).pitch_classes(), vec![2, 6, 9]);
+        assert_eq!(major_triad.transpose(-1).pitch_classes(), vec![3, 6, 11]);
+        // the inversion of a major triad is a minor triad
+        assert_eq!(major_triad.invert().pitch_classes(), vec![0, 5, 8]);
+        assert_eq!(major_triad.invert().normal_form(), vec![5, 8, 0]);
+        assert_eq!(major_triad.normal_form(), vec![0, 4, 7]);
+        assert_eq!(major_triad.interval_vector(), [0, 0, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn normal_form_test() {
+        // of the equally compact rotations the one starting lowest is taken
+        assert_eq!(
+            PitchClassSet::new([0, 3, 6, 9]).normal_form(),
+            vec![0, 3, 6, 9]
+        );
+        assert_eq!(PitchClassSet::new([1, 5, 11]).normal_form(), vec![11, 1, 5]);
+        assert_eq!(PitchClassSet::new([]).normal_form(), Vec::<u8>::new());
+        assert_eq!(
+            PitchClassSet::pitch_class_of(Pitch(27.5), STUTTGART_PITCH),
+            9
+        );
+        assert_eq!(
+            format_interval_vector(&PitchClassSet::new(0..12).interval_vector()),
+            "<CCCCC6>"
+        );
+    }
+}
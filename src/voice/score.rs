@@ -0,0 +1,116 @@
+/* This module models a Score, the voices
+ * of a piece that sound together.
+ */
+
+use super::{with_context, NoteContext, Voice};
+
+use fundsp::audiounit::AudioUnit64;
+use fundsp::sequencer::Sequencer;
+
+/**
+ * A Score holds voices that start at the same time.
+ */
+pub struct Score {
+    voices: Vec<Voice>,
+}
+
+impl Score {
+    pub fn new(voices: Vec<Voice>) -> Score {
+        Score { voices }
+    }
+
+    pub fn voices(&self) -> &[Voice] {
+        &self.voices
+    }
+
+    /**
+     * Calculate the pan position of every voice, fanning them out evenly
+     * from left to right. Each voice sits in the middle of an equal share of
+     * the stereo field, which is narrowed by the spread: a spread of 1.0 places
+     * four voices at -0.75, -0.25, 0.25 and 0.75, a spread of 0.0 places all of
+     * them in the center. The positions are clamped to [-1, 1].
+     */
+    pub fn pan_positions(&self, spread: f64) -> Vec<f64> {
+        let number_of_voices = self.voices.len() as f64;
+
+        (0..self.voices.len())
+            .map(|index| {
+                let position = (2.0 * index as f64 + 1.0) / number_of_voices - 1.0;
+                (spread * position).clamp(-1.0, 1.0)
+            })
+            .collect()
+    }
+
+    /**
+     * Add every voice to the sequencer at the given tempo like `Voice::sequence`,
+     * passing its pan position of `pan_positions` along with every NoteContext.
+     */
+    pub fn sequence_spread<F>(
+        &self,
+        sequencer: &mut Sequencer,
+        bpm: u16,
+        spread: f64,
+        create_audio_unit: F,
+    ) where
+        F: Fn(&NoteContext, f64) -> Box<dyn AudioUnit64>,
+    {
+        for (voice, pan) in self.voices.iter().zip(self.pan_positions(spread)) {
+            voice.sequence_with(
+                sequencer,
+                bpm,
+                with_context(|context: &NoteContext| create_audio_unit(context, pan)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Score;
+    use crate::musical_notation::{Duration, MusicalElement, Pitch, M};
+    use crate::voice::Voice;
+
+    use fundsp::hacker::{pan, sine_hz, AudioUnit64};
+    use fundsp::sequencer::Sequencer;
+
+    use std::cell::RefCell;
+
+    fn score(number_of_voices: usize) -> Score {
+        Score::new(
+            (0..number_of_voices)
+                .map(|_| {
+                    Voice::from_musical_elements(vec![MusicalElement::note(
+                        Pitch(440.0),
+                        Duration::eighth(),
+                        M,
+                    )])
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn pan_positions_test() {
+        assert_eq!(score(3).pan_positions(1.5), vec![-1.0, 0.0, 1.0]);
+        assert_eq!(score(3).pan_positions(0.0), vec![0.0, 0.0, 0.0]);
+        assert_eq!(score(4).pan_positions(1.0), vec![-0.75, -0.25, 0.25, 0.75]);
+        assert_eq!(score(1).pan_positions(1.0), vec![0.0]);
+    }
+
+    #[test]
+    fn sequence_spread_test() {
+        let pans = RefCell::new(vec![]);
+        let mut sequencer = Sequencer::new(44100.0, 2);
+
+        score(3).sequence_spread(&mut sequencer, 120, 0.9, |context, position| {
+            pans.borrow_mut().push(position);
+            Box::new(sine_hz(context.pitch.get_hz()) >> pan(position)) as Box<dyn AudioUnit64>
+        });
+
+        let pans = pans.into_inner();
+        assert_eq!(pans.len(), 3);
+        assert!((pans[0] + 0.6).abs() < 1e-9);
+        assert_eq!(pans[1], 0.0);
+        assert!((pans[2] - 0.6).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,618 @@
+use music_generator::test_helpers::windowed_frequencies;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/* The WAV files are written as 16 bit stereo at 44100 Hz with a 44 byte header. */
+const BYTES_PER_SECOND: u64 = 44100 * 2 * 2;
+const HEADER_BYTES: u64 = 44;
+
+fn gen_path(file_name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/gen");
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(file_name)
+}
+
+fn seconds_of_wav(path: &Path) -> f64 {
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(&bytes[8..12], b"WAVE");
+    (bytes.len() as u64 - HEADER_BYTES) as f64 / BYTES_PER_SECOND as f64
+}
+
+#[test]
+fn render_voice_from_config_file() {
+    let output = gen_path("config_voice.wav");
+    let config = gen_path("config_voice.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "axiom = \"A\"\n\
+             output = {:?}\n\
+             scale_tonic = \"D\"\n\
+             scale_kind = \"minor\"\n\
+             volume_curve = \"exponential\"\n\
+             bpm = 240\n\
+             rules = [\"A->AB\", \"B->CA\"]\n\
+             iterations = 2\n\
+             unknown_key = 1\n",
+            output
+        ),
+    )
+    .unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("--config")
+        .arg(&config)
+        .output()
+        .unwrap();
+
+    assert!(result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr)
+        .contains("warning: unknown key 'unknown_key' in config file"));
+    // ABCA are four eighth notes, at 240 bpm each lasts a quarter second
+    assert_eq!(format!("{:.2}", seconds_of_wav(&output)), "1.00");
+}
+
+#[test]
+fn command_line_overrides_config_file() {
+    let output = gen_path("config_override.wav");
+    let config = gen_path("config_override.toml");
+    std::fs::write(&config, "axiom = \"ABCD\"\nbpm = 240\n").unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("--config")
+        .arg(&config)
+        .arg("--bpm")
+        .arg("120")
+        .arg("-o")
+        .arg(&output)
+        .output()
+        .unwrap();
+
+    assert!(result.status.success());
+    assert_eq!(format!("{:.2}", seconds_of_wav(&output)), "2.00");
+}
+
+#[test]
+fn invalid_config_file() {
+    let config = gen_path("config_invalid.toml");
+    std::fs::write(&config, "axiom = \"A\"\nbpm = \"fast\"\n").unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("--config")
+        .arg(&config)
+        .arg("-o")
+        .arg(gen_path("config_invalid.wav"))
+        .output()
+        .unwrap();
+
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("Invalid config file"));
+}
+
+#[test]
+fn render_voice_from_arguments() {
+    let output = gen_path("cli_voice.wav");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("ABCD")
+        .arg("--scale-tonic")
+        .arg("Eb")
+        .arg("--bpm")
+        .arg("240")
+        .arg("-o")
+        .arg(&output)
+        .output()
+        .unwrap();
+
+    assert!(result.status.success());
+    assert_eq!(format!("{:.2}", seconds_of_wav(&output)), "1.00");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn render_writes_metadata() {
+    let output = gen_path("cli_metadata.wav");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("A")
+        .arg("--rules")
+        .arg("A->AB")
+        .arg("--iterations")
+        .arg("3")
+        .arg("--scale-tonic")
+        .arg("G")
+        .arg("--scale-kind")
+        .arg("blues")
+        .arg("--bpm")
+        .arg("180")
+        .arg("-o")
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert!(result.status.success());
+
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(gen_path("cli_metadata.wav.json")).unwrap()).unwrap();
+    assert_eq!(
+        metadata,
+        serde_json::json!({
+            "axiom": "A",
+            "rules": ["A->AB"],
+            "iterations": 3,
+            "scale_tonic": "G",
+            "scale_kind": "blues",
+            "temperament_kind": "equal-temperament",
+            "pitch_standard": "stuttgart",
+            "bpm": 180,
+            "seed": null
+        })
+    );
+}
+
+#[test]
+fn unsupported_temperament() {
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("A")
+        .arg("--temperament-kind")
+        .arg("just-intonation")
+        .arg("-o")
+        .arg(gen_path("cli_just_intonation.wav"))
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(!result.status.success());
+    assert!(stderr.contains("The temperament 'just-intonation' is not supported yet."));
+    assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn expand_subcommand() {
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .args([
+            "expand",
+            "A",
+            "--rules",
+            "A->AB",
+            "--rules",
+            "B->A",
+            "--iterations",
+            "3",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(result.status.success());
+    assert_eq!(String::from_utf8_lossy(&result.stdout), "ABAAB\n");
+}
+
+#[test]
+fn expand_axiom_and_rules_files() {
+    let axiom_file = gen_path("expand_axiom.txt");
+    let rules_file = gen_path("expand_rules.txt");
+    std::fs::write(&axiom_file, "# a single note\nA\n").unwrap();
+    std::fs::write(&rules_file, "# grow\nA->AB\n\nB->A\n").unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .args(["expand", "--iterations", "3", "--axiom-file"])
+        .arg(&axiom_file)
+        .arg("--rules-file")
+        .arg(&rules_file)
+        .output()
+        .unwrap();
+
+    assert!(result.status.success(), "{:?}", result);
+    assert_eq!(String::from_utf8_lossy(&result.stdout), "ABAAB\n");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .args(["expand", "A", "--axiom-file"])
+        .arg(&axiom_file)
+        .output()
+        .unwrap();
+    assert!(!result.status.success());
+}
+
+#[test]
+fn global_options_before_subcommand() {
+    let output = gen_path("cli_render_subcommand.wav");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .args(["--bpm", "240", "render", "ABCD", "-o"])
+        .arg(&output)
+        .output()
+        .unwrap();
+
+    assert!(result.status.success());
+    assert_eq!(format!("{:.2}", seconds_of_wav(&output)), "1.00");
+}
+
+#[test]
+fn render_project_file() {
+    let output = gen_path("project.wav");
+    let project = gen_path("project.toml");
+    std::fs::write(
+        &project,
+        format!(
+            "[lsystem]\n\
+             axiom = \"A\"\n\
+             rules = [\"A->AB[C]\", \"B->A\"]\n\
+             iterations = 2\n\
+             \n\
+             [key]\n\
+             tonic = \"Bb\"\n\
+             scale = \"blues\"\n\
+             \n\
+             [voices.bass]\n\
+             mapping = {{ \"[\" = \"push\", \"]\" = \"pop\", \"C\" = \"none\" }}\n\
+             instrument = \"saw\"\n\
+             pan = -0.5\n\
+             gain = 0.5\n\
+             \n\
+             [voices.melody]\n\
+             mapping = {{ \"[\" = \"none\", \"]\" = \"none\" }}\n\
+             instrument = \"triangle\"\n\
+             pan = 0.5\n\
+             \n\
+             [render]\n\
+             bpm = 240\n\
+             output = {:?}\n",
+            output
+        ),
+    )
+    .unwrap();
+
+    // the project overrides all other parameters
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("--project")
+        .arg(&project)
+        .arg("--bpm")
+        .arg("60")
+        .arg("ABCDEFGH")
+        .output()
+        .unwrap();
+
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    // the melody plays AB[C]A[C] as five eighth notes, at 240 bpm each lasts a quarter second
+    assert_eq!(format!("{:.2}", seconds_of_wav(&output)), "1.25");
+}
+
+#[test]
+fn invalid_project_file() {
+    let project = gen_path("project_invalid.toml");
+    std::fs::write(
+        &project,
+        "[lsystem]\naxiom = \"A\"\n[voices.lead]\npan = 2.0\n",
+    )
+    .unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("--project")
+        .arg(&project)
+        .output()
+        .unwrap();
+
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr)
+        .contains("[voices.lead] pan: 2 is not between -1 and 1"));
+}
+
+#[test]
+fn render_batch_file() {
+    let batch_file = gen_path("batch.txt");
+    std::fs::write(&batch_file, "ABC\nA B\nCBA\nAACC\n").unwrap();
+
+    let output_pattern = gen_path("batch_{index}.wav");
+    for index in 1..=4 {
+        let _ = std::fs::remove_file(gen_path(&format!("batch_{}.wav", index)));
+    }
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("--batch-file")
+        .arg(&batch_file)
+        .arg("--output-pattern")
+        .arg(&output_pattern)
+        .arg("--rules")
+        .arg("A->AB")
+        .arg("--iterations")
+        .arg("1")
+        .output()
+        .unwrap();
+
+    // the invalid axiom fails the batch, but doesn't stop it
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("error: line 2 'A B'"));
+    assert!(String::from_utf8_lossy(&result.stdout).contains("Rendered 3 of 4 axioms, 1 failed."));
+
+    for index in [1, 3, 4] {
+        let path = gen_path(&format!("batch_{}.wav", index));
+        assert!(std::fs::metadata(&path).unwrap().len() > HEADER_BYTES);
+    }
+    assert!(!gen_path("batch_2.wav").exists());
+}
+
+#[test]
+fn batch_file_conflicts_with_output() {
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("--batch-file")
+        .arg(gen_path("batch.txt"))
+        .arg("-o")
+        .arg(gen_path("batch.wav"))
+        .output()
+        .unwrap();
+
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn export_with_mapping_file() {
+    let mapping = gen_path("mapping.toml");
+    std::fs::write(
+        &mapping,
+        "\"[\" = \"push\"\n\
+         \"]\" = \"pop\"\n\
+         \"+\" = \"octave_up\"\n\
+         \"(\" = \"louder\"\n\
+         \".\" = \"rest\"\n\
+         \" \" = \"none\"\n",
+    )
+    .unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("export")
+        .arg("A [+(A] .A")
+        .arg("--mapping")
+        .arg(&mapping)
+        .output()
+        .unwrap();
+
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&result.stdout),
+        "note 1/8 261.626 Hz m\n\
+         note 1/8 523.251 Hz mf\n\
+         rest 1/8\n\
+         note 1/8 261.626 Hz m\n"
+    );
+}
+
+#[test]
+fn unknown_action_in_mapping_file() {
+    let mapping = gen_path("mapping_invalid.toml");
+    std::fs::write(&mapping, "\"[\" = \"push\"\n\"+\" = \"up\"\n").unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .arg("export")
+        .arg("A+A")
+        .arg("--mapping")
+        .arg(&mapping)
+        .output()
+        .unwrap();
+
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("line 2: unknown action 'up'"), "{}", stderr);
+}
+
+/* The number of times the left channel changes its sign, which grows with the frequency of a single tone. */
+fn zero_crossings_of_wav(path: &Path) -> usize {
+    let bytes = std::fs::read(path).unwrap();
+    let samples: Vec<i16> = bytes[HEADER_BYTES as usize..]
+        .chunks_exact(4)
+        .map(|frame| i16::from_le_bytes([frame[0], frame[1]]))
+        .collect();
+
+    samples
+        .windows(2)
+        .filter(|pair| (pair[0] < 0) != (pair[1] < 0))
+        .count()
+}
+
+#[test]
+fn render_in_several_keys() {
+    let output = gen_path("keys.wav");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .args(["render", "A", "--keys", "C,D,Eb", "-o"])
+        .arg(&output)
+        .output()
+        .unwrap();
+
+    assert!(result.status.success());
+
+    let crossings: Vec<usize> = ["keys-C.wav", "keys-D.wav", "keys-Eb.wav"]
+        .iter()
+        .map(|file_name| zero_crossings_of_wav(&gen_path(file_name)))
+        .collect();
+    assert!(crossings[0] < crossings[1] && crossings[1] < crossings[2]);
+}
+
+#[test]
+fn render_transpositions() {
+    let output = gen_path("transposed.wav");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .args(["render", "AB", "--transpose-semitones", "-12,0,7", "-o"])
+        .arg(&output)
+        .output()
+        .unwrap();
+
+    assert!(result.status.success());
+
+    let crossings: Vec<usize> = ["transposed-12.wav", "transposed+0.wav", "transposed+7.wav"]
+        .iter()
+        .map(|file_name| zero_crossings_of_wav(&gen_path(file_name)))
+        .collect();
+    assert!(crossings[0] < crossings[1] && crossings[1] < crossings[2]);
+}
+
+#[test]
+fn render_with_echo() {
+    let output = gen_path("echo.wav");
+    let plain_output = gen_path("echo_plain.wav");
+
+    for (path, extra_args) in [
+        (&output, &["--echo", "8,-12,0.5"][..]),
+        (&plain_output, &[][..]),
+    ] {
+        let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+            .args(["render", "AB", "--bpm", "120", "-o"])
+            .arg(path)
+            .args(extra_args)
+            .output()
+            .unwrap();
+        assert!(result.status.success(), "{:?}", result);
+    }
+
+    // the echo starts a whole note, i.e. four seconds at 120 bpm, after the voice
+    let difference = seconds_of_wav(&output) - seconds_of_wav(&plain_output);
+    assert!((difference - 4.0).abs() < 0.01, "{}", difference);
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .args(["render", "AB", "--echo", "8,-12", "-o"])
+        .arg(gen_path("echo_invalid.wav"))
+        .output()
+        .unwrap();
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("'delay,semitones,volume scale'"));
+}
+
+#[test]
+fn warn_about_inaudible_notes() {
+    let output = gen_path("inaudible.wav");
+
+    // the pitch ceiling folds C4 down to about 8 Hz
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .args(["render", "AA", "--pitch-ceiling", "10", "-o"])
+        .arg(&output)
+        .output()
+        .unwrap();
+
+    assert!(result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr)
+        .contains("warning: the note at position 2 has the inaudible pitch 8.176 Hz"));
+}
+
+#[test]
+fn render_with_wavetable() {
+    let cycle = gen_path("wavetable_cycle.wav");
+    let mut wave = fundsp::hacker::Wave64::new(1, 44100.0);
+    wave.resize(200);
+    for index in 0..200 {
+        wave.set(
+            0,
+            index,
+            (index as f64 * std::f64::consts::TAU / 100.0).sin(),
+        );
+    }
+    wave.save_wav16(&cycle).unwrap();
+
+    let output = gen_path("wavetable.wav");
+    let plain_output = gen_path("wavetable_plain.wav");
+
+    for (output, wavetable) in [(&output, Some(&cycle)), (&plain_output, None)] {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_music_generator"));
+        command.args(["render", "AB", "-o"]).arg(output);
+        if let Some(wavetable) = wavetable {
+            command.arg("--wavetable").arg(wavetable);
+        }
+        assert!(command.output().unwrap().status.success());
+    }
+
+    // a sine cycle sounds like the sine wave itself
+    assert_eq!(seconds_of_wav(&output), seconds_of_wav(&plain_output));
+    let crossings = zero_crossings_of_wav(&output) as f64;
+    let plain_crossings = zero_crossings_of_wav(&plain_output) as f64;
+    assert!((crossings / plain_crossings - 1.0).abs() < 0.05);
+
+    let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+        .args(["render", "AB", "--wavetable"])
+        .arg(gen_path("missing_cycle.wav"))
+        .arg("-o")
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("Invalid wavetable"));
+}
+
+/* The frequencies of the left channel in consecutive windows of 50 ms,
+ * leaving out the first and the last 100 ms.
+ */
+fn windowed_frequencies_of_wav(path: &Path) -> Vec<f64> {
+    let bytes = std::fs::read(path).unwrap();
+    let samples: Vec<f64> = bytes[HEADER_BYTES as usize..]
+        .chunks_exact(4)
+        .map(|frame| i16::from_le_bytes([frame[0], frame[1]]) as f64)
+        .collect();
+
+    windowed_frequencies(&samples[4410..samples.len() - 4410], 44100.0, 2205)
+}
+
+#[test]
+fn render_with_vibrato_and_detune() {
+    let render = |output: &Path, args: &[&str]| {
+        // F is A4 in C major, at 60 bpm it lasts a second
+        let result = Command::new(env!("CARGO_BIN_EXE_music_generator"))
+            .args(["render", "F", "--bpm", "60", "-o"])
+            .arg(output)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            result.status.success(),
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+    };
+
+    let vibrato = gen_path("vibrato.wav");
+    render(&vibrato, &["--vibrato-hz", "4", "--vibrato-cents", "50"]);
+    let frequencies = windowed_frequencies_of_wav(&vibrato);
+    // 50 cents above and below 440 Hz are 452.9 Hz and 427.5 Hz
+    assert!(
+        frequencies.iter().any(|hz| *hz > 446.0),
+        "{:?}",
+        frequencies
+    );
+    assert!(
+        frequencies.iter().any(|hz| *hz < 434.0),
+        "{:?}",
+        frequencies
+    );
+
+    let steady = gen_path("no_vibrato.wav");
+    render(&steady, &["--vibrato-hz", "4", "--vibrato-cents", "0"]);
+    let frequencies = windowed_frequencies_of_wav(&steady);
+    assert!(
+        frequencies.iter().all(|hz| (hz - 440.0).abs() < 2.0),
+        "{:?}",
+        frequencies
+    );
+
+    // the detune is the same for the same seed
+    let detuned = [
+        gen_path("detune_1.wav"),
+        gen_path("detune_1_again.wav"),
+        gen_path("detune_2.wav"),
+    ];
+    render(&detuned[0], &["--detune-cents", "30", "--seed", "1"]);
+    render(&detuned[1], &["--detune-cents", "30", "--seed", "1"]);
+    render(&detuned[2], &["--detune-cents", "30", "--seed", "2"]);
+    let bytes: Vec<Vec<u8>> = detuned
+        .iter()
+        .map(|path| std::fs::read(path).unwrap())
+        .collect();
+    assert_eq!(bytes[0], bytes[1]);
+    assert_ne!(bytes[0], bytes[2]);
+}
@@ -1,34 +1,34 @@
-use music_generator::musical_notation::{
-    Accidental, Duration, EqualTemperament, Key, MusicalElement, Note, Pitch, ScaleKind,
-    Temperament, Volume, M, STUTTGART_PITCH,
-};
+use music_generator::musical_notation::*;
 
 use music_generator::voice::action::{Action, AtomType, NeutralActionState, SimpleAction};
 use music_generator::voice::Voice;
 
-use music_generator::l_system::{Atom, Axiom};
+use music_generator::error::MappingError;
+use music_generator::l_system::{Atom, Axiom, RuleSet};
+use music_generator::progress::{Progress, Stage};
+use music_generator::synthesis::render_with_progress;
+use music_generator::test_helpers::{note, rest, tone};
 
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::error::Error as _;
+use std::sync::Arc;
 
 use fundsp::hacker::*;
 
-fn mff(frequency: f64) -> MusicalElement {
-    MusicalElement::Note {
-        duration: Duration(1),
-        volume: M,
-        pitch: Pitch(frequency),
-    }
-}
+/* The expected pitches are calculated by the test helpers,
+ * the voices are compared with a tolerance of a hundredth of a cent.
+ */
+const CENTS_TOLERANCE: f64 = 0.01;
 
 fn sequence_helper(voice: Voice) {
     let sample_rate = 44100.0;
     let mut sequencer = Sequencer::new(sample_rate, 2);
 
-    let env = || envelope(|t| cos(t));
+    let env = || envelope(cos);
     let magic = |pitch: f64| 200.0_f64 * sine_hz(pitch) * env();
     let magic = |pitch: Pitch, volume: Volume| -> Box<dyn AudioUnit64> {
-        Box::new(volume.get() as f64 * magic(pitch.get_hz()) >> pan(0.0))
+        Box::new((volume.as_amplitude() * magic(pitch.get_hz())) >> pan(0.0))
     };
 
     let bpm = 120;
@@ -64,21 +64,20 @@ fn sequence_helper(voice: Voice) {
 
 #[test]
 fn voice_of_c_major_seven_octaves() {
-    let temp = Rc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
     let key = Key::new(&Note::C, &Accidental::Natural, temp);
     let axiom: Axiom = Axiom::from("AHOVcjqBIPWdkrCJQXelsDKRYfmtELSZgnuFMTahovGNUbipw").unwrap();
 
     let mut atom_types: HashMap<&Atom, AtomType<NeutralActionState>> = HashMap::new();
 
-    let action: Rc<dyn Action<_>> = Rc::new(SimpleAction::new(key, &ScaleKind::Major));
+    let action: Arc<dyn Action<_> + Send + Sync> =
+        Arc::new(SimpleAction::new(key, &ScaleKind::Major));
 
     for atom in axiom.atoms() {
         atom_types.insert(
             atom,
-            match atom.symbol {
-                _ => AtomType::HasAction {
-                    action: Rc::clone(&action),
-                },
+            AtomType::HasAction {
+                action: Arc::clone(&action),
             },
         );
     }
@@ -86,60 +85,62 @@ fn voice_of_c_major_seven_octaves() {
     let voice_actual = Voice::from(&axiom, atom_types).unwrap();
 
     let voice_expected = Voice::from_musical_elements(vec![
-        mff(261.626),   /*-9 C_4*/
-        mff(523.251),   /*3  C_5*/
-        mff(1046.502),  /*15 C_6*/
-        mff(2093.005),  /*27 C_7*/
-        mff(4186.009),  /*39 C_8*/
-        mff(8372.018),  /*51 C_9*/
-        mff(16744.036), /*63 C_10*/
-        mff(293.665),   /*-7 D_4*/
-        mff(587.330),   /*5  D_5*/
-        mff(1174.659),  /*17 D_6*/
-        mff(2349.318),  /*29 D_7*/
-        mff(4698.636),  /*41 D_8*/
-        mff(9397.273),  /*53 D_9*/
-        mff(18794.545), /*65 D_10*/
-        mff(329.628),   /*-5 E_4*/
-        mff(659.255),   /*7  E_5*/
-        mff(1318.51),   /*19 E_6*/
-        mff(2637.02),   /*31 E_7*/
-        mff(5274.041),  /*43 E_8*/
-        mff(10548.082), /*55 E_9*/
-        mff(21096.164), /*67 E_10*/
-        mff(349.228),   /*-4 F_4*/
-        mff(698.456),   /*8  F_5*/
-        mff(1396.913),  /*20 F_6*/
-        mff(2793.826),  /*32 F_7*/
-        mff(5587.652),  /*44 F_8*/
-        mff(11175.303), /*56 F_9*/
-        mff(22350.607), /*68 F_10*/
-        mff(391.995),   /*-2 G_4*/
-        mff(783.991),   /*10 G_5*/
-        mff(1567.982),  /*22 G_6*/
-        mff(3135.963),  /*34 G_7*/
-        mff(6271.927),  /*46 G_8*/
-        mff(12543.854), /*58 G_9*/
-        mff(25087.708), /*70 G_10*/
-        mff(440.000),   /*0  A_4*/
-        mff(880.000),   /*12 A_5*/
-        mff(1760.000),  /*24 A_6*/
-        mff(3520.000),  /*36 A_7*/
-        mff(7040.000),  /*48 A_8*/
-        mff(14080.000), /*60 A_9*/
-        mff(28160.000), /*72 A_10*/
-        mff(493.883),   /*2  B_4*/
-        mff(987.767),   /*14 B_5*/
-        mff(1975.533),  /*26 B_6*/
-        mff(3951.066),  /*38 B_7*/
-        mff(7902.133),  /*50 B_8*/
-        mff(15804.266), /*62 B_9*/
-        mff(31608.531), /*74 B_10*/
+        note(tone("C"), 4, Duration::eighth(), M),
+        note(tone("C"), 5, Duration::eighth(), M),
+        note(tone("C"), 6, Duration::eighth(), M),
+        note(tone("C"), 7, Duration::eighth(), M),
+        note(tone("C"), 8, Duration::eighth(), M),
+        note(tone("C"), 9, Duration::eighth(), M),
+        note(tone("C"), 10, Duration::eighth(), M),
+        note(tone("D"), 4, Duration::eighth(), M),
+        note(tone("D"), 5, Duration::eighth(), M),
+        note(tone("D"), 6, Duration::eighth(), M),
+        note(tone("D"), 7, Duration::eighth(), M),
+        note(tone("D"), 8, Duration::eighth(), M),
+        note(tone("D"), 9, Duration::eighth(), M),
+        note(tone("D"), 10, Duration::eighth(), M),
+        note(tone("E"), 4, Duration::eighth(), M),
+        note(tone("E"), 5, Duration::eighth(), M),
+        note(tone("E"), 6, Duration::eighth(), M),
+        note(tone("E"), 7, Duration::eighth(), M),
+        note(tone("E"), 8, Duration::eighth(), M),
+        note(tone("E"), 9, Duration::eighth(), M),
+        note(tone("E"), 10, Duration::eighth(), M),
+        note(tone("F"), 4, Duration::eighth(), M),
+        note(tone("F"), 5, Duration::eighth(), M),
+        note(tone("F"), 6, Duration::eighth(), M),
+        note(tone("F"), 7, Duration::eighth(), M),
+        note(tone("F"), 8, Duration::eighth(), M),
+        note(tone("F"), 9, Duration::eighth(), M),
+        note(tone("F"), 10, Duration::eighth(), M),
+        note(tone("G"), 4, Duration::eighth(), M),
+        note(tone("G"), 5, Duration::eighth(), M),
+        note(tone("G"), 6, Duration::eighth(), M),
+        note(tone("G"), 7, Duration::eighth(), M),
+        note(tone("G"), 8, Duration::eighth(), M),
+        note(tone("G"), 9, Duration::eighth(), M),
+        note(tone("G"), 10, Duration::eighth(), M),
+        note(tone("A"), 4, Duration::eighth(), M),
+        note(tone("A"), 5, Duration::eighth(), M),
+        note(tone("A"), 6, Duration::eighth(), M),
+        note(tone("A"), 7, Duration::eighth(), M),
+        note(tone("A"), 8, Duration::eighth(), M),
+        note(tone("A"), 9, Duration::eighth(), M),
+        note(tone("A"), 10, Duration::eighth(), M),
+        note(tone("B"), 4, Duration::eighth(), M),
+        note(tone("B"), 5, Duration::eighth(), M),
+        note(tone("B"), 6, Duration::eighth(), M),
+        note(tone("B"), 7, Duration::eighth(), M),
+        note(tone("B"), 8, Duration::eighth(), M),
+        note(tone("B"), 9, Duration::eighth(), M),
+        note(tone("B"), 10, Duration::eighth(), M),
     ]);
 
-    assert_eq!(
-        format!("{:.3?}", voice_actual),
-        format!("{:.3?}", voice_expected)
+    assert!(
+        voice_actual.approx_eq(&voice_expected, CENTS_TOLERANCE),
+        "{:.3?} != {:.3?}",
+        voice_actual,
+        voice_expected
     );
 
     sequence_helper(voice_actual);
@@ -147,21 +148,20 @@ fn voice_of_c_major_seven_octaves() {
 
 #[test]
 fn voice_of_d_flat_major_two_octave_scale() {
-    let temp = Rc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
     let key = Key::new(&Note::C, &Accidental::Sharp, temp);
     let axiom: Axiom = Axiom::from("ABCDEFGHIJKLMNO").unwrap();
 
     let mut atom_types: HashMap<&Atom, AtomType<NeutralActionState>> = HashMap::new();
 
-    let action: Rc<dyn Action<_>> = Rc::new(SimpleAction::new(key, &ScaleKind::Major));
+    let action: Arc<dyn Action<_> + Send + Sync> =
+        Arc::new(SimpleAction::new(key, &ScaleKind::Major));
 
     for atom in axiom.atoms() {
         atom_types.insert(
             atom,
-            match atom.symbol {
-                _ => AtomType::HasAction {
-                    action: Rc::clone(&action),
-                },
+            AtomType::HasAction {
+                action: Arc::clone(&action),
             },
         );
     }
@@ -169,27 +169,555 @@ fn voice_of_d_flat_major_two_octave_scale() {
     let voice_actual = Voice::from(&axiom, atom_types).unwrap();
 
     let voice_expected = Voice::from_musical_elements(vec![
-        mff(277.183),  /*(+0=-8) Db_4*/
-        mff(311.127),  /*(+2=-6) Eb_4*/
-        mff(349.228),  /*(+2=-4) F_4*/
-        mff(369.994),  /*(+1=-3) Gb_4*/
-        mff(415.305),  /*(+2=-1) Ab_4*/
-        mff(466.164),  /*(+2=1) Bb_4*/
-        mff(523.251),  /*(+2=3) C_5*/
-        mff(554.365),  /*(+1=4) Db_5*/
-        mff(622.254),  /*(+2=6) Eb_5*/
-        mff(698.456),  /*(+2=8) F_5*/
-        mff(739.989),  /*(+1=9) Gb_5*/
-        mff(830.609),  /*(+2=11) Ab_5*/
-        mff(932.328),  /*(+2=13) Bb_5*/
-        mff(1046.502), /*(+2=15) C_6*/
-        mff(1108.731), /*(+1=16) Db_6*/
+        note(tone("Db"), 4, Duration::eighth(), M),
+        note(tone("Eb"), 4, Duration::eighth(), M),
+        note(tone("F"), 4, Duration::eighth(), M),
+        note(tone("Gb"), 4, Duration::eighth(), M),
+        note(tone("Ab"), 4, Duration::eighth(), M),
+        note(tone("Bb"), 4, Duration::eighth(), M),
+        note(tone("C"), 5, Duration::eighth(), M),
+        note(tone("Db"), 5, Duration::eighth(), M),
+        note(tone("Eb"), 5, Duration::eighth(), M),
+        note(tone("F"), 5, Duration::eighth(), M),
+        note(tone("Gb"), 5, Duration::eighth(), M),
+        note(tone("Ab"), 5, Duration::eighth(), M),
+        note(tone("Bb"), 5, Duration::eighth(), M),
+        note(tone("C"), 6, Duration::eighth(), M),
+        note(tone("Db"), 6, Duration::eighth(), M),
     ]);
 
-    assert_eq!(
-        format!("{:.3?}", voice_actual),
-        format!("{:.3?}", voice_expected)
+    assert!(
+        voice_actual.approx_eq(&voice_expected, CENTS_TOLERANCE),
+        "{:.3?} != {:.3?}",
+        voice_actual,
+        voice_expected
     );
 
     sequence_helper(voice_actual);
 }
+
+#[test]
+fn voice_of_c_major_with_octave_range() {
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let key = Key::new(&Note::C, &Accidental::Natural, temp);
+    let axiom: Axiom = Axiom::from("AGHN").unwrap();
+
+    let mut atom_types: HashMap<&Atom, AtomType<NeutralActionState>> = HashMap::new();
+
+    let action: Arc<dyn Action<_> + Send + Sync> =
+        Arc::new(SimpleAction::new_with_range(key, &ScaleKind::Major, 3, 2).unwrap());
+
+    for atom in axiom.atoms() {
+        atom_types.insert(
+            atom,
+            AtomType::HasAction {
+                action: Arc::clone(&action),
+            },
+        );
+    }
+
+    let voice_actual = Voice::from(&axiom, atom_types).unwrap();
+
+    let voice_expected = Voice::from_musical_elements(vec![
+        note(tone("C"), 3, Duration::eighth(), M),
+        note(tone("B"), 3, Duration::eighth(), M),
+        note(tone("C"), 4, Duration::eighth(), M),
+        note(tone("B"), 4, Duration::eighth(), M),
+    ]);
+
+    assert!(
+        voice_actual.approx_eq(&voice_expected, CENTS_TOLERANCE),
+        "{:.3?} != {:.3?}",
+        voice_actual,
+        voice_expected
+    );
+}
+
+#[test]
+fn voice_with_letter_outside_of_octave_range() {
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let key = Key::new(&Note::C, &Accidental::Natural, temp);
+    let axiom: Axiom = Axiom::from("AGH").unwrap();
+
+    let mut atom_types: HashMap<&Atom, AtomType<NeutralActionState>> = HashMap::new();
+
+    let action: Arc<dyn Action<_> + Send + Sync> =
+        Arc::new(SimpleAction::new_with_range(key, &ScaleKind::Major, 4, 1).unwrap());
+
+    for atom in axiom.atoms() {
+        atom_types.insert(
+            atom,
+            AtomType::HasAction {
+                action: Arc::clone(&action),
+            },
+        );
+    }
+
+    match Voice::from(&axiom, atom_types) {
+        Err(e) => {
+            assert_eq!(
+                format!("{}", e),
+                "GenerationError: Unexpected symbol: 'H' (atom 'H' at index 2)."
+            );
+            assert!(matches!(e, music_generator::Error::Action(_)));
+
+            let source = e.source().expect("The MappingError is kept as source.");
+            assert!(source.is::<MappingError>());
+            assert_eq!(format!("{}", source), "Unexpected symbol: 'H'.");
+        }
+        Ok(_) => panic!("Mapped a letter outside of the octave range."),
+    }
+}
+
+#[test]
+fn voice_of_unicode_symbols_mapped_to_degrees() {
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let key = Key::new(&Note::C, &Accidental::Natural, temp);
+    let mut action = SimpleAction::new_with_range(key, &ScaleKind::Major, 4, 1).unwrap();
+    action.set_symbol_degrees(HashMap::from([
+        ('♩', 1),
+        ('♪', 2),
+        ('♫', 3),
+        ('♬', 4),
+        ('♭', 9),
+    ]));
+    let action: Arc<dyn Action<NeutralActionState> + Send + Sync> = Arc::new(action);
+
+    let voice =
+        Voice::from_axiom_with_default_action(&Axiom::from("♩♪♫♬").unwrap(), Arc::clone(&action))
+            .unwrap();
+    let voice_expected = Voice::from_musical_elements(vec![
+        note(tone("C"), 4, Duration::eighth(), M),
+        note(tone("D"), 4, Duration::eighth(), M),
+        note(tone("E"), 4, Duration::eighth(), M),
+        note(tone("F"), 4, Duration::eighth(), M),
+    ]);
+    assert!(
+        voice.approx_eq(&voice_expected, CENTS_TOLERANCE),
+        "{:.3?} != {:.3?}",
+        voice,
+        voice_expected
+    );
+
+    // mapped symbols reach beyond the octave span, the letters still work
+    let voice = Voice::from_axiom_with_default_action(&Axiom::from("♭A").unwrap(), action).unwrap();
+    let voice_expected = Voice::from_musical_elements(vec![
+        note(tone("D"), 5, Duration::eighth(), M),
+        note(tone("C"), 4, Duration::eighth(), M),
+    ]);
+    assert!(voice.approx_eq(&voice_expected, CENTS_TOLERANCE));
+}
+
+#[test]
+fn symbol_mapped_to_degree_zero_stays_unmapped() {
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let key = Key::new(&Note::C, &Accidental::Natural, temp);
+    let mut action = SimpleAction::new_with_range(key, &ScaleKind::Major, 4, 1).unwrap();
+    action.set_symbol_degrees(HashMap::from([('B', 0), ('♩', 0)]));
+    let action: Arc<dyn Action<NeutralActionState> + Send + Sync> = Arc::new(action);
+
+    // a letter mapped to 0 keeps the note of the letter
+    let voice =
+        Voice::from_axiom_with_default_action(&Axiom::from("B").unwrap(), Arc::clone(&action))
+            .unwrap();
+    let voice_expected =
+        Voice::from_musical_elements(vec![note(tone("D"), 4, Duration::eighth(), M)]);
+    assert!(voice.approx_eq(&voice_expected, CENTS_TOLERANCE));
+
+    // any other symbol mapped to 0 is as unexpected as if it wasn't mapped at all
+    match Voice::from_axiom_with_default_action(&Axiom::from("♩").unwrap(), action) {
+        Err(error) => {
+            let source = error.source().unwrap();
+            assert!(source.is::<MappingError>());
+        }
+        Ok(_) => panic!("Mapped a symbol mapped to 0."),
+    }
+}
+
+#[test]
+fn voice_from_note_list() {
+    let voice = Voice::from_musical_elements(vec![
+        MusicalElement::note(Pitch(440.0), Duration::quarter(), M),
+        MusicalElement::rest(2),
+        MusicalElement::note(Pitch(220.0), Duration::half(), Volume::percent(0.5)),
+    ]);
+
+    assert_eq!(voice.total_duration(), Duration::new(1, 1));
+    assert_eq!(
+        voice.musical_elements()[2],
+        MusicalElement::Note {
+            pitch: Pitch(220.0),
+            duration: Duration::half(),
+            volume: Volume::new(126),
+            articulation: Articulation::Normal,
+        }
+    );
+}
+
+#[test]
+fn voice_from_note_specs() {
+    let note_specs: Vec<NoteSpec> = vec![
+        NoteSpec {
+            pitch: Pitch(440.0),
+            duration: Duration::quarter(),
+            volume: M,
+        },
+        NoteSpec {
+            pitch: Pitch(220.0),
+            duration: Duration::half(),
+            volume: P,
+        },
+    ];
+
+    let voice =
+        Voice::from_musical_elements(note_specs.into_iter().map(MusicalElement::from).collect());
+
+    assert_eq!(voice.get_note_count(), 2);
+    assert_eq!(voice.total_duration(), Duration::new(3, 4));
+    assert_eq!(
+        voice.musical_elements()[1],
+        MusicalElement::Note {
+            pitch: Pitch(220.0),
+            duration: Duration::half(),
+            volume: P,
+            articulation: Articulation::Normal,
+        }
+    );
+}
+
+#[test]
+fn simple_action_with_invalid_octave_span() {
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+
+    for octave_span in [0, 8] {
+        let key = Key::new(&Note::C, &Accidental::Natural, Arc::clone(&temp));
+        match SimpleAction::new_with_range(key, &ScaleKind::Major, 4, octave_span) {
+            Err(e) => assert_eq!(
+                format!("{}", e),
+                format!("The octave span {} is not between 1 and 7.", octave_span)
+            ),
+            Ok(_) => panic!("Created a SimpleAction with an invalid octave span."),
+        }
+    }
+}
+
+#[test]
+fn voice_with_pitches_folded_below_ceiling() {
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let key = Key::new(&Note::C, &Accidental::Natural, temp);
+    let axiom: Axiom = Axiom::from("Aqjx").unwrap();
+
+    let mut atom_types: HashMap<&Atom, AtomType<NeutralActionState>> = HashMap::new();
+
+    let mut simple_action = SimpleAction::new(key, &ScaleKind::Major);
+    simple_action.set_pitch_ceiling(Some(Pitch(10000.0)));
+    let action: Arc<dyn Action<_> + Send + Sync> = Arc::new(simple_action);
+
+    for atom in axiom.atoms() {
+        atom_types.insert(
+            atom,
+            AtomType::HasAction {
+                action: Arc::clone(&action),
+            },
+        );
+    }
+
+    let voice_actual = Voice::from(&axiom, atom_types).unwrap();
+
+    let voice_expected = Voice::from_musical_elements(vec![
+        note(tone("C"), 4, Duration::eighth(), M),
+        note(tone("C"), 9, Duration::eighth(), M), // folded from C_10
+        note(tone("C"), 9, Duration::eighth(), M),
+        rest(Duration::eighth()),
+    ]);
+
+    assert!(
+        voice_actual.approx_eq(&voice_expected, CENTS_TOLERANCE),
+        "{:.3?} != {:.3?}",
+        voice_actual,
+        voice_expected
+    );
+}
+
+#[test]
+fn voice_with_volume() {
+    let key = Key::equal_tempered(&Note::C, &Accidental::Natural, STUTTGART_PITCH);
+    let mut simple_action = SimpleAction::new(key, &ScaleKind::Major);
+    simple_action.set_volume(P);
+
+    let axiom = Axiom::from("ACxE").unwrap();
+    let voice = Voice::from_axiom_with_default_action(&axiom, Arc::new(simple_action)).unwrap();
+
+    assert_eq!(voice.get_note_count(), 3);
+    for musical_element in voice.musical_elements() {
+        match musical_element {
+            MusicalElement::Note { volume, .. } => assert_eq!(*volume, P),
+            MusicalElement::Rest { .. } => {}
+            musical_element => panic!("Unexpected element {:?}", musical_element),
+        }
+    }
+}
+
+#[test]
+fn voice_of_c_blues_scale_one_octave() {
+    let blues = ScaleKind::custom([3, 2, 1, 1, 3, 2]).unwrap();
+
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let key = Key::new(&Note::C, &Accidental::Natural, temp);
+    let axiom: Axiom = Axiom::from("ABCDEF").unwrap();
+
+    let mut atom_types: HashMap<&Atom, AtomType<NeutralActionState>> = HashMap::new();
+
+    let action: Arc<dyn Action<_> + Send + Sync> =
+        Arc::new(SimpleAction::new_with_range(key, &blues, 4, 1).unwrap());
+
+    for atom in axiom.atoms() {
+        atom_types.insert(
+            atom,
+            AtomType::HasAction {
+                action: Arc::clone(&action),
+            },
+        );
+    }
+
+    let voice_actual = Voice::from(&axiom, atom_types).unwrap();
+
+    let voice_expected = Voice::from_musical_elements(vec![
+        note(tone("C"), 4, Duration::eighth(), M),
+        note(tone("Eb"), 4, Duration::eighth(), M),
+        note(tone("F"), 4, Duration::eighth(), M),
+        note(tone("Gb"), 4, Duration::eighth(), M),
+        note(tone("G"), 4, Duration::eighth(), M),
+        note(tone("Bb"), 4, Duration::eighth(), M),
+    ]);
+
+    assert!(
+        voice_actual.approx_eq(&voice_expected, CENTS_TOLERANCE),
+        "{:.3?} != {:.3?}",
+        voice_actual,
+        voice_expected
+    );
+}
+
+fn atom_types_for<'a>(
+    axiom: &'a Axiom,
+    action: &Arc<dyn Action<NeutralActionState> + Send + Sync>,
+) -> HashMap<&'a Atom, AtomType<NeutralActionState>> {
+    let mut atom_types: HashMap<&Atom, AtomType<NeutralActionState>> = HashMap::new();
+
+    for atom in axiom.atoms() {
+        atom_types.insert(
+            atom,
+            AtomType::HasAction {
+                action: Arc::clone(action),
+            },
+        );
+    }
+
+    atom_types
+}
+
+#[test]
+fn voices_built_in_parallel() {
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let ruleset: RuleSet = "A->ABC\nB->CAx\nC->BD".parse().unwrap();
+    let axioms: Vec<Axiom> = ["A", "B", "C", "AB", "BC", "CA", "ABC", "CBA"]
+        .iter()
+        .map(|axiom| ruleset.expand(&Axiom::from(axiom).unwrap(), 8))
+        .collect();
+
+    let action: Arc<dyn Action<_> + Send + Sync> = Arc::new(SimpleAction::new(
+        Key::new(&Note::E, &Accidental::Flat, temp),
+        &ScaleKind::Minor,
+    ));
+
+    let voices_parallel =
+        Voice::from_parallel(&axioms, |axiom| atom_types_for(axiom, &action)).unwrap();
+
+    assert_eq!(voices_parallel.len(), axioms.len());
+
+    for (axiom, voice_parallel) in axioms.iter().zip(voices_parallel.iter()) {
+        let voice_sequential = Voice::from(axiom, atom_types_for(axiom, &action)).unwrap();
+        assert!(voice_sequential.total_duration() > Duration::new(20, 1));
+        assert!(voice_parallel.approx_eq(&voice_sequential, 0.0));
+    }
+}
+
+#[test]
+fn voice_with_default_action() {
+    let action: Arc<dyn Action<NeutralActionState> + Send + Sync> = Arc::new(SimpleAction::new(
+        Key::new(
+            &Note::C,
+            &Accidental::Natural,
+            Arc::new(EqualTemperament::new(STUTTGART_PITCH)),
+        ),
+        &ScaleKind::Major,
+    ));
+    let is_note = |element: &MusicalElement| matches!(element, MusicalElement::Note { .. });
+
+    let voice =
+        Voice::from_axiom_with_default_action(&Axiom::from("ABCDE").unwrap(), Arc::clone(&action))
+            .unwrap();
+    assert_eq!(voice.musical_elements().len(), 5);
+    assert!(voice.musical_elements().iter().all(is_note));
+
+    let voice = Voice::from_axiom_with_rest_symbol(
+        &Axiom::from("ABxDE").unwrap(),
+        Arc::clone(&action),
+        'x',
+    )
+    .unwrap();
+    assert_eq!(voice.musical_elements().len(), 5);
+    assert!(matches!(
+        voice.musical_elements()[2],
+        MusicalElement::Rest { .. }
+    ));
+
+    // the rest symbol does not need to be known to the action
+    let voice =
+        Voice::from_axiom_with_rest_symbol(&Axiom::from("AB-DE").unwrap(), action, '-').unwrap();
+    assert!(matches!(
+        voice.musical_elements()[2],
+        MusicalElement::Rest { .. }
+    ));
+    assert_eq!(
+        voice
+            .musical_elements()
+            .iter()
+            .filter(|e| is_note(e))
+            .count(),
+        4
+    );
+}
+
+/* Remembers every reported stage, so the order of the reports can be checked.
+ */
+#[derive(Default)]
+struct CollectingProgress {
+    reports: RefCell<Vec<(Stage, u64, u64)>>,
+}
+
+impl Progress for CollectingProgress {
+    fn on_stage(&self, stage: Stage, done: u64, total: u64) {
+        self.reports.borrow_mut().push((stage, done, total));
+    }
+}
+
+#[test]
+fn progress_of_expansion_generation_and_rendering() {
+    let temp = Arc::new(EqualTemperament::new(STUTTGART_PITCH));
+    let ruleset: RuleSet = "A->AB\nB->CA".parse().unwrap();
+    let progress = CollectingProgress::default();
+
+    let axiom = ruleset.expand_with_progress(&Axiom::from("A").unwrap(), 4, &progress);
+
+    let action: Arc<dyn Action<_> + Send + Sync> = Arc::new(SimpleAction::new(
+        Key::new(&Note::C, &Accidental::Natural, temp),
+        &ScaleKind::Major,
+    ));
+    let voice =
+        Voice::from_with_progress(&axiom, atom_types_for(&axiom, &action), &progress).unwrap();
+
+    let mut sequencer = Sequencer::new(44100.0, 2);
+    let bpm = 120;
+    voice.sequence(
+        &mut sequencer,
+        bpm,
+        |pitch, volume| -> Box<dyn AudioUnit64> {
+            Box::new((volume.as_amplitude() * sine_hz(pitch.get_hz())) >> pan(0.0))
+        },
+    );
+    render_with_progress(44100.0, voice.get_duration(bpm), &mut sequencer, &progress);
+
+    let reports = progress.reports.borrow();
+    let stages: Vec<Stage> = reports.iter().map(|(stage, _, _)| *stage).collect();
+    assert!(stages.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(stages.first(), Some(&Stage::Expansion));
+    assert!(stages.contains(&Stage::Generation));
+    assert_eq!(stages.last(), Some(&Stage::Rendering));
+
+    for stage in [Stage::Expansion, Stage::Generation, Stage::Rendering] {
+        let counts: Vec<(u64, u64)> = reports
+            .iter()
+            .filter(|(reported_stage, _, _)| *reported_stage == stage)
+            .map(|(_, done, total)| (*done, *total))
+            .collect();
+
+        assert_eq!(counts.first().map(|(done, _)| *done), Some(0));
+        assert!(counts.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(counts.iter().all(|(_, total)| *total == counts[0].1));
+        assert_eq!(counts.last().map(|(done, total)| done == total), Some(true));
+    }
+
+    // one report per iteration and per atom besides the start of the stage
+    assert_eq!(
+        stages
+            .iter()
+            .filter(|stage| **stage == Stage::Expansion)
+            .count(),
+        5
+    );
+    assert_eq!(
+        stages
+            .iter()
+            .filter(|stage| **stage == Stage::Generation)
+            .count(),
+        axiom.atoms().len() + 1
+    );
+}
+
+/* The proportions of C major in just intonation,
+ * each from one tone up to the next.
+ */
+fn c_major_just_intonation() -> JustIntonation {
+    JustIntonation::new(
+        261.626,
+        1,
+        [
+            Proportion::new(8, 9),
+            Proportion::new(9, 10),
+            Proportion::new(15, 16),
+            Proportion::new(8, 9),
+            Proportion::new(9, 10),
+            Proportion::new(8, 9),
+            Proportion::new(15, 16),
+        ],
+    )
+}
+
+#[test]
+fn just_intonation_of_c_major() {
+    let just = c_major_just_intonation();
+    let c4 = just.get_pitch(4, 1).unwrap().get_hz();
+    let ratios: Vec<String> = (1..=8)
+        .map(|position| format!("{:.4}", just.get_pitch(4, position).unwrap().get_hz() / c4))
+        .collect();
+
+    // 1, 9/8, 5/4, 4/3, 3/2, 5/3, 15/8, 2
+    assert_eq!(
+        ratios,
+        vec!["1.0000", "1.1250", "1.2500", "1.3333", "1.5000", "1.6667", "1.8750", "2.0000"]
+    );
+    assert_eq!(just.octave_additive(), 7);
+}
+
+#[test]
+fn nearest_tone_of_just_intonation() {
+    let just = c_major_just_intonation();
+    let equal = EqualTemperament::new(STUTTGART_PITCH);
+
+    // the equal tempered E4 is 13.7 cents sharper than the just major third above C4
+    let e4 = equal.get_pitch(4, 5).unwrap();
+    let (tone, octave, cents) = just.nearest_tone(e4).unwrap();
+    assert_eq!((tone, octave), (Tone::new(Note::E, Accidental::Natural), 4));
+    assert_eq!(format!("{:+.1}", cents), "+13.7");
+
+    // C#4 has no tone of its own in a seven tone temperament
+    let c_sharp4 = equal.get_pitch(4, 2).unwrap();
+    let (tone, octave, _) = just.nearest_tone(c_sharp4).unwrap();
+    assert_eq!((tone, octave), (Tone::new(Note::C, Accidental::Natural), 4));
+
+    let (tone, octave, cents) = equal.nearest_tone(e4).unwrap();
+    assert_eq!((tone, octave), (Tone::new(Note::E, Accidental::Natural), 4));
+    assert!(cents.abs() < CENTS_TOLERANCE);
+}